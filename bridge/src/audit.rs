@@ -0,0 +1,273 @@
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use cosmos_sdk_proto::cosmos::base::abci::v1beta1::TxResponse;
+use eyre::Result;
+use web3::signing::keccak256;
+
+/// Hash chained before the first record
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+pub const STAGE_SIGNED: &str = "signed";
+pub const STAGE_BROADCAST: &str = "broadcast";
+
+/// One signature or claim produced by the bridge, a line of the audit log
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub seq: u64,
+    pub timestamp: u64,
+    /// valset_confirm, batch_confirm or the claim name, e.g. deposit_claim
+    pub kind: String,
+    pub gravity_id: String,
+    /// f(x)Core address of the orchestrator that signed or claimed
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub orchestrator: String,
+    /// valset nonce, batch nonce or event nonce of a claim
+    pub nonce: u64,
+    /// checkpoint hash signed by the Ethereum key, empty for claims
+    pub checkpoint: String,
+    pub signature: String,
+    pub tx_hash: String,
+    pub code: u32,
+    /// signed in a dry run, never broadcast, `tx_hash` is empty
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub dry_run: bool,
+    /// `signed` before the tx is broadcast, `broadcast` once it returned
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub stage: String,
+    /// broadcast error, the tx may not have reached f(x)Core
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub error: String,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+/// Records of older logs have no `dry_run`, `orchestrator`, `stage` or `error`, they are left out when unset so their hashes still verify
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+impl AuditRecord {
+    /// keccak256 of the record serialized with an empty `hash`
+    pub fn compute_hash(&self) -> Result<String> {
+        let mut record = self.clone();
+        record.hash = String::new();
+        Ok(hex::encode(keccak256(serde_json::to_vec(&record)?.as_slice())))
+    }
+
+    /// The record of a signature, appended before its tx is broadcast
+    pub fn signed(&self) -> Self {
+        AuditRecord {
+            stage: STAGE_SIGNED.to_string(),
+            ..self.clone()
+        }
+    }
+
+    /// Follow-up of a signed record once the tx returned, with its hash and code or the broadcast error
+    pub fn broadcast(&self, result: &Result<TxResponse>) -> Self {
+        let (tx_hash, code, error) = match result {
+            Ok(tx_resp) => (tx_resp.txhash.clone(), tx_resp.code, String::new()),
+            Err(report) => (String::new(), 0, report.to_string()),
+        };
+        AuditRecord {
+            stage: STAGE_BROADCAST.to_string(),
+            tx_hash,
+            code,
+            error,
+            ..self.clone()
+        }
+    }
+}
+
+/// Append-only JSON Lines audit log, each record chained to the hash of the previous one
+pub struct AuditLog {
+    path: PathBuf,
+    /// (next seq, last hash)
+    head: Mutex<(u64, String)>,
+}
+
+impl AuditLog {
+    /// Opens or creates the log, the existing chain is verified and continued
+    pub fn open(path: &str) -> Result<Self> {
+        let path = PathBuf::from(path);
+        let head = if path.exists() {
+            match verify(&path)? {
+                Some(last) => (last.seq + 1, last.hash),
+                None => (0, GENESIS_HASH.to_string()),
+            }
+        } else {
+            (0, GENESIS_HASH.to_string())
+        };
+        Ok(AuditLog { path, head: Mutex::new(head) })
+    }
+
+    /// Chains and appends a record, `seq`, `timestamp`, `prev_hash` and `hash` are filled in
+    pub fn append(&self, mut record: AuditRecord) -> Result<()> {
+        let mut head = self.head.lock().map_err(|_| eyre::Error::msg("audit log lock poisoned"))?;
+        record.seq = head.0;
+        record.timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        record.prev_hash = head.1.clone();
+        record.hash = record.compute_hash()?;
+
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        let mut line = serde_json::to_vec(&record)?;
+        line.push(b'\n');
+        file.write_all(line.as_slice())?;
+        file.sync_data()?;
+
+        *head = (record.seq + 1, record.hash);
+        Ok(())
+    }
+
+    /// Like `append`, but a failure is only logged so it never stops the bridge loops
+    pub fn record(&self, record: AuditRecord) {
+        if let Err(report) = self.append(record) {
+            error!("Write audit log {} failed {:?}", self.path.display(), report.root_cause());
+        }
+    }
+}
+
+/// Audit kind of a claim msg, e.g. `/fx.gravity.v1.MsgDepositClaim` is `deposit_claim`
+pub fn claim_kind(type_url: &str) -> String {
+    let name = type_url.rsplit('.').next().unwrap_or(type_url).trim_start_matches("Msg");
+    let mut kind = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            kind.push('_');
+        }
+        kind.push(c.to_ascii_lowercase());
+    }
+    kind
+}
+
+/// Checks the hash chain of an audit log, returns the last record
+pub fn verify(path: &Path) -> Result<Option<AuditRecord>> {
+    let file = std::fs::File::open(path)?;
+    let mut last: Option<AuditRecord> = None;
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: AuditRecord = serde_json::from_str(line.as_str()).map_err(|err| eyre::Error::msg(format!("line {}: invalid record, {}", index + 1, err)))?;
+        let (seq, prev_hash) = match &last {
+            Some(last) => (last.seq + 1, last.hash.as_str()),
+            None => (0, GENESIS_HASH),
+        };
+        if record.seq != seq {
+            return Err(eyre::Error::msg(format!("line {}: expected seq {}, got {}", index + 1, seq, record.seq)));
+        }
+        if record.prev_hash != prev_hash {
+            return Err(eyre::Error::msg(format!("line {}: seq {} is not chained to the previous record", index + 1, record.seq)));
+        }
+        if record.compute_hash()? != record.hash {
+            return Err(eyre::Error::msg(format!("line {}: seq {} hash mismatch, record was modified", index + 1, record.seq)));
+        }
+        last = Some(record);
+    }
+    Ok(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(kind: &str, nonce: u64, code: u32) -> AuditRecord {
+        AuditRecord {
+            kind: kind.to_string(),
+            gravity_id: "fx-bridge".to_string(),
+            nonce,
+            checkpoint: "aa".to_string(),
+            signature: "bb".to_string(),
+            tx_hash: "CC".to_string(),
+            code,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_claim_kind() {
+        assert_eq!(claim_kind("/fx.gravity.v1.MsgDepositClaim"), "deposit_claim");
+        assert_eq!(claim_kind("/fx.gravity.v1.MsgFxOriginatedTokenClaim"), "fx_originated_token_claim");
+    }
+
+    #[test]
+    fn test_audit_log_chain() {
+        let path = std::env::temp_dir().join(format!("fxeth-audit-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let audit_log = AuditLog::open(path.to_str().unwrap()).unwrap();
+        audit_log.append(record("valset_confirm", 1, 0)).unwrap();
+        audit_log.append(record("batch_confirm", 2, 0)).unwrap();
+
+        // reopening continues the chain
+        let audit_log = AuditLog::open(path.to_str().unwrap()).unwrap();
+        audit_log.append(record("deposit_claim", 3, 5)).unwrap();
+        let last = verify(&path).unwrap().unwrap();
+        assert_eq!(last.seq, 2);
+        assert_eq!(last.kind, "deposit_claim");
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, content.replacen("\"nonce\":2", "\"nonce\":4", 1)).unwrap();
+        assert!(verify(&path).is_err());
+
+        let lines: Vec<&str> = content.lines().collect();
+        std::fs::write(&path, format!("{}\n{}\n", lines[0], lines[2])).unwrap();
+        assert!(verify(&path).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_audit_record_dry_run() {
+        let record = record("valset_confirm", 1, 0);
+        assert!(!serde_json::to_string(&record).unwrap().contains("dry_run"));
+        let hash = record.compute_hash().unwrap();
+
+        let dry_run = AuditRecord { dry_run: true, ..record.clone() };
+        assert!(serde_json::to_string(&dry_run).unwrap().contains("\"dry_run\":true"));
+        assert_ne!(dry_run.compute_hash().unwrap(), hash);
+
+        let signed = AuditRecord {
+            orchestrator: "fx1zgpzdf2uqla7hkx85wnn4p2r3duwqzd8xst6v2".to_string(),
+            ..record
+        };
+        assert_ne!(signed.compute_hash().unwrap(), hash);
+    }
+
+    #[test]
+    fn test_audit_record_broadcast() {
+        let signed = AuditRecord {
+            tx_hash: String::new(),
+            ..record("valset_confirm", 1, 0)
+        }
+        .signed();
+        assert_eq!(signed.stage, STAGE_SIGNED);
+        assert!(!serde_json::to_string(&signed).unwrap().contains("error"));
+
+        let tx_resp = TxResponse {
+            txhash: "CC".to_string(),
+            code: 5,
+            ..Default::default()
+        };
+        let broadcast = signed.broadcast(&Ok(tx_resp));
+        assert_eq!((broadcast.stage.as_str(), broadcast.tx_hash.as_str(), broadcast.code), (STAGE_BROADCAST, "CC", 5));
+        assert_eq!(broadcast.signature, signed.signature);
+
+        let failed = signed.broadcast(&Err(eyre::Error::msg("connection refused")));
+        assert_eq!((failed.tx_hash.as_str(), failed.error.as_str()), ("", "connection refused"));
+    }
+
+    #[test]
+    fn test_audit_record_without_orchestrator() {
+        // a record written before `orchestrator` was added
+        let line = r#"{"seq":0,"timestamp":1,"kind":"valset_confirm","gravity_id":"fx-bridge","nonce":1,"checkpoint":"aa","signature":"bb","tx_hash":"CC","code":0,"prev_hash":"","hash":""}"#;
+        let mut record: AuditRecord = serde_json::from_str(line).unwrap();
+        assert!(record.orchestrator.is_empty());
+        record.hash = record.compute_hash().unwrap();
+        assert_eq!(record.hash, hex::encode(keccak256(line.as_bytes())));
+    }
+}
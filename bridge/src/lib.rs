@@ -1,9 +1,14 @@
 #[macro_use]
 extern crate log;
+#[macro_use]
+extern crate serde_derive;
 
 use std::time::Duration;
 
+pub mod audit;
 pub mod oracle_loop;
+pub mod remote_signer;
+pub mod signer;
 pub mod singer_loop;
 
 /// Average block time in Ethereum
@@ -20,3 +25,6 @@ const ETH_EVENT_TO_SEARCH: u64 = 3000u64;
 
 /// Ethereum event delay processing block height
 const ETH_BLOCK_DELAY: u64 = 12u64;
+
+/// Remote signer socket read and write timeout
+const REMOTE_SIGNER_TIMEOUT: Duration = Duration::from_secs(10);
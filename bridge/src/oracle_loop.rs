@@ -1,4 +1,3 @@
-use std::collections::HashMap;
 use std::ops::{Add, Sub};
 use std::str::FromStr;
 
@@ -13,17 +12,24 @@ use web3::Web3;
 
 use ethereum::address::Checksum;
 use ethereum::fx_bridge;
+use ethereum::fx_bridge::{FxOriginatedTokenEvent, SendToFxEvent, TransactionBatchExecutedEvent, ValsetUpdatedEvent};
 use fxchain::address::Address as FxAddress;
 use fxchain::builder::Builder;
 use fxchain::grpc_client::{get_last_event_block_height_by_addr, get_last_event_nonce, get_orchestrator_validator_status};
 use fxchain::proto_ext::MessageExt;
 use fxchain::x::gravity::{BridgeValidator, MsgDepositClaim, MsgFxOriginatedTokenClaim, MsgValsetUpdatedClaim, MsgWithdrawClaim};
 
+use crate::audit::{claim_kind, AuditLog, AuditRecord};
 use crate::singer_loop::set_fx_key_balance_metrics;
 use crate::{ETH_AVG_BLOCK_TIME, ETH_BLOCKS_TO_SEARCH, ETH_BLOCK_DELAY, ETH_EVENT_TO_SEARCH};
 
+/// Events emitted by the FxBridge contract in a block range
+pub type BridgeEvents = (Vec<SendToFxEvent>, Vec<TransactionBatchExecutedEvent>, Vec<FxOriginatedTokenEvent>, Vec<ValsetUpdatedEvent>);
+
 /// Periodically check for Ethereum FxBridge contract events and forward the monitored events to the Fx chain
-pub async fn eth_oracle_bridge_loop(fx_builder: &Builder, grpc_channel: &Channel, web3: &Web3<Http>, bridge_addr: EthAddress, mut eth_last_block: U64) {
+pub async fn eth_oracle_bridge_loop(fx_builder: &Builder, grpc_channel: &Channel, web3: &Web3<Http>, bridge_addr: EthAddress, mut eth_last_block: U64, audit_log: Option<&AuditLog>) {
+    let gravity_id = fxchain::grpc_client::get_gravity_id(grpc_channel).await.unwrap();
+
     loop {
         sleep(ETH_AVG_BLOCK_TIME).await;
 
@@ -38,7 +44,13 @@ pub async fn eth_oracle_bridge_loop(fx_builder: &Builder, grpc_channel: &Channel
             warn!("Get orchestrator status is not 'Bonded', {:?}", status);
             continue;
         }
-        let eth_address = EthAddress::from_str(eth_address_str.as_str()).unwrap();
+        let eth_address = match EthAddress::from_str(eth_address_str.as_str()) {
+            Ok(eth_address) => eth_address,
+            Err(_) => {
+                error!("Invalid registered eth address {}", eth_address_str);
+                continue;
+            }
+        };
 
         let result = web3.eth().block_number().await;
         if result.is_err() {
@@ -63,7 +75,9 @@ pub async fn eth_oracle_bridge_loop(fx_builder: &Builder, grpc_channel: &Channel
             info!("Oracle sync complete, Oracle now operational, last block {:?}", eth_last_block);
             continue;
         }
-        prometheus::metrics::ETH_BRIDGE_ORACLE_SYNC_BLOCK_HEIGHT.set(eth_last_block.as_u64() as f64);
+        prometheus::metrics::ETH_BRIDGE_ORACLE_SYNC_BLOCK_HEIGHT
+            .with_label_values(&[fx_builder.address().to_string().as_str()])
+            .set(eth_last_block.as_u64() as f64);
         if eth_last_block.ge(&eth_latest_block) {
             // Waiting for a new block
             continue;
@@ -74,7 +88,7 @@ pub async fn eth_oracle_bridge_loop(fx_builder: &Builder, grpc_channel: &Channel
             eth_latest_block = eth_last_block.add(ETH_EVENT_TO_SEARCH)
         }
 
-        let result = eth_oracle_bridge(fx_builder, grpc_channel, web3, bridge_addr, eth_last_block, eth_latest_block).await;
+        let result = eth_oracle_bridge(fx_builder, grpc_channel, web3, bridge_addr, eth_last_block, eth_latest_block, &gravity_id, audit_log).await;
         match result {
             Ok(latest_block) => {
                 eth_last_block = latest_block;
@@ -89,85 +103,258 @@ pub async fn eth_oracle_bridge_loop(fx_builder: &Builder, grpc_channel: &Channel
     }
 }
 
-#[async_recursion(? Send)]
-async fn eth_oracle_bridge(fx_builder: &Builder, grpc_channel: &Channel, web3: &Web3<Http>, bridge_addr: EthAddress, from_block: U64, mut to_block: U64) -> Result<U64> {
-    let fx_address = fx_builder.address();
+/// Oracle loop for several orchestrators in one process, the FxBridge events of each block range are
+/// queried once and the claims fan out to every bonded orchestrator
+pub async fn eth_oracle_multi_bridge_loop(
+    fx_builders: &[Builder],
+    grpc_channel: &Channel,
+    web3: &Web3<Http>,
+    bridge_addr: EthAddress,
+    eth_last_block: U64,
+    audit_log: Option<&AuditLog>,
+) {
+    let gravity_id = fxchain::grpc_client::get_gravity_id(grpc_channel).await.unwrap();
+    // next block to process by each orchestrator, zero until synced
+    let mut eth_last_blocks = vec![eth_last_block; fx_builders.len()];
 
-    let last_event_nonce = fxchain::grpc_client::get_last_event_nonce(grpc_channel, fx_address).await?;
-    info!("Query last event nonce by fx address {}", last_event_nonce);
+    loop {
+        sleep(ETH_AVG_BLOCK_TIME).await;
 
+        let result = web3.eth().block_number().await;
+        if result.is_err() {
+            error!("Oracle fetch ethereum block number failed {:?}", result.unwrap_err());
+            continue;
+        }
+        let eth_latest_block = result.unwrap();
+        if eth_latest_block <= U64::from(ETH_BLOCK_DELAY) {
+            warn!("Get eth latest block is invalid {}", eth_latest_block);
+            continue;
+        }
+        let eth_latest_block = eth_latest_block.sub(U64::from(ETH_BLOCK_DELAY));
+
+        // only bonded and synced orchestrators take part in this round
+        let mut bonded = vec![false; fx_builders.len()];
+        for (i, fx_builder) in fx_builders.iter().enumerate() {
+            let eth_address = match get_orchestrator_validator_status(grpc_channel, fx_builder.address()).await {
+                Ok((status, eth_address)) if status == BondStatus::Bonded as i32 => eth_address,
+                Ok((status, _)) => {
+                    warn!("Orchestrator {} status is not 'Bonded', {:?}", fx_builder.address(), status);
+                    continue;
+                }
+                Err(report) => {
+                    warn!("Get orchestrator {} status failed {:?}", fx_builder.address(), report.root_cause());
+                    continue;
+                }
+            };
+            if eth_last_blocks[i].is_zero() {
+                let eth_address = match EthAddress::from_str(eth_address.as_str()) {
+                    Ok(eth_address) => eth_address,
+                    Err(_) => {
+                        error!("Orchestrator {} invalid registered eth address {}", fx_builder.address(), eth_address);
+                        continue;
+                    }
+                };
+                match get_last_checked_block_height(web3, grpc_channel, fx_builder.address(), eth_address, bridge_addr, eth_latest_block).await {
+                    Ok(last_block) => {
+                        info!("Oracle of {} sync complete, last block {:?}", fx_builder.address(), last_block);
+                        eth_last_blocks[i] = last_block;
+                    }
+                    Err(report) => {
+                        warn!("Oracle of {} get last block height failed {:?}", fx_builder.address(), report.root_cause());
+                        continue;
+                    }
+                }
+            }
+            bonded[i] = true;
+            prometheus::metrics::ETH_BRIDGE_ORACLE_SYNC_BLOCK_HEIGHT
+                .with_label_values(&[fx_builder.address().to_string().as_str()])
+                .set(eth_last_blocks[i].as_u64() as f64);
+        }
+
+        let from_block = eth_last_blocks.iter().zip(bonded.iter()).filter(|(_, bonded)| **bonded).map(|(last_block, _)| *last_block).min();
+        let from_block = match from_block {
+            Some(from_block) => from_block,
+            None => continue,
+        };
+        if from_block.ge(&eth_latest_block) {
+            // Waiting for a new block
+            continue;
+        }
+        let to_block = if eth_latest_block.sub(from_block).gt(&(ETH_EVENT_TO_SEARCH.into())) {
+            from_block.add(ETH_EVENT_TO_SEARCH)
+        } else {
+            eth_latest_block
+        };
+
+        let (to_block, events) = match query_bridge_events(web3, bridge_addr, from_block, to_block).await {
+            Ok(result) => result,
+            Err(report) => {
+                error!("Failed to ethereum oracle {:?}", report.root_cause());
+                continue;
+            }
+        };
+        for (i, fx_builder) in fx_builders.iter().enumerate() {
+            // orchestrators ahead of this range wait for the others to catch up
+            if !bonded[i] || eth_last_blocks[i] > to_block {
+                continue;
+            }
+            match submit_oracle_claims(fx_builder, grpc_channel, &events, eth_last_blocks[i], to_block, &gravity_id, audit_log).await {
+                Ok(latest_block) => eth_last_blocks[i] = latest_block,
+                Err(report) => error!("Failed to ethereum oracle of {} {:?}", fx_builder.address(), report.root_cause()),
+            }
+        }
+
+        let synced_block = eth_last_blocks.iter().filter(|last_block| !last_block.is_zero()).min().cloned().unwrap_or_default();
+        if synced_block > 2000.into() {
+            let _ = std::fs::write("config", (synced_block.sub(U64::from(2000))).to_string());
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn eth_oracle_bridge(
+    fx_builder: &Builder,
+    grpc_channel: &Channel,
+    web3: &Web3<Http>,
+    bridge_addr: EthAddress,
+    from_block: U64,
+    to_block: U64,
+    gravity_id: &String,
+    audit_log: Option<&AuditLog>,
+) -> Result<U64> {
+    let (to_block, events) = query_bridge_events(web3, bridge_addr, from_block, to_block).await?;
+    submit_oracle_claims(fx_builder, grpc_channel, &events, from_block, to_block, gravity_id, audit_log).await
+}
+
+/// Query the FxBridge events from `from_block` to `to_block`, halving the range if the node refuses it.
+/// Returns the last block actually queried.
+#[async_recursion(? Send)]
+async fn query_bridge_events(web3: &Web3<Http>, bridge_addr: EthAddress, from_block: U64, to_block: U64) -> Result<(U64, BridgeEvents)> {
     info!("Query oracle bridge event from {} to {}", from_block, to_block);
     prometheus::metrics::ETH_BRIDGE_ORACLE_QUERY_LOG_BLOCK_HEIGHT_INTERVAL.set((to_block - from_block).as_u64() as f64);
 
-    let (deposits, withdraws, fx_originated_token, valset_updated) = match fx_bridge::query_all_event(web3, bridge_addr, from_block, Some(to_block)).await {
-        Ok(event_list) => event_list,
+    match fx_bridge::query_all_event(web3, bridge_addr, from_block, Some(to_block)).await {
+        Ok(event_list) => Ok((to_block, event_list)),
         Err(report) => {
             error!("Eth bridge oracle query all event error: {:?}", report.root_cause());
 
             let eth_block_buf = (to_block - from_block) / 2;
             if eth_block_buf > 1.into() {
-                return eth_oracle_bridge(fx_builder, grpc_channel, web3, bridge_addr, from_block, from_block + eth_block_buf).await;
+                return query_bridge_events(web3, bridge_addr, from_block, from_block + eth_block_buf).await;
             }
-            to_block = from_block;
-            fx_bridge::query_all_event_san_block(web3, bridge_addr, from_block).await?
+            Ok((from_block, fx_bridge::query_all_event_san_block(web3, bridge_addr, from_block).await?))
         }
-    };
-    let mut unordered_msgs = HashMap::new();
+    }
+}
 
-    for deposit in deposits {
-        if deposit.event_nonce <= U256::from(last_event_nonce) {
-            continue;
+/// Send the claims of `events` not yet observed by the orchestrator of `fx_builder`
+async fn submit_oracle_claims(
+    fx_builder: &Builder,
+    grpc_channel: &Channel,
+    events: &BridgeEvents,
+    from_block: U64,
+    to_block: U64,
+    gravity_id: &String,
+    audit_log: Option<&AuditLog>,
+) -> Result<U64> {
+    let fx_address = fx_builder.address();
+
+    let last_event_nonce = fxchain::grpc_client::get_last_event_nonce(grpc_channel, fx_address).await?;
+    info!("Query last event nonce by fx address {}", last_event_nonce);
+
+    let claims = oracle_claims(events, fx_address, last_event_nonce);
+    if claims.is_empty() {
+        info!("An Oracle event to be processed was not found(eth-fx) {}", to_block);
+        return Ok(to_block + 1);
+    }
+    let keys: Vec<u64> = claims.iter().map(|claim| claim.event_nonce()).collect();
+    prometheus::metrics::ETH_BRIDGE_ORACLE_MSG_PENDING_LEN
+        .with_label_values(&[fx_address.to_string().as_str()])
+        .set(claims.len() as f64);
+    info!("Oracle bridge originated token and deposit and withdraw len {}, {:?}", claims.len(), keys);
+
+    // in a dry run the on-chain nonce never moves, so later ranges start above it
+    if keys[0] != last_event_nonce + 1 && !fx_builder.is_dry_run() {
+        return Err(eyre::Error::msg(format!(
+            "Oracle bridge event nonce {} out of the current state, last event nonce {}",
+            keys[0], last_event_nonce
+        )));
+    }
+
+    if !send_oracle_claims(fx_builder, grpc_channel, &claims, gravity_id, audit_log).await? {
+        return Ok(from_block);
+    }
+    debug!("Oracle loop complete {}", to_block + 1);
+    Ok(to_block + 1)
+}
+
+/// Claim of one FxBridge event
+#[derive(Debug, Clone, PartialEq)]
+pub enum OracleClaim {
+    Deposit(MsgDepositClaim),
+    Withdraw(MsgWithdrawClaim),
+    FxOriginatedToken(MsgFxOriginatedTokenClaim),
+    ValsetUpdated(MsgValsetUpdatedClaim),
+}
+
+impl OracleClaim {
+    pub fn event_nonce(&self) -> u64 {
+        match self {
+            OracleClaim::Deposit(claim) => claim.event_nonce,
+            OracleClaim::Withdraw(claim) => claim.event_nonce,
+            OracleClaim::FxOriginatedToken(claim) => claim.event_nonce,
+            OracleClaim::ValsetUpdated(claim) => claim.event_nonce,
         }
-        let claim = MsgDepositClaim {
+    }
+
+    pub fn type_url(&self) -> &'static str {
+        match self {
+            OracleClaim::Deposit(_) => "/fx.gravity.v1.MsgDepositClaim",
+            OracleClaim::Withdraw(_) => "/fx.gravity.v1.MsgWithdrawClaim",
+            OracleClaim::FxOriginatedToken(_) => "/fx.gravity.v1.MsgFxOriginatedTokenClaim",
+            OracleClaim::ValsetUpdated(_) => "/fx.gravity.v1.MsgValsetUpdatedClaim",
+        }
+    }
+}
+
+/// Claims of the `events` above `last_event_nonce`, ordered by event nonce
+pub fn oracle_claims(events: &BridgeEvents, fx_address: FxAddress, last_event_nonce: u64) -> Vec<OracleClaim> {
+    let (deposits, withdraws, fx_originated_token, valset_updated) = events;
+    let mut claims = Vec::new();
+
+    for deposit in deposits.iter() {
+        claims.push(OracleClaim::Deposit(MsgDepositClaim {
             event_nonce: deposit.event_nonce.as_u64(),
             block_height: deposit.block_number.as_u64(),
             token_contract: deposit.erc20.to_hex_string(),
             amount: deposit.amount.to_string(),
             fx_receiver: FxAddress::from_bytes(deposit.destination).to_string(),
-            target_ibc: deposit.target_ibc,
+            target_ibc: deposit.target_ibc.clone(),
             eth_sender: deposit.sender.to_hex_string(),
             orchestrator: fx_address.to_string(),
-        };
-        debug!("deposit claim {:?}", claim);
-        let msg = claim.to_any("/fx.gravity.v1.MsgDepositClaim");
-        unordered_msgs.insert(deposit.event_nonce.clone(), msg);
+        }));
     }
-    for withdraw in withdraws {
-        if withdraw.event_nonce <= U256::from(last_event_nonce) {
-            continue;
-        }
-        let claim = MsgWithdrawClaim {
+    for withdraw in withdraws.iter() {
+        claims.push(OracleClaim::Withdraw(MsgWithdrawClaim {
             event_nonce: withdraw.event_nonce.as_u64(),
             block_height: withdraw.block_number.as_u64(),
             token_contract: withdraw.erc20.to_hex_string(),
             batch_nonce: withdraw.batch_nonce.as_u64(),
             orchestrator: fx_address.to_string(),
-        };
-        debug!("withdraw claim {:?}", claim);
-        let msg = claim.to_any("/fx.gravity.v1.MsgWithdrawClaim");
-        unordered_msgs.insert(withdraw.event_nonce.clone(), msg);
+        }));
     }
-    for token in fx_originated_token {
-        if token.event_nonce <= U256::from(last_event_nonce) {
-            continue;
-        }
-        let claim = MsgFxOriginatedTokenClaim {
+    for token in fx_originated_token.iter() {
+        claims.push(OracleClaim::FxOriginatedToken(MsgFxOriginatedTokenClaim {
             event_nonce: token.event_nonce.as_u64(),
             block_height: token.block_number.as_u64(),
             token_contract: token.erc20.to_hex_string(),
-            name: token.name,
-            symbol: token.symbol,
+            name: token.name.clone(),
+            symbol: token.symbol.clone(),
             decimals: token.decimals.as_u64(),
             orchestrator: fx_address.to_string(),
-        };
-        debug!("fx originated token claim {:?}", claim);
-        let msg = claim.to_any("/fx.gravity.v1.MsgFxOriginatedTokenClaim");
-        unordered_msgs.insert(token.event_nonce.clone(), msg);
+        }));
     }
-    for valset in valset_updated {
-        if valset.event_nonce <= U256::from(last_event_nonce) {
-            continue;
-        }
+    for valset in valset_updated.iter() {
         let mut bridge_validator = Vec::new();
         for i in 0..valset.validators.len() {
             bridge_validator.push(BridgeValidator {
@@ -175,59 +362,123 @@ async fn eth_oracle_bridge(fx_builder: &Builder, grpc_channel: &Channel, web3: &
                 eth_address: valset.validators[i].to_hex_string(),
             })
         }
-        let claim = MsgValsetUpdatedClaim {
+        claims.push(OracleClaim::ValsetUpdated(MsgValsetUpdatedClaim {
             event_nonce: valset.event_nonce.as_u64(),
             block_height: valset.block_number.as_u64(),
             valset_nonce: valset.valset_nonce.as_u64(),
             members: bridge_validator,
             orchestrator: fx_address.to_string(),
-        };
-        debug!("valset updated token claim {:?}", claim);
-        let msg = claim.to_any("/fx.gravity.v1.MsgValsetUpdatedClaim");
-        unordered_msgs.insert(valset.event_nonce.clone(), msg);
+        }));
     }
-    if unordered_msgs.len() <= 0 {
-        info!("An Oracle event to be processed was not found(eth-fx) {}", to_block);
-        return Ok(to_block + 1);
-    }
-
-    let mut keys = Vec::new();
-    for (key, _) in unordered_msgs.iter() {
-        keys.push(key.clone());
-    }
-    keys.sort();
-    prometheus::metrics::ETH_BRIDGE_ORACLE_MSG_PENDING_LEN.set(unordered_msgs.len() as f64);
-    info!("Oracle bridge originated token and deposit and withdraw len {}, {:?}", unordered_msgs.len(), keys);
-
-    if keys[0] != (last_event_nonce + 1).into() {
-        panic!("Oracle bridge event nonce out of the current state")
+    claims.retain(|claim| claim.event_nonce() > last_event_nonce);
+    claims.sort_by_key(|claim| claim.event_nonce());
+    claims.dedup_by_key(|claim| claim.event_nonce());
+    for claim in claims.iter() {
+        debug!("oracle claim {:?}", claim);
     }
+    claims
+}
 
-    let mut msgs = Vec::new();
-    for i in keys {
-        msgs.push(unordered_msgs.remove_entry(&i).unwrap().1);
-    }
-    let mut cur_msg = Vec::new();
-    for i in 0..msgs.len() {
-        cur_msg.push(msgs[i].clone());
-        if (i + 1) % fxchain::FX_MSG_MAX_NUMBER != 0 && (i + 1) != msgs.len() {
+/// Send `claims` in transactions of at most `FX_MSG_MAX_NUMBER` messages, returns false when a transaction fails
+async fn send_oracle_claims(fx_builder: &Builder, grpc_channel: &Channel, claims: &[OracleClaim], gravity_id: &String, audit_log: Option<&AuditLog>) -> Result<bool> {
+    let fx_address = fx_builder.address();
+    for chunk in claims.chunks(fxchain::FX_MSG_MAX_NUMBER) {
+        let msgs = chunk
+            .iter()
+            .map(|claim| match claim {
+                OracleClaim::Deposit(msg) => msg.to_any(claim.type_url()),
+                OracleClaim::Withdraw(msg) => msg.to_any(claim.type_url()),
+                OracleClaim::FxOriginatedToken(msg) => msg.to_any(claim.type_url()),
+                OracleClaim::ValsetUpdated(msg) => msg.to_any(claim.type_url()),
+            })
+            .collect();
+        let audit_records: Vec<AuditRecord> = chunk
+            .iter()
+            .map(|claim| AuditRecord {
+                kind: claim_kind(claim.type_url()),
+                gravity_id: gravity_id.clone(),
+                orchestrator: fx_address.to_string(),
+                nonce: claim.event_nonce(),
+                dry_run: fx_builder.is_dry_run(),
+                ..Default::default()
+            })
+            .collect();
+        if fx_builder.is_dry_run() {
+            fxchain::grpc_client::dry_run_tx(fx_builder, grpc_channel, msgs).await?;
+            if let Some(audit_log) = audit_log {
+                audit_records.iter().for_each(|record| audit_log.record(record.signed()));
+            }
             continue;
         }
-        let tx_resp = fxchain::grpc_client::send_tx(fx_builder, grpc_channel, cur_msg.clone()).await?;
+        if let Some(audit_log) = audit_log {
+            audit_records.iter().for_each(|record| audit_log.record(record.signed()));
+        }
+        let result = fxchain::grpc_client::send_tx(fx_builder, grpc_channel, msgs).await;
+        if let Some(audit_log) = audit_log {
+            audit_records.iter().for_each(|record| audit_log.record(record.broadcast(&result)));
+        }
+        let tx_resp = result?;
         info!("Eth oracle bridge fx tx response code {}, tx hash {}", tx_resp.code, tx_resp.txhash);
 
         if tx_resp.code != 0 {
             error!("Send eth oracle bridge tx failed: {:?}", tx_resp.raw_log);
-
-            return Ok(from_block);
+            return Ok(false);
         }
-        info!("Oracle Claims processed, new nonce {}", last_event_nonce);
+        info!("Oracle Claims processed, new nonce {}", chunk[chunk.len() - 1].event_nonce());
+        prometheus::metrics::ETH_BRIDGE_ORACLE_CLAIMS_SUBMITTED
+            .with_label_values(&[fx_address.to_string().as_str()])
+            .inc_by(chunk.len() as f64);
 
         set_fx_key_balance_metrics(fx_builder, grpc_channel).await;
-        cur_msg.clear()
     }
-    debug!("Oracle loop complete {}", to_block + 1);
-    Ok(to_block + 1)
+    Ok(true)
+}
+
+/// Scan `from_block..=to_block` once and send the claims above the last event nonce of `orchestrator`.
+/// Without a `fx_builder` nothing is sent. Returns the claims sent, or that would be sent.
+#[allow(clippy::too_many_arguments)]
+pub async fn eth_oracle_replay(
+    fx_builder: Option<&Builder>,
+    orchestrator: FxAddress,
+    grpc_channel: &Channel,
+    web3: &Web3<Http>,
+    bridge_addr: EthAddress,
+    from_block: U64,
+    to_block: U64,
+    audit_log: Option<&AuditLog>,
+) -> Result<Vec<OracleClaim>> {
+    let gravity_id = fxchain::grpc_client::get_gravity_id(grpc_channel).await?;
+    let mut last_event_nonce = get_last_event_nonce(grpc_channel, orchestrator).await?;
+    info!("Replay oracle from {} to {}, last event nonce {}", from_block, to_block, last_event_nonce);
+
+    let mut replayed = Vec::new();
+    let mut from = from_block;
+    while from <= to_block {
+        let end = to_block.min(from + ETH_EVENT_TO_SEARCH);
+        let (end, events) = query_bridge_events(web3, bridge_addr, from, end).await?;
+        let claims = oracle_claims(&events, orchestrator, last_event_nonce);
+        if let Some(first) = claims.first() {
+            if first.event_nonce() != last_event_nonce + 1 {
+                return Err(eyre::Error::msg(format!(
+                    "event nonce {} at block {} skips nonce {}, start the replay from an earlier block",
+                    first.event_nonce(),
+                    from,
+                    last_event_nonce + 1
+                )));
+            }
+        }
+        if let Some(fx_builder) = fx_builder {
+            if !send_oracle_claims(fx_builder, grpc_channel, &claims, &gravity_id, audit_log).await? {
+                return Err(eyre::Error::msg(format!("send oracle claims from block {} failed", from)));
+            }
+        }
+        if let Some(last) = claims.last() {
+            last_event_nonce = last.event_nonce();
+        }
+        replayed.extend(claims);
+        from = end + 1;
+    }
+    Ok(replayed)
 }
 
 async fn get_last_checked_block_height(web3: &Web3<Http>, grpc_channel: &Channel, fx_address: FxAddress, eth_address: EthAddress, bridge_addr: EthAddress, eth_latest_block: U64) -> Result<U64> {
@@ -287,59 +538,10 @@ mod tests {
 
     use ethereum::fx_bridge::query_all_event_san_block;
     use fxchain::address::Address as FxAddress;
-    use fxchain::grpc_client::{get_account_info, get_chain_id, new_grpc_channel};
-    use fxchain::private_key::PrivateKey;
-    use fxchain::x::gravity::query_client::QueryClient as GravityQueryClient;
-    use fxchain::x::gravity::QueryLastEventNonceByAddrRequest;
 
     use super::*;
 
-    const FX_MNEMONIC: &str = "";
     const ETH_RPC_URL: &str = "http://localhost:8545";
-    const FX_GRPC_URL: &str = "http://127.0.0.1:9090";
-    const BRIDGE_ADDR: &str = "0x0412C7c846bb6b7DC462CF6B453f76D8440b2609";
-
-    #[tokio::test]
-    async fn query_event_nonce() {
-        let grpc_channel = new_grpc_channel(FX_GRPC_URL).await.unwrap();
-        let fx_address = FxAddress::from_str("fx1qllms2p25gec8fn4xvyak83g856xdltp4wc335").unwrap();
-        let last_event_nonce = fxchain::grpc_client::get_last_event_nonce(&grpc_channel, fx_address).await.unwrap();
-        println!("{}", last_event_nonce);
-
-        let block_height = get_last_event_block_height_by_addr(&grpc_channel, fx_address).await.unwrap();
-        println!("{}", block_height);
-    }
-
-    #[tokio::test]
-    async fn test_eth_oracle_main_loop() {
-        let transport = web3::transports::Http::new(ETH_RPC_URL).unwrap();
-        let web3 = web3::Web3::new(transport);
-
-        let private_key = PrivateKey::from_phrase(FX_MNEMONIC, "").unwrap();
-        let acc_address = private_key.public_key().to_address().to_string();
-        let grpc_channel = new_grpc_channel(FX_GRPC_URL).await.unwrap();
-        let auth_account = get_account_info(&grpc_channel, acc_address).await.unwrap();
-        let chain_id = get_chain_id(&grpc_channel).await.unwrap();
-        let builder = Builder::new(chain_id, private_key, auth_account.account_number, "FX");
-
-        let bridge_addr = EthAddress::from_str(BRIDGE_ADDR).unwrap();
-        eth_oracle_bridge_loop(&builder, &grpc_channel, &web3, bridge_addr, U64::from(0)).await;
-    }
-
-    #[tokio::test]
-    async fn test_last_event_nonce_by_addr() {
-        let grpc_channel = new_grpc_channel(FX_GRPC_URL).await.unwrap();
-
-        let mut gravity_query_client = GravityQueryClient::new(grpc_channel.clone());
-
-        let response = gravity_query_client
-            .last_event_nonce_by_addr(QueryLastEventNonceByAddrRequest {
-                address: "fx16wvwsmpp4y4ttgzknyr6kqla877jud6u04lqey".to_string(),
-            })
-            .await
-            .unwrap();
-        println!("{}", response.into_inner().event_nonce);
-    }
 
     #[async_recursion(? Send)]
     async fn test_eth_bridge_event(web3: &Web3<Http>, bridge_addr: EthAddress, from_block: U64, mut to_block: U64) -> Result<U64> {
@@ -413,4 +615,31 @@ mod tests {
             cur_msg.clear()
         }
     }
+
+    #[test]
+    fn test_oracle_claims() {
+        let fx_address = FxAddress::from_str("fx1qllms2p25gec8fn4xvyak83g856xdltp4wc335").unwrap();
+        let deposit = |event_nonce: u64| SendToFxEvent {
+            event_nonce: event_nonce.into(),
+            ..Default::default()
+        };
+        let withdraw = TransactionBatchExecutedEvent {
+            batch_nonce: 7.into(),
+            event_nonce: 3.into(),
+            ..Default::default()
+        };
+        let events: BridgeEvents = (vec![deposit(4), deposit(2), deposit(1)], vec![withdraw], vec![], vec![]);
+
+        let claims = oracle_claims(&events, fx_address, 1);
+        assert_eq!(claims.iter().map(|claim| claim.event_nonce()).collect::<Vec<_>>(), vec![2, 3, 4]);
+        assert_eq!(claims[1].type_url(), "/fx.gravity.v1.MsgWithdrawClaim");
+        match &claims[1] {
+            OracleClaim::Withdraw(claim) => {
+                assert_eq!(claim.batch_nonce, 7);
+                assert_eq!(claim.orchestrator, fx_address.to_string());
+            }
+            claim => panic!("unexpected claim {:?}", claim),
+        }
+        assert!(oracle_claims(&events, fx_address, 4).is_empty());
+    }
 }
@@ -0,0 +1,567 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fs::{DirBuilder, Permissions};
+use std::os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use cosmos_sdk_proto::cosmos::tx::v1beta1::{SignDoc, TxBody};
+use ecdsa::signature::Signer;
+use eyre::Result;
+use k256::ecdsa::{Signature as FxSignature, VerifyingKey};
+use prost::Message;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::runtime::Handle;
+use tokio::sync::Mutex;
+use web3::signing::keccak256;
+use web3::types::Address as EthAddress;
+
+use ethereum::address::Checksum;
+use ethereum::private_key::{PrivateKey as EthPrivateKey, Signature};
+use fxchain::private_key::PrivateKey as FxPrivateKey;
+use fxchain::proto_ext::MessageExt;
+use fxchain::x::gravity::{OutgoingTxBatch, Valset};
+
+use crate::signer::{ConfirmSigner, SignFuture};
+use crate::REMOTE_SIGNER_TIMEOUT;
+
+/// Messages the orchestrator is allowed to sign f(x)Core transactions for
+const ALLOWED_TX_MSG_TYPES: [&str; 6] = [
+    "/fx.gravity.v1.MsgValsetConfirm",
+    "/fx.gravity.v1.MsgConfirmBatch",
+    "/fx.gravity.v1.MsgDepositClaim",
+    "/fx.gravity.v1.MsgWithdrawClaim",
+    "/fx.gravity.v1.MsgFxOriginatedTokenClaim",
+    "/fx.gravity.v1.MsgValsetUpdatedClaim",
+];
+
+/// A request sent to the signer daemon, one JSON object per line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum SignRequest {
+    Keys,
+    /// hex encoded protobuf `SignDoc`
+    SignTx { sign_doc: String },
+    /// hex encoded protobuf `Valset`
+    SignValsetConfirm { gravity_id: String, valset: String },
+    /// hex encoded protobuf `OutgoingTxBatch`
+    SignBatchConfirm { gravity_id: String, batch: String },
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SignResponse {
+    #[serde(default)]
+    pub fx_public_key: String,
+    #[serde(default)]
+    pub eth_address: String,
+    #[serde(default)]
+    pub signature: String,
+    #[serde(default)]
+    pub error: String,
+}
+
+/// Checks enforced by the signer daemon before anything is signed
+#[derive(Debug, Clone, Default)]
+pub struct SigningPolicy {
+    /// Only sign f(x)Core transactions for this chain id
+    pub chain_id: Option<String>,
+    /// Only sign confirms for this gravity id
+    pub gravity_id: Option<String>,
+}
+
+/// Highest nonce of a confirm the signer signed, and the keccak256 of what it signed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignedNonce {
+    pub nonce: u64,
+    pub hash: String,
+}
+
+/// Confirms signed by the daemon, saved after each one so that a restarted signer still refuses
+/// an older nonce or a conflicting confirm of the last one
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SignedNonces {
+    #[serde(default)]
+    pub valset: Option<SignedNonce>,
+    /// by lowercase token contract, batches of each token are confirmed in nonce order
+    #[serde(default)]
+    pub batches: HashMap<String, SignedNonce>,
+}
+
+/* ============================== client ============================== */
+
+/// Orchestrator keys held by a `fxeth signer` daemon listening on a Unix socket
+#[derive(Debug, Clone)]
+pub struct RemoteSigner {
+    socket_path: String,
+    fx_public_key: VerifyingKey,
+    eth_address: EthAddress,
+}
+
+impl RemoteSigner {
+    pub async fn connect(socket_path: &str) -> Result<Self> {
+        let response = send_request(socket_path, &SignRequest::Keys).await?;
+        let fx_public_key = VerifyingKey::from_sec1_bytes(hex::decode(response.fx_public_key)?.as_slice())
+            .map_err(|_| eyre::Error::msg("remote signer returned an invalid fx public key"))?;
+        let eth_address = EthAddress::from_str(response.eth_address.as_str())?;
+        info!("Remote signer {}, eth address {}", socket_path, eth_address.to_hex_string());
+        Ok(RemoteSigner {
+            socket_path: socket_path.to_string(),
+            fx_public_key,
+            eth_address,
+        })
+    }
+
+    async fn sign(&self, request: &SignRequest) -> Result<Vec<u8>> {
+        let response = send_request(self.socket_path.as_str(), request).await?;
+        Ok(hex::decode(response.signature)?)
+    }
+}
+
+impl ConfirmSigner for RemoteSigner {
+    fn address(&self) -> EthAddress {
+        self.eth_address
+    }
+
+    fn sign_valset_confirm<'a>(&'a self, gravity_id: &'a str, valset: &'a Valset) -> SignFuture<'a> {
+        Box::pin(async move {
+            let signature = self
+                .sign(&SignRequest::SignValsetConfirm {
+                    gravity_id: gravity_id.to_string(),
+                    valset: hex::encode(valset.to_bytes()?),
+                })
+                .await?;
+            Ok(Signature::from_bytes(signature.as_slice())?)
+        })
+    }
+
+    fn sign_batch_confirm<'a>(&'a self, gravity_id: &'a str, batch: &'a OutgoingTxBatch) -> SignFuture<'a> {
+        Box::pin(async move {
+            let signature = self
+                .sign(&SignRequest::SignBatchConfirm {
+                    gravity_id: gravity_id.to_string(),
+                    batch: hex::encode(batch.to_bytes()?),
+                })
+                .await?;
+            Ok(Signature::from_bytes(signature.as_slice())?)
+        })
+    }
+}
+
+impl Signer<FxSignature> for RemoteSigner {
+    /// The `Signer` of the f(x)Core tx builder is sync, the worker thread is handed over to the other tasks
+    /// while the daemon answers, which needs the multi-threaded runtime
+    fn try_sign(&self, msg: &[u8]) -> std::result::Result<FxSignature, ecdsa::Error> {
+        let request = SignRequest::SignTx { sign_doc: hex::encode(msg) };
+        let signature = tokio::task::block_in_place(|| Handle::current().block_on(self.sign(&request))).map_err(|report| ecdsa::Error::from_source(report.to_string()))?;
+        FxSignature::try_from(signature.as_slice())
+    }
+}
+
+impl From<&RemoteSigner> for VerifyingKey {
+    fn from(signer: &RemoteSigner) -> Self {
+        signer.fx_public_key
+    }
+}
+
+async fn send_request(socket_path: &str, request: &SignRequest) -> Result<SignResponse> {
+    let exchange = async {
+        let mut stream = UnixStream::connect(socket_path).await?;
+        stream.write_all(format!("{}\n", serde_json::to_string(request)?).as_bytes()).await?;
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line).await?;
+        Ok::<String, eyre::Error>(line)
+    };
+    let line = tokio::time::timeout(REMOTE_SIGNER_TIMEOUT, exchange)
+        .await
+        .map_err(|_| eyre::Error::msg(format!("remote signer {} timeout {:?}", socket_path, REMOTE_SIGNER_TIMEOUT)))??;
+    let response: SignResponse = serde_json::from_str(line.as_str())?;
+    if !response.error.is_empty() {
+        return Err(eyre::Error::msg(format!("remote signer refused: {}", response.error)));
+    }
+    Ok(response)
+}
+
+/* ============================== server ============================== */
+
+/// Holds the orchestrator keys and answers `SignRequest`s on a Unix socket
+pub struct SignerServer {
+    fx_key: FxPrivateKey,
+    eth_key: EthPrivateKey,
+    policy: SigningPolicy,
+    state_path: PathBuf,
+    signed: SignedNonces,
+}
+
+impl SignerServer {
+    /// Signer keeping the confirms it signed in `state_path`, loaded when the file exists
+    pub fn new(fx_key: FxPrivateKey, eth_key: EthPrivateKey, policy: SigningPolicy, state_path: &Path) -> Result<Self> {
+        let signed = match std::fs::read_to_string(state_path) {
+            Ok(state) => serde_json::from_str(state.as_str()).map_err(|err| eyre::Error::msg(format!("invalid signer state {}: {}", state_path.display(), err)))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => SignedNonces::default(),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(SignerServer {
+            fx_key,
+            eth_key,
+            policy,
+            state_path: state_path.to_path_buf(),
+            signed,
+        })
+    }
+
+    /// Serve requests until the listener fails, each connection on its own task sharing the signed nonces
+    pub async fn serve(self, socket_path: &str) -> Result<()> {
+        let listener = bind_socket(socket_path).await?;
+        info!("Signer listening on {}, fx address {}, eth address {}", socket_path, self.fx_key.public_key().to_address(), self.eth_key.address().to_hex_string());
+
+        let server = Arc::new(Mutex::new(self));
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    let server = server.clone();
+                    tokio::spawn(async move {
+                        if let Err(report) = handle_connection(server, stream).await {
+                            warn!("Signer connection failed {:?}", report.root_cause());
+                        }
+                    });
+                }
+                Err(err) => warn!("Signer accept connection failed {:?}", err),
+            }
+        }
+    }
+
+    pub async fn handle(&mut self, request: SignRequest) -> Result<SignResponse> {
+        let signature = match request {
+            SignRequest::Keys => {
+                return Ok(SignResponse {
+                    fx_public_key: hex::encode(self.fx_key.public_key().to_bytes()),
+                    eth_address: self.eth_key.address().to_hex_string(),
+                    ..Default::default()
+                });
+            }
+            SignRequest::SignTx { sign_doc } => {
+                let sign_doc_bytes = hex::decode(sign_doc)?;
+                self.check_sign_doc(&SignDoc::decode(sign_doc_bytes.as_slice())?)?;
+                self.fx_key.sign(sign_doc_bytes.as_slice())?.as_ref().to_vec()
+            }
+            SignRequest::SignValsetConfirm { gravity_id, valset } => {
+                self.check_gravity_id(gravity_id.as_str())?;
+                let valset = Valset::decode(hex::decode(valset)?.as_slice())?;
+                let signed = SignedNonce {
+                    nonce: valset.nonce,
+                    hash: hex::encode(keccak256(valset.to_bytes()?.as_slice())),
+                };
+                check_signed_nonce("valset", self.signed.valset.as_ref(), &signed)?;
+                let signature = self.eth_key.sign_valset_confirm(gravity_id.as_str(), &valset).await?;
+                self.signed.valset = Some(signed);
+                self.save_signed()?;
+                info!("Signer signed valset confirm nonce {}", valset.nonce);
+                signature.to_bytes().to_vec()
+            }
+            SignRequest::SignBatchConfirm { gravity_id, batch } => {
+                self.check_gravity_id(gravity_id.as_str())?;
+                let batch = OutgoingTxBatch::decode(hex::decode(batch)?.as_slice())?;
+                let token_contract = batch.token_contract.to_lowercase();
+                let signed = SignedNonce {
+                    nonce: batch.batch_nonce,
+                    hash: hex::encode(keccak256(batch.to_bytes()?.as_slice())),
+                };
+                check_signed_nonce(format!("{} batch", batch.token_contract).as_str(), self.signed.batches.get(&token_contract), &signed)?;
+                let signature = self.eth_key.sign_batch_confirm(gravity_id.as_str(), &batch).await?;
+                self.signed.batches.insert(token_contract, signed);
+                self.save_signed()?;
+                info!("Signer signed batch confirm {} nonce {}", batch.token_contract, batch.batch_nonce);
+                signature.to_bytes().to_vec()
+            }
+        };
+        Ok(SignResponse {
+            signature: hex::encode(signature),
+            ..Default::default()
+        })
+    }
+
+    /// Write the signed confirms to a temporary file renamed over the state, a crash never leaves half of it
+    fn save_signed(&self) -> Result<()> {
+        let tmp_path = self.state_path.with_extension("tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(&self.signed)?)?;
+        std::fs::rename(&tmp_path, &self.state_path)?;
+        Ok(())
+    }
+
+    fn check_gravity_id(&self, gravity_id: &str) -> Result<()> {
+        match &self.policy.gravity_id {
+            Some(expect) if expect != gravity_id => Err(eyre::Error::msg(format!("gravity id {} is not allowed, expect {}", gravity_id, expect))),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_sign_doc(&self, sign_doc: &SignDoc) -> Result<()> {
+        if let Some(chain_id) = &self.policy.chain_id {
+            if &sign_doc.chain_id != chain_id {
+                return Err(eyre::Error::msg(format!("chain id {} is not allowed, expect {}", sign_doc.chain_id, chain_id)));
+            }
+        }
+        let body = TxBody::decode(sign_doc.body_bytes.as_slice())?;
+        for message in body.messages.iter() {
+            if !ALLOWED_TX_MSG_TYPES.contains(&message.type_url.as_str()) {
+                return Err(eyre::Error::msg(format!("message type {} is not allowed", message.type_url)));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Answer the requests of one connection, closing it when a request does not arrive in time
+async fn handle_connection(server: Arc<Mutex<SignerServer>>, stream: UnixStream) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    loop {
+        let line = match tokio::time::timeout(REMOTE_SIGNER_TIMEOUT, lines.next_line()).await {
+            Ok(line) => match line? {
+                Some(line) => line,
+                None => return Ok(()),
+            },
+            Err(_) => return Err(eyre::Error::msg(format!("no request in {:?}, connection closed", REMOTE_SIGNER_TIMEOUT))),
+        };
+        let response = match serde_json::from_str::<SignRequest>(line.as_str()) {
+            Ok(request) => server.lock().await.handle(request).await.unwrap_or_else(|report| {
+                warn!("Signer refused request: {}", report);
+                SignResponse {
+                    error: report.to_string(),
+                    ..Default::default()
+                }
+            }),
+            Err(err) => SignResponse {
+                error: format!("invalid request: {}", err),
+                ..Default::default()
+            },
+        };
+        let response = format!("{}\n", serde_json::to_string(&response)?);
+        tokio::time::timeout(REMOTE_SIGNER_TIMEOUT, writer.write_all(response.as_bytes()))
+            .await
+            .map_err(|_| eyre::Error::msg(format!("response not written in {:?}, connection closed", REMOTE_SIGNER_TIMEOUT)))??;
+    }
+}
+
+/// Bind the socket in a 0700 directory and move it into place once it is 0600, so no other user can connect in between.
+/// An existing socket is only replaced when no signer answers on it
+async fn bind_socket(socket_path: &str) -> Result<UnixListener> {
+    let path = Path::new(socket_path);
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            return Err(eyre::Error::msg(format!("{} exists and is not a socket", socket_path)));
+        }
+        if UnixStream::connect(path).await.is_ok() {
+            return Err(eyre::Error::msg(format!("a signer is already listening on {}", socket_path)));
+        }
+        info!("Removing stale signer socket {}", socket_path);
+        std::fs::remove_file(path)?;
+    }
+
+    let bind_dir = PathBuf::from(format!("{}.bind-{}", socket_path, std::process::id()));
+    DirBuilder::new().mode(0o700).create(&bind_dir)?;
+    let bind = async {
+        let bind_path = bind_dir.join("signer.sock");
+        let listener = UnixListener::bind(&bind_path)?;
+        std::fs::set_permissions(&bind_path, Permissions::from_mode(0o600))?;
+        std::fs::rename(&bind_path, path)?;
+        Ok::<UnixListener, eyre::Error>(listener)
+    };
+    let listener = bind.await;
+    let _ = std::fs::remove_dir_all(&bind_dir);
+    listener
+}
+
+/// Refuse a confirm below the highest nonce signed, or a different one at that nonce
+fn check_signed_nonce(what: &str, last: Option<&SignedNonce>, signed: &SignedNonce) -> Result<()> {
+    match last {
+        Some(last) if signed.nonce < last.nonce => Err(eyre::Error::msg(format!("{} nonce {} is below the last signed nonce {}", what, signed.nonce, last.nonce))),
+        Some(last) if signed.nonce == last.nonce && signed.hash != last.hash => Err(eyre::Error::msg(format!("conflicting {} already signed for nonce {}", what, signed.nonce))),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time::Duration;
+
+    use cosmos_sdk_proto::cosmos::bank::v1beta1::MsgSend;
+    use fxchain::x::gravity::{BridgeValidator, MsgValsetConfirm};
+
+    use super::*;
+
+    const FX_PRIVATE_KEY: &str = "7490923dfece4901e603a1a0429ad74327ca574d7033bba145b68dcd00aa7a5d";
+    const ETH_PRIVATE_KEY: &str = "0x7490923dfece4901e603a1a0429ad74327ca574d7033bba145b68dcd00aa7a5d";
+    const GRAVITY_ID: &str = "fx-bridge-eth";
+    const CHAIN_ID: &str = "fxcore";
+
+    fn state_path(name: &str) -> PathBuf {
+        let state_path = std::env::temp_dir().join(format!("fxeth-signer-{}-{}.json", name, std::process::id()));
+        let _ = std::fs::remove_file(&state_path);
+        state_path
+    }
+
+    /// Signer daemon on its own thread and runtime, as `fxeth signer` runs it
+    fn start_signer(name: &str, state_path: &Path) -> String {
+        let socket_path = std::env::temp_dir().join(format!("fxeth-signer-{}-{}.sock", name, std::process::id()));
+        let socket_path = socket_path.to_str().unwrap().to_string();
+        let _ = std::fs::remove_file(socket_path.as_str());
+        let path = socket_path.clone();
+        let state_path = state_path.to_path_buf();
+        thread::spawn(move || {
+            let fx_key = FxPrivateKey::from_str(FX_PRIVATE_KEY).unwrap();
+            let eth_key = EthPrivateKey::from_str(ETH_PRIVATE_KEY).unwrap();
+            let policy = SigningPolicy {
+                chain_id: Some(CHAIN_ID.to_string()),
+                gravity_id: Some(GRAVITY_ID.to_string()),
+            };
+            let server = SignerServer::new(fx_key, eth_key, policy, state_path.as_path()).unwrap();
+            let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+            runtime.block_on(server.serve(path.as_str())).unwrap();
+        });
+        for _ in 0..50 {
+            if Path::new(socket_path.as_str()).exists() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        socket_path
+    }
+
+    fn valset(nonce: u64, power: u64) -> Valset {
+        Valset {
+            nonce,
+            members: vec![BridgeValidator {
+                power,
+                eth_address: "0xb4fA5979babd8Bb7e427157d0d353Cf205F43752".to_string(),
+            }],
+            height: 100,
+        }
+    }
+
+    fn sign_doc(type_url: &str, chain_id: &str) -> Vec<u8> {
+        let msg = MsgValsetConfirm::default();
+        let body = TxBody {
+            messages: vec![msg.to_any(type_url)],
+            ..Default::default()
+        };
+        SignDoc {
+            body_bytes: body.to_bytes().unwrap(),
+            auth_info_bytes: vec![],
+            chain_id: chain_id.to_string(),
+            account_number: 1,
+        }
+        .to_bytes()
+        .unwrap()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_remote_signer_keys() {
+        let socket_path = start_signer("keys", state_path("keys").as_path());
+        let remote_signer = RemoteSigner::connect(socket_path.as_str()).await.unwrap();
+
+        let eth_key = EthPrivateKey::from_str(ETH_PRIVATE_KEY).unwrap();
+        assert_eq!(ConfirmSigner::address(&remote_signer), ConfirmSigner::address(&eth_key));
+
+        let fx_key = FxPrivateKey::from_str(FX_PRIVATE_KEY).unwrap();
+        let remote_fx_key = FxPrivateKey::from(Box::new(remote_signer) as Box<dyn fxchain::private_key::Secp256k1Signer>);
+        assert_eq!(remote_fx_key.public_key(), fx_key.public_key());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_remote_signer_socket() {
+        let socket_path = start_signer("socket", state_path("socket").as_path());
+        let mode = std::fs::metadata(socket_path.as_str()).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        // an idle connection does not hold up the others
+        let _idle = UnixStream::connect(socket_path.as_str()).await.unwrap();
+        RemoteSigner::connect(socket_path.as_str()).await.unwrap();
+
+        let report = bind_socket(socket_path.as_str()).await.err().unwrap();
+        assert!(report.to_string().contains("already listening"));
+
+        let stale_path = std::env::temp_dir().join(format!("fxeth-signer-stale-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&stale_path);
+        drop(std::os::unix::net::UnixListener::bind(&stale_path).unwrap());
+        bind_socket(stale_path.to_str().unwrap()).await.unwrap();
+        std::fs::remove_file(&stale_path).unwrap();
+
+        std::fs::write(&stale_path, "not a socket").unwrap();
+        let report = bind_socket(stale_path.to_str().unwrap()).await.err().unwrap();
+        assert!(report.to_string().contains("is not a socket"));
+        std::fs::remove_file(&stale_path).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_remote_signer_confirm() {
+        let state_path = state_path("confirm");
+        let socket_path = start_signer("confirm", state_path.as_path());
+        let remote_signer = RemoteSigner::connect(socket_path.as_str()).await.unwrap();
+        let eth_key = EthPrivateKey::from_str(ETH_PRIVATE_KEY).unwrap();
+
+        let valset = valset(2, 10000);
+        let remote = remote_signer.sign_valset_confirm(GRAVITY_ID, &valset).await.unwrap();
+        let local = eth_key.sign_valset_confirm(GRAVITY_ID, &valset).await.unwrap();
+        assert_eq!(remote.to_bytes().to_vec(), local.to_bytes().to_vec());
+
+        // signing the same valset again is allowed, a different valset with the same nonce or an older one is not
+        assert!(remote_signer.sign_valset_confirm(GRAVITY_ID, &valset).await.is_ok());
+        assert!(remote_signer.sign_valset_confirm(GRAVITY_ID, &self::valset(2, 20000)).await.is_err());
+        assert!(remote_signer.sign_valset_confirm(GRAVITY_ID, &self::valset(1, 10000)).await.is_err());
+
+        assert!(remote_signer.sign_valset_confirm("other-gravity-id", &self::valset(3, 10000)).await.is_err());
+
+        // a restarted signer remembers the last valset it signed
+        let socket_path = start_signer("confirm-restarted", state_path.as_path());
+        let remote_signer = RemoteSigner::connect(socket_path.as_str()).await.unwrap();
+        assert!(remote_signer.sign_valset_confirm(GRAVITY_ID, &self::valset(2, 20000)).await.is_err());
+        assert!(remote_signer.sign_valset_confirm(GRAVITY_ID, &valset).await.is_ok());
+        assert!(remote_signer.sign_valset_confirm(GRAVITY_ID, &self::valset(3, 10000)).await.is_ok());
+        let signed: SignedNonces = serde_json::from_str(std::fs::read_to_string(&state_path).unwrap().as_str()).unwrap();
+        assert_eq!(signed.valset.map(|signed| signed.nonce), Some(3));
+        std::fs::remove_file(state_path).unwrap();
+    }
+
+    #[test]
+    fn test_check_signed_nonce() {
+        let signed = |nonce: u64, hash: &str| SignedNonce { nonce, hash: hash.to_string() };
+        assert!(check_signed_nonce("batch", None, &signed(1, "a")).is_ok());
+        assert!(check_signed_nonce("batch", Some(&signed(5, "a")), &signed(5, "a")).is_ok());
+        assert!(check_signed_nonce("batch", Some(&signed(5, "a")), &signed(6, "b")).is_ok());
+        assert!(check_signed_nonce("batch", Some(&signed(5, "a")), &signed(5, "b")).is_err());
+        assert!(check_signed_nonce("batch", Some(&signed(5, "a")), &signed(4, "a")).is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_remote_signer_sign_tx() {
+        let socket_path = start_signer("tx", state_path("tx").as_path());
+        let remote_signer = RemoteSigner::connect(socket_path.as_str()).await.unwrap();
+        let fx_key = FxPrivateKey::from_str(FX_PRIVATE_KEY).unwrap();
+
+        let message = sign_doc("/fx.gravity.v1.MsgValsetConfirm", CHAIN_ID);
+        let remote = remote_signer.try_sign(message.as_slice()).unwrap();
+        let local = fx_key.sign(message.as_slice()).unwrap();
+        assert_eq!(remote, local);
+
+        assert!(remote_signer.try_sign(sign_doc("/fx.gravity.v1.MsgValsetConfirm", "other-chain").as_slice()).is_err());
+
+        let send = MsgSend::default().to_any("/cosmos.bank.v1beta1.MsgSend");
+        let body = TxBody {
+            messages: vec![send],
+            ..Default::default()
+        };
+        let message = SignDoc {
+            body_bytes: body.to_bytes().unwrap(),
+            auth_info_bytes: vec![],
+            chain_id: CHAIN_ID.to_string(),
+            account_number: 1,
+        }
+        .to_bytes()
+        .unwrap();
+        assert!(remote_signer.try_sign(message.as_slice()).is_err());
+    }
+}
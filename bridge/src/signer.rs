@@ -0,0 +1,39 @@
+use std::future::{self, Future};
+use std::pin::Pin;
+
+use eyre::Result;
+use web3::types::Address as EthAddress;
+
+use ethereum::private_key::{Key, PrivateKey as EthPrivateKey, Signature};
+use fxchain::x::gravity::{OutgoingTxBatch, Valset};
+
+use crate::singer_loop::{encode_tx_batch_confirm_hash, encode_valset_confirm_hash};
+
+/// Confirm signature of a `ConfirmSigner`, a remote signer answers over a socket
+pub type SignFuture<'a> = Pin<Box<dyn Future<Output = Result<Signature>> + Send + 'a>>;
+
+/// Signs valset and batch confirms with the orchestrator Ethereum key
+pub trait ConfirmSigner {
+    /// Ethereum address of the orchestrator key
+    fn address(&self) -> EthAddress;
+
+    fn sign_valset_confirm<'a>(&'a self, gravity_id: &'a str, valset: &'a Valset) -> SignFuture<'a>;
+
+    fn sign_batch_confirm<'a>(&'a self, gravity_id: &'a str, batch: &'a OutgoingTxBatch) -> SignFuture<'a>;
+}
+
+impl ConfirmSigner for EthPrivateKey {
+    fn address(&self) -> EthAddress {
+        Key::address(self)
+    }
+
+    fn sign_valset_confirm<'a>(&'a self, gravity_id: &'a str, valset: &'a Valset) -> SignFuture<'a> {
+        let message = encode_valset_confirm_hash(gravity_id.to_string(), valset);
+        Box::pin(future::ready(self.sign_ethereum_msg(message.as_slice()).map_err(eyre::Error::from)))
+    }
+
+    fn sign_batch_confirm<'a>(&'a self, gravity_id: &'a str, batch: &'a OutgoingTxBatch) -> SignFuture<'a> {
+        let message = encode_tx_batch_confirm_hash(gravity_id.to_string(), batch.clone());
+        Box::pin(future::ready(self.sign_ethereum_msg(message.as_slice()).map_err(eyre::Error::from)))
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::ops::Div;
 use std::str::FromStr;
 
@@ -6,47 +7,97 @@ use eyre::Result;
 use tokio::time::sleep;
 use tonic::transport::Channel;
 use web3::ethabi::{FixedBytes, Token, Uint};
+use web3::signing::keccak256;
 use web3::types::{Address as EthAddress, U256};
 
 use ethereum::address::Checksum;
-use ethereum::private_key::{Key, PrivateKey as EthPrivateKey, PrivateKey};
+use ethereum::keystore::KeystoreDir;
+use ethereum::private_key::{PrivateKey as EthPrivateKey, Signature};
 use fxchain::builder::Builder;
-use fxchain::grpc_client::get_orchestrator_validator_status;
+use fxchain::grpc_client::{get_batch_confirms, get_orchestrator_validator_status, get_valset_confirms};
 use fxchain::proto_ext::MessageExt;
 use fxchain::x::gravity::query_client::QueryClient as GravityQueryClient;
 use fxchain::x::gravity::QueryLastPendingBatchRequestByAddrRequest;
 use fxchain::x::gravity::QueryLastPendingValsetRequestByAddrRequest;
 use fxchain::x::gravity::{MsgConfirmBatch, MsgValsetConfirm, OutgoingTxBatch, Valset};
 
+use crate::audit::{AuditLog, AuditRecord};
+use crate::signer::ConfirmSigner;
 use crate::FX_AVG_BLOCK_TIME;
 
-pub async fn eth_signer_main_loop(fx_builder: &Builder, grpc_channel: &Channel, eth_private_key: &EthPrivateKey) {
+/// Key loaded from the keystore dir after the registered eth address changed
+#[derive(Default)]
+struct HotLoadedKey {
+    key: Option<EthPrivateKey>,
+    /// registered address the keystore dir had no usable key for, not searched again until the address changes
+    missing: Option<EthAddress>,
+}
+
+/// Valsets and (token contract, batch) nonces signed in a dry run, their confirms never land on chain
+#[derive(Debug, Default)]
+struct DryRunSigned {
+    valsets: HashSet<u64>,
+    batches: HashSet<(String, u64)>,
+}
+
+pub async fn eth_signer_main_loop(fx_builder: &Builder, grpc_channel: &Channel, eth_signer: &dyn ConfirmSigner, eth_keystore: Option<&KeystoreDir>, audit_log: Option<&AuditLog>) {
     let gravity_id = fxchain::grpc_client::get_gravity_id(grpc_channel).await.unwrap();
+    let mut hot_loaded_key = HotLoadedKey::default();
+    let mut dry_run_signed = DryRunSigned::default();
+    let orchestrator = fx_builder.address().to_string();
 
     loop {
         sleep(FX_AVG_BLOCK_TIME).await;
 
-        let result = get_orchestrator_validator_status(&grpc_channel, fx_builder.address()).await;
-        if result.is_err() {
-            warn!("Get orchestrator status failed {:?}", result.unwrap_err().root_cause());
-            continue;
-        }
-        let (status, eth_address_str) = result.unwrap();
-        if status != BondStatus::Bonded as i32 {
-            warn!("Get orchestrator status is not 'Bonded', {:?}", status);
-            continue;
-        }
-        if eth_address_str != eth_private_key.address().to_hex_string() {
-            panic!("invalid eth private key, expect {}", eth_address_str)
-        }
+        let eth_address_str = match get_orchestrator_validator_status(&grpc_channel, fx_builder.address()).await {
+            Ok((status, _)) if status != BondStatus::Bonded as i32 && !fx_builder.is_dry_run() => {
+                warn!("Get orchestrator status is not 'Bonded', {:?}", status);
+                continue;
+            }
+            Ok((_, eth_address_str)) => Some(eth_address_str),
+            // a dry run observes as an orchestrator that is not registered yet
+            Err(report) if fx_builder.is_dry_run() => {
+                debug!("Dry run as an unregistered orchestrator, {:?}", report.root_cause());
+                None
+            }
+            Err(report) => {
+                warn!("Get orchestrator status failed {:?}", report.root_cause());
+                continue;
+            }
+        };
+        let eth_signer = match eth_address_str {
+            Some(eth_address_str) => {
+                let registered_address = match EthAddress::from_str(eth_address_str.as_str()) {
+                    Ok(address) => address,
+                    Err(_) => {
+                        error!("Invalid registered eth address {}", eth_address_str);
+                        continue;
+                    }
+                };
+                match active_eth_signer(eth_signer, &mut hot_loaded_key, eth_keystore, registered_address).await {
+                    Some(eth_signer) => eth_signer,
+                    None => {
+                        error!(
+                            "Eth key mismatch, registered eth address {}, orchestrator eth key {}, pause signing",
+                            eth_address_str,
+                            eth_signer.address().to_hex_string()
+                        );
+                        prometheus::metrics::ETH_KEY_MISMATCH.with_label_values(&[orchestrator.as_str()]).set(1.0);
+                        continue;
+                    }
+                }
+            }
+            None => eth_signer,
+        };
+        prometheus::metrics::ETH_KEY_MISMATCH.with_label_values(&[orchestrator.as_str()]).set(0.0);
 
-        let result = singer_last_pending_valset_request(fx_builder, grpc_channel, eth_private_key, &gravity_id).await;
+        let result = singer_last_pending_valset_request(fx_builder, grpc_channel, eth_signer, &gravity_id, audit_log, &mut dry_run_signed).await;
         if result.is_err() {
             error!("singer last pending valset request error: {:?}", result.unwrap_err().root_cause());
             continue;
         }
 
-        let result = singer_last_pending_batch_request(fx_builder, grpc_channel, eth_private_key, &gravity_id).await;
+        let result = singer_last_pending_batch_request(fx_builder, grpc_channel, eth_signer, &gravity_id, audit_log, &mut dry_run_signed).await;
         if result.is_err() {
             error!("singer last pending batch request error: {:?}", result.unwrap_err().root_cause());
             continue;
@@ -56,30 +107,76 @@ pub async fn eth_signer_main_loop(fx_builder: &Builder, grpc_channel: &Channel,
     }
 }
 
+/// Picks the signer matching the registered eth address, hot-loading it from the keystore dir if needed.
+/// A failed load is not retried until the registered address changes, decrypting runs on the blocking pool
+async fn active_eth_signer<'a>(
+    eth_signer: &'a dyn ConfirmSigner,
+    hot_loaded_key: &'a mut HotLoadedKey,
+    eth_keystore: Option<&KeystoreDir>,
+    registered_address: EthAddress,
+) -> Option<&'a dyn ConfirmSigner> {
+    if eth_signer.address() == registered_address {
+        hot_loaded_key.missing = None;
+        return Some(eth_signer);
+    }
+    let hot_loaded = hot_loaded_key.key.as_ref().map(|key| ConfirmSigner::address(key) == registered_address).unwrap_or(false);
+    if !hot_loaded {
+        hot_loaded_key.key = None;
+        let eth_keystore = match eth_keystore {
+            Some(eth_keystore) if hot_loaded_key.missing != Some(registered_address) => eth_keystore.clone(),
+            _ => return None,
+        };
+        let loaded = tokio::task::spawn_blocking(move || eth_keystore.load(registered_address)).await;
+        match loaded.map_err(eyre::Error::from).and_then(|loaded| loaded) {
+            Ok(Some(key)) => {
+                info!("Hot-loaded eth key {} from keystore dir", registered_address.to_hex_string());
+                hot_loaded_key.key = Some(key);
+            }
+            Ok(None) => warn!("No keystore for eth address {} in keystore dir", registered_address.to_hex_string()),
+            Err(report) => error!("Load eth keystore failed {:?}", report.root_cause()),
+        }
+        hot_loaded_key.missing = match hot_loaded_key.key {
+            Some(_) => None,
+            None => Some(registered_address),
+        };
+    }
+    hot_loaded_key.key.as_ref().map(|key| key as &dyn ConfirmSigner)
+}
+
 pub async fn set_fx_key_balance_metrics(fx_builder: &Builder, grpc_channel: &Channel) {
     let result = fxchain::grpc_client::get_balance(grpc_channel, fx_builder.address(), fx_builder.get_fee_denom()).await;
     match result {
         Ok(balance) => {
             let amount = U256::from_dec_str(balance.amount.as_str()).unwrap().div(U256::from(10).pow(U256::from(18)));
-            prometheus::metrics::FX_KEY_BALANCE.set(amount.as_u64() as f64)
+            prometheus::metrics::FX_KEY_BALANCE
+                .with_label_values(&[fx_builder.address().to_string().as_str()])
+                .set(amount.as_u64() as f64)
         }
         Err(report) => error!("Query fx account {} balance failed {}", fx_builder.address(), report.root_cause()),
     }
 }
 
-async fn singer_last_pending_valset_request(fx_builder: &Builder, grpc_channel: &Channel, eth_private_key: &PrivateKey, gravity_id: &String) -> Result<()> {
+async fn singer_last_pending_valset_request(
+    fx_builder: &Builder,
+    grpc_channel: &Channel,
+    eth_signer: &dyn ConfirmSigner,
+    gravity_id: &String,
+    audit_log: Option<&AuditLog>,
+    dry_run_signed: &mut DryRunSigned,
+) -> Result<()> {
     let mut gravity_query_client = GravityQueryClient::new(grpc_channel.clone());
     let response = gravity_query_client
         .last_pending_valset_request_by_addr(QueryLastPendingValsetRequestByAddrRequest {
             address: fx_builder.address().to_string(),
         })
         .await?;
-    let valsets = response.into_inner().valsets;
+    let mut valsets = response.into_inner().valsets;
+    valsets.retain(|valset| !dry_run_signed.valsets.contains(&valset.nonce));
     if valsets.is_empty() {
         debug!("No validator sets to sign, node is caught up!");
         return Ok(());
     }
-    let eth_address = eth_private_key.address().to_hex_string();
+    let eth_address = eth_signer.address().to_hex_string();
     info!(
         "Sending {} valset confirms starting with nonce {}, valset update singer {}",
         valsets.len(),
@@ -88,30 +185,69 @@ async fn singer_last_pending_valset_request(fx_builder: &Builder, grpc_channel:
     );
 
     let mut messages = Vec::new();
+    let mut audit_records = Vec::new();
     for valset in valsets.iter() {
         info!("Submitting signature for valset {}, {}", valset.nonce, valset.height);
-        let message = encode_valset_confirm_hash(gravity_id.clone(), &valset);
-        let eth_signature = eth_private_key.sign_ethereum_msg(message.as_slice()).unwrap();
+        let eth_signature = eth_signer.sign_valset_confirm(gravity_id, &valset).await?;
         let confirm = MsgValsetConfirm {
             orchestrator: fx_builder.address().to_string(),
-            eth_address: eth_private_key.address().to_hex_string(),
+            eth_address: eth_address.clone(),
             nonce: valset.nonce,
             signature: format!("{:x}", eth_signature.to_hash()),
         };
+        audit_records.push(AuditRecord {
+            kind: "valset_confirm".to_string(),
+            gravity_id: gravity_id.clone(),
+            orchestrator: fx_builder.address().to_string(),
+            nonce: valset.nonce,
+            checkpoint: hex::encode(keccak256(encode_valset_confirm_hash(gravity_id.clone(), valset).as_slice())),
+            signature: confirm.signature.clone(),
+            dry_run: fx_builder.is_dry_run(),
+            ..Default::default()
+        });
         let msg = confirm.to_any("/fx.gravity.v1.MsgValsetConfirm");
         messages.push(msg);
     }
 
-    let tx_resp = fxchain::grpc_client::send_tx(fx_builder, grpc_channel, messages).await?;
+    if fx_builder.is_dry_run() {
+        for valset in valsets.iter() {
+            let confirms = get_valset_confirms(grpc_channel, valset.nonce).await?;
+            let confirms: Vec<(String, String)> = confirms.into_iter().map(|confirm| (confirm.eth_address, confirm.signature)).collect();
+            let matched = matching_confirms(encode_valset_confirm_hash(gravity_id.clone(), valset).as_slice(), &confirms);
+            info!("Dry run valset {} checkpoint matches {} of {} confirms on chain", valset.nonce, matched, confirms.len());
+        }
+        fxchain::grpc_client::dry_run_tx(fx_builder, grpc_channel, messages).await?;
+        dry_run_signed.valsets.extend(valsets.iter().map(|valset| valset.nonce));
+        if let Some(audit_log) = audit_log {
+            audit_records.iter().for_each(|record| audit_log.record(record.signed()));
+        }
+        return Ok(());
+    }
+
+    if let Some(audit_log) = audit_log {
+        audit_records.iter().for_each(|record| audit_log.record(record.signed()));
+    }
+    let result = fxchain::grpc_client::send_tx(fx_builder, grpc_channel, messages).await;
+    if let Some(audit_log) = audit_log {
+        audit_records.iter().for_each(|record| audit_log.record(record.broadcast(&result)));
+    }
+    let tx_resp = result?;
     info!("Valset confirm tx response code {}, tx hash {}", tx_resp.code, tx_resp.txhash);
     if tx_resp.code != 0 {
         error!("Send valset confirm tx failed: {:?}", tx_resp.raw_log);
     }
-    prometheus::metrics::UPDATE_VALSET_SIGN.inc();
+    prometheus::metrics::UPDATE_VALSET_SIGN.with_label_values(&[fx_builder.address().to_string().as_str()]).inc();
     return Ok(());
 }
 
-async fn singer_last_pending_batch_request(fx_builder: &Builder, grpc_channel: &Channel, eth_private_key: &PrivateKey, gravity_id: &String) -> Result<()> {
+async fn singer_last_pending_batch_request(
+    fx_builder: &Builder,
+    grpc_channel: &Channel,
+    eth_signer: &dyn ConfirmSigner,
+    gravity_id: &String,
+    audit_log: Option<&AuditLog>,
+    dry_run_signed: &mut DryRunSigned,
+) -> Result<()> {
     let mut gravity_query_client = GravityQueryClient::new(grpc_channel.clone());
     let response = gravity_query_client
         .last_pending_batch_request_by_addr(QueryLastPendingBatchRequestByAddrRequest {
@@ -123,33 +259,89 @@ async fn singer_last_pending_batch_request(fx_builder: &Builder, grpc_channel: &
         return Ok(());
     }
     let unsigned_batch = outgoing_tx_batch_opt.unwrap();
+    let batch_key = (unsigned_batch.token_contract.clone(), unsigned_batch.batch_nonce);
+    if dry_run_signed.batches.contains(&batch_key) {
+        debug!("Dry run batch {} of {} already signed", unsigned_batch.batch_nonce, unsigned_batch.token_contract);
+        return Ok(());
+    }
     info!(
         "Sending batch confirm for {} nonce {} with block {} in fees",
         unsigned_batch.token_contract, unsigned_batch.batch_nonce, unsigned_batch.block
     );
 
-    let message = encode_tx_batch_confirm_hash(gravity_id.clone(), unsigned_batch.clone());
-    let eth_signature = eth_private_key.sign_ethereum_msg(message.as_slice()).unwrap();
-    info!("Sending batch update with address {}", eth_private_key.address().to_hex_string());
+    let eth_signature = eth_signer.sign_batch_confirm(gravity_id, &unsigned_batch).await?;
+    info!("Sending batch update with address {}", eth_signer.address().to_hex_string());
 
+    let batch_message = encode_tx_batch_confirm_hash(gravity_id.clone(), unsigned_batch.clone());
+    let checkpoint = keccak256(batch_message.as_slice());
     let confirm = MsgConfirmBatch {
         token_contract: unsigned_batch.token_contract,
         orchestrator: fx_builder.address().to_string(),
-        eth_signer: eth_private_key.address().to_hex_string(),
+        eth_signer: eth_signer.address().to_hex_string(),
         nonce: unsigned_batch.batch_nonce,
         signature: format!("{:x}", eth_signature.to_hash()),
     };
     let msg = confirm.to_any("/fx.gravity.v1.MsgConfirmBatch");
 
-    let tx_resp = fxchain::grpc_client::send_tx(fx_builder, grpc_channel, vec![msg]).await?;
+    let audit_record = AuditRecord {
+        kind: "batch_confirm".to_string(),
+        gravity_id: gravity_id.clone(),
+        orchestrator: fx_builder.address().to_string(),
+        nonce: confirm.nonce,
+        checkpoint: hex::encode(checkpoint),
+        signature: confirm.signature.clone(),
+        dry_run: fx_builder.is_dry_run(),
+        ..Default::default()
+    };
+
+    if fx_builder.is_dry_run() {
+        let confirms = get_batch_confirms(grpc_channel, confirm.token_contract.clone(), confirm.nonce).await?;
+        let confirms: Vec<(String, String)> = confirms.into_iter().map(|confirm| (confirm.eth_signer, confirm.signature)).collect();
+        let matched = matching_confirms(batch_message.as_slice(), &confirms);
+        info!("Dry run batch {} checkpoint matches {} of {} confirms on chain", confirm.nonce, matched, confirms.len());
+        fxchain::grpc_client::dry_run_tx(fx_builder, grpc_channel, vec![msg]).await?;
+        dry_run_signed.batches.insert(batch_key);
+        if let Some(audit_log) = audit_log {
+            audit_log.record(audit_record.signed());
+        }
+        return Ok(());
+    }
+
+    if let Some(audit_log) = audit_log {
+        audit_log.record(audit_record.signed());
+    }
+    let result = fxchain::grpc_client::send_tx(fx_builder, grpc_channel, vec![msg]).await;
+    if let Some(audit_log) = audit_log {
+        audit_log.record(audit_record.broadcast(&result));
+    }
+    let tx_resp = result?;
     info!("batch confirm tx response {}, {}", tx_resp.code, tx_resp.txhash);
     if tx_resp.code != 0 {
         error!("Send batch confirm tx failed: {:?}", tx_resp.raw_log);
     }
-    prometheus::metrics::SUBMIT_BATCH_SIGN.inc();
+    prometheus::metrics::SUBMIT_BATCH_SIGN.with_label_values(&[fx_builder.address().to_string().as_str()]).inc();
     Ok(())
 }
 
+/// Number of `(eth_address, signature)` confirms whose signature over `message` recovers to their eth address,
+/// checks the checkpoint signed in a dry run against the confirms of the other orchestrators
+fn matching_confirms(message: &[u8], confirms: &[(String, String)]) -> usize {
+    confirms
+        .iter()
+        .filter(|(eth_address, signature)| {
+            let eth_address = match EthAddress::from_str(eth_address.as_str()) {
+                Ok(eth_address) => eth_address,
+                Err(_) => return false,
+            };
+            let signature = match hex::decode(signature.trim_start_matches("0x")).ok().and_then(|bytes| Signature::from_bytes(&bytes).ok()) {
+                Some(signature) => signature,
+                None => return false,
+            };
+            signature.recover_ethereum_msg(message).map(|signer| signer == eth_address).unwrap_or(false)
+        })
+        .count()
+}
+
 pub fn encode_valset_confirm_hash(gravity_id: String, valset: &Valset) -> Vec<u8> {
     let mut powers = Vec::new();
     let mut addresses = Vec::new();
@@ -190,108 +382,16 @@ pub fn encode_tx_batch_confirm_hash(gravity_id: String, batch: OutgoingTxBatch)
 
 #[cfg(test)]
 mod tests {
-    use log::LevelFilter::{Debug, Info};
+    use revm::db::{CacheDB, EmptyDB};
+    use revm::primitives::{AccountInfo, Bytecode, ExecutionResult, Output, TransactTo};
+    use revm::Evm;
     use web3::types::{H256, U256};
 
-    use ethereum::private_key::{ethereum_msg_hash, Signature};
-    use fxchain::grpc_client::{get_account_info, get_chain_id, new_grpc_channel};
-    use fxchain::private_key::PrivateKey as FxPrivateKey;
-    use fxchain::x::gravity::{QueryBatchRequestByNonceRequest, QueryParamsRequest};
+    use ethereum::private_key::{ethereum_msg_hash, PrivateKey as EthPrivateKey, Signature};
+    use fxchain::x::gravity::{BridgeValidator, Erc20Token, OutgoingTransferTx};
 
     use super::*;
 
-    const ETH_PRIVATE_KEY: &str = "";
-    const FX_GRPC_URL: &str = "http://127.0.0.1:9090";
-    const FX_MNEMONIC: &str = "";
-
-    #[tokio::test]
-    async fn test_valset_confirm_signature() {
-        env_logger::builder().filter_level(Info).init();
-
-        let fx_private_key = FxPrivateKey::from_phrase(FX_MNEMONIC, "").unwrap();
-        let fx_address = fx_private_key.public_key().to_address().to_string();
-        let grpc_channel = new_grpc_channel(FX_GRPC_URL).await.unwrap();
-        let auth_account = get_account_info(&grpc_channel, fx_address.clone()).await.unwrap();
-        let fx_chain_id = get_chain_id(&grpc_channel).await.unwrap();
-        let fx_builder = Builder::new(fx_chain_id, fx_private_key, auth_account.account_number, "FX");
-
-        let eth_private_key = EthPrivateKey::from_str(ETH_PRIVATE_KEY).unwrap();
-
-        let grpc_channel = new_grpc_channel(FX_GRPC_URL).await.unwrap();
-
-        let mut gravity_query_client = GravityQueryClient::new(grpc_channel.clone());
-        let response = gravity_query_client.params(QueryParamsRequest {}).await.unwrap();
-
-        let gravity_id = response.into_inner().params.unwrap().gravity_id;
-        println!("gravity_id {}", gravity_id);
-
-        let response = gravity_query_client
-            .last_pending_valset_request_by_addr(QueryLastPendingValsetRequestByAddrRequest { address: fx_address })
-            .await
-            .unwrap();
-
-        for valset in response.into_inner().valsets.iter() {
-            // println!("{:?}", valset);
-            let message = encode_valset_confirm_hash(gravity_id.clone(), valset);
-            let eth_signature = eth_private_key.sign_ethereum_msg(message.as_slice()).unwrap();
-            // println!("{:x}", eth_signature.to_hash());
-            // let address = eth_signature.recover(message.as_slice()).unwrap();
-            // println!("{:?}", address);
-            let confirm = MsgValsetConfirm {
-                orchestrator: fx_builder.address().to_string(),
-                eth_address: eth_private_key.address().to_hex_string(),
-                nonce: valset.nonce,
-                signature: format!("{:x}", eth_signature.to_hash()),
-            };
-            let msg = confirm.to_any("/fx.gravity.v1.MsgValsetConfirm");
-
-            let response = fxchain::grpc_client::send_tx(&fx_builder, &grpc_channel, vec![msg]).await.unwrap();
-            println!("Valset confirm tx response code {}, tx hash {}", response.code, response.txhash)
-        }
-    }
-
-    #[tokio::test]
-    async fn test_tx_batch_confirm_signature() {
-        env_logger::builder().filter_level(Debug).init();
-        let eth_private_key = EthPrivateKey::from_str(ETH_PRIVATE_KEY).unwrap();
-
-        let grpc_channel = new_grpc_channel(FX_GRPC_URL).await.unwrap();
-
-        let mut gravity_query_client = GravityQueryClient::new(grpc_channel.clone());
-
-        let response = gravity_query_client.params(QueryParamsRequest {}).await.unwrap();
-
-        let gravity_id = response.into_inner().params.unwrap().gravity_id;
-        println!("gravity_id {}", gravity_id);
-
-        let response = gravity_query_client
-            .last_pending_batch_request_by_addr(QueryLastPendingBatchRequestByAddrRequest {
-                address: "fx16wvwsmpp4y4ttgzknyr6kqla877jud6u04lqey".to_string(),
-            })
-            .await
-            .unwrap();
-
-        let batch = response.into_inner().batch.unwrap();
-        // println!("{:?}", batch);
-        let message = encode_tx_batch_confirm_hash(gravity_id.clone(), batch.clone());
-        let eth_signature = eth_private_key.sign_ethereum_msg(message.as_slice()).unwrap();
-        println!("{:x}", eth_signature.to_hash());
-
-        let response = gravity_query_client
-            .batch_request_by_nonce(QueryBatchRequestByNonceRequest {
-                nonce: batch.batch_nonce,
-                contract_address: batch.token_contract,
-            })
-            .await
-            .unwrap();
-
-        let batch = response.into_inner().batch.unwrap();
-        // println!("{:?}", batch);
-        let message = encode_tx_batch_confirm_hash(gravity_id.clone(), batch.clone());
-        let eth_signature = eth_private_key.sign_ethereum_msg(message.as_ref()).unwrap();
-        println!("{:x}", eth_signature.to_hash());
-    }
-
     #[test]
     fn test_u256() {
         assert_eq!(U256::from(100), U256::from_dec_str("100").unwrap());
@@ -323,13 +423,233 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_set_fx_key_balance_metrics() {
-        let fx_private_key = FxPrivateKey::from_phrase(FX_MNEMONIC, "").unwrap();
-        let fx_address = fx_private_key.public_key().to_address().to_string();
-        let grpc_channel = new_grpc_channel(FX_GRPC_URL).await.unwrap();
-        let auth_account = get_account_info(&grpc_channel, fx_address.clone()).await.unwrap();
-        let fx_chain_id = get_chain_id(&grpc_channel).await.unwrap();
-        let fx_builder = Builder::new(fx_chain_id, fx_private_key, auth_account.account_number, "FX");
-        set_fx_key_balance_metrics(&fx_builder, &grpc_channel).await
+    async fn test_active_eth_signer() {
+        let private_key = EthPrivateKey::from_str("7490923dfece4901e603a1a0429ad74327ca574d7033bba145b68dcd00aa7a5d").unwrap();
+        let rotated_address = EthAddress::from_str("0x008aeeda4d805471df9b2a5b0f38a0c3bcba786b").unwrap();
+        let mut hot_loaded_key = HotLoadedKey::default();
+
+        let eth_signer = active_eth_signer(&private_key, &mut hot_loaded_key, None, private_key.address()).await.unwrap();
+        assert_eq!(eth_signer.address(), private_key.address());
+        assert!(active_eth_signer(&private_key, &mut hot_loaded_key, None, rotated_address).await.is_none());
+
+        let dir = std::env::temp_dir().join(format!("fxeth-keystore-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let eth_keystore = KeystoreDir {
+            dir: dir.clone(),
+            password: "testpassword".to_string(),
+        };
+        assert!(active_eth_signer(&private_key, &mut hot_loaded_key, Some(&eth_keystore), rotated_address).await.is_none());
+        assert_eq!(hot_loaded_key.missing, Some(rotated_address));
+
+        // the miss is cached until the registered address changes
+        std::fs::write(dir.join("rotated.json"), ROTATED_KEYSTORE).unwrap();
+        assert!(active_eth_signer(&private_key, &mut hot_loaded_key, Some(&eth_keystore), rotated_address).await.is_none());
+        assert!(active_eth_signer(&private_key, &mut hot_loaded_key, Some(&eth_keystore), private_key.address()).await.is_some());
+
+        let eth_signer = active_eth_signer(&private_key, &mut hot_loaded_key, Some(&eth_keystore), rotated_address).await.unwrap();
+        assert_eq!(eth_signer.address(), rotated_address);
+        assert!(hot_loaded_key.key.is_some());
+        assert!(hot_loaded_key.missing.is_none());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_matching_confirms() {
+        let private_key = EthPrivateKey::from_str("7490923dfece4901e603a1a0429ad74327ca574d7033bba145b68dcd00aa7a5d").unwrap();
+        let valset = Valset {
+            nonce: 3,
+            members: vec![BridgeValidator {
+                power: 4294967295,
+                eth_address: private_key.address().to_hex_string(),
+            }],
+            height: 100,
+        };
+        let signature = format!("{:x}", private_key.sign_valset_confirm("fx-bridge-eth", &valset).await.unwrap().to_hash());
+        let eth_address = private_key.address().to_hex_string();
+        let confirms = vec![
+            (eth_address.clone(), signature.clone()),
+            ("0x008aeeda4d805471df9b2a5b0f38a0c3bcba786b".to_string(), signature.clone()),
+            (eth_address, "00".to_string()),
+        ];
+        assert_eq!(matching_confirms(encode_valset_confirm_hash("fx-bridge-eth".to_string(), &valset).as_slice(), &confirms), 1);
+        assert_eq!(matching_confirms(encode_valset_confirm_hash("other-bridge".to_string(), &valset).as_slice(), &confirms), 0);
     }
+
+    /// Address of `[1; 32]`, `[2; 32]` and `[3; 32]`
+    const VECTOR_ADDRESSES: [&str; 3] = [
+        "0x1a642f0E3c3aF545E7AcBD38b07251B3990914F1",
+        "0x5050A4F4b3f9338C3472dcC01A87C76A144b3c9c",
+        "0x3325a78425F17a7E487Eb5666b2bFd93aBb06c70",
+    ];
+
+    /// (gravity id, valset, keccak256 of the checkpoint, confirm signature of the `[1; 32]` key),
+    /// pinned from the FxBridge `makeCheckpoint` encoding, see `test_valset_checkpoint_on_evm` to check them against the contract
+    fn valset_vectors() -> Vec<(&'static str, Valset, &'static str, &'static str)> {
+        let valset = |nonce: u64, members: &[(usize, u64)]| Valset {
+            nonce,
+            members: members
+                .iter()
+                .map(|(i, power)| BridgeValidator {
+                    power: *power,
+                    eth_address: VECTOR_ADDRESSES[*i].to_string(),
+                })
+                .collect(),
+            height: 0,
+        };
+        vec![
+            (
+                "fx-bridge-eth",
+                valset(1, &[(0, 4294967295)]),
+                "4a26f48c3bc6a49e9761ea25b14e588d5eb22840cc0cd74ca5e5681f8f436a3d",
+                "af5a36ba0f03b05b6f20aa8585b77795c40659e47b47f2e9573c63d12fa09f8a05b23ef5ecb553670ff0a7da303fd497a137fc68d65eabeb49718ed55eb65cf81b",
+            ),
+            (
+                "fx-bridge-eth",
+                valset(42, &[(0, 2147483647), (1, 1431655765), (2, 715827882)]),
+                "e87c06602ca5bbb3c799255928f452e087ef28ff8d5a47385591c3b3fa1afad4",
+                "628f62c66bef2552b04372479566a36c726138cae54fd54717ced57b5f8906ee17e2564cf2e9dca253753c7785e3f957c5238b55434f7fda7db78a51052d270f1c",
+            ),
+            (
+                "fx-bridge-bsc",
+                valset(u64::MAX, &[(2, 1), (1, 2)]),
+                "04fc16b25ade2adc532ad553457ef46887135a22880d8c6dbae641ee3f38e981",
+                "cd3dd3b49a4e7c9208bec5eddb79548540bedc054dcd618b90d332f7a9374b7a4ed76b663343f24c863e6e5b031bed833ddda4c9ea67ef9e4979aaeabf5825d81b",
+            ),
+        ]
+    }
+
+    /// (gravity id, batch, keccak256 of the checkpoint, confirm signature of the `[1; 32]` key),
+    /// pinned from the FxBridge `submitBatch` encoding, which has no contract function to check them against
+    fn batch_vectors() -> Vec<(&'static str, OutgoingTxBatch, &'static str, &'static str)> {
+        let batch = |batch_nonce: u64, transfers: &[(&str, &str, &str)], batch_timeout: u64, fee_receive: &str| OutgoingTxBatch {
+            batch_nonce,
+            batch_timeout,
+            transactions: transfers
+                .iter()
+                .map(|(dest_address, amount, fee)| OutgoingTransferTx {
+                    dest_address: dest_address.to_string(),
+                    erc20_token: Some(Erc20Token {
+                        contract: VECTOR_TOKEN.to_string(),
+                        amount: amount.to_string(),
+                    }),
+                    erc20_fee: Some(Erc20Token {
+                        contract: VECTOR_TOKEN.to_string(),
+                        amount: fee.to_string(),
+                    }),
+                    ..Default::default()
+                })
+                .collect(),
+            token_contract: VECTOR_TOKEN.to_string(),
+            fee_receive: fee_receive.to_string(),
+            ..Default::default()
+        };
+        vec![
+            (
+                "fx-bridge-eth",
+                batch(1, &[("0x1111111111111111111111111111111111111111", "100", "1")], 0, "0x00000000000000000000000000000000000000fe"),
+                "de6b95582c97f3b90f0fb6c142bf18356de347abb22431e1c5ea76d3b9f9a150",
+                "a8ff858b05f6180d919978ecbcb2bb41eedb53b8f62e4d572a6f2481e6b51514064daae4234288c335644c31b732ee06b6ace9e3e972460ddc94b0b56ecc327f1c",
+            ),
+            (
+                "fx-bridge-eth",
+                batch(
+                    7,
+                    &[
+                        ("0x1111111111111111111111111111111111111111", "1000000000000000000000000", "3000000000000000000"),
+                        ("0x2222222222222222222222222222222222222222", "1", "0"),
+                        (VECTOR_ADDRESSES[0], "340282366920938463463374607431768211456", "5"),
+                    ],
+                    15000000,
+                    VECTOR_ADDRESSES[1],
+                ),
+                "b3db4edb035fe85941d49eafa40683ae0de71081b04fd906a49ae9a2f1396b21",
+                "e3ec2519e7b6a56aad938c5fc53ec2e6118dc12c3cd6eafc3915d23f1a1b33c5431e18ce253f2571e4e00d899e7ee396ee262ab9f521c90e945e416c26b8d7e11b",
+            ),
+        ]
+    }
+
+    const VECTOR_TOKEN: &str = "0xdAC17F958D2ee523a2206206994597C13D831ec7";
+
+    #[tokio::test]
+    async fn test_valset_checkpoint_vectors() {
+        let private_key = EthPrivateKey::from([1u8; 32]);
+        assert_eq!(private_key.address().to_hex_string(), VECTOR_ADDRESSES[0]);
+        for (gravity_id, valset, checkpoint, signature) in valset_vectors() {
+            let message = encode_valset_confirm_hash(gravity_id.to_string(), &valset);
+            assert_eq!(hex::encode(keccak256(message.as_slice())), checkpoint, "valset {} of {}", valset.nonce, gravity_id);
+            let eth_signature = private_key.sign_valset_confirm(gravity_id, &valset).await.unwrap();
+            assert_eq!(format!("{:x}", eth_signature.to_hash()), signature, "valset {} of {}", valset.nonce, gravity_id);
+            assert_eq!(eth_signature.recover_ethereum_msg(message.as_slice()).unwrap(), private_key.address());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_checkpoint_vectors() {
+        let private_key = EthPrivateKey::from([1u8; 32]);
+        for (gravity_id, batch, checkpoint, signature) in batch_vectors() {
+            let message = encode_tx_batch_confirm_hash(gravity_id.to_string(), batch.clone());
+            assert_eq!(hex::encode(keccak256(message.as_slice())), checkpoint, "batch {} of {}", batch.batch_nonce, gravity_id);
+            let eth_signature = private_key.sign_batch_confirm(gravity_id, &batch).await.unwrap();
+            assert_eq!(format!("{:x}", eth_signature.to_hash()), signature, "batch {} of {}", batch.batch_nonce, gravity_id);
+            assert_eq!(eth_signature.recover_ethereum_msg(message.as_slice()).unwrap(), private_key.address());
+        }
+    }
+
+    /// Checks the valset vectors against `makeCheckpoint` of the FxBridge contract run on an in-process EVM.
+    /// Needs the deployed runtime bytecode, as returned by `eth_getCode`, in hex in `FX_BRIDGE_BYTECODE`,
+    /// which is not in this repository, so the test only runs with `--ignored`.
+    #[test]
+    #[ignore]
+    fn test_valset_checkpoint_on_evm() {
+        let bytecode = std::env::var("FX_BRIDGE_BYTECODE").expect("FX_BRIDGE_BYTECODE is not set");
+        let bytecode = hex::decode(bytecode.trim().trim_start_matches("0x")).unwrap();
+        let bridge_addr = revm::primitives::Address::with_last_byte(0x42);
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_account_info(
+            bridge_addr,
+            AccountInfo {
+                code: Some(Bytecode::new_raw(bytecode.into())),
+                ..Default::default()
+            },
+        );
+
+        for (gravity_id, valset, checkpoint, _) in valset_vectors() {
+            let validators = valset.members.iter().map(|member| Token::Address(EthAddress::from_str(member.eth_address.as_str()).unwrap())).collect();
+            let powers = valset.members.iter().map(|member| Token::Uint(member.power.into())).collect();
+            let mut calldata = keccak256(b"makeCheckpoint(address[],uint256[],uint256,bytes32)")[..4].to_vec();
+            calldata.extend(web3::ethabi::encode(&[
+                Token::Array(validators),
+                Token::Array(powers),
+                Token::Uint(valset.nonce.into()),
+                Token::FixedBytes(FixedBytes::from(gravity_id)),
+            ]));
+            let mut evm = Evm::builder()
+                .with_db(db.clone())
+                .modify_tx_env(|tx| {
+                    tx.transact_to = TransactTo::Call(bridge_addr);
+                    tx.data = calldata.into();
+                })
+                .build();
+            match evm.transact().unwrap().result {
+                ExecutionResult::Success { output: Output::Call(output), .. } => {
+                    assert_eq!(hex::encode(output), checkpoint, "valset {} of {}", valset.nonce, gravity_id)
+                }
+                result => panic!("makeCheckpoint of valset {} failed: {:?}", valset.nonce, result),
+            }
+        }
+    }
+
+    const ROTATED_KEYSTORE: &str = r#"{
+        "address" : "008aeeda4d805471df9b2a5b0f38a0c3bcba786b",
+        "crypto" : {
+            "cipher" : "aes-128-ctr",
+            "cipherparams" : {"iv" : "83dbcc02d8ccb40e466191a123791e0e"},
+            "ciphertext" : "91c23c03a8b5117a6e3d78dbb5dfc46f037aff9351dff59c2879586f51756502",
+            "kdf" : "scrypt",
+            "kdfparams" : {"dklen" : 32, "n" : 8192, "r" : 8, "p" : 1, "salt" : "ab0c7876052600dd703518d6fc3fe8984592145b591fc8fb5c6d43190334ba19"},
+            "mac" : "8f8639fe08fe1f43d6c13006d66ad2d993c6674ee2ba03f025596797a73614b9"
+        },
+        "id" : "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+        "version" : 3
+    }"#;
 }
@@ -0,0 +1,483 @@
+//! Generates the typed bindings of `ethereum::abi` from the contract ABIs in `abi/`.
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use serde_json::Value;
+use sha3::{Digest, Keccak256};
+
+/// Contract ABIs and the file of `OUT_DIR` their bindings are written to
+const CONTRACTS: &[(&str, &str)] = &[("abi/FxBridge.json", "fx_bridge.rs"), ("abi/ERC20.json", "erc20.rs"), ("abi/Multicall3.json", "multicall3.rs")];
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+    "pub", "ref", "return", "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where", "while",
+];
+
+/// Types the generated code uses, a contract struct of the same name is prefixed with the contract name
+const IMPORTED_TYPES: &[&str] = &["Address", "Log", "ParamType", "Result", "Token", "Tokenize", "U256", "Vec", "String", "Option"];
+
+fn main() {
+    let out_dir = env::var("OUT_DIR").expect("OUT_DIR is not set");
+    for (abi_file, out_file) in CONTRACTS {
+        println!("cargo:rerun-if-changed={}", abi_file);
+        let abi = fs::read_to_string(abi_file).unwrap_or_else(|err| panic!("read {}: {}", abi_file, err));
+        let abi: Vec<Value> = serde_json::from_str(abi.as_str()).unwrap_or_else(|err| panic!("parse {}: {}", abi_file, err));
+        let code = generate(abi_file, &abi);
+        fs::write(Path::new(&out_dir).join(out_file), code).unwrap_or_else(|err| panic!("write {}: {}", out_file, err));
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Kind {
+    Address,
+    Bool,
+    String,
+    Bytes,
+    FixedBytes(usize),
+    Uint(usize),
+    Int(usize),
+    Array(Box<Kind>),
+    FixedArray(Box<Kind>, usize),
+    Tuple(String, Vec<Field>),
+}
+
+#[derive(Debug, Clone)]
+struct Field {
+    name: String,
+    kind: Kind,
+    indexed: bool,
+}
+
+impl Kind {
+    fn parse(ty: &str, param: &Value) -> Kind {
+        if let Some(elem) = ty.strip_suffix(']') {
+            let open = elem.rfind('[').unwrap_or_else(|| panic!("invalid abi type {}", ty));
+            let (elem, size) = (Box::new(Kind::parse(&elem[..open], param)), &elem[open + 1..]);
+            return match size {
+                "" => Kind::Array(elem),
+                size => Kind::FixedArray(elem, size.parse().unwrap_or_else(|_| panic!("invalid abi type {}", ty))),
+            };
+        }
+        let size = |prefix: &str| match &ty[prefix.len()..] {
+            "" => 256,
+            size => size.parse().unwrap_or_else(|_| panic!("invalid abi type {}", ty)),
+        };
+        match ty {
+            "address" => Kind::Address,
+            "bool" => Kind::Bool,
+            "string" => Kind::String,
+            "bytes" => Kind::Bytes,
+            "tuple" => Kind::Tuple(tuple_name(param), fields(&param["components"])),
+            _ if ty.starts_with("bytes") => Kind::FixedBytes(size("bytes")),
+            _ if ty.starts_with("uint") => Kind::Uint(size("uint")),
+            _ if ty.starts_with("int") => Kind::Int(size("int")),
+            _ => panic!("unsupported abi type {}", ty),
+        }
+    }
+
+    /// Type in the canonical signature of functions and events
+    fn signature(&self) -> String {
+        match self {
+            Kind::Address => "address".to_string(),
+            Kind::Bool => "bool".to_string(),
+            Kind::String => "string".to_string(),
+            Kind::Bytes => "bytes".to_string(),
+            Kind::FixedBytes(size) => format!("bytes{}", size),
+            Kind::Uint(size) => format!("uint{}", size),
+            Kind::Int(size) => format!("int{}", size),
+            Kind::Array(elem) => format!("{}[]", elem.signature()),
+            Kind::FixedArray(elem, size) => format!("{}[{}]", elem.signature(), size),
+            Kind::Tuple(_, fields) => format!("({})", fields.iter().map(|field| field.kind.signature()).collect::<Vec<_>>().join(",")),
+        }
+    }
+
+    fn rust_type(&self) -> String {
+        match self {
+            Kind::Address => "Address".to_string(),
+            Kind::Bool => "bool".to_string(),
+            Kind::String => "String".to_string(),
+            Kind::Bytes => "Vec<u8>".to_string(),
+            Kind::FixedBytes(size) => format!("[u8; {}]", size),
+            Kind::Uint(8) => "u8".to_string(),
+            Kind::Uint(_) | Kind::Int(_) => "U256".to_string(),
+            Kind::Array(elem) => format!("Vec<{}>", elem.rust_type()),
+            Kind::FixedArray(elem, size) => format!("[{}; {}]", elem.rust_type(), size),
+            Kind::Tuple(name, _) => name.clone(),
+        }
+    }
+
+    fn param_type(&self) -> String {
+        match self {
+            Kind::Address => "ParamType::Address".to_string(),
+            Kind::Bool => "ParamType::Bool".to_string(),
+            Kind::String => "ParamType::String".to_string(),
+            Kind::Bytes => "ParamType::Bytes".to_string(),
+            Kind::FixedBytes(size) => format!("ParamType::FixedBytes({})", size),
+            Kind::Uint(size) => format!("ParamType::Uint({})", size),
+            Kind::Int(size) => format!("ParamType::Int({})", size),
+            Kind::Array(elem) => format!("ParamType::Array(Box::new({}))", elem.param_type()),
+            Kind::FixedArray(elem, size) => format!("ParamType::FixedArray(Box::new({}), {})", elem.param_type(), size),
+            Kind::Tuple(name, _) => format!("{}::param_type()", name),
+        }
+    }
+
+    /// Expression of a function from the rust type to a `Token`
+    fn encoder(&self) -> String {
+        match self {
+            Kind::Address => "Token::Address".to_string(),
+            Kind::Bool => "Token::Bool".to_string(),
+            Kind::String => "Token::String".to_string(),
+            Kind::Bytes => "Token::Bytes".to_string(),
+            Kind::FixedBytes(size) => format!("|value: [u8; {}]| Token::FixedBytes(value.to_vec())", size),
+            Kind::Uint(8) => "|value: u8| Token::Uint(value.into())".to_string(),
+            Kind::Uint(_) => "Token::Uint".to_string(),
+            Kind::Int(_) => "Token::Int".to_string(),
+            Kind::Array(elem) => format!("|values: {}| Token::Array(values.into_iter().map({}).collect())", self.rust_type(), elem.encoder()),
+            Kind::FixedArray(elem, _) => format!("|values: {}| Token::FixedArray(Vec::from(values).into_iter().map({}).collect())", self.rust_type(), elem.encoder()),
+            Kind::Tuple(name, _) => format!("{}::into_token", name),
+        }
+    }
+
+    /// Expression of a function from a `Token` to `Result` of the rust type
+    fn decoder(&self) -> String {
+        match self {
+            Kind::Address => "abi::to_address".to_string(),
+            Kind::Bool => "abi::to_bool".to_string(),
+            Kind::String => "abi::to_string".to_string(),
+            Kind::Bytes => "abi::to_bytes".to_string(),
+            Kind::FixedBytes(size) => format!("abi::to_fixed_bytes::<{}>", size),
+            Kind::Uint(8) => "abi::to_u8".to_string(),
+            Kind::Uint(_) => "abi::to_uint".to_string(),
+            Kind::Int(_) => "abi::to_int".to_string(),
+            Kind::Array(elem) => format!("|token| abi::to_array(token, {})", elem.decoder()),
+            Kind::FixedArray(elem, size) => format!("|token| abi::to_fixed_array::<_, {}>(token, {})", size, elem.decoder()),
+            Kind::Tuple(name, _) => format!("{}::from_token", name),
+        }
+    }
+
+    /// Indexed event parameters of these types are stored as the keccak hash of their encoding
+    fn is_hashed_in_topic(&self) -> bool {
+        matches!(self, Kind::String | Kind::Bytes | Kind::Array(_) | Kind::FixedArray(_, _) | Kind::Tuple(_, _))
+    }
+
+    fn tuples(&self, tuples: &mut Vec<(String, Vec<Field>)>) {
+        match self {
+            Kind::Array(elem) | Kind::FixedArray(elem, _) => elem.tuples(tuples),
+            Kind::Tuple(name, fields) => {
+                for field in fields {
+                    field.kind.tuples(tuples);
+                }
+                if !tuples.iter().any(|(tuple, _)| tuple == name) {
+                    tuples.push((name.clone(), fields.clone()));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// `struct FxBridge.BridgeToken[]` is `BridgeToken`, `struct Multicall3.Result[]` is `Multicall3Result` not to shadow `Result`
+fn tuple_name(param: &Value) -> String {
+    let internal_type = param["internalType"].as_str().unwrap_or_else(|| panic!("tuple without internalType: {}", param));
+    let path = internal_type.trim_start_matches("struct ").split('[').next().unwrap_or_default();
+    let name = path.rsplit('.').next().unwrap_or_default();
+    if IMPORTED_TYPES.contains(&name) {
+        path.replace('.', "")
+    } else {
+        name.to_string()
+    }
+}
+
+fn fields(params: &Value) -> Vec<Field> {
+    let params = params.as_array().map(Vec::as_slice).unwrap_or_default();
+    params
+        .iter()
+        .enumerate()
+        .map(|(i, param)| Field {
+            name: field_name(param["name"].as_str().unwrap_or_default(), i),
+            kind: Kind::parse(param["type"].as_str().unwrap_or_else(|| panic!("param without type: {}", param)), param),
+            indexed: param["indexed"].as_bool().unwrap_or_default(),
+        })
+        .collect()
+}
+
+/// `_currentValidators` is `current_validators`, `_targetIBC` is `target_ibc` and unnamed params are `arg0`, `arg1`...
+fn field_name(name: &str, index: usize) -> String {
+    let name = name.trim_start_matches('_');
+    if name.is_empty() {
+        return format!("arg{}", index);
+    }
+    let chars: Vec<char> = name.chars().collect();
+    let mut snake = String::new();
+    for (i, c) in chars.iter().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).map(|next| next.is_ascii_lowercase()).unwrap_or(false);
+            if prev != '_' && (prev.is_ascii_lowercase() || prev.is_ascii_digit() || next_lower) {
+                snake.push('_');
+            }
+        }
+        snake.push(c.to_ascii_lowercase());
+    }
+    if KEYWORDS.contains(&snake.as_str()) {
+        format!("r#{}", snake)
+    } else {
+        snake
+    }
+}
+
+/// `state_lastBatchNonces` is `StateLastBatchNonces`
+fn type_name(name: &str) -> String {
+    name.split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars.next().map(|first| first.to_ascii_uppercase().to_string() + chars.as_str()).unwrap_or_default()
+        })
+        .collect()
+}
+
+fn keccak256(data: &str) -> Vec<u8> {
+    Keccak256::digest(data.as_bytes()).to_vec()
+}
+
+fn byte_array(bytes: &[u8]) -> String {
+    format!("[{}]", bytes.iter().map(|byte| format!("0x{:02x}", byte)).collect::<Vec<_>>().join(", "))
+}
+
+fn signature(name: &str, fields: &[Field]) -> String {
+    format!("{}({})", name, fields.iter().map(|field| field.kind.signature()).collect::<Vec<_>>().join(","))
+}
+
+fn struct_definition(code: &mut String, doc: &str, name: &str, fields: &[Field]) {
+    code.push_str(&format!("/// {}\n#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]\npub struct {} {{\n", doc, name));
+    for field in fields {
+        let ty = if field.indexed && field.kind.is_hashed_in_topic() {
+            "[u8; 32]".to_string()
+        } else {
+            field.kind.rust_type()
+        };
+        code.push_str(&format!("    pub {}: {},\n", field.name, ty));
+    }
+    code.push_str("}\n\n");
+}
+
+/// `Name { field: decode(next token)?, .. }` from the `tokens` iterator
+fn struct_from_tokens(name: &str, fields: &[Field]) -> String {
+    let mut code = format!("{} {{\n", name);
+    for field in fields {
+        code.push_str(&format!("            {}: ({})(abi::next_token(&mut tokens)?)?,\n", field.name, field.kind.decoder()));
+    }
+    code.push_str("        }");
+    code
+}
+
+fn tokens_of_self(fields: &[Field]) -> String {
+    let tokens: Vec<String> = fields.iter().map(|field| format!("({})(self.{})", field.kind.encoder(), field.name)).collect();
+    format!("vec![{}]", tokens.join(", "))
+}
+
+fn param_list(fields: &[Field]) -> String {
+    fields.iter().map(|field| field.kind.param_type()).collect::<Vec<_>>().join(", ")
+}
+
+fn param_types(fields: &[Field]) -> String {
+    format!("vec![{}]", param_list(fields))
+}
+
+fn generate_tuple(code: &mut String, name: &str, fields: &[Field]) {
+    struct_definition(code, &format!("`{}` struct of the contract", name), name, fields);
+    code.push_str(&format!(
+        "impl {name} {{
+    pub fn param_type() -> ParamType {{
+        ParamType::Tuple({param_types})
+    }}
+
+    pub fn into_token(self) -> Token {{
+        Token::Tuple({tokens})
+    }}
+
+    pub fn from_token(token: Token) -> Result<{name}> {{
+        let mut tokens = abi::to_tuple(token, {len})?.into_iter();
+        Ok({from_tokens})
+    }}
+}}
+
+",
+        name = name,
+        param_types = param_types(fields),
+        tokens = tokens_of_self(fields),
+        len = fields.len(),
+        from_tokens = struct_from_tokens(name, fields),
+    ));
+}
+
+fn generate_function(code: &mut String, name: &str, function: &str, inputs: &[Field], outputs: &[Field]) {
+    let signature = signature(function, inputs);
+    let selector = keccak256(signature.as_str());
+    struct_definition(code, &format!("Call of `{}`", signature), name, inputs);
+    let output_type = match outputs {
+        [] => "()".to_string(),
+        [output] => output.kind.rust_type(),
+        outputs => format!("({})", outputs.iter().map(|output| output.kind.rust_type()).collect::<Vec<_>>().join(", ")),
+    };
+    let decode_output = match outputs {
+        [] => "fn decode_output(_: &[u8]) -> Result<()> {\n        Ok(())\n    }".to_string(),
+        outputs => {
+            let values: Vec<String> = outputs.iter().map(|output| format!("({})(abi::next_token(&mut tokens)?)?", output.kind.decoder())).collect();
+            let value = if values.len() == 1 { values[0].clone() } else { format!("({})", values.join(", ")) };
+            format!(
+                "fn decode_output(data: &[u8]) -> Result<{}> {{\n        let mut tokens = abi::decode_data(&[{}], data)?.into_iter();\n        Ok({})\n    }}",
+                output_type,
+                param_list(outputs),
+                value
+            )
+        }
+    };
+    let from_tokens = if inputs.is_empty() {
+        format!("fn from_tokens(_: Vec<Token>) -> Result<{}> {{\n        Ok({} {{}})\n    }}", name, name)
+    } else {
+        format!(
+            "fn from_tokens(tokens: Vec<Token>) -> Result<{}> {{\n        let mut tokens = tokens.into_iter();\n        Ok({})\n    }}",
+            name,
+            struct_from_tokens(name, inputs)
+        )
+    };
+    code.push_str(&format!(
+        "impl ContractCall for {name} {{
+    type Output = {output_type};
+    const NAME: &'static str = \"{function}\";
+    const SIGNATURE: &'static str = \"{signature}\";
+    const SELECTOR: [u8; 4] = {selector};
+
+    fn param_types() -> Vec<ParamType> {{
+        {param_types}
+    }}
+
+    {from_tokens}
+
+    {decode_output}
+}}
+
+impl Tokenize for {name} {{
+    fn into_tokens(self) -> Vec<Token> {{
+        {tokens}
+    }}
+}}
+
+",
+        name = name,
+        output_type = output_type,
+        function = function,
+        signature = signature,
+        selector = byte_array(&selector[..4]),
+        param_types = param_types(inputs),
+        from_tokens = from_tokens,
+        decode_output = decode_output,
+        tokens = tokens_of_self(inputs),
+    ));
+}
+
+fn generate_event(code: &mut String, name: &str, event: &str, anonymous: bool, inputs: &[Field]) {
+    let signature = signature(event, inputs);
+    struct_definition(code, &format!("`{}` event", signature), name, inputs);
+    let (indexed, data): (Vec<&Field>, Vec<&Field>) = inputs.iter().partition(|field| field.indexed);
+    let first_topic = if anonymous { 0 } else { 1 };
+    let mut fields = String::new();
+    let mut topic = first_topic;
+    for field in inputs {
+        let value = if !field.indexed {
+            format!("({})(abi::next_token(&mut tokens)?)?", field.kind.decoder())
+        } else if field.kind.is_hashed_in_topic() {
+            format!("log.topics[{}].0", topic)
+        } else {
+            format!("({})(abi::decode_topic({}, &log.topics[{}])?)?", field.kind.decoder(), field.kind.param_type(), topic)
+        };
+        if field.indexed {
+            topic += 1;
+        }
+        fields.push_str(&format!("            {}: {},\n", field.name, value));
+    }
+    let decode_data = if data.is_empty() {
+        String::new()
+    } else {
+        let data: Vec<Field> = data.into_iter().cloned().collect();
+        format!("let mut tokens = abi::decode_data(&[{}], &log.data.0)?.into_iter();\n        ", param_list(&data))
+    };
+    let topic_check = if anonymous {
+        format!("abi::check_topics(log, Self::SIGNATURE, None, {})?;", indexed.len())
+    } else {
+        format!("abi::check_topics(log, Self::SIGNATURE, Some(&Self::TOPIC), {})?;", indexed.len() + first_topic)
+    };
+    code.push_str(&format!(
+        "impl ContractEvent for {name} {{
+    const SIGNATURE: &'static str = \"{signature}\";
+    const TOPIC: [u8; 32] = {topic};
+
+    fn from_log(log: &Log) -> Result<{name}> {{
+        {topic_check}
+        {decode_data}Ok({name} {{
+{fields}        }})
+    }}
+}}
+
+",
+        name = name,
+        signature = signature,
+        topic = byte_array(&keccak256(signature.as_str())),
+        topic_check = topic_check,
+        decode_data = decode_data,
+        fields = fields,
+    ));
+}
+
+fn generate(abi_file: &str, abi: &[Value]) -> String {
+    let mut code = format!(
+        "// Generated by build.rs from {}, do not edit.
+
+use web3::contract::tokens::Tokenize;
+use web3::ethabi::{{ParamType, Token}};
+#[allow(unused_imports)]
+use web3::types::{{Address, Log, U256}};
+
+#[allow(unused_imports)]
+use crate::abi::{{self, ContractCall, ContractEvent}};
+use eyre::Result;
+
+",
+        abi_file
+    );
+
+    let mut tuples = Vec::new();
+    for item in abi {
+        for params in [&item["inputs"], &item["outputs"]].iter() {
+            for field in fields(params) {
+                field.kind.tuples(&mut tuples);
+            }
+        }
+    }
+    for (name, fields) in &tuples {
+        generate_tuple(&mut code, name, fields);
+    }
+
+    let mut overloads: HashMap<String, usize> = HashMap::new();
+    for function in abi.iter().filter(|item| item["type"] == "function") {
+        let function_name = function["name"].as_str().expect("function without name");
+        let count = overloads.entry(function_name.to_string()).or_default();
+        *count += 1;
+        let name = match *count {
+            1 => format!("{}Call", type_name(function_name)),
+            count => format!("{}{}Call", type_name(function_name), count),
+        };
+        generate_function(&mut code, name.as_str(), function_name, &fields(&function["inputs"]), &fields(&function["outputs"]));
+    }
+    for event in abi.iter().filter(|item| item["type"] == "event") {
+        let event_name = event["name"].as_str().expect("event without name");
+        let anonymous = event["anonymous"].as_bool().unwrap_or_default();
+        generate_event(&mut code, type_name(event_name).as_str(), event_name, anonymous, &fields(&event["inputs"]));
+    }
+    code
+}
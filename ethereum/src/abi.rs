@@ -0,0 +1,269 @@
+use std::convert::TryFrom;
+
+use eyre::Result;
+use web3::api::Eth;
+use web3::contract::tokens::Tokenize;
+use web3::ethabi::{decode, encode, ParamType, Token};
+use web3::transports::Http;
+use web3::types::{Address, BlockId, BlockNumber, Bytes, CallRequest, Log, H256, U256};
+
+/// Typed bindings of `abi/FxBridge.json`
+#[allow(clippy::redundant_closure_call, clippy::needless_question_mark)]
+pub mod fx_bridge {
+    include!(concat!(env!("OUT_DIR"), "/fx_bridge.rs"));
+}
+
+/// Typed bindings of `abi/ERC20.json`
+#[allow(clippy::redundant_closure_call, clippy::needless_question_mark)]
+pub mod erc20 {
+    include!(concat!(env!("OUT_DIR"), "/erc20.rs"));
+}
+
+/// Typed bindings of `abi/Multicall3.json`, the subset of Multicall3 `ethereum::multicall` uses
+#[allow(clippy::redundant_closure_call, clippy::needless_question_mark)]
+pub mod multicall3 {
+    include!(concat!(env!("OUT_DIR"), "/multicall3.rs"));
+}
+
+/// A contract function and its arguments, generated from the contract ABI
+pub trait ContractCall: Tokenize + Sized {
+    type Output;
+    const NAME: &'static str;
+    const SIGNATURE: &'static str;
+    const SELECTOR: [u8; 4];
+
+    fn param_types() -> Vec<ParamType>;
+
+    fn from_tokens(tokens: Vec<Token>) -> Result<Self>;
+
+    /// Decode the data returned by `eth_call`
+    fn decode_output(data: &[u8]) -> Result<Self::Output>;
+
+    /// Transaction data of the call, the selector followed by the encoded arguments
+    fn encode(self) -> Vec<u8> {
+        let mut data = Self::SELECTOR.to_vec();
+        data.extend(encode(&self.into_tokens()));
+        data
+    }
+
+    /// Decode the transaction data of the call
+    fn decode(data: &[u8]) -> Result<Self> {
+        if data.len() < 4 || data[..4] != Self::SELECTOR {
+            return Err(eyre::Error::msg(format!("not a call of {}", Self::SIGNATURE)));
+        }
+        Self::from_tokens(decode_data(&Self::param_types(), &data[4..])?)
+    }
+}
+
+/// A contract event, generated from the contract ABI
+pub trait ContractEvent: Sized {
+    const SIGNATURE: &'static str;
+    const TOPIC: [u8; 32];
+
+    /// First topic of the logs of the event
+    fn signature() -> H256 {
+        H256(Self::TOPIC)
+    }
+
+    /// Decode a log of the event, which must have a topic for each indexed parameter
+    fn from_log(log: &Log) -> Result<Self>;
+}
+
+/// Run `call` against `contract` at the latest block with `eth_call`
+pub async fn query<C: ContractCall>(eth: &Eth<Http>, from: Address, contract: Address, call: C) -> Result<C::Output> {
+    let request = CallRequest {
+        from: Some(from),
+        to: Some(contract),
+        data: Some(Bytes(call.encode())),
+        ..Default::default()
+    };
+    let output = eth.call(request, Some(BlockId::Number(BlockNumber::Latest))).await?;
+    C::decode_output(output.0.as_slice()).map_err(|err| eyre::Error::msg(format!("{} output: {}", C::NAME, err)))
+}
+
+fn invalid(kind: &str, token: &Token) -> eyre::Error {
+    eyre::Error::msg(format!("invalid {}: {:?}", kind, token))
+}
+
+pub(crate) fn decode_data(types: &[ParamType], data: &[u8]) -> Result<Vec<Token>> {
+    decode(types, data).map_err(|err| eyre::Error::msg(format!("invalid abi data: {}", err)))
+}
+
+pub(crate) fn decode_topic(kind: ParamType, topic: &H256) -> Result<Token> {
+    Ok(decode_data(&[kind], topic.as_bytes())?.remove(0))
+}
+
+/// Check the event signature and the number of topics of `log`
+pub(crate) fn check_topics(log: &Log, signature: &str, topic: Option<&[u8; 32]>, count: usize) -> Result<()> {
+    if log.topics.len() != count {
+        return Err(eyre::Error::msg(format!("{} log has {} topics, expected {}", signature, log.topics.len(), count)));
+    }
+    match (topic, log.topics.first()) {
+        (Some(topic), Some(first)) if first.as_bytes() != topic => Err(eyre::Error::msg(format!("not a {} log", signature))),
+        _ => Ok(()),
+    }
+}
+
+pub(crate) fn next_token(tokens: &mut impl Iterator<Item = Token>) -> Result<Token> {
+    tokens.next().ok_or_else(|| eyre::Error::msg("missing abi token"))
+}
+
+pub(crate) fn to_address(token: Token) -> Result<Address> {
+    token.clone().into_address().ok_or_else(|| invalid("address", &token))
+}
+
+pub(crate) fn to_bool(token: Token) -> Result<bool> {
+    token.clone().into_bool().ok_or_else(|| invalid("bool", &token))
+}
+
+pub(crate) fn to_string(token: Token) -> Result<String> {
+    token.clone().into_string().ok_or_else(|| invalid("string", &token))
+}
+
+pub(crate) fn to_bytes(token: Token) -> Result<Vec<u8>> {
+    token.clone().into_bytes().ok_or_else(|| invalid("bytes", &token))
+}
+
+pub(crate) fn to_fixed_bytes<const N: usize>(token: Token) -> Result<[u8; N]> {
+    let bytes = token.clone().into_fixed_bytes().ok_or_else(|| invalid("fixed bytes", &token))?;
+    <[u8; N]>::try_from(bytes.as_slice()).map_err(|_| invalid(&format!("bytes{}", N), &token))
+}
+
+pub(crate) fn to_uint(token: Token) -> Result<U256> {
+    token.clone().into_uint().ok_or_else(|| invalid("uint", &token))
+}
+
+#[allow(dead_code)] // no abi has an `int` yet
+pub(crate) fn to_int(token: Token) -> Result<U256> {
+    token.clone().into_int().ok_or_else(|| invalid("int", &token))
+}
+
+pub(crate) fn to_u8(token: Token) -> Result<u8> {
+    let value = to_uint(token.clone())?;
+    if value > U256::from(u8::MAX) {
+        return Err(invalid("uint8", &token));
+    }
+    Ok(value.low_u32() as u8)
+}
+
+pub(crate) fn to_array<T>(token: Token, decode: impl Fn(Token) -> Result<T>) -> Result<Vec<T>> {
+    match token {
+        Token::Array(tokens) | Token::FixedArray(tokens) => tokens.into_iter().map(decode).collect(),
+        token => Err(invalid("array", &token)),
+    }
+}
+
+pub(crate) fn to_fixed_array<T, const N: usize>(token: Token, decode: impl Fn(Token) -> Result<T>) -> Result<[T; N]> {
+    let values = to_array(token, decode)?;
+    let len = values.len();
+    <[T; N]>::try_from(values).map_err(|_| eyre::Error::msg(format!("invalid array length {}, expected {}", len, N)))
+}
+
+pub(crate) fn to_tuple(token: Token, len: usize) -> Result<Vec<Token>> {
+    match token {
+        Token::Tuple(tokens) if tokens.len() == len => Ok(tokens),
+        token => Err(invalid(&format!("tuple of {}", len), &token)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use web3::types::H160;
+
+    use super::erc20::{Transfer, TransferCall};
+    use super::fx_bridge::{BridgeToken, GetBridgeTokenListCall, SendToFxCall, SendToFxEvent, SubmitBatchCall, UpdateValsetCall, ValsetUpdatedEvent};
+    use super::*;
+
+    fn log(topics: Vec<H256>, data: Vec<u8>) -> Log {
+        Log {
+            topics,
+            data: Bytes(data),
+            ..serde_json::from_str(r#"{"address":"0x0412c7c846bb6b7dc462cf6b453f76d8440b2609","topics":[],"data":"0x"}"#).unwrap()
+        }
+    }
+
+    #[test]
+    fn test_selectors() {
+        assert_eq!(SendToFxCall::SIGNATURE, "sendToFx(address,bytes32,bytes32,uint256)");
+        assert_eq!(SendToFxCall::SELECTOR, [0x61, 0x89, 0xd1, 0x07]);
+        assert_eq!(UpdateValsetCall::SELECTOR, [0xe3, 0xcb, 0x9f, 0x62]);
+        assert_eq!(
+            SubmitBatchCall::SIGNATURE,
+            "submitBatch(address[],uint256[],uint8[],bytes32[],bytes32[],uint256[],address[],uint256[],uint256[2],address,uint256,address)"
+        );
+        assert_eq!(TransferCall::SELECTOR, [0xa9, 0x05, 0x9c, 0xbb]);
+        assert_eq!(format!("{:?}", Transfer::signature()), "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef");
+    }
+
+    #[test]
+    fn test_call_round_trip() {
+        let call = SubmitBatchCall {
+            current_validators: vec![Address::repeat_byte(1), Address::repeat_byte(2)],
+            current_powers: vec![U256::from(u32::MAX / 2), U256::from(u32::MAX / 2)],
+            v: vec![27, 28],
+            r: vec![[3; 32], [4; 32]],
+            s: vec![[5; 32], [6; 32]],
+            amounts: vec![U256::from(100)],
+            destinations: vec![Address::repeat_byte(7)],
+            fees: vec![U256::from(1)],
+            nonce_array: [U256::from(8), U256::from(9)],
+            token_contract: Address::repeat_byte(10),
+            batch_timeout: U256::from(11),
+            fee_receive: Address::repeat_byte(12),
+        };
+        let data = call.clone().encode();
+        assert_eq!(data[..4], SubmitBatchCall::SELECTOR);
+        assert_eq!(SubmitBatchCall::decode(data.as_slice()).unwrap(), call);
+        assert!(UpdateValsetCall::decode(data.as_slice()).is_err());
+
+        // v above 255 is not a uint8
+        let mut tokens = call.into_tokens();
+        tokens[2] = Token::Array(vec![Token::Uint(256.into())]);
+        assert!(SubmitBatchCall::from_tokens(tokens).is_err());
+    }
+
+    #[test]
+    fn test_decode_output() {
+        let token = BridgeToken {
+            addr: H160::from_str("0xdAC17F958D2ee523a2206206994597C13D831ec7").unwrap(),
+            name: "Tether USD".to_string(),
+            symbol: "USDT".to_string(),
+            decimals: 6,
+        };
+        let output = encode(&[Token::Array(vec![token.clone().into_token()])]);
+        assert_eq!(GetBridgeTokenListCall::decode_output(output.as_slice()).unwrap(), vec![token]);
+        assert!(GetBridgeTokenListCall::decode_output(&output[..64]).is_err());
+    }
+
+    #[test]
+    fn test_event_from_log() {
+        let topics = vec![
+            SendToFxEvent::signature(),
+            H256::from(Address::repeat_byte(1)),
+            H256::from(Address::repeat_byte(2)),
+            H256::repeat_byte(3),
+        ];
+        let data = encode(&[Token::FixedBytes(vec![4; 32]), Token::Uint(1000.into()), Token::Uint(5.into())]);
+        let event = SendToFxEvent::from_log(&log(topics.clone(), data.clone())).unwrap();
+        assert_eq!(
+            event,
+            SendToFxEvent {
+                token_contract: Address::repeat_byte(1),
+                sender: Address::repeat_byte(2),
+                destination: [3; 32],
+                target_ibc: [4; 32],
+                amount: 1000.into(),
+                event_nonce: 5.into(),
+            }
+        );
+
+        // a topic less, or the signature of another event
+        assert!(SendToFxEvent::from_log(&log(topics[..3].to_vec(), data.clone())).is_err());
+        let mut other = topics.clone();
+        other[0] = ValsetUpdatedEvent::signature();
+        assert!(SendToFxEvent::from_log(&log(other, data.clone())).is_err());
+        assert!(SendToFxEvent::from_log(&log(topics, data[..64].to_vec())).is_err());
+    }
+}
@@ -4,12 +4,19 @@ use futures::{Future, StreamExt};
 use web3::{
     api::{Eth, EthFilter, Namespace},
     error,
-    types::{Bytes, TransactionReceipt, TransactionRequest, H256, U64},
+    types::{TransactionReceipt, TransactionRequest, H256, U64},
     Transport,
 };
 
 use crate::TX_CONFIRMATIONS_TIMEOUT;
 
+const CONFIRM_TIMEOUT: &str = "tx confirm timeout";
+
+/// Whether `err` is the timeout of `wait_for_confirmations`, the tx may still be pending
+pub fn is_confirm_timeout(err: &error::Error) -> bool {
+    matches!(err, error::Error::InvalidResponse(message) if message.starts_with(CONFIRM_TIMEOUT))
+}
+
 pub trait ConfirmationCheck {
     type Check: Future<Output = error::Result<Option<U64>>>;
 
@@ -28,7 +35,7 @@ where
     }
 }
 
-pub async fn wait_for_confirmations<T, V, F>(eth: Eth<T>, eth_filter: EthFilter<T>, poll_interval: Duration, confirmations: usize, check: V) -> error::Result<()>
+pub async fn wait_for_confirmations<T, V, F>(eth: Eth<T>, eth_filter: EthFilter<T>, poll_interval: Duration, confirmations: usize, timeout: Duration, check: V) -> error::Result<()>
 where
     T: Transport,
     V: ConfirmationCheck<Check = F>,
@@ -47,8 +54,8 @@ where
                 return Ok(());
             }
         } else {
-            if Instant::now() - start_loop > TX_CONFIRMATIONS_TIMEOUT {
-                return Err(error::Error::InvalidResponse( format!("tx confirm timeout: {:?}", TX_CONFIRMATIONS_TIMEOUT)));
+            if Instant::now() - start_loop > timeout {
+                return Err(error::Error::InvalidResponse(format!("{}: {:?}", CONFIRM_TIMEOUT, timeout)));
             }
         }
     }
@@ -59,20 +66,35 @@ async fn transaction_receipt_block_number_check<T: Transport>(eth: &Eth<T>, hash
     Ok(receipt.and_then(|receipt| receipt.block_number))
 }
 
-async fn send_transaction_with_confirmation_<T: Transport>(hash: H256, transport: T, poll_interval: Duration, confirmations: usize) -> error::Result<TransactionReceipt> {
+async fn any_receipt_block_number_check<T: Transport>(eth: &Eth<T>, hashes: &[H256]) -> error::Result<Option<U64>> {
+    for hash in hashes.iter() {
+        if let Some(block_number) = transaction_receipt_block_number_check(eth, *hash).await? {
+            return Ok(Some(block_number));
+        }
+    }
+    Ok(None)
+}
+
+/// Receipt of whichever of `hashes` is mined, txs with the same nonce replacing each other, after `confirmations`.
+/// Fails with a confirm timeout when none is mined within `timeout`
+pub async fn wait_for_any_receipt<T: Transport>(transport: T, hashes: &[H256], poll_interval: Duration, confirmations: usize, timeout: Duration) -> error::Result<TransactionReceipt> {
     let eth = Eth::new(transport.clone());
     if confirmations > 0 {
-        let confirmation_check = || transaction_receipt_block_number_check(&eth, hash);
+        let confirmation_check = || any_receipt_block_number_check(&eth, hashes);
         let eth_filter = EthFilter::new(transport.clone());
         let eth = eth.clone();
-        wait_for_confirmations(eth, eth_filter, poll_interval, confirmations, confirmation_check).await?;
+        wait_for_confirmations(eth, eth_filter, poll_interval, confirmations, timeout, confirmation_check).await?;
     }
-    let receipt = eth.transaction_receipt(hash).await?;
-    if receipt.is_none() {
-        Err(error::Error::InvalidResponse("receipt can't be null after wait for confirmations".to_string()))
-    } else {
-        Ok(receipt.unwrap())
+    for hash in hashes.iter() {
+        if let Some(receipt) = eth.transaction_receipt(*hash).await? {
+            return Ok(receipt);
+        }
     }
+    Err(error::Error::InvalidResponse("receipt can't be null after wait for confirmations".to_string()))
+}
+
+async fn send_transaction_with_confirmation_<T: Transport>(hash: H256, transport: T, poll_interval: Duration, confirmations: usize) -> error::Result<TransactionReceipt> {
+    wait_for_any_receipt(transport, &[hash], poll_interval, confirmations, TX_CONFIRMATIONS_TIMEOUT).await
 }
 
 #[allow(dead_code)]
@@ -83,11 +105,3 @@ where
     let hash = Eth::new(&transport).send_transaction(tx).await?;
     send_transaction_with_confirmation_(hash, transport, poll_interval, confirmations).await
 }
-
-pub async fn send_raw_transaction_with_confirmation<T>(transport: T, tx: Bytes, poll_interval: Duration, confirmations: usize) -> error::Result<TransactionReceipt>
-where
-    T: Transport,
-{
-    let hash = Eth::new(&transport).send_raw_transaction(tx).await?;
-    send_transaction_with_confirmation_(hash, transport, poll_interval, confirmations).await
-}
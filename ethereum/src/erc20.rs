@@ -1,18 +1,16 @@
 use std::fmt::Debug;
 
 use eyre::Result;
-use sha3::{Digest, Keccak256};
 use web3::api::Eth;
-use web3::contract::tokens::{Detokenize, Tokenizable};
 use web3::contract::{Contract, Options};
-use web3::ethabi::{Contract as ContractABI, Hash, Token};
+use web3::ethabi::Contract as ContractABI;
 use web3::transports::Http;
 use web3::types::{Address, BlockId, BlockNumber, TransactionReceipt, U256};
 
 use crate::private_key::{Key, PrivateKey};
 use crate::TX_CONFIRMATIONS_BLOCK_NUMBER;
 
-const ERC20_ABI: &str = r#"[{"inputs":[{"internalType":"string","name":"name","type":"string"},{"internalType":"string","name":"symbol","type":"string"}],"stateMutability":"nonpayable","type":"constructor"},{"anonymous":false,"inputs":[{"indexed":true,"internalType":"address","name":"owner","type":"address"},{"indexed":true,"internalType":"address","name":"spender","type":"address"},{"indexed":false,"internalType":"uint256","name":"value","type":"uint256"}],"name":"Approval","type":"event"},{"anonymous":false,"inputs":[{"indexed":true,"internalType":"address","name":"from","type":"address"},{"indexed":true,"internalType":"address","name":"to","type":"address"},{"indexed":false,"internalType":"uint256","name":"value","type":"uint256"}],"name":"Transfer","type":"event"},{"inputs":[{"internalType":"address","name":"owner","type":"address"},{"internalType":"address","name":"spender","type":"address"}],"name":"allowance","outputs":[{"internalType":"uint256","name":"","type":"uint256"}],"stateMutability":"view","type":"function"},{"inputs":[{"internalType":"address","name":"spender","type":"address"},{"internalType":"uint256","name":"amount","type":"uint256"}],"name":"approve","outputs":[{"internalType":"bool","name":"","type":"bool"}],"stateMutability":"nonpayable","type":"function"},{"inputs":[{"internalType":"address","name":"account","type":"address"}],"name":"balanceOf","outputs":[{"internalType":"uint256","name":"","type":"uint256"}],"stateMutability":"view","type":"function"},{"inputs":[],"name":"decimals","outputs":[{"internalType":"uint8","name":"","type":"uint8"}],"stateMutability":"view","type":"function"},{"inputs":[{"internalType":"address","name":"spender","type":"address"},{"internalType":"uint256","name":"subtractedValue","type":"uint256"}],"name":"decreaseAllowance","outputs":[{"internalType":"bool","name":"","type":"bool"}],"stateMutability":"nonpayable","type":"function"},{"inputs":[{"internalType":"address","name":"spender","type":"address"},{"internalType":"uint256","name":"addedValue","type":"uint256"}],"name":"increaseAllowance","outputs":[{"internalType":"bool","name":"","type":"bool"}],"stateMutability":"nonpayable","type":"function"},{"inputs":[],"name":"name","outputs":[{"internalType":"string","name":"","type":"string"}],"stateMutability":"view","type":"function"},{"inputs":[],"name":"symbol","outputs":[{"internalType":"string","name":"","type":"string"}],"stateMutability":"view","type":"function"},{"inputs":[],"name":"totalSupply","outputs":[{"internalType":"uint256","name":"","type":"uint256"}],"stateMutability":"view","type":"function"},{"inputs":[{"internalType":"address","name":"recipient","type":"address"},{"internalType":"uint256","name":"amount","type":"uint256"}],"name":"transfer","outputs":[{"internalType":"bool","name":"","type":"bool"}],"stateMutability":"nonpayable","type":"function"},{"inputs":[{"internalType":"address","name":"sender","type":"address"},{"internalType":"address","name":"recipient","type":"address"},{"internalType":"uint256","name":"amount","type":"uint256"}],"name":"transferFrom","outputs":[{"internalType":"bool","name":"","type":"bool"}],"stateMutability":"nonpayable","type":"function"}]"#;
+const ERC20_ABI: &str = include_str!("../abi/ERC20.json");
 
 #[derive(Debug, Clone)]
 pub struct ERC20 {
@@ -136,65 +134,8 @@ impl ERC20 {
     }
 }
 
-#[derive(Clone, Debug, Default, Eq, PartialEq)]
-pub struct ApprovalEvent {
-    pub owner: Address,
-    pub spender: Address,
-    pub value: U256,
-}
-
-impl ApprovalEvent {
-    pub fn signature() -> Hash {
-        Hash::from_slice(Keccak256::digest(ApprovalEvent::abi_signature().as_bytes()).as_slice())
-    }
-    pub const fn abi_signature() -> &'static str {
-        "Approval(address,address,uint256)"
-    }
-}
-
-impl Detokenize for ApprovalEvent {
-    fn from_tokens(tokens: Vec<Token>) -> web3::contract::Result<Self> {
-        if tokens.len() != 3 {
-            return Err(web3::contract::Error::InvalidOutputType(format!("Expected {} tokens, got {}: {:?}", 3, tokens.len(), tokens)));
-        }
-        #[allow(unused_mut)]
-        let mut tokens = tokens.into_iter();
-        let owner = Tokenizable::from_token(tokens.next().unwrap())?;
-        let spender = Tokenizable::from_token(tokens.next().unwrap())?;
-        let value = Tokenizable::from_token(tokens.next().unwrap())?;
-        Ok(ApprovalEvent { owner, spender, value })
-    }
-}
-
-#[derive(Clone, Debug, Default, Eq, PartialEq)]
-pub struct TransferEvent {
-    pub from: Address,
-    pub to: Address,
-    pub value: U256,
-}
-
-impl TransferEvent {
-    pub fn signature() -> Hash {
-        Hash::from_slice(Keccak256::digest(TransferEvent::abi_signature().as_bytes()).as_slice())
-    }
-    pub const fn abi_signature() -> &'static str {
-        "Transfer(address,address,uint256)"
-    }
-}
-
-impl Detokenize for TransferEvent {
-    fn from_tokens(tokens: Vec<Token>) -> web3::contract::Result<Self> {
-        if tokens.len() != 3 {
-            return Err(web3::contract::Error::InvalidOutputType(format!("Expected {} tokens, got {}: {:?}", 3, tokens.len(), tokens)));
-        }
-        #[allow(unused_mut)]
-        let mut tokens = tokens.into_iter();
-        let from = Tokenizable::from_token(tokens.next().unwrap())?;
-        let to = Tokenizable::from_token(tokens.next().unwrap())?;
-        let value = Tokenizable::from_token(tokens.next().unwrap())?;
-        Ok(TransferEvent { from, to, value })
-    }
-}
+/// `Approval` and `Transfer` logs, decoded with `ContractEvent::from_log`
+pub use crate::abi::erc20::{Approval as ApprovalEvent, Transfer as TransferEvent};
 
 #[cfg(test)]
 mod test {
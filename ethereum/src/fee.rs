@@ -0,0 +1,384 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::Duration;
+
+use eyre::Result;
+use futures::future::{self, BoxFuture};
+use futures::FutureExt;
+use web3::api::Eth;
+use web3::transports::Http;
+use web3::types::{BlockId, BlockNumber, FeeHistory, TransactionReceipt, U256};
+
+use crate::units::parse_units;
+use crate::TX_CONFIRMATIONS_TIMEOUT;
+
+/// FxBridge transaction, each with its own fee cap
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    ValsetRelay,
+    BatchRelay,
+    SendToFx,
+    Other,
+}
+
+impl Operation {
+    /// Operation of a call of the FxBridge function `func`
+    pub fn of(func: &str) -> Self {
+        match func {
+            "updateValset" => Operation::ValsetRelay,
+            "submitBatch" => Operation::BatchRelay,
+            "sendToFx" => Operation::SendToFx,
+            _ => Operation::Other,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Operation::ValsetRelay => "valset_relay",
+            Operation::BatchRelay => "batch_relay",
+            Operation::SendToFx => "send_to_fx",
+            Operation::Other => "other",
+        }
+    }
+}
+
+/// Fees per gas of a transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fees {
+    /// EIP-1559 transaction
+    Eip1559 { max_fee_per_gas: U256, max_priority_fee_per_gas: U256 },
+    /// Transaction with a gas price, before London
+    Legacy { gas_price: U256 },
+}
+
+impl Fees {
+    /// Most the transaction can pay per gas
+    pub fn max_fee_per_gas(&self) -> U256 {
+        match *self {
+            Fees::Eip1559 { max_fee_per_gas, .. } => max_fee_per_gas,
+            Fees::Legacy { gas_price } => gas_price,
+        }
+    }
+
+    /// Fees raised by `percent` for each level of `urgency`
+    pub fn escalate(&self, urgency: u32, percent: u64) -> Fees {
+        let raise = |fee: U256| (0..urgency).fold(fee, |fee, _| fee * (100 + percent) / 100);
+        match *self {
+            Fees::Eip1559 {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            } => Fees::Eip1559 {
+                max_fee_per_gas: raise(max_fee_per_gas),
+                max_priority_fee_per_gas: raise(max_priority_fee_per_gas),
+            },
+            Fees::Legacy { gas_price } => Fees::Legacy { gas_price: raise(gas_price) },
+        }
+    }
+
+    /// Fees lowered to pay at most `cap` per gas
+    pub fn cap(&self, cap: U256) -> Fees {
+        match *self {
+            Fees::Eip1559 {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            } => {
+                let max_fee_per_gas = max_fee_per_gas.min(cap);
+                Fees::Eip1559 {
+                    max_fee_per_gas,
+                    max_priority_fee_per_gas: max_priority_fee_per_gas.min(max_fee_per_gas),
+                }
+            }
+            Fees::Legacy { gas_price } => Fees::Legacy { gas_price: gas_price.min(cap) },
+        }
+    }
+}
+
+/// How the fees of a transaction are chosen
+pub trait FeeStrategy: Debug + Send + Sync {
+    /// Fees of a transaction in the next block
+    fn fees<'a>(&'a self, eth: &'a Eth<Http>) -> BoxFuture<'a, Result<Fees>>;
+}
+
+/// Priority fee of the `percentile` of the recent blocks from `eth_feeHistory`, on top of twice the next base fee
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeeHistoryStrategy {
+    /// blocks of the fee history, the priority fee is the median of their `percentile`
+    pub blocks: u64,
+    pub percentile: f64,
+    /// floor of the priority fee, for fee histories of empty blocks
+    pub min_priority_fee_per_gas: U256,
+}
+
+impl Default for FeeHistoryStrategy {
+    fn default() -> Self {
+        FeeHistoryStrategy {
+            blocks: 10,
+            percentile: 50.0,
+            min_priority_fee_per_gas: U256::exp10(9),
+        }
+    }
+}
+
+impl FeeHistoryStrategy {
+    /// Fees of `history`, requested with the rewards of `self.percentile` only
+    pub fn fees_of(&self, history: &FeeHistory) -> Result<Fees> {
+        // the base fees end with the one of the next block
+        let next_base_fee = history.base_fee_per_gas.last().cloned().ok_or_else(|| eyre::Error::msg("fee history without base fee"))?;
+        let mut rewards: Vec<U256> = history.reward.iter().flatten().filter_map(|rewards| rewards.first().cloned()).collect();
+        rewards.sort();
+        let max_priority_fee_per_gas = rewards.get(rewards.len() / 2).cloned().unwrap_or_default().max(self.min_priority_fee_per_gas);
+        Ok(Fees::Eip1559 {
+            max_fee_per_gas: next_base_fee * 2 + max_priority_fee_per_gas,
+            max_priority_fee_per_gas,
+        })
+    }
+}
+
+impl FeeStrategy for FeeHistoryStrategy {
+    fn fees<'a>(&'a self, eth: &'a Eth<Http>) -> BoxFuture<'a, Result<Fees>> {
+        async move {
+            let history = eth.fee_history(self.blocks.into(), BlockNumber::Latest, Some(vec![self.percentile])).await?;
+            self.fees_of(&history)
+        }
+        .boxed()
+    }
+}
+
+/// The same fees for every transaction
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixedFeeStrategy {
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+}
+
+impl FeeStrategy for FixedFeeStrategy {
+    fn fees<'a>(&'a self, _eth: &'a Eth<Http>) -> BoxFuture<'a, Result<Fees>> {
+        future::ready(Ok(Fees::Eip1559 {
+            max_fee_per_gas: self.max_fee_per_gas,
+            max_priority_fee_per_gas: self.max_priority_fee_per_gas,
+        }))
+        .boxed()
+    }
+}
+
+/// Legacy transactions with the `eth_gasPrice` of the node
+#[derive(Debug, Clone, PartialEq)]
+pub struct LegacyGasPriceStrategy;
+
+impl FeeStrategy for LegacyGasPriceStrategy {
+    fn fees<'a>(&'a self, eth: &'a Eth<Http>) -> BoxFuture<'a, Result<Fees>> {
+        async move { Ok(Fees::Legacy { gas_price: eth.gas_price().await? }) }.boxed()
+    }
+}
+
+/// Fee strategy of the bridge transactions, with the cap of each operation and the raise of each replacement
+#[derive(Debug, Clone)]
+pub struct FeePolicy {
+    pub strategy: Arc<dyn FeeStrategy>,
+    /// max fee per gas of the operations without a cap in `caps`
+    pub max_fee_per_gas: U256,
+    pub caps: HashMap<Operation, U256>,
+    /// raise of the fees of a replacement, nodes reject replacements raised by less than 10%
+    pub escalation_percent: u64,
+    /// replacements of a transaction not confirmed in time, 0 to fail on the first timeout
+    pub max_escalations: u32,
+    /// wait for the receipt of a transaction before replacing it
+    pub confirmation_timeout: Duration,
+    /// interval of the polls for new blocks while waiting for a receipt
+    pub poll_interval: Duration,
+}
+
+impl Default for FeePolicy {
+    fn default() -> Self {
+        FeePolicy {
+            strategy: Arc::new(FeeHistoryStrategy::default()),
+            max_fee_per_gas: U256::exp10(9) * 200,
+            caps: HashMap::new(),
+            escalation_percent: 15,
+            max_escalations: 3,
+            confirmation_timeout: TX_CONFIRMATIONS_TIMEOUT,
+            poll_interval: Duration::from_secs(10),
+        }
+    }
+}
+
+impl FeePolicy {
+    /// Policy of the env vars, fees in gwei:
+    /// `ETH_FEE_STRATEGY` is `fee-history` (default, `ETH_FEE_HISTORY_PERCENTILE`), `fixed` (`ETH_MAX_FEE_PER_GAS`,
+    /// `ETH_MAX_PRIORITY_FEE_PER_GAS`) or `legacy`, `ETH_MAX_GAS_PRICE` caps every operation unless
+    /// `ETH_MAX_GAS_PRICE_VALSET_RELAY` or `ETH_MAX_GAS_PRICE_BATCH_RELAY` is set, and replacements are
+    /// raised by `ETH_FEE_ESCALATION_PERCENT` at most `ETH_FEE_MAX_ESCALATIONS` times, each after
+    /// `ETH_TX_CONFIRM_TIMEOUT` seconds without a receipt
+    pub fn from_env() -> Result<Self> {
+        let mut policy = FeePolicy::default();
+        if let Some(max_fee_per_gas) = env_gwei("ETH_MAX_GAS_PRICE")? {
+            policy.max_fee_per_gas = max_fee_per_gas;
+        }
+        policy.strategy = match env("ETH_FEE_STRATEGY").as_deref() {
+            None | Some("fee-history") => {
+                let mut strategy = FeeHistoryStrategy::default();
+                if let Some(percentile) = env("ETH_FEE_HISTORY_PERCENTILE") {
+                    strategy.percentile = percentile.parse().map_err(|_| eyre::Error::msg(format!("invalid ETH_FEE_HISTORY_PERCENTILE '{}'", percentile)))?;
+                }
+                Arc::new(strategy)
+            }
+            Some("fixed") => Arc::new(FixedFeeStrategy {
+                max_fee_per_gas: env_gwei("ETH_MAX_FEE_PER_GAS")?.unwrap_or(policy.max_fee_per_gas),
+                max_priority_fee_per_gas: env_gwei("ETH_MAX_PRIORITY_FEE_PER_GAS")?.unwrap_or_else(|| U256::exp10(8) * 12),
+            }),
+            Some("legacy") => Arc::new(LegacyGasPriceStrategy),
+            Some(strategy) => return Err(eyre::Error::msg(format!("unknown ETH_FEE_STRATEGY '{}'", strategy))),
+        };
+        for (operation, name) in [(Operation::ValsetRelay, "ETH_MAX_GAS_PRICE_VALSET_RELAY"), (Operation::BatchRelay, "ETH_MAX_GAS_PRICE_BATCH_RELAY")].iter() {
+            if let Some(cap) = env_gwei(name)? {
+                policy.caps.insert(*operation, cap);
+            }
+        }
+        if let Some(percent) = env("ETH_FEE_ESCALATION_PERCENT") {
+            policy.escalation_percent = percent.parse().map_err(|_| eyre::Error::msg(format!("invalid ETH_FEE_ESCALATION_PERCENT '{}'", percent)))?;
+        }
+        if let Some(escalations) = env("ETH_FEE_MAX_ESCALATIONS") {
+            policy.max_escalations = escalations.parse().map_err(|_| eyre::Error::msg(format!("invalid ETH_FEE_MAX_ESCALATIONS '{}'", escalations)))?;
+        }
+        if let Some(timeout) = env("ETH_TX_CONFIRM_TIMEOUT") {
+            let seconds = timeout.parse().map_err(|_| eyre::Error::msg(format!("invalid ETH_TX_CONFIRM_TIMEOUT '{}'", timeout)))?;
+            policy.confirmation_timeout = Duration::from_secs(seconds);
+        }
+        Ok(policy)
+    }
+
+    /// Max fee per gas of `operation`
+    pub fn cap(&self, operation: Operation) -> U256 {
+        self.caps.get(&operation).cloned().unwrap_or(self.max_fee_per_gas)
+    }
+
+    /// Fees of the first transaction of `operation`.
+    /// Before London the fees are the gas price of the node, whatever the strategy
+    pub async fn fees(&self, eth: &Eth<Http>, operation: Operation) -> Result<Fees> {
+        let block = eth.block(BlockId::Number(BlockNumber::Latest)).await?.ok_or_else(|| eyre::Error::msg("invalid block"))?;
+        let fees = match block.base_fee_per_gas {
+            Some(_) => self.strategy.fees(eth).await?,
+            None => LegacyGasPriceStrategy.fees(eth).await?,
+        };
+        self.limit(operation, fees, block.base_fee_per_gas)
+    }
+
+    /// Fees of the replacement of a transaction of `operation` paying `fees`, raised by `escalation_percent` up to the cap
+    pub fn escalate(&self, operation: Operation, fees: Fees) -> Fees {
+        fees.escalate(1, self.escalation_percent).cap(self.cap(operation))
+    }
+
+    /// `fees` capped for `operation`, an error when the base fee or the gas price is above the cap already
+    fn limit(&self, operation: Operation, fees: Fees, base_fee_per_gas: Option<U256>) -> Result<Fees> {
+        let cap = self.cap(operation);
+        let min_gas_price = match fees {
+            Fees::Eip1559 { .. } => base_fee_per_gas.unwrap_or_default(),
+            Fees::Legacy { gas_price } => gas_price,
+        };
+        if min_gas_price > cap {
+            return Err(eyre::Error::msg(format!("{} gas price {} > max gas price {}", operation.as_str(), min_gas_price, cap)));
+        }
+        Ok(fees.cap(cap))
+    }
+}
+
+fn env(name: &str) -> Option<String> {
+    std::env::var(name).ok()
+}
+
+fn env_gwei(name: &str) -> Result<Option<U256>> {
+    match env(name) {
+        Some(gwei) => Ok(Some(parse_units(gwei.as_str(), 9).map_err(|err| eyre::Error::msg(format!("invalid {}: {}", name, err)))?)),
+        None => Ok(None),
+    }
+}
+
+/// Add the fee `receipt` paid to the metrics of `operation`
+pub fn observe_fee_paid(operation: Operation, receipt: &TransactionReceipt) {
+    let (gas_used, gas_price) = match (receipt.gas_used, receipt.effective_gas_price) {
+        (Some(gas_used), Some(gas_price)) => (gas_used, gas_price),
+        _ => return,
+    };
+    let gwei = |wei: U256| wei.low_u128() as f64 / 1e9;
+    prometheus::metrics::ETH_TX_EFFECTIVE_GAS_PRICE.with_label_values(&[operation.as_str()]).set(gwei(gas_price));
+    prometheus::metrics::ETH_TX_FEE_PAID.with_label_values(&[operation.as_str()]).inc_by(gwei(gas_used * gas_price));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gwei(gwei: u64) -> U256 {
+        U256::exp10(9) * gwei
+    }
+
+    #[test]
+    fn test_fee_history_fees() {
+        let strategy = FeeHistoryStrategy::default();
+        let history = FeeHistory {
+            oldest_block: BlockNumber::Number(1.into()),
+            base_fee_per_gas: vec![gwei(20), gwei(30), gwei(40), gwei(50)],
+            gas_used_ratio: vec![0.5, 0.9, 0.9],
+            reward: Some(vec![vec![gwei(3)], vec![gwei(2)], vec![gwei(5)]]),
+        };
+        let fees = Fees::Eip1559 {
+            max_fee_per_gas: gwei(103),
+            max_priority_fee_per_gas: gwei(3),
+        };
+        assert_eq!(strategy.fees_of(&history).unwrap(), fees);
+
+        let empty_blocks = FeeHistory {
+            reward: Some(vec![vec![U256::zero()]; 3]),
+            ..history
+        };
+        assert_eq!(strategy.fees_of(&empty_blocks).unwrap().max_fee_per_gas(), gwei(101));
+    }
+
+    #[test]
+    fn test_limit() {
+        let mut policy = FeePolicy {
+            strategy: Arc::new(LegacyGasPriceStrategy),
+            max_fee_per_gas: gwei(100),
+            caps: HashMap::new(),
+            escalation_percent: 15,
+            max_escalations: 3,
+            confirmation_timeout: TX_CONFIRMATIONS_TIMEOUT,
+            poll_interval: Duration::from_secs(10),
+        };
+        policy.caps.insert(Operation::ValsetRelay, gwei(150));
+        let fees = Fees::Eip1559 {
+            max_fee_per_gas: gwei(90),
+            max_priority_fee_per_gas: gwei(2),
+        };
+
+        assert_eq!(policy.limit(Operation::BatchRelay, fees, Some(gwei(44))).unwrap(), fees);
+        let escalated = Fees::Eip1559 {
+            max_fee_per_gas: gwei(100),
+            max_priority_fee_per_gas: U256::from(2_300_000_000u64),
+        };
+        assert_eq!(policy.escalate(Operation::BatchRelay, fees), escalated);
+        let twice = policy.escalate(Operation::ValsetRelay, policy.escalate(Operation::ValsetRelay, fees));
+        assert_eq!(twice.max_fee_per_gas(), U256::from(119_025_000_000u64));
+        assert!(policy.limit(Operation::BatchRelay, fees, Some(gwei(101))).is_err());
+        assert!(policy.limit(Operation::ValsetRelay, fees, Some(gwei(101))).is_ok());
+
+        let legacy = Fees::Legacy { gas_price: gwei(120) };
+        assert!(policy.limit(Operation::SendToFx, legacy, None).is_err());
+        assert_eq!(policy.escalate(Operation::ValsetRelay, legacy), Fees::Legacy { gas_price: gwei(138) });
+    }
+
+    #[test]
+    fn test_cap() {
+        let fees = Fees::Eip1559 {
+            max_fee_per_gas: gwei(10),
+            max_priority_fee_per_gas: gwei(8),
+        };
+        let capped = Fees::Eip1559 {
+            max_fee_per_gas: gwei(5),
+            max_priority_fee_per_gas: gwei(5),
+        };
+        assert_eq!(fees.cap(gwei(5)), capped);
+        assert_eq!(fees.cap(gwei(50)), fees);
+    }
+}
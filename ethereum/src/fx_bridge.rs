@@ -1,8 +1,6 @@
 use std::fmt::Debug;
-use std::time;
 
 use eyre::Result;
-use sha3::{Digest, Keccak256};
 use web3::api::{Eth, Namespace};
 use web3::contract::{Contract, Options};
 use web3::contract::tokens::Tokenize;
@@ -11,15 +9,24 @@ use web3::ethabi::Hash;
 use web3::signing::Key;
 use web3::transports::Http;
 use web3::types::{Address, BlockId, BlockNumber, Bytes, CallRequest, TransactionParameters, U256, U64};
-use web3::types::{FilterBuilder, Log, TransactionReceipt};
+use web3::types::{FilterBuilder, Log, SignedTransaction, TransactionReceipt};
 use web3::Web3;
 
-use crate::confirm_tx::send_raw_transaction_with_confirmation;
-use crate::gas_price::get_max_gas_price;
+use crate::abi::fx_bridge::{
+    BridgeTokensCall, CheckAssetStatusCall, CheckValidatorSignaturesCall, GetBridgeTokenListCall, LastBatchNonceCall, MakeCheckpointCall, OwnerCall, SendToFxCall, StateFxBridgeIdCall,
+    StateFxOriginatedTokenCall, StateLastBatchNoncesCall, StateLastEventNonceCall, StateLastValsetCheckpointCall, StateLastValsetNonceCall, StatePowerThresholdCall, SubmitBatchCall, UpdateValsetCall,
+};
+use crate::abi::{self, ContractCall, ContractEvent};
+use crate::confirm_tx::{is_confirm_timeout, wait_for_any_receipt};
+use crate::fee::{observe_fee_paid, FeePolicy, Fees, Operation};
+use crate::multicall::Multicall;
 use crate::private_key::PrivateKey;
+use crate::revert::FxBridgeError;
+#[cfg(feature = "simulate")]
+use crate::simulate::{Simulation, Simulator};
 use crate::TX_CONFIRMATIONS_BLOCK_NUMBER;
 
-const FX_BRIDGE_ABI: &str = r#"[{"inputs":[{"internalType":"bytes32","name":"_fxBridgeId","type":"bytes32"},{"internalType":"uint256","name":"_powerThreshold","type":"uint256"},{"internalType":"address[]","name":"_validators","type":"address[]"},{"internalType":"uint256[]","name":"_powers","type":"uint256[]"}],"stateMutability":"nonpayable","type":"constructor"},{"anonymous":false,"inputs":[{"indexed":true,"internalType":"address","name":"_tokenContract","type":"address"},{"indexed":false,"internalType":"string","name":"_name","type":"string"},{"indexed":false,"internalType":"string","name":"_symbol","type":"string"},{"indexed":false,"internalType":"uint8","name":"_decimals","type":"uint8"},{"indexed":false,"internalType":"uint256","name":"_eventNonce","type":"uint256"}],"name":"FxOriginatedTokenEvent","type":"event"},{"anonymous":false,"inputs":[{"indexed":true,"internalType":"address","name":"previousOwner","type":"address"},{"indexed":true,"internalType":"address","name":"newOwner","type":"address"}],"name":"OwnershipTransferred","type":"event"},{"anonymous":false,"inputs":[{"indexed":true,"internalType":"address","name":"_tokenContract","type":"address"},{"indexed":true,"internalType":"address","name":"_sender","type":"address"},{"indexed":true,"internalType":"bytes32","name":"_destination","type":"bytes32"},{"indexed":false,"internalType":"bytes32","name":"_targetIBC","type":"bytes32"},{"indexed":false,"internalType":"uint256","name":"_amount","type":"uint256"},{"indexed":false,"internalType":"uint256","name":"_eventNonce","type":"uint256"}],"name":"SendToFxEvent","type":"event"},{"anonymous":false,"inputs":[{"indexed":true,"internalType":"uint256","name":"_batchNonce","type":"uint256"},{"indexed":true,"internalType":"address","name":"_token","type":"address"},{"indexed":false,"internalType":"uint256","name":"_eventNonce","type":"uint256"}],"name":"TransactionBatchExecutedEvent","type":"event"},{"anonymous":false,"inputs":[{"indexed":true,"internalType":"uint256","name":"_newValsetNonce","type":"uint256"},{"indexed":false,"internalType":"uint256","name":"_eventNonce","type":"uint256"},{"indexed":false,"internalType":"address[]","name":"_validators","type":"address[]"},{"indexed":false,"internalType":"uint256[]","name":"_powers","type":"uint256[]"}],"name":"ValsetUpdatedEvent","type":"event"},{"anonymous":false,"inputs":[{"indexed":false,"internalType":"address","name":"_tokenContract","type":"address"},{"indexed":false,"internalType":"address","name":"_newOwner","type":"address"}],"name":"transferOwnerEvent","type":"event"},{"inputs":[{"internalType":"address","name":"_tokenAddr","type":"address"}],"name":"addBridgeToken","outputs":[{"internalType":"bool","name":"","type":"bool"}],"stateMutability":"nonpayable","type":"function"},{"inputs":[{"internalType":"uint256","name":"","type":"uint256"}],"name":"bridgeTokens","outputs":[{"internalType":"address","name":"","type":"address"}],"stateMutability":"view","type":"function"},{"inputs":[{"internalType":"address","name":"_tokenAddr","type":"address"}],"name":"checkAssetStatus","outputs":[{"internalType":"bool","name":"","type":"bool"}],"stateMutability":"view","type":"function"},{"inputs":[{"internalType":"address[]","name":"_currentValidators","type":"address[]"},{"internalType":"uint256[]","name":"_currentPowers","type":"uint256[]"},{"internalType":"uint8[]","name":"_v","type":"uint8[]"},{"internalType":"bytes32[]","name":"_r","type":"bytes32[]"},{"internalType":"bytes32[]","name":"_s","type":"bytes32[]"},{"internalType":"bytes32","name":"_theHash","type":"bytes32"},{"internalType":"uint256","name":"_powerThreshold","type":"uint256"}],"name":"checkValidatorSignatures","outputs":[],"stateMutability":"pure","type":"function"},{"inputs":[{"internalType":"address","name":"_tokenAddr","type":"address"}],"name":"delBridgeToken","outputs":[{"internalType":"bool","name":"","type":"bool"}],"stateMutability":"nonpayable","type":"function"},{"inputs":[],"name":"getBridgeTokenList","outputs":[{"components":[{"internalType":"address","name":"addr","type":"address"},{"internalType":"string","name":"name","type":"string"},{"internalType":"string","name":"symbol","type":"string"},{"internalType":"uint8","name":"decimals","type":"uint8"}],"internalType":"struct FxBridge.BridgeToken[]","name":"","type":"tuple[]"}],"stateMutability":"view","type":"function"},{"inputs":[{"internalType":"address","name":"_erc20Address","type":"address"}],"name":"lastBatchNonce","outputs":[{"internalType":"uint256","name":"","type":"uint256"}],"stateMutability":"view","type":"function"},{"inputs":[{"internalType":"address[]","name":"_validators","type":"address[]"},{"internalType":"uint256[]","name":"_powers","type":"uint256[]"},{"internalType":"uint256","name":"_valsetNonce","type":"uint256"},{"internalType":"bytes32","name":"_fxBridgeId","type":"bytes32"}],"name":"makeCheckpoint","outputs":[{"internalType":"bytes32","name":"","type":"bytes32"}],"stateMutability":"pure","type":"function"},{"inputs":[],"name":"owner","outputs":[{"internalType":"address","name":"","type":"address"}],"stateMutability":"view","type":"function"},{"inputs":[],"name":"renounceOwnership","outputs":[],"stateMutability":"nonpayable","type":"function"},{"inputs":[{"internalType":"address","name":"_tokenContract","type":"address"},{"internalType":"bytes32","name":"_destination","type":"bytes32"},{"internalType":"bytes32","name":"_targetIBC","type":"bytes32"},{"internalType":"uint256","name":"_amount","type":"uint256"}],"name":"sendToFx","outputs":[],"stateMutability":"nonpayable","type":"function"},{"inputs":[{"internalType":"address","name":"_tokenAddr","type":"address"}],"name":"setFxOriginatedToken","outputs":[{"internalType":"bool","name":"","type":"bool"}],"stateMutability":"nonpayable","type":"function"},{"inputs":[],"name":"state_fxBridgeId","outputs":[{"internalType":"bytes32","name":"","type":"bytes32"}],"stateMutability":"view","type":"function"},{"inputs":[],"name":"state_fxOriginatedToken","outputs":[{"internalType":"address","name":"","type":"address"}],"stateMutability":"view","type":"function"},{"inputs":[{"internalType":"address","name":"","type":"address"}],"name":"state_lastBatchNonces","outputs":[{"internalType":"uint256","name":"","type":"uint256"}],"stateMutability":"view","type":"function"},{"inputs":[],"name":"state_lastEventNonce","outputs":[{"internalType":"uint256","name":"","type":"uint256"}],"stateMutability":"view","type":"function"},{"inputs":[],"name":"state_lastValsetCheckpoint","outputs":[{"internalType":"bytes32","name":"","type":"bytes32"}],"stateMutability":"view","type":"function"},{"inputs":[],"name":"state_lastValsetNonce","outputs":[{"internalType":"uint256","name":"","type":"uint256"}],"stateMutability":"view","type":"function"},{"inputs":[],"name":"state_powerThreshold","outputs":[{"internalType":"uint256","name":"","type":"uint256"}],"stateMutability":"view","type":"function"},{"inputs":[{"internalType":"address[]","name":"_currentValidators","type":"address[]"},{"internalType":"uint256[]","name":"_currentPowers","type":"uint256[]"},{"internalType":"uint8[]","name":"_v","type":"uint8[]"},{"internalType":"bytes32[]","name":"_r","type":"bytes32[]"},{"internalType":"bytes32[]","name":"_s","type":"bytes32[]"},{"internalType":"uint256[]","name":"_amounts","type":"uint256[]"},{"internalType":"address[]","name":"_destinations","type":"address[]"},{"internalType":"uint256[]","name":"_fees","type":"uint256[]"},{"internalType":"uint256[2]","name":"_nonceArray","type":"uint256[2]"},{"internalType":"address","name":"_tokenContract","type":"address"},{"internalType":"uint256","name":"_batchTimeout","type":"uint256"},{"internalType":"address","name":"_feeReceive","type":"address"}],"name":"submitBatch","outputs":[],"stateMutability":"nonpayable","type":"function"},{"inputs":[{"internalType":"address","name":"_token","type":"address"},{"internalType":"address","name":"_newOwner","type":"address"}],"name":"transferOwner","outputs":[{"internalType":"bool","name":"","type":"bool"}],"stateMutability":"nonpayable","type":"function"},{"inputs":[{"internalType":"address","name":"newOwner","type":"address"}],"name":"transferOwnership","outputs":[],"stateMutability":"nonpayable","type":"function"},{"inputs":[{"internalType":"address[]","name":"_newValidators","type":"address[]"},{"internalType":"uint256[]","name":"_newPowers","type":"uint256[]"},{"internalType":"uint256","name":"_newValsetNonce","type":"uint256"},{"internalType":"address[]","name":"_currentValidators","type":"address[]"},{"internalType":"uint256[]","name":"_currentPowers","type":"uint256[]"},{"internalType":"uint256","name":"_currentValsetNonce","type":"uint256"},{"internalType":"uint8[]","name":"_v","type":"uint8[]"},{"internalType":"bytes32[]","name":"_r","type":"bytes32[]"},{"internalType":"bytes32[]","name":"_s","type":"bytes32[]"}],"name":"updateValset","outputs":[],"stateMutability":"nonpayable","type":"function"}]"#;
+const FX_BRIDGE_ABI: &str = include_str!("../abi/FxBridge.json");
 
 #[derive(Debug, Clone)]
 pub struct FxBridge {
@@ -28,6 +35,7 @@ pub struct FxBridge {
     options: Options,
     private_key: Option<PrivateKey>,
     from: Address,
+    fee_policy: FeePolicy,
 }
 
 impl FxBridge {
@@ -46,66 +54,59 @@ impl FxBridge {
             options,
             private_key,
             from,
+            fee_policy: FeePolicy::default(),
         }
     }
+    /// Sign the transactions with the fees of `fee_policy`, the fees of `Options` still override it
+    pub fn with_fee_policy(mut self, fee_policy: FeePolicy) -> Self {
+        self.fee_policy = fee_policy;
+        self
+    }
+    /// Address of the FxBridge contract
+    pub fn address(&self) -> Address {
+        self.contract.address()
+    }
+    /// Batch of view calls on the node of the bridge, one `eth_call` to `multicall` or one JSON-RPC batch without it
+    pub fn multicall(&self, multicall: Option<Address>) -> Multicall {
+        Multicall::new(self.eth.clone(), self.from, multicall)
+    }
     ///"Calls the contract's `state_fxOriginatedToken` () function"
     pub async fn fx_originated_token(&self) -> Result<Address> {
-        let result = self
-            .contract
-            .query("state_fxOriginatedToken", (), self.from, self.options.clone(), BlockId::Number(BlockNumber::Latest))
-            .await?;
-        Ok(result)
+        self.query(StateFxOriginatedTokenCall {}).await
     }
     ///"Calls the contract's `bridgeTokens` (0x70e5a898) function"
     pub async fn bridge_tokens(&self, index: U256) -> Result<Address> {
-        let result = self
-            .contract
-            .query("bridgeTokens", index, self.from, self.options.clone(), BlockId::Number(BlockNumber::Latest))
-            .await?;
-        Ok(result)
+        self.query(BridgeTokensCall { arg0: index }).await
+    }
+    ///"Calls the contract's `getBridgeTokenList` function"
+    pub async fn get_bridge_token_list(&self) -> Result<Vec<BridgeToken>> {
+        self.query(GetBridgeTokenListCall {}).await
     }
     ///"Calls the contract's `checkAssetStatus` () function"
     pub async fn check_asset_status(&self, token_addr: Address) -> Result<bool> {
-        let result = self
-            .contract
-            .query("checkAssetStatus", token_addr, self.from, self.options.clone(), BlockId::Number(BlockNumber::Latest))
-            .await?;
-        Ok(result)
+        self.query(CheckAssetStatusCall { token_addr }).await
     }
     ///"Calls the contract's `lastBatchNonce` (0x011b2174) function"
     pub async fn last_batch_nonce(&self, erc20_address: Address) -> Result<U256> {
-        let result = self
-            .contract
-            .query("lastBatchNonce", erc20_address, self.from, self.options.clone(), BlockId::Number(BlockNumber::Latest))
-            .await?;
-        Ok(result)
+        self.query(LastBatchNonceCall { erc20_address }).await
     }
     ///"Calls the contract's `makeCheckpoint` (0x71cbf381) function"
     pub async fn make_checkpoint(&self, validators: Vec<Address>, powers: Vec<U256>, valset_nonce: U256, fx_bridge_id: [u8; 32]) -> Result<[u8; 32]> {
-        let result = self
-            .contract
-            .query(
-                "makeCheckpoint",
-                (validators, powers, valset_nonce, fx_bridge_id),
-                self.from,
-                self.options.clone(),
-                BlockId::Number(BlockNumber::Latest),
-            )
-            .await?;
-        Ok(result)
+        self.query(MakeCheckpointCall {
+            validators,
+            powers,
+            valset_nonce,
+            fx_bridge_id,
+        })
+        .await
     }
     ///"Calls the contract's `owner` (0x8da5cb5b) function"
     pub async fn owner(&self) -> Result<Address> {
-        let result = self.contract.query("owner", (), self.from, self.options.clone(), BlockId::Number(BlockNumber::Latest)).await?;
-        Ok(result)
+        self.query(OwnerCall {}).await
     }
     ///"Calls the contract's `state_fxBridgeId` (0xf92367fd) function"
     pub async fn state_fx_bridge_id(&self) -> Result<[u8; 32]> {
-        let result = self
-            .contract
-            .query("state_fxBridgeId", (), self.from, self.options.clone(), BlockId::Number(BlockNumber::Latest))
-            .await?;
-        Ok(result)
+        self.query(StateFxBridgeIdCall {}).await
     }
     ///"Calls the contract's `state_invalidationMapping` (0x7dfb6f86) function"
     pub async fn state_invalidation_mapping(&self, bytes: [u8; 32]) -> Result<U256> {
@@ -117,187 +118,239 @@ impl FxBridge {
     }
     ///"Calls the contract's `state_lastBatchNonces` (0xdf97174b) function"
     pub async fn state_last_batch_nonces(&self, address: Address) -> Result<U256> {
-        let result = self
-            .contract
-            .query("state_lastBatchNonces", address, self.from, self.options.clone(), BlockId::Number(BlockNumber::Latest))
-            .await?;
-        Ok(result)
+        self.query(StateLastBatchNoncesCall { arg0: address }).await
     }
     /// "Calls the contract's `state_lastEventNonce` (0x73b20547) function"
     pub async fn state_last_event_nonce(&self) -> Result<U256> {
-        let result = self
-            .contract
-            .query("state_lastEventNonce", (), self.from, self.options.clone(), BlockId::Number(BlockNumber::Latest))
-            .await?;
-        Ok(result)
+        self.query(StateLastEventNonceCall {}).await
     }
     ///"Calls the contract's `state_lastValsetCheckpoint` (0xf2b53307) function"
     pub async fn state_last_valset_checkpoint(&self) -> Result<[u8; 32]> {
-        let result = self
-            .contract
-            .query("state_lastValsetCheckpoint", (), self.from, self.options.clone(), BlockId::Number(BlockNumber::Latest))
-            .await?;
-        Ok(result)
+        self.query(StateLastValsetCheckpointCall {}).await
     }
     /// "Calls the contract's `state_lastValsetNonce` (0xb56561fe) function"
     pub async fn state_last_valset_nonce(&self) -> Result<U256> {
-        let result = self
-            .contract
-            .query("state_lastValsetNonce", (), self.from, self.options.clone(), BlockId::Number(BlockNumber::Latest))
-            .await?;
-        Ok(result)
+        self.query(StateLastValsetNonceCall {}).await
     }
     /// "Calls the contract's `state_powerThreshold` (0xe5a2b5d2) function"
     pub async fn state_power_threshold(&self) -> Result<U256> {
-        let result = self
-            .contract
-            .query("state_powerThreshold", (), self.from, self.options.clone(), BlockId::Number(BlockNumber::Latest))
-            .await?;
-        Ok(result)
-    }
-    ///"Calls the contract's `checkValidatorSignatures` (0xea10bb20) function"
-    pub async fn check_validator_signatures(
-        &self,
-        current_validators: Vec<Address>,
-        current_powers: Vec<U256>,
-        v: Vec<u8>,
-        r: Vec<[u8; 32]>,
-        s: Vec<[u8; 32]>,
-        the_hash: [u8; 32],
-        power_threshold: U256,
-    ) -> Result<Vec<u8>> {
-        let result = self
-            .contract
-            .query(
-                "checkValidatorSignatures",
-                (current_validators, current_powers, v, r, s, the_hash, power_threshold),
-                self.from,
-                self.options.clone(),
-                BlockId::Number(BlockNumber::Latest),
-            )
-            .await?;
-        Ok(result)
+        self.query(StatePowerThresholdCall {}).await
+    }
+    ///"Calls the contract's `checkValidatorSignatures` (0xea10bb20) function", which reverts when the signatures don't have enough power
+    ///
+    /// Takes the generated call instead of one argument per parameter, and returns `()` instead of the raw output bytes
+    pub async fn check_validator_signatures(&self, call: CheckValidatorSignaturesCall) -> Result<()> {
+        self.query(call).await
+    }
+    /// Every token of `getBridgeTokenList` with its bridge state, read with one `multicall`
+    pub async fn bridge_token_statuses(&self, multicall: Option<Address>) -> Result<Vec<BridgeTokenStatus>> {
+        let bridge_tokens = self.get_bridge_token_list().await?;
+        let mut batch = self.multicall(multicall);
+        let fx_originated_token = batch.add(self.address(), StateFxOriginatedTokenCall {});
+        let calls: Vec<_> = bridge_tokens
+            .iter()
+            .map(|token| {
+                (
+                    batch.add(self.address(), CheckAssetStatusCall { token_addr: token.addr }),
+                    batch.add(self.address(), LastBatchNonceCall { erc20_address: token.addr }),
+                )
+            })
+            .collect();
+        let results = batch.call().await?;
+        let fx_originated_token = fx_originated_token.decode(results.as_slice())?;
+        let mut tokens = Vec::new();
+        for (token, (active, last_batch_nonce)) in bridge_tokens.into_iter().zip(calls) {
+            tokens.push(BridgeTokenStatus {
+                fx_originated: token.addr == fx_originated_token,
+                addr: token.addr,
+                name: token.name,
+                symbol: token.symbol,
+                decimals: token.decimals,
+                active: active.decode(results.as_slice())?,
+                last_batch_nonce: last_batch_nonce.decode(results.as_slice())?,
+            });
+        }
+        Ok(tokens)
     }
-    ///"Calls the contract's `sendToFx` (0xd593c5bf) function"
+    ///"Calls the contract's `sendToFx` (0x6189d107) function"
     pub async fn send_to_fx(&self, token_contract: Address, destination: [u8; 32], target_ibc: [u8; 32], amount: U256) -> Result<TransactionReceipt> {
-        let transaction_receipt = self
-            .signed_call_with_confirmations("sendToFx", (token_contract, destination, target_ibc, amount), TX_CONFIRMATIONS_BLOCK_NUMBER)
-            .await?;
-        Ok(transaction_receipt)
+        let call = SendToFxCall {
+            token_contract,
+            destination,
+            target_ibc,
+            amount,
+        };
+        self.signed_call_with_confirmations(SendToFxCall::NAME, call, TX_CONFIRMATIONS_BLOCK_NUMBER).await
     }
     ///"Calls the contract's `submitBatch` function"
-    pub async fn submit_batch(
-        &self,
-        current_validators: Vec<Token>,
-        current_powers: Vec<Token>,
-        v: Vec<Token>,
-        r: Vec<Token>,
-        s: Vec<Token>,
-        amounts: Vec<Token>,
-        destinations: Vec<Token>,
-        fees: Vec<Token>,
-        nonce_array: [U256; 2],
-        token_contract: Address,
-        batch_timeout: U256,
-        fee_receive: Address,
-    ) -> Result<TransactionReceipt> {
-        let transaction_receipt = self
-            .signed_call_with_confirmations(
-                "submitBatch",
-                (
-                    current_validators,
-                    current_powers,
-                    v,
-                    r,
-                    s,
-                    amounts,
-                    destinations,
-                    fees,
-                    nonce_array,
-                    token_contract,
-                    batch_timeout,
-                    fee_receive,
-                ),
-                TX_CONFIRMATIONS_BLOCK_NUMBER,
-            )
-            .await?;
-        Ok(transaction_receipt)
+    ///
+    /// Takes the generated call instead of one `Vec<Token>` per parameter
+    pub async fn submit_batch(&self, call: SubmitBatchCall) -> Result<TransactionReceipt> {
+        self.signed_call_with_confirmations(SubmitBatchCall::NAME, call, TX_CONFIRMATIONS_BLOCK_NUMBER).await
     }
     ///"Calls the contract's `updateValset` (0xe3cb9f62) function"
-    pub async fn update_valset(
-        &self,
-        new_validators: Vec<Token>,
-        new_powers: Vec<Token>,
-        new_valset_nonce: U256,
-        current_validators: Vec<Token>,
-        current_powers: Vec<Token>,
-        current_valset_nonce: U256,
-        v: Vec<Token>,
-        r: Vec<Token>,
-        s: Vec<Token>,
-    ) -> Result<TransactionReceipt> {
-        let transaction_receipt = self
-            .signed_call_with_confirmations(
-                "updateValset",
-                (new_validators, new_powers, new_valset_nonce, current_validators, current_powers, current_valset_nonce, v, r, s),
-                TX_CONFIRMATIONS_BLOCK_NUMBER,
-            )
-            .await?;
-        Ok(transaction_receipt)
+    ///
+    /// Takes the generated call instead of one `Vec<Token>` per parameter
+    pub async fn update_valset(&self, call: UpdateValsetCall) -> Result<TransactionReceipt> {
+        self.signed_call_with_confirmations(UpdateValsetCall::NAME, call, TX_CONFIRMATIONS_BLOCK_NUMBER).await
+    }
+
+    /// Run a view function of the contract, reverts are decoded into a `FxBridgeError`
+    async fn query<C: ContractCall>(&self, call: C) -> Result<C::Output> {
+        abi::query(&self.eth, self.from, self.contract.address(), call)
+            .await
+            .map_err(|err| match err.downcast_ref::<web3::Error>().and_then(FxBridgeError::from_web3_error) {
+                Some(revert) => revert.into(),
+                None => err,
+            })
     }
 
+    /// Sign, send and confirm a call of `func`. A tx not confirmed in time is replaced by one with the same nonce
+    /// and the fees of the previous one raised, up to the `max_escalations` of the fee policy
     pub async fn signed_call_with_confirmations(&self, func: &str, params: impl Tokenize, confirmations: usize) -> Result<TransactionReceipt> {
+        info!("signed_call_with_confirmations: {}", func);
+        let operation = Operation::of(func);
+        let tokens = params.into_tokens();
+        let nonce = self.next_nonce().await?;
+        let mut fees = self.fees(func).await?;
+        let mut hashes = Vec::new();
+        let mut escalations = 0;
+        let receipt = loop {
+            // the txs sent already may still be mined when a replacement can't be signed or sent
+            match self.sign_call_with_fees(func, tokens.as_slice(), nonce, fees).await {
+                Ok(signed) => match self.eth.send_raw_transaction(signed.raw_transaction).await {
+                    Ok(hash) => hashes.push(hash),
+                    // a tx replaced may be mined already, or the fees at the cap are not raised enough to replace it
+                    Err(err) if !hashes.is_empty() => warn!("Replace {} tx {:?} failed: {}", func, hashes.last(), err),
+                    Err(err) => return Err(err.into()),
+                },
+                Err(err) if !hashes.is_empty() => warn!("Sign replacement of {} tx {:?} failed: {}", func, hashes.last(), err),
+                Err(err) => return Err(err),
+            }
+            let timeout = self.fee_policy.confirmation_timeout;
+            match wait_for_any_receipt(self.eth.transport().clone(), hashes.as_slice(), self.fee_policy.poll_interval, confirmations, timeout).await {
+                Ok(receipt) => break receipt,
+                Err(err) if is_confirm_timeout(&err) && escalations < self.fee_policy.max_escalations => {
+                    let percent = self.fee_policy.escalation_percent;
+                    warn!("{} tx {:?} not confirmed in {:?}, raising its fees {}%", func, hashes.last(), timeout, percent);
+                    prometheus::metrics::ETH_TX_FEE_ESCALATIONS.with_label_values(&[operation.as_str()]).inc();
+                    fees = self.fee_policy.escalate(operation, fees);
+                    escalations += 1;
+                }
+                Err(err) => return Err(err.into()),
+            }
+        };
+        observe_fee_paid(operation, &receipt);
+        if receipt.status == Some(U64::zero()) {
+            let err = self.replay_failed_call(self.encode_input(func, tokens.as_slice())?, &receipt).await;
+            warn!("{} tx {:?} reverted: {}", func, receipt.transaction_hash, err);
+            return Err(err.into());
+        }
+        Ok(receipt)
+    }
+
+    /// Replay the call of a reverted tx with `eth_call` at its block, to get the revert reason the receipt doesn't have
+    pub async fn replay_failed_call(&self, fn_data: Vec<u8>, receipt: &TransactionReceipt) -> FxBridgeError {
+        let call = CallRequest {
+            from: Some(self.from),
+            to: Some(self.contract.address()),
+            value: self.options.value,
+            data: Some(Bytes(fn_data)),
+            ..Default::default()
+        };
+        let block = receipt.block_number.map(|number| BlockId::Number(BlockNumber::Number(number)));
+        match self.eth.call(call, block).await {
+            Err(err) => FxBridgeError::from_web3_error(&err).unwrap_or_else(|| {
+                warn!("Replay tx {:?} failed: {}", receipt.transaction_hash, err);
+                FxBridgeError::Unknown(Vec::new())
+            }),
+            // the tx ran out of gas, or a later tx of the block changed the state it reverted on
+            Ok(_) => FxBridgeError::Unknown(Vec::new()),
+        }
+    }
+
+    /// Build and sign a transaction calling `func` with the nonce, fees and gas limit from the node, without sending it
+    pub async fn sign_call(&self, func: &str, params: impl Tokenize) -> Result<SignedTransaction> {
+        let nonce = self.next_nonce().await?;
+        let fees = self.fees(func).await?;
+        self.sign_call_with_fees(func, params, nonce, fees).await
+    }
+
+    /// Sign a call of `func` with `nonce` and `fees`
+    async fn sign_call_with_fees(&self, func: &str, params: impl Tokenize, nonce: U256, fees: Fees) -> Result<SignedTransaction> {
         if self.private_key.is_none() {
             return Err(eyre::Error::msg("no private key to authorize the transaction with"));
         }
-        info!("signed_call_with_confirmations: {}", func);
-        let poll_interval = time::Duration::from_secs(10);
-        let fn_data = self
-            .contract
-            .abi()
-            .function(func)
-            .and_then(|function| function.encode_input(&params.into_tokens()))
-            .map_err(|err| web3::Error::Decoder(format!("{:?}", err)))?;
+        let fn_data = self.encode_input(func, params)?;
 
         let accounts = web3::api::Accounts::new(self.eth.transport().clone());
         let mut tx = TransactionParameters {
             to: Some(self.contract.address()),
             data: Bytes(fn_data),
+            nonce: Some(nonce),
+            value: self.options.value.unwrap_or_default(),
             ..Default::default()
         };
-        tx.nonce = Some(self.options.nonce.unwrap_or(self.eth.transaction_count(self.from, Some(BlockNumber::Latest)).await?));
-        tx.value = self.options.value.unwrap_or(U256::from(0));
-        let block = self.eth.block(BlockId::Number(BlockNumber::Latest)).await?.ok_or(eyre::Error::msg("invalid block"))?;
-        let gas_price = self.eth.gas_price().await?;
-        let max_gas_price = get_max_gas_price();
-        if gas_price > max_gas_price {
-            return Err(eyre::Error::msg(format!("gas price {} > mas gas price {}", gas_price, max_gas_price)));
-        }
-        if block.base_fee_per_gas.is_some() && self.options.gas_price.is_none() {
-            // tx.max_priority_fee_per_gas = Some(self.options.max_priority_fee_per_gas.unwrap_or(self.eth.gas_price().await? - block.base_fee_per_gas.unwrap()));
-            tx.max_priority_fee_per_gas = Some(self.options.max_priority_fee_per_gas.unwrap_or( U256::from(10).pow(U256::from(8)) * 12));
-            // tx.max_fee_per_gas = Some(self.options.max_fee_per_gas.unwrap_or(block.base_fee_per_gas.unwrap() * 2 + tx.max_priority_fee_per_gas.unwrap()));
-            tx.max_fee_per_gas = Some(self.options.max_fee_per_gas.unwrap_or(gas_price + U256::from(10).pow(U256::from(9)) * 10));
-            if tx.max_fee_per_gas.cmp(&tx.max_priority_fee_per_gas).is_le() {
-                return Err(eyre::Error::msg(format!(
-                    "maxFeePerGas ({:?}) < maxPriorityFeePerGas ({:?})",
-                    tx.max_fee_per_gas, tx.max_priority_fee_per_gas
-                )));
+        match fees {
+            Fees::Eip1559 {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            } => {
+                tx.max_fee_per_gas = Some(max_fee_per_gas);
+                tx.max_priority_fee_per_gas = Some(max_priority_fee_per_gas);
+                tx.transaction_type = Some(U64::from(2));
             }
-            tx.transaction_type = Some(U64::from(2));
-        } else {
-            if self.options.max_fee_per_gas.is_some() || self.options.max_priority_fee_per_gas.is_some() {
-                return Err(eyre::Error::msg("maxFeePerGas or maxPriorityFeePerGas specified but london is not active yet"));
+            Fees::Legacy { gas_price } => {
+                tx.gas_price = Some(gas_price);
+                tx.transaction_type = Some(U64::from(1));
             }
-            tx.gas_price = Some(self.options.gas_price.unwrap_or(gas_price));
-            tx.transaction_type = Some(U64::from(1));
         }
-        tx.gas = self.options.gas.unwrap_or(self.estimate_gas(&tx).await?);
+        tx.gas = match self.options.gas {
+            Some(gas) => gas,
+            None => self.estimate_gas(&tx).await?,
+        };
 
         let key = self.private_key.clone().unwrap();
         let signed = accounts.sign_transaction(tx.clone(), key).await?;
-        let receipt = send_raw_transaction_with_confirmation(self.eth.transport().clone(), signed.raw_transaction, poll_interval, confirmations).await?;
-        return Ok(receipt);
+        Ok(signed)
+    }
+
+    /// Fees of a call of `func` from the fee policy, overridden by the fees of `self.options`
+    async fn fees(&self, func: &str) -> Result<Fees> {
+        if let Some(gas_price) = self.options.gas_price {
+            return Ok(Fees::Legacy { gas_price });
+        }
+        match self.fee_policy.fees(&self.eth, Operation::of(func)).await? {
+            Fees::Eip1559 {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            } => {
+                let max_fee_per_gas = self.options.max_fee_per_gas.unwrap_or(max_fee_per_gas);
+                let max_priority_fee_per_gas = self.options.max_priority_fee_per_gas.unwrap_or(max_priority_fee_per_gas);
+                if max_fee_per_gas < max_priority_fee_per_gas {
+                    return Err(eyre::Error::msg(format!("maxFeePerGas ({}) < maxPriorityFeePerGas ({})", max_fee_per_gas, max_priority_fee_per_gas)));
+                }
+                Ok(Fees::Eip1559 {
+                    max_fee_per_gas,
+                    max_priority_fee_per_gas,
+                })
+            }
+            Fees::Legacy { .. } if self.options.max_fee_per_gas.is_some() || self.options.max_priority_fee_per_gas.is_some() => {
+                Err(eyre::Error::msg("maxFeePerGas or maxPriorityFeePerGas specified but london is not active yet"))
+            }
+            fees => Ok(fees),
+        }
+    }
+
+    /// Nonce of the next transaction of the signer
+    async fn next_nonce(&self) -> Result<U256> {
+        if self.private_key.is_none() {
+            return Err(eyre::Error::msg("no private key to authorize the transaction with"));
+        }
+        match self.options.nonce {
+            Some(nonce) => Ok(nonce),
+            None => Ok(self.eth.transaction_count(self.from, Some(BlockNumber::Latest)).await?),
+        }
     }
 
     pub async fn estimate_gas(&self, tx: &TransactionParameters) -> Result<U256> {
@@ -318,10 +371,54 @@ impl FxBridge {
                 None,
             )
             .await
-            .map_err(Into::into)
+            .map_err(|err| match FxBridgeError::from_web3_error(&err) {
+                Some(revert) => revert.into(),
+                None => err.into(),
+            })
+    }
+
+    /// Run `func` on `simulator` from this bridge's sender, to see whether it reverts before paying for it
+    #[cfg(feature = "simulate")]
+    pub async fn simulate(&self, simulator: &mut Simulator, func: &str, params: impl Tokenize) -> Result<Simulation> {
+        let fn_data = self.encode_input(func, params)?;
+        simulator.call(self.from, self.contract.address(), fn_data).await
+    }
+
+    fn encode_input(&self, func: &str, params: impl Tokenize) -> Result<Vec<u8>> {
+        let fn_data = self
+            .contract
+            .abi()
+            .function(func)
+            .and_then(|function| function.encode_input(&params.into_tokens()))
+            .map_err(|err| web3::Error::Decoder(format!("{:?}", err)))?;
+        Ok(fn_data)
     }
 }
 
+/// A token registered in the FxBridge contract
+pub use crate::abi::fx_bridge::BridgeToken;
+
+impl BridgeToken {
+    /// Decode the `BridgeToken[]` returned by `getBridgeTokenList`
+    pub fn from_tokens(token: Token) -> Result<Vec<BridgeToken>> {
+        abi::to_array(token, BridgeToken::from_token)
+    }
+}
+
+/// A bridge token with its ERC20 metadata and its state in the FxBridge contract
+#[derive(Debug, Default, Clone, Eq, PartialEq, Hash)]
+pub struct BridgeTokenStatus {
+    pub addr: Address,
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    /// `checkAssetStatus`, false once the token is removed from the bridge
+    pub active: bool,
+    pub last_batch_nonce: U256,
+    /// The `state_fxOriginatedToken`, minted by the bridge for the FX sent from f(x)Core
+    pub fx_originated: bool,
+}
+
 pub async fn query_all_event_san_block(
     web3: &Web3<Http>,
     bridge_addr: Address,
@@ -362,12 +459,8 @@ pub async fn query_all_event_san_block(
     Ok((deposits, withdraws, fx_originated_token, valset_updated))
 }
 
-pub async fn query_all_event(
-    web3: &Web3<Http>,
-    bridge_addr: Address,
-    from_block: U64,
-    to_block: Option<U64>,
-) -> Result<(Vec<SendToFxEvent>, Vec<TransactionBatchExecutedEvent>, Vec<FxOriginatedTokenEvent>, Vec<ValsetUpdatedEvent>)> {
+/// Raw logs of the four FxBridge events in a block range
+pub async fn query_bridge_logs(web3: &Web3<Http>, bridge_addr: Address, from_block: U64, to_block: Option<U64>) -> Result<Vec<Log>> {
     let to_block = if to_block.is_some() { BlockNumber::Number(to_block.unwrap()) } else { BlockNumber::Latest };
     let filter_builder = FilterBuilder::default()
         .address(vec![bridge_addr])
@@ -384,7 +477,16 @@ pub async fn query_all_event(
             None,
             None,
         );
-    let logs = web3.eth().logs(filter_builder.build()).await?;
+    Ok(web3.eth().logs(filter_builder.build()).await?)
+}
+
+pub async fn query_all_event(
+    web3: &Web3<Http>,
+    bridge_addr: Address,
+    from_block: U64,
+    to_block: Option<U64>,
+) -> Result<(Vec<SendToFxEvent>, Vec<TransactionBatchExecutedEvent>, Vec<FxOriginatedTokenEvent>, Vec<ValsetUpdatedEvent>)> {
+    let logs = query_bridge_logs(web3, bridge_addr, from_block, to_block).await?;
     let mut deposits = Vec::new();
     let mut withdraws = Vec::new();
     let mut fx_originated_token = Vec::new();
@@ -418,13 +520,19 @@ pub async fn query_valset_updated_event(web3: &Web3<Http>, bridge_addr: Address,
     for log in logs {
         for topic in &log.topics {
             if topic.eq(&ValsetUpdatedEvent::signature()) {
-                valset_updated.push(ValsetUpdatedEvent::from_log(&log).unwrap())
+                valset_updated.push(ValsetUpdatedEvent::from_log(&log)?)
             }
         }
     }
     Ok(valset_updated)
 }
 
+fn log_block_number(input: &Log) -> Result<U64> {
+    input
+        .block_number
+        .ok_or_else(|| eyre::Error::msg("Log does not have block number, we only search logs already in blocks?"))
+}
+
 #[derive(Debug, Default, Clone, Eq, PartialEq, Hash)]
 pub struct FxOriginatedTokenEvent {
     pub erc20: Address,
@@ -437,54 +545,19 @@ pub struct FxOriginatedTokenEvent {
 
 impl FxOriginatedTokenEvent {
     pub fn signature() -> Hash {
-        Hash::from_slice(Keccak256::digest("FxOriginatedTokenEvent(address,string,string,uint8,uint256)".as_bytes()).as_slice())
+        abi::fx_bridge::FxOriginatedTokenEvent::signature()
     }
 
     pub fn from_log(input: &Log) -> Result<FxOriginatedTokenEvent> {
-        if input.topics.len() != 2 {
-            return Err(eyre::Error::msg("Invalid log topics"));
-        }
-        if let Some(erc20_data) = input.topics.get(1) {
-            let erc20 = Address::from_slice(&erc20_data[12..32]);
-
-            let index_start = 2 * 32;
-            let index_end = index_start + 32;
-            let decimals = U256::from(&input.data.0[index_start..index_end]);
-
-            let index_start = index_end;
-            let index_end = index_start + 32;
-            let event_nonce = U256::from(&input.data.0[index_start..index_end]);
-
-            let index_start = index_end;
-            let index_end = index_start + 32;
-            let name_len = U256::from(&input.data.0[index_start..index_end]).as_usize();
-            let index_start = index_end;
-            let index_end = index_start + name_len;
-            let name = String::from_utf8(input.data.0[index_start..index_end].to_vec()).unwrap();
-
-            let index_start = ((index_end + 31) / 32) * 32;
-            let index_end = index_start + 32;
-            let symbol_len = U256::from(&input.data.0[index_start..index_end]).as_usize();
-            let index_start = index_end;
-            let index_end = index_start + symbol_len;
-            let symbol = String::from_utf8(input.data.0[index_start..index_end].to_vec()).unwrap();
-
-            let block_number = if let Some(bn) = input.block_number.clone() {
-                bn
-            } else {
-                return Err(eyre::Error::msg("Log does not have block number, we only search logs already in blocks?"));
-            };
-            Ok(FxOriginatedTokenEvent {
-                erc20,
-                name,
-                symbol,
-                decimals,
-                event_nonce,
-                block_number,
-            })
-        } else {
-            Err(eyre::Error::msg("Topics parsing failed"))
-        }
+        let event = abi::fx_bridge::FxOriginatedTokenEvent::from_log(input)?;
+        Ok(FxOriginatedTokenEvent {
+            erc20: event.token_contract,
+            name: event.name,
+            symbol: event.symbol,
+            decimals: event.decimals.into(),
+            event_nonce: event.event_nonce,
+            block_number: log_block_number(input)?,
+        })
     }
     pub fn from_logs(input: &[Log]) -> Result<Vec<FxOriginatedTokenEvent>> {
         let mut res = Vec::new();
@@ -506,61 +579,17 @@ pub struct ValsetUpdatedEvent {
 
 impl ValsetUpdatedEvent {
     pub fn signature() -> Hash {
-        Hash::from_slice(Keccak256::digest("ValsetUpdatedEvent(uint256,uint256,address[],uint256[])".as_bytes()).as_slice())
+        abi::fx_bridge::ValsetUpdatedEvent::signature()
     }
 
     pub fn from_log(input: &Log) -> Result<ValsetUpdatedEvent> {
-        if input.topics.len() != 2 {
-            return Err(eyre::Error::msg("Invalid log topics"));
-        }
-        if input.topics.get(1).is_none() {
-            return Err(eyre::Error::msg("Too few topics".to_string()));
-        }
-
-        let valset_nonce = U256::from(input.topics[1].as_bytes());
-
-        let index_start = 0;
-        let index_end = index_start + 32;
-        let event_nonce = U256::from(&input.data.0[index_start..index_end]);
-
-        let index_start = 3 * 32;
-        let index_end = index_start + 32;
-        let validators_offset = index_start + 32;
-        let validators_len = U256::from(&input.data.0[index_start..index_end]).as_usize();
-
-        let mut validators = Vec::new();
-        for i in 0..validators_len {
-            let address_start = (i * 32) + validators_offset;
-            let address_end = address_start + 32;
-            let validator = Address::from_slice(&input.data.0[address_start + 12..address_end]);
-            validators.push(validator);
-        }
-
-        let index_start = (4 + validators_len) * 32;
-        let index_end = index_start + 32;
-        let powers_offset = index_start + 32;
-        let powers_len = U256::from(&input.data.0[index_start..index_end]).as_usize();
-
-        let mut powers = Vec::new();
-        for i in 0..powers_len {
-            let power_start = (i * 32) + powers_offset;
-            let power_end = power_start + 32;
-            let power = U256::from(&input.data.0[power_start..power_end]);
-            powers.push(power);
-        }
-
-        let block_number = if let Some(bn) = input.block_number.clone() {
-            bn
-        } else {
-            return Err(eyre::Error::msg("Log does not have block number, we only search logs already in blocks?"));
-        };
-
+        let event = abi::fx_bridge::ValsetUpdatedEvent::from_log(input)?;
         Ok(ValsetUpdatedEvent {
-            valset_nonce,
-            event_nonce,
-            validators,
-            powers,
-            block_number,
+            valset_nonce: event.new_valset_nonce,
+            event_nonce: event.event_nonce,
+            validators: event.validators,
+            powers: event.powers,
+            block_number: log_block_number(input)?,
         })
     }
     pub fn from_logs(input: &[Log]) -> Result<Vec<ValsetUpdatedEvent>> {
@@ -585,57 +614,28 @@ pub struct SendToFxEvent {
 
 impl SendToFxEvent {
     pub fn signature() -> Hash {
-        Hash::from_slice(Keccak256::digest("SendToFxEvent(address,address,bytes32,bytes32,uint256,uint256)".as_bytes()).as_slice())
+        abi::fx_bridge::SendToFxEvent::signature()
     }
 
     pub fn from_log(input: &Log) -> Result<SendToFxEvent> {
-        if input.topics.len() != 4 {
-            return Err(eyre::Error::msg("Invalid log topics"));
-        }
-
-        if let (Some(erc20_data), Some(sender_data), Some(destination_data)) = (input.topics.get(1), input.topics.get(2), input.topics.get(3)) {
-            let erc20 = Address::from_slice(&erc20_data[12..32]);
-            let sender = Address::from_slice(&sender_data[12..32]);
-
-            let mut destination: [u8; 20] = [0; 20];
-            destination.copy_from_slice(&destination_data[12..32]);
-
-            let index_start = 0;
-            let index_end = index_start + 32;
-
-            let target_ibc_bytes = input.data.0[index_start..index_end].to_vec();
-            let mut length = target_ibc_bytes.len() - 1;
-            while length > 0 && target_ibc_bytes[length - 1] == 0 {
-                length = length - 1
-            }
-            let target_ibc = hex::encode(target_ibc_bytes.split_at(length).0.to_vec());
-
-            let index_start = index_end;
-            let index_end = index_start + 32;
-            let amount = U256::from(&input.data.0[index_start..index_end]);
-
-            let index_start = index_end;
-            let index_end = index_start + 32;
-            let event_nonce = U256::from(&input.data.0[index_start..index_end]);
-
-            let block_number = if let Some(bn) = input.block_number.clone() {
-                bn
-            } else {
-                return Err(eyre::Error::msg("Log does not have block number, we only search logs already in blocks?"));
-            };
-
-            Ok(SendToFxEvent {
-                erc20,
-                sender,
-                destination,
-                target_ibc,
-                amount,
-                event_nonce,
-                block_number,
-            })
-        } else {
-            Err(eyre::Error::msg("Topics parsing failed"))
-        }
+        let event = abi::fx_bridge::SendToFxEvent::from_log(input)?;
+
+        let mut destination: [u8; 20] = [0; 20];
+        destination.copy_from_slice(&event.destination[12..32]);
+
+        // right padded with zeros, a 32 byte target keeps its last byte
+        let length = event.target_ibc.iter().rposition(|b| *b != 0).map_or(0, |i| i + 1);
+        let target_ibc = hex::encode(&event.target_ibc[..length]);
+
+        Ok(SendToFxEvent {
+            erc20: event.token_contract,
+            sender: event.sender,
+            destination,
+            target_ibc,
+            amount: event.amount,
+            event_nonce: event.event_nonce,
+            block_number: log_block_number(input)?,
+        })
     }
 
     pub fn from_logs(input: &[Log]) -> Result<Vec<SendToFxEvent>> {
@@ -645,6 +645,17 @@ impl SendToFxEvent {
         }
         Ok(res)
     }
+
+    /// The events emitted by `bridge_addr` in a `sendToFx` transaction receipt
+    pub fn from_receipt(receipt: &TransactionReceipt, bridge_addr: Address) -> Result<Vec<SendToFxEvent>> {
+        let logs: Vec<Log> = receipt
+            .logs
+            .iter()
+            .filter(|log| log.address == bridge_addr && log.topics.first() == Some(&SendToFxEvent::signature()))
+            .cloned()
+            .collect();
+        SendToFxEvent::from_logs(logs.as_slice())
+    }
 }
 
 #[derive(Debug, Default, Clone, Eq, PartialEq, Hash)]
@@ -657,31 +668,17 @@ pub struct TransactionBatchExecutedEvent {
 
 impl TransactionBatchExecutedEvent {
     pub fn signature() -> Hash {
-        Hash::from_slice(Keccak256::digest("TransactionBatchExecutedEvent(uint256,address,uint256)".as_bytes()).as_slice())
+        abi::fx_bridge::TransactionBatchExecutedEvent::signature()
     }
 
     pub fn from_log(input: &Log) -> Result<TransactionBatchExecutedEvent> {
-        if input.topics.len() != 3 {
-            return Err(eyre::Error::msg("Invalid log topics"));
-        }
-        if let (Some(batch_nonce_data), Some(erc20_data)) = (input.topics.get(1), input.topics.get(2)) {
-            let batch_nonce = U256::from(batch_nonce_data.as_bytes());
-            let erc20 = Address::from_slice(&erc20_data[12..32]);
-            let event_nonce = U256::from(input.data.0.as_slice());
-            let block_number = if let Some(bn) = input.block_number.clone() {
-                bn
-            } else {
-                return Err(eyre::Error::msg("Log does not have block number, we only search logs already in blocks?"));
-            };
-            Ok(TransactionBatchExecutedEvent {
-                batch_nonce,
-                erc20,
-                event_nonce,
-                block_number,
-            })
-        } else {
-            Err(eyre::Error::msg("Topics parsing failed"))
-        }
+        let event = abi::fx_bridge::TransactionBatchExecutedEvent::from_log(input)?;
+        Ok(TransactionBatchExecutedEvent {
+            batch_nonce: event.batch_nonce,
+            erc20: event.token,
+            event_nonce: event.event_nonce,
+            block_number: log_block_number(input)?,
+        })
     }
 
     pub fn from_logs(input: &[Log]) -> Result<Vec<TransactionBatchExecutedEvent>> {
@@ -852,7 +849,7 @@ mod tests {
       "0xb119f1f36224601586b5037da909ecf37e83864dddea5d32ad4e32ac1d97e62b",
       "0x0000000000000000000000000000000000000000000000000000000000000000"
     ],
-    "data": "0x0000000000000000000000000000000000000000000000000000000000000002000000000000000000000000000000000000000000000000000000000000004000000000000000000000000000000000000000000000000000000000000000800000000000000000000000000000000000000000000000000000000000000001000000000000000000000000b4fA5979babd8Bb7e427157d0d353Cf205F4375200000000000000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000002710",
+    "data": "0x0000000000000000000000000000000000000000000000000000000000000002000000000000000000000000000000000000000000000000000000000000006000000000000000000000000000000000000000000000000000000000000000a00000000000000000000000000000000000000000000000000000000000000001000000000000000000000000b4fA5979babd8Bb7e427157d0d353Cf205F4375200000000000000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000002710",
     "logIndex": "0x1",
     "blockHash": "0xe390492be21e4c23aac9f08c422b90b78a1987691255f3a5bc8ff4398958a73b"
   }
@@ -866,6 +863,23 @@ mod tests {
         assert_eq!(event.block_number, U64::from(18));
     }
 
+    #[test]
+    fn test_bridge_token_from_tokens() {
+        let addr = Address::from_str("0x30dA8589BFa1E509A319489E014d384b87815D89").unwrap();
+        let token = Token::Array(vec![Token::Tuple(vec![
+            Token::Address(addr),
+            Token::String("Tether USD".to_string()),
+            Token::String("USDT".to_string()),
+            Token::Uint(6.into()),
+        ])]);
+        let bridge_tokens = BridgeToken::from_tokens(token).unwrap();
+        assert_eq!(bridge_tokens.len(), 1);
+        assert_eq!(bridge_tokens[0].addr, addr);
+        assert_eq!(bridge_tokens[0].symbol, "USDT");
+        assert_eq!(bridge_tokens[0].decimals, 6);
+        assert!(BridgeToken::from_tokens(Token::Array(vec![Token::Tuple(vec![Token::Address(addr)])])).is_err());
+    }
+
     #[test]
     fn test_send_to_fx_event() {
         let res: Log = serde_json::from_str(
@@ -932,6 +946,35 @@ mod tests {
         assert_eq!(event.event_nonce, U256::from(2));
     }
 
+    #[test]
+    fn test_send_to_fx_event_full_target_ibc() {
+        let target_ibc = "px/transfer/channel-0/channel-12";
+        assert_eq!(target_ibc.len(), 32);
+        let log = format!(
+            r#"
+  {{
+    "transactionIndex": "0x0",
+    "blockNumber": "0x11",
+    "transactionHash": "0x2610f3e9f4ddbf8cfa2743987d45e456e4692378f752a1c6d519f545ba435788",
+    "address": "0x8B5B7a6055E54a36fF574bbE40cf2eA68d5554b3",
+    "topics": [
+      "0x034c5b22dd525a50d0a6b15549df0a6ac83b833a6c3da57ea16890832c72507c",
+      "0x000000000000000000000000d6c850aebfdc46d7f4c207e445cc0d6b0919bdbe",
+      "0x000000000000000000000000b4fA5979babd8Bb7e427157d0d353Cf205F43752",
+      "0x0000000000000000000000000000000000000000000000000000000000000001"
+    ],
+    "data": "0x{}00000000000000000000000000000000000000000000000000000000000003e80000000000000000000000000000000000000000000000000000000000000002",
+    "logIndex": "0x2",
+    "blockHash": "0xb7fbe05d17d064e248ae6a92075e5817be136719b88b0dde64b49e240e779252"
+  }}
+"#,
+            hex::encode(target_ibc)
+        );
+        let event = SendToFxEvent::from_log(&serde_json::from_str(log.as_str()).unwrap()).unwrap();
+        assert_eq!(event.target_ibc, hex::encode(target_ibc));
+        assert_eq!(event.amount, U256::from(1000));
+    }
+
     #[test]
     fn test_transaction_batch_executed_event() {
         let res: Log = serde_json::from_str(
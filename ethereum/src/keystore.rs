@@ -0,0 +1,334 @@
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crypto::aes::{ctr, KeySize};
+use crypto::hmac::Hmac;
+use crypto::pbkdf2::pbkdf2;
+use crypto::scrypt::{scrypt, ScryptParams};
+use crypto::sha2::Sha256;
+use crypto::symmetriccipher::SynchronousStreamCipher;
+use eyre::Result;
+use rand::Rng;
+use serde_derive::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+use web3::types::Address;
+
+use crate::address::Checksum;
+use crate::private_key::{Key, PrivateKey};
+
+/// scrypt cost of new keystores, the geth standard
+const SCRYPT_N: u32 = 262144;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+
+/// Encrypted private key in the Web3 Secret Storage (version 3) format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Keystore {
+    #[serde(default)]
+    pub address: String,
+    pub crypto: KeystoreCrypto,
+    #[serde(default)]
+    pub id: String,
+    pub version: u8,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeystoreCrypto {
+    pub cipher: String,
+    pub cipherparams: CipherParams,
+    pub ciphertext: String,
+    pub kdf: String,
+    pub kdfparams: KdfParams,
+    pub mac: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CipherParams {
+    pub iv: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub dklen: usize,
+    pub salt: String,
+    /// scrypt cost
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub n: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub r: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub p: Option<u32>,
+    /// pbkdf2 iterations
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub c: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prf: Option<String>,
+}
+
+impl Keystore {
+    pub fn from_file(path: &Path) -> Result<Keystore> {
+        let json = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(json.as_str())?)
+    }
+
+    /// Encrypts the key with scrypt and aes-128-ctr under a random salt and iv
+    pub fn encrypt(private_key: &PrivateKey, password: &str) -> Result<Keystore> {
+        let mut rng = rand::thread_rng();
+        let kdfparams = KdfParams {
+            dklen: 32,
+            salt: hex::encode(rng.gen::<[u8; 32]>()),
+            n: Some(SCRYPT_N),
+            r: Some(SCRYPT_R),
+            p: Some(SCRYPT_P),
+            ..Default::default()
+        };
+        Keystore::encrypt_with(private_key, password, kdfparams, rng.gen(), rng.gen())
+    }
+
+    fn encrypt_with(private_key: &PrivateKey, password: &str, kdfparams: KdfParams, iv: [u8; 16], mut id: [u8; 16]) -> Result<Keystore> {
+        let derived_key = derive_key(password, "scrypt", &kdfparams)?;
+        let mut ciphertext = vec![0u8; 32];
+        ctr(KeySize::KeySize128, &derived_key[0..16], &iv).process(private_key.to_bytes(), ciphertext.as_mut_slice());
+        let mac = Keccak256::digest(&[&derived_key[16..32], ciphertext.as_slice()].concat());
+
+        // random uuid, version 4 variant 1
+        id[6] = (id[6] & 0x0f) | 0x40;
+        id[8] = (id[8] & 0x3f) | 0x80;
+        let id = hex::encode(id);
+        Ok(Keystore {
+            address: hex::encode(private_key.address()),
+            crypto: KeystoreCrypto {
+                cipher: "aes-128-ctr".to_string(),
+                cipherparams: CipherParams { iv: hex::encode(iv) },
+                ciphertext: hex::encode(ciphertext),
+                kdf: "scrypt".to_string(),
+                kdfparams,
+                mac: hex::encode(mac),
+            },
+            id: format!("{}-{}-{}-{}-{}", &id[0..8], &id[8..12], &id[12..16], &id[16..20], &id[20..32]),
+            version: 3,
+        })
+    }
+
+    /// Writes the keystore as `<address>.json` in `dir`, readable by the owner only. An existing file is never overwritten
+    pub fn save(&self, dir: &Path) -> Result<PathBuf> {
+        let path = dir.join(format!("{}.json", self.address.trim_start_matches("0x").to_lowercase()));
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options.open(&path).map_err(|err| match err.kind() {
+            ErrorKind::AlreadyExists => eyre::Error::msg(format!("keystore {} already exists", path.display())),
+            _ => err.into(),
+        })?;
+        file.write_all(serde_json::to_string(self)?.as_bytes())?;
+        file.sync_all()?;
+        Ok(path)
+    }
+
+    /// The address recorded in the keystore, not verified until decrypted
+    pub fn address(&self) -> Option<Address> {
+        Address::from_str(self.address.trim_start_matches("0x")).ok()
+    }
+
+    pub fn decrypt(&self, password: &str) -> Result<PrivateKey> {
+        if self.version != 3 {
+            return Err(eyre::Error::msg(format!("unsupported keystore version {}", self.version)));
+        }
+        if self.crypto.cipher != "aes-128-ctr" {
+            return Err(eyre::Error::msg(format!("unsupported keystore cipher {}", self.crypto.cipher)));
+        }
+        let derived_key = derive_key(password, &self.crypto.kdf, &self.crypto.kdfparams)?;
+        let ciphertext = hex::decode(self.crypto.ciphertext.as_str())?;
+
+        let mac = Keccak256::digest(&[&derived_key[16..32], ciphertext.as_slice()].concat());
+        if hex::encode(mac) != self.crypto.mac.to_lowercase() {
+            return Err(eyre::Error::msg("keystore mac mismatch, wrong password?"));
+        }
+
+        let iv = hex::decode(self.crypto.cipherparams.iv.as_str())?;
+        let mut secret = vec![0u8; ciphertext.len()];
+        ctr(KeySize::KeySize128, &derived_key[0..16], iv.as_slice()).process(ciphertext.as_slice(), secret.as_mut_slice());
+        let private_key = PrivateKey::from_slice(secret.as_slice())?;
+
+        if let Some(address) = self.address() {
+            if address != private_key.address() {
+                return Err(eyre::Error::msg(format!(
+                    "keystore address {} does not match key {}",
+                    address.to_hex_string(),
+                    private_key.address().to_hex_string()
+                )));
+            }
+        }
+        Ok(private_key)
+    }
+}
+
+fn derive_key(password: &str, kdf: &str, params: &KdfParams) -> Result<Vec<u8>> {
+    let salt = hex::decode(params.salt.as_str())?;
+    let mut derived_key = vec![0u8; params.dklen];
+    if derived_key.len() < 32 {
+        return Err(eyre::Error::msg("keystore dklen must be at least 32"));
+    }
+    match kdf {
+        "scrypt" => {
+            let (n, r, p) = match (params.n, params.r, params.p) {
+                (Some(n), Some(r), Some(p)) if n.is_power_of_two() && r > 0 && p > 0 => (n, r, p),
+                _ => return Err(eyre::Error::msg("invalid scrypt params")),
+            };
+            let log_n = n.trailing_zeros() as u8;
+            // scrypt requires n < 2^(16 * r)
+            if log_n as u32 >= r * 16 {
+                return Err(eyre::Error::msg("invalid scrypt params, n too large for r"));
+            }
+            scrypt(password.as_bytes(), salt.as_slice(), &ScryptParams::new(log_n, r, p), derived_key.as_mut_slice());
+        }
+        "pbkdf2" => {
+            if params.prf.as_deref().unwrap_or("hmac-sha256") != "hmac-sha256" {
+                return Err(eyre::Error::msg(format!("unsupported pbkdf2 prf {:?}", params.prf)));
+            }
+            let c = params.c.ok_or_else(|| eyre::Error::msg("invalid pbkdf2 params"))?;
+            let mut mac = Hmac::new(Sha256::new(), password.as_bytes());
+            pbkdf2(&mut mac, salt.as_slice(), c, derived_key.as_mut_slice());
+        }
+        _ => return Err(eyre::Error::msg(format!("unsupported keystore kdf {}", kdf))),
+    }
+    Ok(derived_key)
+}
+
+/// A directory of keystore files sharing one password
+#[derive(Debug, Clone)]
+pub struct KeystoreDir {
+    pub dir: PathBuf,
+    pub password: String,
+}
+
+impl KeystoreDir {
+    pub fn new(dir: &str, password_file: &str) -> Result<Self> {
+        let password = std::fs::read_to_string(password_file)?;
+        Ok(KeystoreDir {
+            dir: PathBuf::from(dir),
+            password: password.trim_end_matches(&['\r', '\n'][..]).to_string(),
+        })
+    }
+
+    /// Find and decrypt the keystore of `address`, `None` if the directory has no such key
+    pub fn load(&self, address: Address) -> Result<Option<PrivateKey>> {
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if !path.is_file() {
+                continue;
+            }
+            let keystore = match Keystore::from_file(&path) {
+                Ok(keystore) => keystore,
+                Err(_) => continue,
+            };
+            if keystore.address() != Some(address) {
+                continue;
+            }
+            debug!("Found keystore {} for {}", path.display(), address.to_hex_string());
+            return Ok(Some(keystore.decrypt(self.password.as_str())?));
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+
+    use super::*;
+
+    const PASSWORD: &str = "testpassword";
+    const PRIVATE_KEY: &str = "7a28b5ba57c53603b0b07b56bba752f7784bf506fa95edc395f5cf6c7514fe9d";
+
+    // Test vector from the Web3 Secret Storage Definition
+    const PBKDF2_KEYSTORE: &str = r#"{
+        "crypto" : {
+            "cipher" : "aes-128-ctr",
+            "cipherparams" : {"iv" : "6087dab2f9fdbbfaddc31a909735c1e6"},
+            "ciphertext" : "5318b4d5bcd28de64ee5559e671353e16f075ecae9f99c7a79a38af5f869aa46",
+            "kdf" : "pbkdf2",
+            "kdfparams" : {"c" : 262144, "dklen" : 32, "prf" : "hmac-sha256", "salt" : "ae3cd4e7013836a3df6bd7241b12db061dbe2c6785853cce422d148a624ce0bd"},
+            "mac" : "517ead924a9d0dc3124507e3393d175ce3ff7c1e96529c6c555ce9e51205e9b2"
+        },
+        "id" : "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+        "version" : 3
+    }"#;
+
+    // Same key and password, scrypt with geth's light parameters
+    const SCRYPT_KEYSTORE: &str = r#"{
+        "crypto" : {
+            "cipher" : "aes-128-ctr",
+            "cipherparams" : {"iv" : "83dbcc02d8ccb40e466191a123791e0e"},
+            "ciphertext" : "91c23c03a8b5117a6e3d78dbb5dfc46f037aff9351dff59c2879586f51756502",
+            "kdf" : "scrypt",
+            "kdfparams" : {"dklen" : 32, "n" : 8192, "r" : 8, "p" : 1, "salt" : "ab0c7876052600dd703518d6fc3fe8984592145b591fc8fb5c6d43190334ba19"},
+            "mac" : "8f8639fe08fe1f43d6c13006d66ad2d993c6674ee2ba03f025596797a73614b9"
+        },
+        "id" : "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+        "version" : 3
+    }"#;
+
+    #[test]
+    fn test_decrypt_pbkdf2_keystore() {
+        let keystore: Keystore = serde_json::from_str(PBKDF2_KEYSTORE).unwrap();
+        let private_key = keystore.decrypt(PASSWORD).unwrap();
+        assert_eq!(hex::encode(private_key.to_bytes()), PRIVATE_KEY);
+        assert!(keystore.decrypt("wrongpassword").is_err());
+    }
+
+    #[test]
+    fn test_decrypt_scrypt_keystore() {
+        let keystore: Keystore = serde_json::from_str(SCRYPT_KEYSTORE).unwrap();
+        let private_key = keystore.decrypt(PASSWORD).unwrap();
+        assert_eq!(hex::encode(private_key.to_bytes()), PRIVATE_KEY);
+
+        let mut invalid = keystore;
+        invalid.crypto.kdfparams.r = Some(1);
+        invalid.crypto.kdfparams.n = Some(262144);
+        assert!(invalid.decrypt(PASSWORD).is_err());
+    }
+
+    #[test]
+    fn test_encrypt_keystore() {
+        let expected: Keystore = serde_json::from_str(SCRYPT_KEYSTORE).unwrap();
+        let private_key = PrivateKey::from_str(PRIVATE_KEY).unwrap();
+        let iv: [u8; 16] = hex::decode(expected.crypto.cipherparams.iv.as_str()).unwrap().as_slice().try_into().unwrap();
+        let keystore = Keystore::encrypt_with(&private_key, PASSWORD, expected.crypto.kdfparams.clone(), iv, [0xff; 16]).unwrap();
+        assert_eq!(keystore.crypto.ciphertext, expected.crypto.ciphertext);
+        assert_eq!(keystore.crypto.mac, expected.crypto.mac);
+        assert_eq!(keystore.address, "008aeeda4d805471df9b2a5b0f38a0c3bcba786b");
+        assert_eq!(keystore.id, "ffffffff-ffff-4fff-bfff-ffffffffffff");
+
+        let json = serde_json::to_string(&keystore).unwrap();
+        let decrypted = serde_json::from_str::<Keystore>(json.as_str()).unwrap().decrypt(PASSWORD).unwrap();
+        assert_eq!(hex::encode(decrypted.to_bytes()), PRIVATE_KEY);
+    }
+
+    #[test]
+    fn test_save_keystore() {
+        let keystore = Keystore {
+            address: "008aeeda4d805471df9b2a5b0f38a0c3bcba786b".to_string(),
+            ..serde_json::from_str(SCRYPT_KEYSTORE).unwrap()
+        };
+        let dir = std::env::temp_dir().join(format!("fxeth-keystore-save-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let path = keystore.save(&dir).unwrap();
+        assert_eq!(path, dir.join("008aeeda4d805471df9b2a5b0f38a0c3bcba786b.json"));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+        let saved = Keystore::from_file(&path).unwrap();
+        assert_eq!(hex::encode(saved.decrypt(PASSWORD).unwrap().to_bytes()), PRIVATE_KEY);
+        assert!(keystore.save(&dir).unwrap_err().to_string().contains("already exists"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -6,12 +6,20 @@ extern crate serde_json;
 
 use std::time::Duration;
 
+pub mod abi;
 pub mod address;
 mod confirm_tx;
 pub mod erc20;
+pub mod fee;
 pub mod fx_bridge;
 pub mod gas_price;
+pub mod keystore;
+pub mod multicall;
 pub mod private_key;
+pub mod revert;
+#[cfg(feature = "simulate")]
+pub mod simulate;
+pub mod units;
 
 const TX_CONFIRMATIONS_BLOCK_NUMBER: usize = 1;
 
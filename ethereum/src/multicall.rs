@@ -0,0 +1,176 @@
+use std::marker::PhantomData;
+
+use eyre::Result;
+use futures::future::join_all;
+use web3::api::{Eth, Namespace};
+use web3::transports::{Batch, Http};
+use web3::types::{Address, BlockId, BlockNumber, Bytes, CallRequest};
+
+use crate::abi::multicall3::{Aggregate3Call, Call3};
+use crate::abi::{self, ContractCall};
+use crate::revert::FxBridgeError;
+
+/// Multicall3, deployed at the same address on mainnet, the testnets and most EVM chains
+pub const MULTICALL3_ADDRESS: &str = "0xcA11bde05977b3631167028862bE2a173976CA11";
+
+/// Return data of a batched call, or why it reverted
+pub type CallResult = std::result::Result<Vec<u8>, FxBridgeError>;
+
+/// Index of a call in a `Multicall`, to decode its result with the output type of `C`
+#[derive(Debug)]
+pub struct Handle<C> {
+    index: usize,
+    call: PhantomData<C>,
+}
+
+impl<C: ContractCall> Handle<C> {
+    /// Decode the result of the call, a revert fails only this call
+    pub fn decode(&self, results: &[CallResult]) -> Result<C::Output> {
+        match results.get(self.index) {
+            Some(Ok(data)) => C::decode_output(data.as_slice()).map_err(|err| eyre::Error::msg(format!("{} output: {}", C::NAME, err))),
+            Some(Err(err)) => Err(err.clone().into()),
+            None => Err(eyre::Error::msg(format!("no result of call {} {}", self.index, C::NAME))),
+        }
+    }
+}
+
+/// View calls of any contracts, sent with a single `eth_call` to Multicall3 `aggregate3`,
+/// or as a single JSON-RPC batch request when there is no multicall contract
+#[derive(Debug, Clone)]
+pub struct Multicall {
+    eth: Eth<Http>,
+    from: Address,
+    multicall: Option<Address>,
+    calls: Vec<(Address, Vec<u8>)>,
+}
+
+impl Multicall {
+    pub fn new(eth: Eth<Http>, from: Address, multicall: Option<Address>) -> Self {
+        Multicall {
+            eth,
+            from,
+            multicall,
+            calls: Vec::new(),
+        }
+    }
+
+    /// Add `call` of the `target` contract to the batch
+    pub fn add<C: ContractCall>(&mut self, target: Address, call: C) -> Handle<C> {
+        self.calls.push((target, call.encode()));
+        Handle {
+            index: self.calls.len() - 1,
+            call: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.calls.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.calls.is_empty()
+    }
+
+    /// Run the calls at the latest block, in the order they were added
+    pub async fn call(&self) -> Result<Vec<CallResult>> {
+        if self.calls.is_empty() {
+            return Ok(Vec::new());
+        }
+        match self.multicall {
+            Some(multicall) => self.aggregate(multicall).await,
+            None => self.batch().await,
+        }
+    }
+
+    async fn aggregate(&self, multicall: Address) -> Result<Vec<CallResult>> {
+        let calls = self
+            .calls
+            .iter()
+            .map(|(target, data)| Call3 {
+                target: *target,
+                allow_failure: true,
+                call_data: data.clone(),
+            })
+            .collect();
+        let results = abi::query(&self.eth, self.from, multicall, Aggregate3Call { calls }).await?;
+        if results.len() != self.calls.len() {
+            return Err(eyre::Error::msg(format!("multicall returned {} results for {} calls", results.len(), self.calls.len())));
+        }
+        Ok(results
+            .into_iter()
+            .map(|result| {
+                if result.success {
+                    Ok(result.return_data)
+                } else {
+                    Err(FxBridgeError::decode(result.return_data.as_slice()))
+                }
+            })
+            .collect())
+    }
+
+    async fn batch(&self) -> Result<Vec<CallResult>> {
+        let transport = Batch::new(self.eth.transport().clone());
+        let eth = Eth::new(transport.clone());
+        let block = Some(BlockId::Number(BlockNumber::Latest));
+        let calls: Vec<_> = self
+            .calls
+            .iter()
+            .map(|(target, data)| {
+                let request = CallRequest {
+                    from: Some(self.from),
+                    to: Some(*target),
+                    data: Some(Bytes(data.clone())),
+                    ..Default::default()
+                };
+                eth.call(request, block)
+            })
+            .collect();
+        transport.submit_batch().await?;
+        let mut results = Vec::new();
+        for result in join_all(calls).await {
+            results.push(match result {
+                Ok(data) => Ok(data.0),
+                Err(err) => Err(FxBridgeError::from_web3_error(&err).ok_or(err)?),
+            });
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use web3::ethabi::{encode, Token};
+    use web3::types::U256;
+
+    use crate::abi::fx_bridge::LastBatchNonceCall;
+    use crate::abi::multicall3::Multicall3Result;
+
+    use super::*;
+
+    #[test]
+    fn test_handle_decode() {
+        let handle = Handle::<LastBatchNonceCall> { index: 1, call: PhantomData };
+        let results = vec![Ok(Vec::new()), Ok(encode(&[Token::Uint(7.into())]))];
+        assert_eq!(handle.decode(results.as_slice()).unwrap(), U256::from(7));
+
+        let results = vec![Ok(Vec::new()), Err(FxBridgeError::Revert("no token".to_string()))];
+        let err = handle.decode(results.as_slice()).unwrap_err();
+        assert_eq!(err.downcast_ref::<FxBridgeError>(), Some(&FxBridgeError::Revert("no token".to_string())));
+        assert!(handle.decode(&results[..1]).is_err());
+    }
+
+    #[test]
+    fn test_aggregate3_abi() {
+        assert_eq!(Aggregate3Call::SIGNATURE, "aggregate3((address,bool,bytes)[])");
+        assert_eq!(Aggregate3Call::SELECTOR, [0x82, 0xad, 0x56, 0xcb]);
+        let results = vec![
+            Multicall3Result {
+                success: true,
+                return_data: vec![1, 2, 3],
+            },
+            Multicall3Result::default(),
+        ];
+        let output = encode(&[Token::Array(results.iter().cloned().map(Multicall3Result::into_token).collect())]);
+        assert_eq!(Aggregate3Call::decode_output(output.as_slice()).unwrap(), results);
+    }
+}
@@ -19,6 +19,9 @@ pub trait Key {
     fn address(&self) -> Address;
 }
 
+/// BIP44 path of Ethereum keys without the account index, `m/44'/60'/0'/0/n`
+pub const ETH_HD_PATH_PREFIX: &str = "m/44'/60'/0'/0";
+
 #[derive(Debug, Clone)]
 pub struct PrivateKey(SecretKey);
 
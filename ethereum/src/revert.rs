@@ -0,0 +1,162 @@
+use std::fmt;
+
+use web3::ethabi::{decode, ParamType, Token};
+use web3::types::U256;
+
+/// Selector of `Error(string)`, the revert data of `require` and `revert` with a reason
+pub const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+/// Selector of `Panic(uint256)`, the revert data of failed asserts and arithmetic errors
+pub const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// Why an FxBridge call reverted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FxBridgeError {
+    MalformedValset,
+    MalformedBatch,
+    IncorrectCheckpoint,
+    InvalidValsetNonce,
+    InvalidBatchNonce,
+    BatchTimeout,
+    InvalidSignature,
+    InsufficientPower,
+    /// `require` or `revert` with a reason the bridge doesn't know about
+    Revert(String),
+    /// `Panic(uint256)` with its code, e.g. 0x11 for an arithmetic overflow
+    Panic(U256),
+    /// Revert without a reason, or with data that isn't `Error(string)` nor `Panic(uint256)`
+    Unknown(Vec<u8>),
+}
+
+/// Start of the FxBridge `require` messages of each error
+const REASONS: &[(&str, FxBridgeError)] = &[
+    ("Malformed current validator set", FxBridgeError::MalformedValset),
+    ("Malformed new validator set", FxBridgeError::MalformedValset),
+    ("Malformed batch", FxBridgeError::MalformedBatch),
+    ("Supplied current validators and powers do not match checkpoint", FxBridgeError::IncorrectCheckpoint),
+    ("New valset nonce must be greater", FxBridgeError::InvalidValsetNonce),
+    ("New batch nonce must be greater", FxBridgeError::InvalidBatchNonce),
+    ("Batch timeout", FxBridgeError::BatchTimeout),
+    ("Validator signature does not match", FxBridgeError::InvalidSignature),
+    ("Submitted power is not sufficient", FxBridgeError::InsufficientPower),
+    ("Submitted validator set signatures do not have enough power", FxBridgeError::InsufficientPower),
+];
+
+impl FxBridgeError {
+    /// Error of a `require` or `revert` reason
+    pub fn from_reason(reason: &str) -> FxBridgeError {
+        REASONS
+            .iter()
+            .find(|(prefix, _)| reason.starts_with(prefix))
+            .map(|(_, err)| err.clone())
+            .unwrap_or_else(|| FxBridgeError::Revert(reason.to_string()))
+    }
+
+    /// Decode the revert data returned by the node
+    pub fn decode(data: &[u8]) -> FxBridgeError {
+        if data.len() >= 4 {
+            let (selector, payload) = data.split_at(4);
+            let token = |kind: ParamType| decode(&[kind], payload).ok().and_then(|tokens| tokens.into_iter().next());
+            if selector == ERROR_SELECTOR {
+                if let Some(Token::String(reason)) = token(ParamType::String) {
+                    return FxBridgeError::from_reason(reason.as_str());
+                }
+            }
+            if selector == PANIC_SELECTOR {
+                if let Some(Token::Uint(code)) = token(ParamType::Uint(256)) {
+                    return FxBridgeError::Panic(code);
+                }
+            }
+        }
+        FxBridgeError::Unknown(data.to_vec())
+    }
+
+    /// Revert of an `eth_call` or `eth_estimateGas`, `None` when `err` is not a revert
+    pub fn from_web3_error(err: &web3::Error) -> Option<FxBridgeError> {
+        let err = match err {
+            web3::Error::Rpc(err) if err.code.code() == 3 || err.message.starts_with("execution reverted") => err,
+            _ => return None,
+        };
+        let data = err.data.as_ref().and_then(|data| data.as_str()).and_then(|data| hex::decode(data.trim_start_matches("0x")).ok());
+        match (data, err.message.strip_prefix("execution reverted: ")) {
+            (Some(data), _) => Some(FxBridgeError::decode(data.as_slice())),
+            (None, Some(reason)) => Some(FxBridgeError::from_reason(reason)),
+            (None, None) => Some(FxBridgeError::Unknown(Vec::new())),
+        }
+    }
+}
+
+impl fmt::Display for FxBridgeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FxBridgeError::MalformedValset => write!(f, "malformed validator set"),
+            FxBridgeError::MalformedBatch => write!(f, "malformed batch of transactions"),
+            FxBridgeError::IncorrectCheckpoint => write!(f, "current validators and powers do not match the checkpoint"),
+            FxBridgeError::InvalidValsetNonce => write!(f, "new valset nonce must be greater than the current nonce"),
+            FxBridgeError::InvalidBatchNonce => write!(f, "new batch nonce must be greater than the current nonce"),
+            FxBridgeError::BatchTimeout => write!(f, "batch timed out"),
+            FxBridgeError::InvalidSignature => write!(f, "validator signature does not match"),
+            FxBridgeError::InsufficientPower => write!(f, "submitted power is not sufficient"),
+            FxBridgeError::Revert(reason) => write!(f, "{}", reason),
+            FxBridgeError::Panic(code) => write!(f, "panic 0x{:02x}", code),
+            FxBridgeError::Unknown(data) if data.is_empty() => write!(f, "execution reverted"),
+            FxBridgeError::Unknown(data) => write!(f, "execution reverted: 0x{}", hex::encode(data)),
+        }
+    }
+}
+
+impl std::error::Error for FxBridgeError {}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use web3::ethabi::encode;
+
+    use super::*;
+
+    fn error_data(reason: &str) -> Vec<u8> {
+        let mut data = ERROR_SELECTOR.to_vec();
+        data.extend(encode(&[Token::String(reason.to_string())]));
+        data
+    }
+
+    #[test]
+    fn test_decode() {
+        let reasons = [
+            ("New batch nonce must be greater than the current nonce", FxBridgeError::InvalidBatchNonce),
+            ("Batch timeout must be greater than the current block height", FxBridgeError::BatchTimeout),
+            ("Submitted power is not sufficient", FxBridgeError::InsufficientPower),
+            ("Supplied current validators and powers do not match checkpoint.", FxBridgeError::IncorrectCheckpoint),
+            ("Ownable: caller is not the owner", FxBridgeError::Revert("Ownable: caller is not the owner".to_string())),
+        ];
+        for (reason, err) in reasons.iter() {
+            assert_eq!(&FxBridgeError::decode(error_data(reason).as_slice()), err);
+        }
+
+        let mut panic = PANIC_SELECTOR.to_vec();
+        panic.extend(encode(&[Token::Uint(0x11.into())]));
+        assert_eq!(FxBridgeError::decode(panic.as_slice()), FxBridgeError::Panic(0x11.into()));
+        assert_eq!(FxBridgeError::decode(panic.as_slice()).to_string(), "panic 0x11");
+        assert_eq!(FxBridgeError::decode(&[]).to_string(), "execution reverted");
+        assert_eq!(FxBridgeError::decode(&[0xde, 0xad, 0xbe, 0xef, 0x01]).to_string(), "execution reverted: 0xdeadbeef01");
+    }
+
+    #[test]
+    fn test_from_web3_error() {
+        let rpc_error = |value| web3::Error::Rpc(serde_json::from_value(value).unwrap());
+        let err = rpc_error(json!({
+            "code": 3,
+            "message": "execution reverted: Batch timeout must be greater than the current block height",
+            "data": format!("0x{}", hex::encode(error_data("Batch timeout must be greater than the current block height"))),
+        }));
+        assert_eq!(FxBridgeError::from_web3_error(&err), Some(FxBridgeError::BatchTimeout));
+
+        let err = rpc_error(json!({ "code": -32000, "message": "execution reverted: Validator signature does not match." }));
+        assert_eq!(FxBridgeError::from_web3_error(&err), Some(FxBridgeError::InvalidSignature));
+        let err = rpc_error(json!({ "code": -32000, "message": "execution reverted" }));
+        assert_eq!(FxBridgeError::from_web3_error(&err), Some(FxBridgeError::Unknown(Vec::new())));
+        let err = rpc_error(json!({ "code": -32000, "message": "nonce too low" }));
+        assert_eq!(FxBridgeError::from_web3_error(&err), None);
+        assert_eq!(FxBridgeError::from_web3_error(&web3::Error::Unreachable), None);
+    }
+}
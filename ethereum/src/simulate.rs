@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+
+use eyre::Result;
+use revm::db::{CacheDB, DatabaseRef};
+use revm::primitives::{AccountInfo, Address, Bytecode, EVMError, ExecutionResult, Output, TransactTo, B256, U256};
+use revm::Evm;
+use web3::ethabi::{encode, Token};
+use web3::transports::Http;
+use web3::types::{Address as EthAddress, BlockId, BlockNumber, U256 as EthU256, U64};
+use web3::Web3;
+
+use crate::revert::FxBridgeError;
+
+/// Block gas limit of the local chain
+const LOCAL_GAS_LIMIT: u64 = 30_000_000;
+
+/// Outcome of a simulated call
+#[derive(Debug, Clone, PartialEq)]
+pub enum Simulation {
+    Success { gas_used: u64, output: Vec<u8> },
+    Revert { gas_used: u64, error: FxBridgeError },
+    Halt { gas_used: u64, reason: String },
+}
+
+impl Simulation {
+    pub fn is_success(&self) -> bool {
+        matches!(self, Simulation::Success { .. })
+    }
+
+    pub fn gas_used(&self) -> u64 {
+        match self {
+            Simulation::Success { gas_used, .. } | Simulation::Revert { gas_used, .. } | Simulation::Halt { gas_used, .. } => *gas_used,
+        }
+    }
+}
+
+/// State the simulation needs that was not loaded from the forked node yet
+#[derive(Debug, Clone, PartialEq)]
+pub enum Missing {
+    Account(Address),
+    Storage(Address, U256),
+    BlockHash(U256),
+}
+
+/// Accounts, storage and block hashes loaded from the forked node. Without a fork everything
+/// not deployed locally is empty.
+#[derive(Debug, Clone, Default)]
+pub struct ForkState {
+    forked: bool,
+    accounts: HashMap<Address, Option<AccountInfo>>,
+    storage: HashMap<(Address, U256), U256>,
+    block_hashes: HashMap<U256, B256>,
+}
+
+impl DatabaseRef for ForkState {
+    type Error = Missing;
+
+    fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Missing> {
+        match self.accounts.get(&address) {
+            Some(info) => Ok(info.clone()),
+            None if self.forked => Err(Missing::Account(address)),
+            None => Ok(None),
+        }
+    }
+
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Missing> {
+        // accounts are loaded with their code, so this is only reached for code deployed in the simulation
+        let code = self.accounts.values().flatten().find(|info| info.code_hash == code_hash).and_then(|info| info.code.clone());
+        Ok(code.unwrap_or_default())
+    }
+
+    fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Missing> {
+        match self.storage.get(&(address, index)) {
+            Some(value) => Ok(*value),
+            None if self.forked => Err(Missing::Storage(address, index)),
+            None => Ok(U256::ZERO),
+        }
+    }
+
+    fn block_hash_ref(&self, number: U256) -> Result<B256, Missing> {
+        match self.block_hashes.get(&number) {
+            Some(hash) => Ok(*hash),
+            None if self.forked => Err(Missing::BlockHash(number)),
+            None => Ok(B256::ZERO),
+        }
+    }
+}
+
+/// In-process EVM running calls against a fork of a node, or against contracts deployed locally.
+///
+/// The fork is pinned to the latest block when it is created. Accounts and storage slots are fetched
+/// over JSON-RPC the first time a call touches them: the call stops on the missing state, which is
+/// loaded, and runs again.
+pub struct Simulator {
+    web3: Option<Web3<Http>>,
+    chain_id: u64,
+    block_number: u64,
+    timestamp: u64,
+    gas_limit: u64,
+    db: CacheDB<ForkState>,
+}
+
+impl Simulator {
+    /// Fork the latest block of the node behind `web3`
+    pub async fn fork(web3: Web3<Http>) -> Result<Simulator> {
+        let block = web3.eth().block(BlockId::Number(BlockNumber::Latest)).await?;
+        let block = block.ok_or_else(|| eyre::Error::msg("invalid block"))?;
+        let block_number = block.number.ok_or_else(|| eyre::Error::msg("latest block has no number"))?;
+        let chain_id = web3.eth().chain_id().await?;
+        info!("Simulator forked block {} of chain {}", block_number, chain_id);
+        Ok(Simulator {
+            chain_id: chain_id.as_u64(),
+            block_number: block_number.as_u64(),
+            timestamp: block.timestamp.as_u64(),
+            gas_limit: block.gas_limit.as_u64(),
+            db: CacheDB::new(ForkState { forked: true, ..Default::default() }),
+            web3: Some(web3),
+        })
+    }
+
+    /// Start from an empty chain, contracts are added with `deploy`
+    pub fn local() -> Simulator {
+        Simulator {
+            web3: None,
+            chain_id: 1,
+            block_number: 1,
+            timestamp: 0,
+            gas_limit: LOCAL_GAS_LIMIT,
+            db: CacheDB::new(ForkState::default()),
+        }
+    }
+
+    pub fn block_number(&self) -> u64 {
+        self.block_number
+    }
+
+    /// Deploy `creation_code` with the abi-encoded `constructor_args` from `from`, returns the contract address
+    pub async fn deploy(&mut self, from: EthAddress, creation_code: &[u8], constructor_args: &[Token]) -> Result<EthAddress> {
+        let mut data = creation_code.to_vec();
+        data.extend(encode(constructor_args));
+        match self.transact(from, TransactTo::create(), data, true).await? {
+            ExecutionResult::Success {
+                output: Output::Create(_, Some(address)),
+                ..
+            } => Ok(EthAddress::from_slice(address.as_slice())),
+            result => Err(eyre::Error::msg(format!("deploy failed: {:?}", Simulation::from(result)))),
+        }
+    }
+
+    /// Run a call of `data` to `to` from `from`, the state changes are discarded
+    pub async fn call(&mut self, from: EthAddress, to: EthAddress, data: Vec<u8>) -> Result<Simulation> {
+        let result = self.transact(from, TransactTo::Call(Address::from_slice(to.as_bytes())), data, false).await?;
+        Ok(result.into())
+    }
+
+    async fn transact(&mut self, from: EthAddress, to: TransactTo, data: Vec<u8>, commit: bool) -> Result<ExecutionResult> {
+        let (chain_id, block_number, timestamp, gas_limit) = (self.chain_id, self.block_number, self.timestamp, self.gas_limit);
+        loop {
+            let mut evm = Evm::builder()
+                .with_db(&mut self.db)
+                .modify_cfg_env(|cfg| cfg.chain_id = chain_id)
+                .modify_block_env(|block| {
+                    block.number = U256::from(block_number);
+                    block.timestamp = U256::from(timestamp);
+                    block.gas_limit = U256::from(gas_limit);
+                })
+                .modify_tx_env(|tx| {
+                    tx.caller = Address::from_slice(from.as_bytes());
+                    tx.transact_to = to.clone();
+                    tx.data = data.clone().into();
+                    tx.gas_limit = gas_limit;
+                })
+                .build();
+            let result = if commit { evm.transact_commit() } else { evm.transact().map(|result| result.result) };
+            drop(evm);
+            match result {
+                Ok(result) => return Ok(result),
+                Err(EVMError::Database(missing)) => self.load(missing).await?,
+                Err(err) => return Err(eyre::Error::msg(format!("simulation failed: {:?}", err))),
+            }
+        }
+    }
+
+    /// Fetch `missing` from the forked node at the forked block
+    async fn load(&mut self, missing: Missing) -> Result<()> {
+        let web3 = self.web3.as_ref().ok_or_else(|| eyre::Error::msg(format!("{:?} without a fork", missing)))?;
+        let block = Some(BlockNumber::Number(U64::from(self.block_number)));
+        debug!("Simulator load {:?}", missing);
+        let fork_state = &mut self.db.db;
+        match missing {
+            Missing::Account(address) => {
+                let eth_address = EthAddress::from_slice(address.as_slice());
+                let balance = web3.eth().balance(eth_address, block).await?;
+                let nonce = web3.eth().transaction_count(eth_address, block).await?;
+                let code = Bytecode::new_raw(web3.eth().code(eth_address, block).await?.0.into());
+                let info = AccountInfo::new(to_u256(balance), nonce.as_u64(), code.hash_slow(), code);
+                fork_state.accounts.insert(address, Some(info));
+            }
+            Missing::Storage(address, index) => {
+                let value = web3
+                    .eth()
+                    .storage(EthAddress::from_slice(address.as_slice()), EthU256::from_big_endian(&index.to_be_bytes::<32>()), block)
+                    .await?;
+                fork_state.storage.insert((address, index), U256::from_be_bytes(value.0));
+            }
+            Missing::BlockHash(number) => {
+                let block_id = BlockId::Number(BlockNumber::Number(U64::from(number.to::<u64>())));
+                let block = web3.eth().block(block_id).await?.ok_or_else(|| eyre::Error::msg(format!("no block {}", number)))?;
+                let hash = block.hash.ok_or_else(|| eyre::Error::msg(format!("block {} has no hash", number)))?;
+                fork_state.block_hashes.insert(number, B256::from_slice(hash.as_bytes()));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<ExecutionResult> for Simulation {
+    fn from(result: ExecutionResult) -> Self {
+        match result {
+            ExecutionResult::Success { gas_used, output, .. } => Simulation::Success {
+                gas_used,
+                output: output.into_data().to_vec(),
+            },
+            ExecutionResult::Revert { gas_used, output } => Simulation::Revert {
+                gas_used,
+                error: FxBridgeError::decode(output.as_ref()),
+            },
+            ExecutionResult::Halt { gas_used, reason } => Simulation::Halt {
+                gas_used,
+                reason: format!("{:?}", reason),
+            },
+        }
+    }
+}
+
+fn to_u256(value: EthU256) -> U256 {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    U256::from_be_bytes(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::revert::ERROR_SELECTOR;
+
+    /// Runtime code that stores 1 in slot 0 when called without data, and reverts with the calldata otherwise
+    const RUNTIME: &str = "3615600f57366000600037366000fd5b600160005500";
+
+    /// Creation code returning `RUNTIME`
+    const CREATION: &str = "601680600b6000396000f3";
+
+    fn error_data(reason: &str) -> Vec<u8> {
+        let mut data = ERROR_SELECTOR.to_vec();
+        data.extend(encode(&[Token::String(reason.to_string())]));
+        data
+    }
+
+    #[tokio::test]
+    async fn test_local_simulation() {
+        let mut simulator = Simulator::local();
+        let from = EthAddress::from_low_u64_be(1);
+        let code = hex::decode(format!("{}{}", CREATION, RUNTIME)).unwrap();
+        let contract = simulator.deploy(from, code.as_slice(), &[]).await.unwrap();
+
+        let simulation = simulator.call(from, contract, Vec::new()).await.unwrap();
+        assert!(simulation.is_success());
+        assert!(simulation.gas_used() > 40_000, "sstore gas {}", simulation.gas_used());
+        // the first call was not committed, so the slot is still zero
+        assert_eq!(simulator.call(from, contract, Vec::new()).await.unwrap().gas_used(), simulation.gas_used());
+
+        let simulation = simulator
+            .call(from, contract, error_data("Supplied current validators and powers do not match checkpoint."))
+            .await
+            .unwrap();
+        assert!(matches!(simulation, Simulation::Revert { ref error, .. } if *error == FxBridgeError::IncorrectCheckpoint));
+    }
+
+    #[test]
+    fn test_fork_state_missing() {
+        let mut fork_state = ForkState { forked: true, ..Default::default() };
+        let address = Address::with_last_byte(7);
+        assert_eq!(fork_state.basic_ref(address), Err(Missing::Account(address)));
+        assert_eq!(fork_state.storage_ref(address, U256::from(1)), Err(Missing::Storage(address, U256::from(1))));
+        fork_state.storage.insert((address, U256::from(1)), U256::from(9));
+        assert_eq!(fork_state.storage_ref(address, U256::from(1)), Ok(U256::from(9)));
+        assert_eq!(ForkState::default().basic_ref(address), Ok(None));
+    }
+}
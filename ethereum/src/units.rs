@@ -0,0 +1,66 @@
+use eyre::Result;
+use web3::types::U256;
+
+/// Parse a decimal amount such as `1.5` into the smallest unit of a token with `decimals`
+pub fn parse_units(amount: &str, decimals: u8) -> Result<U256> {
+    let amount = amount.trim();
+    let (integer, fraction) = match amount.split_once('.') {
+        Some((integer, fraction)) => (integer, fraction),
+        None => (amount, ""),
+    };
+    if integer.is_empty() && fraction.is_empty() {
+        return Err(eyre::Error::msg(format!("invalid amount '{}'", amount)));
+    }
+    if !integer.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit()) {
+        return Err(eyre::Error::msg(format!("invalid amount '{}'", amount)));
+    }
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.len() > decimals as usize {
+        return Err(eyre::Error::msg(format!("amount '{}' has more than {} decimals", amount, decimals)));
+    }
+    let digits = format!("{}{:0<width$}", integer, fraction, width = decimals as usize);
+    let digits = digits.trim_start_matches('0');
+    if digits.is_empty() {
+        return Ok(U256::zero());
+    }
+    U256::from_dec_str(digits).map_err(|err| eyre::Error::msg(format!("invalid amount '{}': {:?}", amount, err)))
+}
+
+/// Format the smallest unit amount of a token with `decimals` as a decimal string
+pub fn format_units(amount: U256, decimals: u8) -> String {
+    let digits = format!("{:0>width$}", amount.to_string(), width = decimals as usize + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - decimals as usize);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        integer.to_string()
+    } else {
+        format!("{}.{}", integer, fraction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_units() {
+        assert_eq!(parse_units("1", 18).unwrap(), U256::exp10(18));
+        assert_eq!(parse_units("1.5", 6).unwrap(), U256::from(1_500_000));
+        assert_eq!(parse_units(".25", 2).unwrap(), U256::from(25));
+        assert_eq!(parse_units("0.100", 1).unwrap(), U256::from(1));
+        assert_eq!(parse_units("0", 18).unwrap(), U256::zero());
+        assert!(parse_units("0.001", 2).is_err());
+        assert!(parse_units("1e18", 18).is_err());
+        assert!(parse_units("-1", 18).is_err());
+        assert!(parse_units(".", 18).is_err());
+    }
+
+    #[test]
+    fn test_format_units() {
+        assert_eq!(format_units(U256::exp10(18), 18), "1");
+        assert_eq!(format_units(U256::from(1_500_000), 6), "1.5");
+        assert_eq!(format_units(U256::from(25), 2), "0.25");
+        assert_eq!(format_units(U256::from(25), 0), "25");
+        assert_eq!(format_units(U256::zero(), 18), "0");
+    }
+}
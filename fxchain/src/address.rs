@@ -1,6 +1,7 @@
 use bech32::{FromBase32, ToBase32};
 use eyre::Result;
 use serde::{Serialize, Serializer};
+use sha2::{Digest, Sha256};
 use std::fmt;
 use std::fmt::Display;
 use std::fmt::Formatter;
@@ -20,6 +21,13 @@ impl Address {
         Address(bytes)
     }
 
+    /// Address of a module account, the first 20 bytes of the sha256 of the module name
+    pub fn from_module_name(name: &str) -> Address {
+        let mut addr = [0u8; 20];
+        addr.copy_from_slice(&Sha256::digest(name.as_bytes())[..20]);
+        Address(addr)
+    }
+
     pub fn from_bech32(s: String) -> Result<Address> {
         let (_hrp, data) = match bech32::decode(&s) {
             Ok(val) => val,
@@ -123,4 +131,10 @@ mod tests {
         let address1 = Address::from_bytes(address.0);
         assert_eq!(address, address1)
     }
+
+    #[test]
+    fn test_module_address() {
+        let address = Address::from_module_name("distribution");
+        assert_eq!(address.to_bech32("cosmos").unwrap(), "cosmos1jv65s3grqf6v6jl3dp4t6c9t9rk99cd88lyufl");
+    }
 }
@@ -7,7 +7,7 @@ use tendermint::{block, chain};
 use tonic::transport::Channel;
 
 use crate::address::Address;
-use crate::grpc_client::{get_account_info, get_chain_id};
+use crate::grpc_client::{find_account_info, get_account_info, get_chain_id};
 use crate::private_key::PrivateKey;
 use crate::proto_ext::MessageExt;
 
@@ -18,6 +18,8 @@ pub struct Builder {
     account_number: u64,
     fees_denom: String,
     memo: String,
+    /// sign transactions without broadcasting them
+    dry_run: bool,
 }
 
 impl Builder {
@@ -29,6 +31,7 @@ impl Builder {
             account_number,
             fees_denom: fees_denom.to_string(),
             memo: Default::default(),
+            dry_run: false,
         }
     }
 
@@ -42,11 +45,37 @@ impl Builder {
         Ok(Builder::new(chain_id, fx_private_key, fx_account.account_number, fees_denom))
     }
 
+    /// Like `from_net` for a dry run, which only observes, so the account may not exist yet
+    pub async fn from_net_dry_run(grpc_channel: &Channel, fx_private_key: PrivateKey, fees_denom: &str) -> eyre::Result<Self> {
+        let fx_address = fx_private_key.public_key().to_address().to_string();
+        let account_number = match find_account_info(&grpc_channel, fx_address.clone()).await? {
+            Some(fx_account) => fx_account.account_number,
+            None => {
+                warn!("Fx chain account {} does not exist, dry run as a new orchestrator", fx_address);
+                0
+            }
+        };
+        let chain_id = get_chain_id(&grpc_channel).await?;
+        info!("Fx chain id {}, use fee denom {}, dry run", chain_id, fees_denom);
+        let mut builder = Builder::new(chain_id, fx_private_key, account_number, fees_denom);
+        builder.with_dry_run(true);
+        Ok(builder)
+    }
+
     pub fn with_memo(&mut self, memo: String) -> &mut Builder {
         self.memo = memo;
         self
     }
 
+    pub fn with_dry_run(&mut self, dry_run: bool) -> &mut Builder {
+        self.dry_run = dry_run;
+        self
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
     pub fn get_fee_denom(&self) -> String {
         self.fees_denom.clone()
     }
@@ -5,9 +5,10 @@ use std::str::FromStr;
 use cosmos_sdk_proto::cosmos::auth::v1beta1::query_client::QueryClient as AuthQueryClient;
 use cosmos_sdk_proto::cosmos::auth::v1beta1::{BaseAccount, QueryAccountRequest};
 use cosmos_sdk_proto::cosmos::bank::v1beta1::query_client::QueryClient as BankQueryClient;
-use cosmos_sdk_proto::cosmos::bank::v1beta1::{QueryAllBalancesRequest, QueryBalanceRequest};
+use cosmos_sdk_proto::cosmos::bank::v1beta1::{QueryAllBalancesRequest, QueryBalanceRequest, QuerySupplyOfRequest, QueryTotalSupplyRequest};
 use cosmos_sdk_proto::cosmos::base::abci::v1beta1::GasInfo;
 use cosmos_sdk_proto::cosmos::base::abci::v1beta1::TxResponse;
+use cosmos_sdk_proto::cosmos::base::query::v1beta1::PageRequest;
 use cosmos_sdk_proto::cosmos::base::tendermint::v1beta1::service_client::ServiceClient as TendermintClient;
 use cosmos_sdk_proto::cosmos::base::tendermint::v1beta1::GetLatestBlockRequest;
 use cosmos_sdk_proto::cosmos::base::v1beta1::Coin;
@@ -23,12 +24,19 @@ use tendermint::block;
 use tendermint::chain;
 use tonic::transport::channel::Channel;
 use tonic::transport::{ClientTlsConfig, Endpoint};
+use tonic::{Code, Status};
 use url::Url;
 
 use crate::address::Address as FxAddress;
 use crate::builder::Builder;
 use crate::proto_ext::{unpack_any, MessageExt};
 use crate::x::gravity::query_client::QueryClient as GravityQueryClient;
+use crate::x::gravity::{QueryDelegateKeyByEthRequest, QueryDelegateKeyByEthResponse, QueryDelegateKeyByOrchestratorResponse, QueryDelegateKeyByValidatorRequest, QueryDelegateKeyByValidatorResponse};
+use crate::x::gravity::{BatchFees, MsgConfirmBatch, MsgValsetConfirm, Params, QueryBatchConfirmsRequest, QueryBatchFeeRequest, QueryBatchRequestByNonceRequest};
+use crate::x::gravity::{QueryCurrentValsetRequest, QueryLastValsetRequestsRequest, QueryValsetConfirmsByNonceRequest, QueryValsetRequestRequest, Valset};
+use crate::x::gravity::{QueryLastPendingBatchRequestByAddrRequest, QueryLastPendingValsetRequestByAddrRequest};
+use crate::x::gravity::{OutgoingTxBatch, QueryOutgoingTxBatchesRequest, QueryPendingSendToEthRequest, QueryPendingSendToEthResponse};
+use crate::x::gravity::{QueryDenomToErc20Request, QueryDenomToErc20Response, QueryErc20ToDenomRequest, QueryErc20ToDenomResponse};
 use crate::x::gravity::{QueryDelegateKeyByOrchestratorRequest, QueryLastEventBlockHeightByAddrRequest, QueryLastEventNonceByAddrRequest, QueryLastObservedEthBlockHeightRequest, QueryParamsRequest};
 use crate::x::other::query_client::QueryClient as OtherQueryClient;
 use crate::x::other::GasPriceRequest;
@@ -37,6 +45,9 @@ use crate::{DEFAULT_GAS_LIMIT, DEFAULT_TX_TIMEOUT_HEIGHT, FX_MSG_MAX_NUMBER, get
 /* ============================== gRPC ============================== */
 
 pub async fn send_tx(builder: &Builder, grpc_channel: &Channel, msgs: Vec<Any>) -> Result<TxResponse> {
+    if builder.is_dry_run() {
+        return Err(Error::msg("Dry run builder never broadcasts, use dry_run_tx"));
+    }
     let sequence = builder.get_next_sequence(grpc_channel).await?;
 
     let mut fee = Fee {
@@ -90,6 +101,51 @@ pub async fn send_tx(builder: &Builder, grpc_channel: &Channel, msgs: Vec<Any>)
     Ok(tx_response)
 }
 
+/// Outcome of a tx signed and simulated but never broadcast
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DryRunResponse {
+    /// gas used by the simulation, None if it failed
+    pub gas_used: Option<u64>,
+    /// simulation error, messages depending on earlier dry run messages never simulate
+    pub error: Option<String>,
+}
+
+/// Sign `msgs` and simulate them without broadcasting. The account of an orchestrator
+/// that is not registered yet may not exist, it signs with sequence 0.
+pub async fn dry_run_tx(builder: &Builder, grpc_channel: &Channel, msgs: Vec<Any>) -> Result<DryRunResponse> {
+    let address = builder.address().to_string();
+    let sequence = find_account_info(grpc_channel, address.clone()).await?.map(|account| account.sequence).unwrap_or_default();
+
+    let gas_price = get_gas_price_by_denom(grpc_channel, builder.get_fee_denom()).await?;
+    let fee = Fee {
+        amount: vec![Coin {
+            denom: gas_price.denom,
+            amount: BigInt::from_str(gas_price.amount.as_str()).unwrap().mul(DEFAULT_GAS_LIMIT).to_string(),
+        }],
+        gas_limit: DEFAULT_GAS_LIMIT,
+        payer: Default::default(),
+        granter: Default::default(),
+    };
+    let type_urls: Vec<String> = msgs.iter().map(|msg| msg.type_url.clone()).collect();
+    let tx = builder.sign_tx(sequence, msgs, fee, block::Height::from(DEFAULT_TX_TIMEOUT_HEIGHT))?;
+
+    let response = match estimating_gas_usage(grpc_channel, tx).await {
+        Ok(gas_info) => DryRunResponse {
+            gas_used: Some(gas_info.gas_used),
+            error: None,
+        },
+        Err(report) => DryRunResponse {
+            gas_used: None,
+            error: Some(report.root_cause().to_string()),
+        },
+    };
+    info!("Dry run, not broadcasting {:?}, simulation {:?}", type_urls, response);
+    for type_url in type_urls.iter() {
+        prometheus::metrics::DRY_RUN_MSGS.with_label_values(&[address.as_str(), type_url.as_str()]).inc();
+    }
+    Ok(response)
+}
+
 pub async fn new_grpc_channel(grpc_url: &str) -> Result<Channel> {
     let url = Url::parse(grpc_url)?;
     if url.scheme() != "http" && url.scheme() != "https" && url.scheme() != "tcp" {
@@ -114,6 +170,16 @@ pub async fn get_account_info(grpc_channel: &Channel, address: String) -> Result
     Ok(auth_account)
 }
 
+/// `get_account_info`, None if the account does not exist yet
+pub async fn find_account_info(grpc_channel: &Channel, address: String) -> Result<Option<BaseAccount>> {
+    let mut auth_query_client = AuthQueryClient::new(grpc_channel.clone());
+    match auth_query_client.account(QueryAccountRequest { address }).await {
+        Ok(response) => Ok(Some(unpack_any(response.into_inner().account.unwrap(), BaseAccount::default())?)),
+        Err(status) if is_not_found(&status) => Ok(None),
+        Err(status) => Err(status.into()),
+    }
+}
+
 #[allow(deprecated)]
 pub async fn estimating_gas_usage(grpc_channel: &Channel, tx: Tx) -> Result<GasInfo> {
     let mut tx_client = TxClient::new(grpc_channel.clone());
@@ -212,6 +278,35 @@ pub async fn get_balance(grpc_channel: &Channel, fx_address: FxAddress, denom: S
     }
 }
 
+pub async fn get_supply_of(grpc_channel: &Channel, denom: String) -> Result<Coin> {
+    let mut bank_query_client = BankQueryClient::new(grpc_channel.clone());
+    let result = bank_query_client.supply_of(QuerySupplyOfRequest { denom: denom.clone() }).await?;
+    if let Some(amount) = result.into_inner().amount {
+        Ok(amount)
+    } else {
+        Err(Error::msg(format!("no found supply by {}", denom)))
+    }
+}
+
+/// Supply of every denom, following the pagination
+pub async fn get_total_supply(grpc_channel: &Channel) -> Result<Vec<Coin>> {
+    let mut bank_query_client = BankQueryClient::new(grpc_channel.clone());
+    let mut supply = Vec::new();
+    let mut next_key = Vec::new();
+    loop {
+        let pagination = PageRequest {
+            key: next_key,
+            ..Default::default()
+        };
+        let result = bank_query_client.total_supply(QueryTotalSupplyRequest { pagination: Some(pagination) }).await?.into_inner();
+        supply.extend(result.supply);
+        next_key = match result.pagination {
+            Some(page) if !page.next_key.is_empty() => page.next_key,
+            _ => return Ok(supply),
+        };
+    }
+}
+
 /* ===== gravity ===== */
 
 pub async fn get_gravity_id(grpc_channel: &Channel) -> Result<String> {
@@ -220,6 +315,56 @@ pub async fn get_gravity_id(grpc_channel: &Channel) -> Result<String> {
     Ok(result.into_inner().params.unwrap().gravity_id)
 }
 
+pub async fn get_params(grpc_channel: &Channel) -> Result<Params> {
+    let mut gravity_query_client = GravityQueryClient::new(grpc_channel.clone());
+    let result = gravity_query_client.params(QueryParamsRequest {}).await?;
+    result.into_inner().params.ok_or_else(|| Error::msg("no gravity params"))
+}
+
+pub async fn get_current_valset(grpc_channel: &Channel) -> Result<Option<Valset>> {
+    let mut gravity_query_client = GravityQueryClient::new(grpc_channel.clone());
+    let result = gravity_query_client.current_valset(QueryCurrentValsetRequest {}).await?;
+    Ok(result.into_inner().valset)
+}
+
+pub async fn get_valset_request(grpc_channel: &Channel, nonce: u64) -> Result<Option<Valset>> {
+    let mut gravity_query_client = GravityQueryClient::new(grpc_channel.clone());
+    let result = gravity_query_client.valset_request(QueryValsetRequestRequest { nonce }).await?;
+    Ok(result.into_inner().valset)
+}
+
+pub async fn get_last_valset_requests(grpc_channel: &Channel) -> Result<Vec<Valset>> {
+    let mut gravity_query_client = GravityQueryClient::new(grpc_channel.clone());
+    let result = gravity_query_client.last_valset_requests(QueryLastValsetRequestsRequest {}).await?;
+    Ok(result.into_inner().valsets)
+}
+
+pub async fn get_valset_confirms(grpc_channel: &Channel, nonce: u64) -> Result<Vec<MsgValsetConfirm>> {
+    let mut gravity_query_client = GravityQueryClient::new(grpc_channel.clone());
+    let result = gravity_query_client.valset_confirms_by_nonce(QueryValsetConfirmsByNonceRequest { nonce }).await?;
+    Ok(result.into_inner().confirms)
+}
+
+pub async fn get_batch_request_by_nonce(grpc_channel: &Channel, contract_address: String, nonce: u64) -> Result<Option<OutgoingTxBatch>> {
+    let mut gravity_query_client = GravityQueryClient::new(grpc_channel.clone());
+    let result = gravity_query_client
+        .batch_request_by_nonce(QueryBatchRequestByNonceRequest { nonce, contract_address })
+        .await?;
+    Ok(result.into_inner().batch)
+}
+
+pub async fn get_batch_confirms(grpc_channel: &Channel, contract_address: String, nonce: u64) -> Result<Vec<MsgConfirmBatch>> {
+    let mut gravity_query_client = GravityQueryClient::new(grpc_channel.clone());
+    let result = gravity_query_client.batch_confirms(QueryBatchConfirmsRequest { nonce, contract_address }).await?;
+    Ok(result.into_inner().confirms)
+}
+
+pub async fn get_batch_fees(grpc_channel: &Channel) -> Result<Vec<BatchFees>> {
+    let mut gravity_query_client = GravityQueryClient::new(grpc_channel.clone());
+    let result = gravity_query_client.batch_fees(QueryBatchFeeRequest {}).await?;
+    Ok(result.into_inner().batch_fees)
+}
+
 pub async fn get_last_event_nonce(grpc_channel: &Channel, fx_address: FxAddress) -> Result<u64> {
     let mut gravity_query_client = GravityQueryClient::new(grpc_channel.clone());
     let result = gravity_query_client
@@ -242,6 +387,70 @@ pub async fn get_last_eth_block_height(grpc_channel: &Channel) -> Result<u64> {
     Ok(result.into_inner().block_height)
 }
 
+pub async fn get_last_pending_valset_requests(grpc_channel: &Channel, fx_address: FxAddress) -> Result<Vec<Valset>> {
+    let mut gravity_query_client = GravityQueryClient::new(grpc_channel.clone());
+    let result = gravity_query_client
+        .last_pending_valset_request_by_addr(QueryLastPendingValsetRequestByAddrRequest { address: fx_address.to_string() })
+        .await?;
+    Ok(result.into_inner().valsets)
+}
+
+pub async fn get_last_pending_batch_request(grpc_channel: &Channel, fx_address: FxAddress) -> Result<Option<OutgoingTxBatch>> {
+    let mut gravity_query_client = GravityQueryClient::new(grpc_channel.clone());
+    let result = gravity_query_client
+        .last_pending_batch_request_by_addr(QueryLastPendingBatchRequestByAddrRequest { address: fx_address.to_string() })
+        .await?;
+    Ok(result.into_inner().batch)
+}
+
+pub async fn get_erc20_to_denom(grpc_channel: &Channel, erc20: String) -> Result<QueryErc20ToDenomResponse> {
+    let mut gravity_query_client = GravityQueryClient::new(grpc_channel.clone());
+    let result = gravity_query_client.erc20_to_denom(QueryErc20ToDenomRequest { erc20 }).await?;
+    Ok(result.into_inner())
+}
+
+pub async fn get_denom_to_erc20(grpc_channel: &Channel, denom: String) -> Result<QueryDenomToErc20Response> {
+    let mut gravity_query_client = GravityQueryClient::new(grpc_channel.clone());
+    let result = gravity_query_client.denom_to_erc20(QueryDenomToErc20Request { denom }).await?;
+    Ok(result.into_inner())
+}
+
+/// `get_erc20_to_denom`, None if the ERC20 is not bridged
+pub async fn find_erc20_to_denom(grpc_channel: &Channel, erc20: String) -> Result<Option<QueryErc20ToDenomResponse>> {
+    let mut gravity_query_client = GravityQueryClient::new(grpc_channel.clone());
+    match gravity_query_client.erc20_to_denom(QueryErc20ToDenomRequest { erc20 }).await {
+        Ok(response) => Ok(Some(response.into_inner())),
+        Err(status) if is_not_found(&status) => Ok(None),
+        Err(status) => Err(status.into()),
+    }
+}
+
+/// `get_denom_to_erc20`, None if the denom is not bridged
+pub async fn find_denom_to_erc20(grpc_channel: &Channel, denom: String) -> Result<Option<QueryDenomToErc20Response>> {
+    let mut gravity_query_client = GravityQueryClient::new(grpc_channel.clone());
+    match gravity_query_client.denom_to_erc20(QueryDenomToErc20Request { denom }).await {
+        Ok(response) => Ok(Some(response.into_inner())),
+        Err(status) if is_not_found(&status) => Ok(None),
+        Err(status) => Err(status.into()),
+    }
+}
+
+pub async fn get_pending_send_to_eth(grpc_channel: &Channel, sender_address: FxAddress) -> Result<QueryPendingSendToEthResponse> {
+    let mut gravity_query_client = GravityQueryClient::new(grpc_channel.clone());
+    let result = gravity_query_client
+        .get_pending_send_to_eth(QueryPendingSendToEthRequest {
+            sender_address: sender_address.to_string(),
+        })
+        .await?;
+    Ok(result.into_inner())
+}
+
+pub async fn get_outgoing_tx_batches(grpc_channel: &Channel) -> Result<Vec<OutgoingTxBatch>> {
+    let mut gravity_query_client = GravityQueryClient::new(grpc_channel.clone());
+    let result = gravity_query_client.outgoing_tx_batches(QueryOutgoingTxBatchesRequest {}).await?;
+    Ok(result.into_inner().batches)
+}
+
 pub async fn get_orchestrator_validator(grpc_channel: &Channel, fx_address: FxAddress) -> Result<String> {
     let mut gravity_query_client = GravityQueryClient::new(grpc_channel.clone());
     let result = gravity_query_client
@@ -273,95 +482,41 @@ pub async fn get_orchestrator_validator_status(grpc_channel: &Channel, fx_addres
     return Ok((val.unwrap().status, orchestrator.eth_address));
 }
 
-#[cfg(test)]
-mod tests {
-    use std::ops::Div;
-
-    use crate::grpc_client::{get_account_info, new_grpc_channel};
-    use crate::private_key::PrivateKey;
-    use crate::x::gravity::MsgSetOrchestratorAddress;
-
-    use super::*;
-
-    const FX_MNEMONIC: &str = "";
-    const FX_GRPC_URL: &str = "http://127.0.0.1:9090";
-
-    #[tokio::test]
-    async fn test_update_gravity_delegate_addresses() {
-        let fx_private_key = PrivateKey::from_phrase(FX_MNEMONIC, "").unwrap();
-        let fx_address = fx_private_key.public_key().to_address().to_string();
-        let grpc_channel = new_grpc_channel(FX_GRPC_URL).await.unwrap();
-        let auth_account = get_account_info(&grpc_channel, fx_address).await.unwrap();
-        let fx_chain_id = get_chain_id(&grpc_channel).await.unwrap();
-        let fx_builder = Builder::new(fx_chain_id, fx_private_key, auth_account.account_number, "FX");
-
-        let msg = MsgSetOrchestratorAddress {
-            validator: fx_builder.address().to_valoper().unwrap(),
-            orchestrator: String::from("fx1zgpzdf2uqla7hkx85wnn4p2r3duwqzd8xst6v2"),
-            eth_address: String::from("0xeAD9C93b79Ae7C1591b1FB5323BD777E86e150d4"),
-        };
-        let message = msg.to_any("/fx.gravity.v1.MsgSetOrchestratorAddress");
-
-        let tx_response = send_tx(&fx_builder, &grpc_channel, vec![message]).await.unwrap();
-        // println!("{:#?}", tx_response)
-        println!("code: {}, info: {}, tx hash: {}", tx_response.code, tx_response.info, tx_response.txhash)
-    }
-
-    #[tokio::test]
-    async fn test_get_account_info() {
-        let private_key = PrivateKey::from_phrase(FX_MNEMONIC, "").unwrap();
-        let fx_address = private_key.public_key().to_address().to_string();
-        let grpc_channel = new_grpc_channel(FX_GRPC_URL).await.unwrap();
-        let auth_account = get_account_info(&grpc_channel, fx_address).await.unwrap();
-        assert_eq!(0u64, auth_account.account_number);
-        // println!("account info: {:?}", auth_account);
-        println!("account info: {}, number: {}, sequence: {}", auth_account.address, auth_account.account_number, auth_account.sequence);
-    }
-
-    #[tokio::test]
-    async fn test_new_grpc_channel() {
-        let grpc_channel = new_grpc_channel(FX_GRPC_URL).await.unwrap();
-        assert_eq!("Channel", format!("{:?}", grpc_channel));
-    }
-
-    #[tokio::test]
-    async fn test_check_for_fee_denom() {
-        env_logger::builder().filter_module("fxchain::client", log::LevelFilter::Trace).init();
-        let grpc_channel = new_grpc_channel("tcp://127.0.0.1:9090").await.unwrap();
-        let private_key = PrivateKey::from_phrase(FX_MNEMONIC, "").unwrap();
-        let fx_address = private_key.public_key().to_address();
-        check_for_fee_denom(&grpc_channel, fx_address, "FX").await;
+pub async fn get_delegate_key_by_validator(grpc_channel: &Channel, validator_address: String) -> Result<Option<QueryDelegateKeyByValidatorResponse>> {
+    let mut gravity_query_client = GravityQueryClient::new(grpc_channel.clone());
+    let result = gravity_query_client
+        .get_delegate_key_by_validator(QueryDelegateKeyByValidatorRequest { validator_address })
+        .await;
+    match result {
+        Ok(response) => Ok(Some(response.into_inner())),
+        Err(status) if is_not_found(&status) => Ok(None),
+        Err(status) => Err(status.into()),
     }
+}
 
-    #[tokio::test]
-    async fn test_get_chain_id() {
-        let grpc_channel = new_grpc_channel(FX_GRPC_URL).await.unwrap();
-        let chain_id = get_chain_id(&grpc_channel).await.unwrap();
-        println!("{}", chain_id);
-        assert_eq!(chain_id.to_string(), "fxcore".to_string())
+pub async fn get_delegate_key_by_eth(grpc_channel: &Channel, eth_address: String) -> Result<Option<QueryDelegateKeyByEthResponse>> {
+    let mut gravity_query_client = GravityQueryClient::new(grpc_channel.clone());
+    let result = gravity_query_client.get_delegate_key_by_eth(QueryDelegateKeyByEthRequest { eth_address }).await;
+    match result {
+        Ok(response) => Ok(Some(response.into_inner())),
+        Err(status) if is_not_found(&status) => Ok(None),
+        Err(status) => Err(status.into()),
     }
+}
 
-    #[tokio::test]
-    async fn test_get_last_event_nonce() {
-        let grpc_channel = new_grpc_channel("tcp://127.0.0.1:9090").await.unwrap();
-
-        let private_key = PrivateKey::from_phrase(FX_MNEMONIC, "").unwrap();
-        let fx_address = private_key.public_key().to_address();
-
-        let result = get_last_event_nonce(&grpc_channel, fx_address).await;
-        match result {
-            Ok(nonce) => println!("{}", nonce),
-            Err(e) => println!("{}", e),
-        };
+pub async fn get_delegate_key_by_orchestrator(grpc_channel: &Channel, orchestrator_address: String) -> Result<Option<QueryDelegateKeyByOrchestratorResponse>> {
+    let mut gravity_query_client = GravityQueryClient::new(grpc_channel.clone());
+    let result = gravity_query_client
+        .get_delegate_key_by_orchestrator(QueryDelegateKeyByOrchestratorRequest { orchestrator_address })
+        .await;
+    match result {
+        Ok(response) => Ok(Some(response.into_inner())),
+        Err(status) if is_not_found(&status) => Ok(None),
+        Err(status) => Err(status.into()),
     }
+}
 
-    #[tokio::test]
-    async fn test_get_gas_price_by_denom() {
-        let grpc_channel = new_grpc_channel("tcp://127.0.0.1:9090").await.unwrap();
-        let gas_price = get_gas_price_by_denom(&grpc_channel, "FX".to_string()).await.unwrap();
-        println!("{}, {}", gas_price.amount, gas_price.denom);
-        println!("{}", BigInt::from_str(gas_price.amount.as_str()).unwrap().div(BigInt::from(10).pow(18)));
-        println!("{}", BigInt::from_str(gas_price.amount.as_str()).unwrap().to_string());
-        println!("{}", BigInt::from_str(gas_price.amount.as_str()).unwrap().mul(DEFAULT_GAS_LIMIT).to_string())
-    }
+/// A query of a missing key, account or denom. Other codes are real failures, a node down or a bad request
+fn is_not_found(status: &Status) -> bool {
+    status.code() == Code::NotFound
 }
@@ -30,6 +30,9 @@ pub const FX_MSG_MAX_NUMBER: usize = 100;
 
 pub const DEFAULT_TX_TIMEOUT_HEIGHT: u32 = 0u32;
 
+/// Prefix of the denoms the gravity module mints for Ethereum originated tokens, followed by the ERC20 address
+pub const GRAVITY_DENOM_PREFIX: &str = "eth0x";
+
 pub fn get_gas_price_multiplier() -> f64 {
     return match std::env::var("FX_GAS_LIMIT_MULTIPLIER") {
         Ok(gas_price) => {
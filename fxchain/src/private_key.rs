@@ -5,13 +5,19 @@ use eyre::Result;
 use k256::ecdsa::Signature;
 use k256::ecdsa::VerifyingKey;
 use num_bigint::BigUint;
-use rand_core::OsRng;
+use rand_core::{OsRng, RngCore};
 use secp256k1::constants::CURVE_ORDER;
 use secp256k1::Secp256k1;
 use secp256k1::{PublicKey as PublicKeyEC, SecretKey};
 use sha2::{Digest, Sha256, Sha512};
 use std::str::FromStr;
 
+/// BIP44 path of f(x)Core keys without the account index, `m/44'/118'/0'/0/n`
+pub const FX_HD_PATH_PREFIX: &str = "m/44'/118'/0'/0";
+
+/// BIP44 path of the f(x)Core key, coin type 118
+pub const FX_HD_PATH: &str = "m/44'/118'/0'/0/0";
+
 pub struct PrivateKey {
     inner: Box<dyn Secp256k1Signer>,
 }
@@ -56,38 +62,11 @@ impl PrivateKey {
         if phrase.is_empty() {
             return Err(eyre::Error::msg("phrase can't be empty"));
         }
-        PrivateKey::from_hd_wallet_path("m/44'/118'/0'/0/0", phrase, passphrase)
+        PrivateKey::from_hd_wallet_path(FX_HD_PATH, phrase, passphrase)
     }
 
     pub fn from_hd_wallet_path(path: &str, phrase: &str, passphrase: &str) -> Result<PrivateKey> {
-        if !path.starts_with('m') || path.contains('\\') {
-            return Err(eyre::Error::msg("invalid path spec"));
-        }
-        let mut iterator = path.split('/');
-        // discard the m
-        let _ = iterator.next();
-
-        let key_import = Mnemonic::from_str(phrase).unwrap();
-        let seed_bytes = key_import.to_seed(passphrase);
-        let (master_secret_key, master_chain_code) = master_key_from_seed(&seed_bytes);
-        let mut secret_key = master_secret_key;
-        let mut chain_code = master_chain_code;
-
-        for mut val in iterator {
-            let mut hardened = false;
-            if val.contains('\'') {
-                hardened = true;
-                val = val.trim_matches('\'');
-            }
-            if let Ok(parsed_int) = val.parse() {
-                let (s, c) = get_child_key(secret_key, chain_code, parsed_int, hardened);
-                secret_key = s;
-                chain_code = c;
-            } else {
-                return Err(eyre::Error::msg("phrase can't be empty"));
-            }
-        }
-        PrivateKey::from_bytes(secret_key.as_ref())
+        PrivateKey::from_bytes(derive_secret(path, phrase, passphrase)?.as_ref())
     }
 
     pub fn public_key(&self) -> PublicKey {
@@ -132,13 +111,13 @@ impl TryFrom<&[u8]> for PrivateKey {
     }
 }
 
-pub trait Secp256k1Signer: ecdsa::signature::Signer<Signature> {
+pub trait Secp256k1Signer: ecdsa::signature::Signer<Signature> + Send + Sync {
     fn verifying_key(&self) -> VerifyingKey;
 }
 
 impl<T> Secp256k1Signer for T
 where
-    T: ecdsa::signature::Signer<Signature>,
+    T: ecdsa::signature::Signer<Signature> + Send + Sync,
     k256::ecdsa::VerifyingKey: for<'a> From<&'a T>,
 {
     fn verifying_key(&self) -> VerifyingKey {
@@ -146,6 +125,50 @@ where
     }
 }
 
+/// Generates a new English BIP39 mnemonic of 12, 15, 18, 21 or 24 words
+pub fn generate_mnemonic(word_count: usize) -> Result<String> {
+    if word_count < 12 || word_count > 24 || word_count % 3 != 0 {
+        return Err(eyre::Error::msg(format!("invalid mnemonic word count {}", word_count)));
+    }
+    let mut entropy = vec![0u8; word_count / 3 * 4];
+    OsRng.fill_bytes(&mut entropy);
+    let mnemonic = Mnemonic::from_entropy(&entropy).map_err(|err| eyre::Error::msg(format!("{}", err)))?;
+    Ok(mnemonic.to_string())
+}
+
+/// Derives the secp256k1 secret at a BIP32 path, e.g. `m/44'/60'/0'/0/0` for an Ethereum key.
+/// The one derivation of both the f(x)Core and the Ethereum keys of a mnemonic
+pub fn derive_secret(path: &str, phrase: &str, passphrase: &str) -> Result<[u8; 32]> {
+    if !path.starts_with('m') || path.contains('\\') {
+        return Err(eyre::Error::msg("invalid path spec"));
+    }
+    let mut iterator = path.split('/');
+    // discard the m
+    let _ = iterator.next();
+
+    let key_import = Mnemonic::from_str(phrase).map_err(|err| eyre::Error::msg(format!("invalid mnemonic: {}", err)))?;
+    let seed_bytes = key_import.to_seed(passphrase);
+    let (master_secret_key, master_chain_code) = master_key_from_seed(&seed_bytes);
+    let mut secret_key = master_secret_key;
+    let mut chain_code = master_chain_code;
+
+    for mut val in iterator {
+        let mut hardened = false;
+        if val.contains('\'') {
+            hardened = true;
+            val = val.trim_matches('\'');
+        }
+        if let Ok(parsed_int) = val.parse() {
+            let (s, c) = get_child_key(secret_key, chain_code, parsed_int, hardened);
+            secret_key = s;
+            chain_code = c;
+        } else {
+            return Err(eyre::Error::msg(format!("invalid path element {}", val)));
+        }
+    }
+    Ok(secret_key)
+}
+
 fn master_key_from_seed(seed_bytes: &[u8]) -> ([u8; 32], [u8; 32]) {
     use hmac::crypto_mac::Mac;
     use hmac::crypto_mac::NewMac;
@@ -220,6 +243,23 @@ mod tests {
         assert_eq!(ACC_ADDRESS, address)
     }
 
+    #[test]
+    fn test_derive_secret() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let secret = derive_secret("m/44'/60'/0'/0/0", phrase, "").unwrap();
+        assert_eq!(hex::encode(secret), "1ab42cc412b618bdea3a599e3c9bae199ebf030895b039e9db1e30dafb12b727");
+        assert!(derive_secret("m/44'/60'/x", phrase, "").is_err());
+        assert!(derive_secret("m/44'/60'/0'/0/0", "abandon about", "").is_err());
+    }
+
+    #[test]
+    fn test_generate_mnemonic() {
+        let phrase = generate_mnemonic(24).unwrap();
+        assert_eq!(phrase.split(' ').count(), 24);
+        assert!(PrivateKey::from_phrase(phrase.as_str(), "").is_ok());
+        assert!(generate_mnemonic(13).is_err());
+    }
+
     #[test]
     fn test_many_key_generation() {
         for _ in 0..1000 {
@@ -0,0 +1,32 @@
+use std::path::Path;
+
+use clap::Parser;
+use eyre::Result;
+
+#[derive(Parser, Debug)]
+pub enum AuditCmd {
+    ///check the hash chain of an audit log
+    #[clap(name = "verify")]
+    Verify(Verify),
+}
+
+#[derive(Parser, Debug)]
+pub struct Verify {
+    /// Audit log file written by `fxeth bridge --audit-log`
+    #[clap(long, default_value = "audit.jsonl")]
+    file: String,
+}
+
+pub async fn run(cmd: AuditCmd) -> Result<()> {
+    match cmd {
+        AuditCmd::Verify(cmd) => verify(cmd),
+    }
+}
+
+fn verify(cmd: Verify) -> Result<()> {
+    match bridge::audit::verify(Path::new(cmd.file.as_str()))? {
+        Some(last) => println!("{} records verified, last seq {} hash {}", last.seq + 1, last.seq, last.hash),
+        None => println!("audit log is empty"),
+    }
+    Ok(())
+}
@@ -0,0 +1,173 @@
+use std::str::FromStr;
+
+use eyre::Result;
+use serde_derive::Deserialize;
+
+use bridge::remote_signer::RemoteSigner;
+use bridge::signer::ConfirmSigner;
+use ethereum::private_key::{PrivateKey as EthPrivateKey, ETH_HD_PATH_PREFIX};
+use fxchain::private_key::{derive_secret, PrivateKey as FxPrivateKey, Secp256k1Signer, FX_HD_PATH_PREFIX};
+
+use crate::secret::Secret;
+
+/// Orchestrator identities hosted by `fxeth multi-bridge`
+#[derive(Debug, Deserialize)]
+pub struct MultiBridgeConfig {
+    #[serde(rename = "orchestrator")]
+    pub orchestrators: Vec<OrchestratorConfig>,
+}
+
+/// The keys of one orchestrator, from the same sources as `fxeth bridge`
+#[derive(Debug, Deserialize)]
+pub struct OrchestratorConfig {
+    /// f(x)Core orchestrator mnemonic
+    pub fx_chain_key: Option<Secret>,
+    /// Ethereum private key
+    pub ethereum_key: Option<Secret>,
+    /// Mnemonic both keys are derived from, instead of `fx_chain_key` and `ethereum_key`
+    pub mnemonic: Option<Secret>,
+    /// Account index of the f(x)Core key derived from `mnemonic`
+    #[serde(default)]
+    pub fx_account_index: u32,
+    /// Account index of the Ethereum key derived from `mnemonic`
+    #[serde(default)]
+    pub eth_account_index: u32,
+    /// Unix socket of a `fxeth signer` holding the keys
+    pub remote_signer: Option<String>,
+    /// Keystore directory to load a rotated Ethereum key from, decrypted with `ethereum_pwd`
+    pub ethereum_keystore_dir: Option<String>,
+    /// Ethereum keystore password file, defaults to --ethereum-pwd
+    pub ethereum_pwd: Option<String>,
+    /// f(x)Core send transactions fee, defaults to --fees
+    pub fees: Option<String>,
+}
+
+impl OrchestratorConfig {
+    pub fn keys(&self) -> Result<OrchestratorKeys> {
+        match (&self.remote_signer, &self.mnemonic, &self.fx_chain_key, &self.ethereum_key) {
+            (Some(socket), None, None, None) => Ok(OrchestratorKeys::Remote(socket.clone())),
+            (None, Some(mnemonic), None, None) => Ok(OrchestratorKeys::Mnemonic {
+                mnemonic: mnemonic.clone(),
+                fx_account_index: self.fx_account_index,
+                eth_account_index: self.eth_account_index,
+            }),
+            (None, None, Some(fx_chain_key), Some(ethereum_key)) => Ok(OrchestratorKeys::Keys {
+                fx_chain_key: fx_chain_key.clone(),
+                ethereum_key: ethereum_key.clone(),
+            }),
+            _ => Err(eyre::Error::msg("set either remote_signer, mnemonic, or both fx_chain_key and ethereum_key")),
+        }
+    }
+}
+
+/// Where the orchestrator keys come from
+#[derive(Debug, Clone)]
+pub enum OrchestratorKeys {
+    /// Unix socket of a `fxeth signer`
+    Remote(String),
+    Mnemonic {
+        mnemonic: Secret,
+        fx_account_index: u32,
+        eth_account_index: u32,
+    },
+    Keys {
+        fx_chain_key: Secret,
+        ethereum_key: Secret,
+    },
+}
+
+impl OrchestratorKeys {
+    /// The f(x)Core key and the signer of the Ethereum confirms
+    pub async fn load(&self) -> Result<(FxPrivateKey, Box<dyn ConfirmSigner>)> {
+        match self {
+            OrchestratorKeys::Remote(socket) => {
+                let remote_signer = RemoteSigner::connect(socket.as_str()).await?;
+                Ok((FxPrivateKey::from(Box::new(remote_signer.clone()) as Box<dyn Secp256k1Signer>), Box::new(remote_signer)))
+            }
+            OrchestratorKeys::Mnemonic {
+                mnemonic,
+                fx_account_index,
+                eth_account_index,
+            } => {
+                let fx_path = format!("{}/{}", FX_HD_PATH_PREFIX, fx_account_index);
+                let fx_private_key = FxPrivateKey::from_hd_wallet_path(fx_path.as_str(), mnemonic.expose(), "")?;
+                let eth_path = format!("{}/{}", ETH_HD_PATH_PREFIX, eth_account_index);
+                let eth_private_key = EthPrivateKey::from_slice(&derive_secret(eth_path.as_str(), mnemonic.expose(), "")?)?;
+                Ok((fx_private_key, Box::new(eth_private_key)))
+            }
+            OrchestratorKeys::Keys { fx_chain_key, ethereum_key } => {
+                let fx_private_key = FxPrivateKey::from_phrase(fx_chain_key.expose(), "")?;
+                let eth_private_key = EthPrivateKey::from_str(ethereum_key.expose())?;
+                Ok((fx_private_key, Box::new(eth_private_key)))
+            }
+        }
+    }
+}
+
+impl MultiBridgeConfig {
+    pub fn from_file(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let config: MultiBridgeConfig = toml::from_str(content.as_str())?;
+        if config.orchestrators.is_empty() {
+            return Err(eyre::Error::msg(format!("no [[orchestrator]] in {}", path)));
+        }
+        for (i, orchestrator) in config.orchestrators.iter().enumerate() {
+            orchestrator.keys().map_err(|report| eyre::Error::msg(format!("[[orchestrator]] {} in {}: {}", i + 1, path, report)))?;
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_multi_bridge_config() {
+        let config: MultiBridgeConfig = toml::from_str(
+            r#"
+            [[orchestrator]]
+            fx_chain_key = "word1 word2"
+            ethereum_key = "0x01"
+
+            [[orchestrator]]
+            fx_chain_key = "word3 word4"
+            ethereum_key = "0x02"
+            fees = "FX"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.orchestrators.len(), 2);
+        assert_eq!(config.orchestrators[0].fees, None);
+        assert_eq!(config.orchestrators[1].fees, Some("FX".to_string()));
+        assert!(matches!(config.orchestrators[0].keys().unwrap(), OrchestratorKeys::Keys { .. }));
+    }
+
+    #[test]
+    fn test_orchestrator_keys() {
+        let config: MultiBridgeConfig = toml::from_str(
+            r#"
+            [[orchestrator]]
+            remote_signer = "/root/fxeth-signer.sock"
+
+            [[orchestrator]]
+            mnemonic = "word1 word2"
+            eth_account_index = 1
+            ethereum_keystore_dir = "keystore"
+
+            [[orchestrator]]
+            fx_chain_key = "word3 word4"
+
+            [[orchestrator]]
+            remote_signer = "/root/fxeth-signer.sock"
+            ethereum_key = "0x01"
+            "#,
+        )
+        .unwrap();
+        let keys: Vec<_> = config.orchestrators.iter().map(|orchestrator| orchestrator.keys()).collect();
+        assert!(matches!(&keys[0], Ok(OrchestratorKeys::Remote(socket)) if socket == "/root/fxeth-signer.sock"));
+        assert!(matches!(&keys[1], Ok(OrchestratorKeys::Mnemonic { eth_account_index: 1, .. })));
+        assert!(keys[2].is_err());
+        assert!(keys[3].is_err());
+    }
+}
@@ -0,0 +1,407 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use clap::{ArgEnum, Parser};
+use eyre::Result;
+use serde_derive::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use web3::transports::Http;
+use web3::types::{Address as EthAddress, BlockId, Log, U256, U64};
+use web3::Web3;
+
+use ethereum::address::Checksum;
+use ethereum::erc20::ERC20;
+use ethereum::fx_bridge::*;
+use ethereum::units::format_units;
+use fxchain::address::Address as FxAddress;
+
+#[derive(Parser, Debug)]
+pub enum ExportCmd {
+    ///write the FxBridge events of a block range as JSON Lines or CSV
+    #[clap(name = "events")]
+    Events(Events),
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Jsonl,
+    Csv,
+}
+
+#[derive(Parser, Debug)]
+pub struct Events {
+    /// Ethereum RPC address
+    #[clap(long, default_value = "http://127.0.0.1:8545")]
+    ethereum_rpc: String,
+    /// f(x)Core Bridge contract address
+    #[clap(long)]
+    bridge_addr: String,
+    /// First Ethereum block of the range
+    #[clap(long)]
+    from_block: u64,
+    /// Last Ethereum block of the range, defaults to the latest block
+    #[clap(long)]
+    to_block: Option<u64>,
+    /// Blocks scanned per eth_getLogs request
+    #[clap(long, default_value = "5000")]
+    chunk_size: u64,
+    /// Output format
+    #[clap(long, arg_enum, default_value = "jsonl")]
+    format: ExportFormat,
+    /// Output file, resumed from `<out>.checkpoint` when it exists
+    #[clap(long)]
+    out: String,
+}
+
+/// Columns of an exported event, in output order
+const COLUMNS: [&str; 16] = [
+    "event",
+    "block_number",
+    "block_time",
+    "tx_hash",
+    "log_index",
+    "event_nonce",
+    "token",
+    "symbol",
+    "decimals",
+    "amount",
+    "amount_decimal",
+    "sender",
+    "destination",
+    "target_ibc",
+    "batch_nonce",
+    "valset_nonce",
+];
+
+/// One exported event, keyed by the names in `COLUMNS`
+#[derive(Debug, Default, Clone, PartialEq)]
+struct EventRecord {
+    fields: HashMap<&'static str, String>,
+}
+
+impl EventRecord {
+    fn set(&mut self, column: &'static str, value: impl ToString) -> &mut Self {
+        self.fields.insert(column, value.to_string());
+        self
+    }
+
+    fn get(&self, column: &str) -> &str {
+        self.fields.get(column).map(|value| value.as_str()).unwrap_or("")
+    }
+
+    fn to_json_line(&self) -> String {
+        let object: Map<String, Value> = COLUMNS.iter().map(|column| (column.to_string(), Value::String(self.get(column).to_string()))).collect();
+        Value::Object(object).to_string()
+    }
+
+    fn to_csv_line(&self) -> String {
+        let cells: Vec<String> = COLUMNS.iter().map(|column| csv_escape(self.get(column))).collect();
+        cells.join(",")
+    }
+}
+
+fn csv_escape(value: &str) -> String {
+    if value.contains(|c: char| c == ',' || c == '"' || c == '\n' || c == '\r') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Progress of an export, written after every chunk
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Checkpoint {
+    /// export the output belongs to, a resume must ask for the same one
+    bridge_addr: EthAddress,
+    format: ExportFormat,
+    from_block: u64,
+    to_block: u64,
+    /// first block not exported yet
+    next_block: u64,
+    /// output length once that block range was written
+    output_len: u64,
+}
+
+impl Checkpoint {
+    fn path(out: &Path) -> PathBuf {
+        let mut path = out.as_os_str().to_owned();
+        path.push(".checkpoint");
+        PathBuf::from(path)
+    }
+
+    fn load(out: &Path) -> Result<Option<Checkpoint>> {
+        match fs::read_to_string(Checkpoint::path(out)) {
+            Ok(data) => Ok(Some(serde_json::from_str(data.as_str())?)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Fails unless the export asked for is the one the checkpoint was written by, `to_block` defaults to the checkpoint's
+    fn check(&self, bridge_addr: EthAddress, format: ExportFormat, from_block: u64, to_block: Option<u64>) -> Result<()> {
+        let mismatch = if self.bridge_addr != bridge_addr {
+            Some(("bridge", self.bridge_addr.to_hex_string(), bridge_addr.to_hex_string()))
+        } else if self.format != format {
+            Some(("format", format!("{:?}", self.format), format!("{:?}", format)))
+        } else if self.from_block != from_block || to_block.map(|to_block| to_block != self.to_block).unwrap_or(false) {
+            let requested = to_block.map(|to_block| to_block.to_string()).unwrap_or_else(|| "latest".to_string());
+            Some(("range", format!("{}-{}", self.from_block, self.to_block), format!("{}-{}", from_block, requested)))
+        } else {
+            None
+        };
+        match mismatch {
+            Some((field, checkpoint, requested)) => Err(eyre::Error::msg(format!("checkpoint {} {} does not match the requested {}", field, checkpoint, requested))),
+            None => Ok(()),
+        }
+    }
+
+    fn save(&self, out: &Path) -> Result<()> {
+        let path = Checkpoint::path(out);
+        let tmp = path.with_extension("checkpoint.tmp");
+        fs::write(&tmp, serde_json::to_string(self)?)?;
+        fs::rename(tmp, path)?;
+        Ok(())
+    }
+}
+
+/// Open the output, cutting off whatever was written after the last checkpoint
+fn open_output(out: &Path, format: ExportFormat, checkpoint: Option<&Checkpoint>) -> Result<File> {
+    match checkpoint {
+        Some(checkpoint) => {
+            let mut file = OpenOptions::new().write(true).open(out)?;
+            file.set_len(checkpoint.output_len)?;
+            file.seek(SeekFrom::End(0))?;
+            Ok(file)
+        }
+        None => {
+            let mut file = File::create(out)?;
+            if format == ExportFormat::Csv {
+                writeln!(file, "{}", COLUMNS.join(","))?;
+            }
+            Ok(file)
+        }
+    }
+}
+
+pub async fn run(cmd: ExportCmd) -> Result<()> {
+    match cmd {
+        ExportCmd::Events(cmd) => export_events(cmd).await,
+    }
+}
+
+async fn export_events(cmd: Events) -> Result<()> {
+    let bridge_addr = EthAddress::from_str(cmd.bridge_addr.as_str())?;
+    let transport = Http::new(cmd.ethereum_rpc.as_str())?;
+    let web3 = Web3::new(transport);
+    let out = Path::new(cmd.out.as_str());
+    let checkpoint = Checkpoint::load(out)?;
+    if let Some(checkpoint) = checkpoint.as_ref() {
+        checkpoint.check(bridge_addr, cmd.format, cmd.from_block, cmd.to_block)?;
+    }
+    let to_block = match (cmd.to_block, checkpoint.as_ref()) {
+        (Some(to_block), _) => to_block,
+        (None, Some(checkpoint)) => checkpoint.to_block,
+        (None, None) => web3.eth().block_number().await?.as_u64(),
+    };
+    if cmd.from_block > to_block {
+        return Err(eyre::Error::msg(format!("--from-block {} is after --to-block {}", cmd.from_block, to_block)));
+    }
+    if cmd.chunk_size == 0 {
+        return Err(eyre::Error::msg("--chunk-size must be greater than 0"));
+    }
+
+    let mut from_block = cmd.from_block;
+    if let Some(checkpoint) = checkpoint.as_ref() {
+        info!("Resume export of {} from block {}", cmd.out, checkpoint.next_block);
+        from_block = checkpoint.next_block;
+    }
+    let mut file = open_output(out, cmd.format, checkpoint.as_ref())?;
+
+    let mut exporter = Exporter::new(&web3, bridge_addr).await;
+    let mut exported = 0;
+    while from_block <= to_block {
+        let chunk_end = to_block.min(from_block + cmd.chunk_size - 1);
+        let logs = query_bridge_logs(&web3, bridge_addr, U64::from(from_block), Some(U64::from(chunk_end))).await?;
+        for log in logs.iter() {
+            let record = exporter.record(log).await?;
+            let line = match cmd.format {
+                ExportFormat::Jsonl => record.to_json_line(),
+                ExportFormat::Csv => record.to_csv_line(),
+            };
+            writeln!(file, "{}", line)?;
+        }
+        file.sync_data()?;
+        exported += logs.len();
+        from_block = chunk_end + 1;
+        Checkpoint {
+            bridge_addr,
+            format: cmd.format,
+            from_block: cmd.from_block,
+            to_block,
+            next_block: from_block,
+            output_len: file.metadata()?.len(),
+        }
+        .save(out)?;
+        info!("Exported blocks up to {}, {} events", chunk_end, exported);
+    }
+    println!("{} events exported to {}, up to block {}", exported, cmd.out, to_block);
+    Ok(())
+}
+
+/// Turns bridge logs into records, caching block times and token metadata
+struct Exporter<'a> {
+    web3: &'a Web3<Http>,
+    block_times: HashMap<U64, U256>,
+    tokens: HashMap<EthAddress, (String, u8)>,
+}
+
+impl<'a> Exporter<'a> {
+    async fn new(web3: &'a Web3<Http>, bridge_addr: EthAddress) -> Exporter<'a> {
+        let fx_bridge = FxBridge::new(None, None, web3.eth(), bridge_addr);
+        let tokens = match fx_bridge.get_bridge_token_list().await {
+            Ok(tokens) => tokens.into_iter().map(|token| (token.addr, (token.symbol, token.decimals))).collect(),
+            Err(report) => {
+                warn!("Failed to get the bridge token list, fall back to ERC20 queries: {:?}", report);
+                HashMap::new()
+            }
+        };
+        Exporter {
+            web3,
+            block_times: HashMap::new(),
+            tokens,
+        }
+    }
+
+    async fn block_time(&mut self, block_number: U64) -> Result<U256> {
+        if let Some(time) = self.block_times.get(&block_number) {
+            return Ok(*time);
+        }
+        let block = self.web3.eth().block(BlockId::from(block_number)).await?;
+        let time = block.ok_or_else(|| eyre::Error::msg(format!("block {} not found", block_number)))?.timestamp;
+        self.block_times.insert(block_number, time);
+        Ok(time)
+    }
+
+    async fn token(&mut self, token: EthAddress) -> (String, u8) {
+        if let Some(metadata) = self.tokens.get(&token) {
+            return metadata.clone();
+        }
+        let erc20 = ERC20::new(None, None, self.web3.eth(), token);
+        let metadata = match (erc20.symbol().await, erc20.decimals().await) {
+            (Ok(symbol), Ok(decimals)) => (symbol, decimals),
+            _ => {
+                warn!("Failed to get the symbol and decimals of {}", token.to_hex_string());
+                (String::new(), 0)
+            }
+        };
+        self.tokens.insert(token, metadata.clone());
+        metadata
+    }
+
+    async fn record(&mut self, log: &Log) -> Result<EventRecord> {
+        let mut record = EventRecord::default();
+        let block_number = log.block_number.ok_or_else(|| eyre::Error::msg("log is not in a block"))?;
+        record
+            .set("block_number", block_number)
+            .set("block_time", self.block_time(block_number).await?)
+            .set("tx_hash", log.transaction_hash.map(|hash| format!("{:?}", hash)).unwrap_or_default())
+            .set("log_index", log.log_index.map(|index| index.to_string()).unwrap_or_default());
+
+        let signature = log.topics.first().cloned().unwrap_or_default();
+        if signature == SendToFxEvent::signature() {
+            let event = SendToFxEvent::from_log(log)?;
+            let (symbol, decimals) = self.token(event.erc20).await;
+            record
+                .set("event", "SendToFxEvent")
+                .set("event_nonce", event.event_nonce)
+                .set("token", event.erc20.to_hex_string())
+                .set("symbol", symbol)
+                .set("decimals", decimals)
+                .set("amount", event.amount)
+                .set("amount_decimal", format_units(event.amount, decimals))
+                .set("sender", event.sender.to_hex_string())
+                .set("destination", FxAddress::from_bytes(event.destination))
+                .set("target_ibc", event.target_ibc);
+        } else if signature == TransactionBatchExecutedEvent::signature() {
+            let event = TransactionBatchExecutedEvent::from_log(log)?;
+            let (symbol, decimals) = self.token(event.erc20).await;
+            record
+                .set("event", "TransactionBatchExecutedEvent")
+                .set("event_nonce", event.event_nonce)
+                .set("token", event.erc20.to_hex_string())
+                .set("symbol", symbol)
+                .set("decimals", decimals)
+                .set("batch_nonce", event.batch_nonce);
+        } else if signature == FxOriginatedTokenEvent::signature() {
+            let event = FxOriginatedTokenEvent::from_log(log)?;
+            self.tokens.insert(event.erc20, (event.symbol.clone(), event.decimals.low_u32() as u8));
+            record
+                .set("event", "FxOriginatedTokenEvent")
+                .set("event_nonce", event.event_nonce)
+                .set("token", event.erc20.to_hex_string())
+                .set("symbol", event.symbol)
+                .set("decimals", event.decimals);
+        } else if signature == ValsetUpdatedEvent::signature() {
+            let event = ValsetUpdatedEvent::from_log(log)?;
+            record.set("event", "ValsetUpdatedEvent").set("event_nonce", event.event_nonce).set("valset_nonce", event.valset_nonce);
+        } else {
+            return Err(eyre::Error::msg(format!("unknown event signature {:?}", signature)));
+        }
+        Ok(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_record() {
+        let mut record = EventRecord::default();
+        record.set("event", "SendToFxEvent").set("block_number", 12).set("symbol", "US\"D,T");
+        assert_eq!(record.to_csv_line(), "SendToFxEvent,12,,,,,,\"US\"\"D,T\",,,,,,,,");
+        let json: Value = serde_json::from_str(record.to_json_line().as_str()).unwrap();
+        assert_eq!(json["symbol"], "US\"D,T");
+        assert_eq!(json["tx_hash"], "");
+        assert_eq!(json.as_object().unwrap().len(), COLUMNS.len());
+    }
+
+    #[test]
+    fn test_checkpoint_resume() {
+        let out = std::env::temp_dir().join(format!("fxeth-export-{}.csv", std::process::id()));
+        let mut file = open_output(&out, ExportFormat::Csv, None).unwrap();
+        writeln!(file, "first").unwrap();
+        let checkpoint = Checkpoint {
+            bridge_addr: EthAddress::repeat_byte(1),
+            format: ExportFormat::Csv,
+            from_block: 10,
+            to_block: 200,
+            next_block: 100,
+            output_len: file.metadata().unwrap().len(),
+        };
+        checkpoint.save(&out).unwrap();
+        writeln!(file, "written after the checkpoint").unwrap();
+        drop(file);
+
+        let loaded = Checkpoint::load(&out).unwrap().unwrap();
+        assert_eq!(loaded, checkpoint);
+        loaded.check(EthAddress::repeat_byte(1), ExportFormat::Csv, 10, None).unwrap();
+        loaded.check(EthAddress::repeat_byte(1), ExportFormat::Csv, 10, Some(200)).unwrap();
+        assert!(loaded.check(EthAddress::repeat_byte(2), ExportFormat::Csv, 10, None).is_err());
+        assert!(loaded.check(EthAddress::repeat_byte(1), ExportFormat::Jsonl, 10, None).is_err());
+        assert!(loaded.check(EthAddress::repeat_byte(1), ExportFormat::Csv, 0, None).is_err());
+        assert!(loaded.check(EthAddress::repeat_byte(1), ExportFormat::Csv, 10, Some(300)).is_err());
+        let mut file = open_output(&out, ExportFormat::Csv, Some(&loaded)).unwrap();
+        writeln!(file, "second").unwrap();
+        drop(file);
+        assert_eq!(fs::read_to_string(&out).unwrap(), format!("{}\nfirst\nsecond\n", COLUMNS.join(",")));
+
+        fs::remove_file(Checkpoint::path(&out)).unwrap();
+        fs::remove_file(&out).unwrap();
+        assert_eq!(Checkpoint::load(&out).unwrap(), None);
+    }
+}
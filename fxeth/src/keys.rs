@@ -0,0 +1,355 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use clap::Parser;
+use eyre::Result;
+use web3::types::Address as EthAddress;
+
+use ethereum::address::Checksum;
+use ethereum::keystore::Keystore;
+use ethereum::private_key::{Key, PrivateKey as EthPrivateKey};
+use fxchain::address::Address as FxAddress;
+use fxchain::builder::Builder;
+use fxchain::grpc_client::{get_delegate_key_by_eth, get_delegate_key_by_orchestrator, get_delegate_key_by_validator, new_grpc_channel, send_tx};
+use fxchain::private_key::{derive_secret, generate_mnemonic, PrivateKey as FxPrivateKey, FX_HD_PATH};
+use fxchain::proto_ext::MessageExt;
+use fxchain::x::gravity::MsgSetOrchestratorAddress;
+
+use crate::output::{OutputFormat, Table};
+use crate::secret::Secret;
+
+/// BIP44 path of the Ethereum key, coin type 60
+const ETH_HD_PATH: &str = "m/44'/60'/0'/0/0";
+
+#[derive(Parser, Debug)]
+pub enum KeysCmd {
+    ///register or rotate the orchestrator and Ethereum delegate keys of a validator
+    #[clap(name = "register")]
+    Register(Register),
+    ///generate a mnemonic and derive the orchestrator keys from it
+    #[clap(name = "generate")]
+    Generate(Generate),
+    ///derive the orchestrator keys from a mnemonic
+    #[clap(name = "derive")]
+    Derive(Derive),
+    ///show the addresses of a hex private key or a keystore
+    #[clap(name = "show")]
+    Show(Show),
+    ///check a keystore and copy it into a keystore directory
+    #[clap(name = "import")]
+    Import(Import),
+    ///encrypt a key derived from a mnemonic, or a hex private key, into a keystore
+    #[clap(name = "export")]
+    Export(Export),
+}
+
+#[derive(Parser, Debug)]
+pub struct Generate {
+    /// Number of mnemonic words
+    #[clap(long, default_value = "24")]
+    words: usize,
+    /// HD path of the f(x)Core key
+    #[clap(long, default_value = FX_HD_PATH)]
+    fx_path: String,
+    /// HD path of the Ethereum key
+    #[clap(long, default_value = ETH_HD_PATH)]
+    eth_path: String,
+    /// Output format
+    #[clap(long, arg_enum, default_value = "table")]
+    output: OutputFormat,
+}
+
+#[derive(Parser, Debug)]
+pub struct Derive {
+    /// Mnemonic of the keys, prefer the FXETH_MNEMONIC env var
+    #[clap(long, env = "FXETH_MNEMONIC", hide_env_values = true)]
+    mnemonic: Secret,
+    /// HD path of the f(x)Core key
+    #[clap(long, default_value = FX_HD_PATH)]
+    fx_path: String,
+    /// HD path of the Ethereum key
+    #[clap(long, default_value = ETH_HD_PATH)]
+    eth_path: String,
+    /// Output format
+    #[clap(long, arg_enum, default_value = "table")]
+    output: OutputFormat,
+}
+
+#[derive(Parser, Debug)]
+pub struct Show {
+    /// Hex private key
+    #[clap(long, required_unless_present = "keystore")]
+    private_key: Option<Secret>,
+    /// Keystore file
+    #[clap(long, requires = "password-file")]
+    keystore: Option<String>,
+    /// File holding the keystore password
+    #[clap(long)]
+    password_file: Option<String>,
+    /// Output format
+    #[clap(long, arg_enum, default_value = "table")]
+    output: OutputFormat,
+}
+
+#[derive(Parser, Debug)]
+pub struct Import {
+    /// Keystore file
+    #[clap(long)]
+    keystore: String,
+    /// File holding the keystore password
+    #[clap(long)]
+    password_file: String,
+    /// Keystore directory, e.g. the bridge --ethereum-keystore-dir
+    #[clap(long)]
+    keystore_dir: String,
+}
+
+#[derive(Parser, Debug)]
+pub struct Export {
+    /// Mnemonic to derive the key from, prefer the FXETH_MNEMONIC env var
+    #[clap(long, env = "FXETH_MNEMONIC", hide_env_values = true, required_unless_present = "private-key")]
+    mnemonic: Option<Secret>,
+    /// HD path of the key derived from the mnemonic, the f(x)Core key is m/44'/118'/0'/0/0
+    #[clap(long, default_value = ETH_HD_PATH)]
+    path: String,
+    /// Hex private key
+    #[clap(long)]
+    private_key: Option<Secret>,
+    /// File holding the keystore password
+    #[clap(long)]
+    password_file: String,
+    /// Directory the keystore is written to, as <address>.json
+    #[clap(long, default_value = ".")]
+    keystore_dir: String,
+}
+
+#[derive(Parser, Debug)]
+pub struct Register {
+    /// f(x)Core validator operator private key
+    #[clap(long)]
+    validator_key: Secret,
+    /// Orchestrator f(x)Core address, defaults to the validator account
+    #[clap(long)]
+    orchestrator: Option<String>,
+    /// Orchestrator Ethereum address
+    #[clap(long, required_unless_present = "ethereum-key")]
+    ethereum_address: Option<String>,
+    /// Orchestrator Ethereum private key, used to derive the Ethereum address
+    #[clap(long)]
+    ethereum_key: Option<Secret>,
+    /// Replace delegate keys already registered for this validator
+    #[clap(long)]
+    rotate: bool,
+    /// f(x)Core gRPC address
+    #[clap(long, default_value = "http://127.0.0.1:9090")]
+    fx_chain_grpc: String,
+    /// f(x)Core send transactions fee
+    #[clap(long, default_value = "FX")]
+    fees: String,
+}
+
+pub async fn run(cmd: KeysCmd) -> Result<()> {
+    match cmd {
+        KeysCmd::Register(cmd) => register(cmd).await,
+        KeysCmd::Generate(cmd) => {
+            let mnemonic = generate_mnemonic(cmd.words)?;
+            let mut fields = vec![("mnemonic", mnemonic.clone())];
+            fields.extend(derive_keys(mnemonic.as_str(), cmd.fx_path.as_str(), cmd.eth_path.as_str())?);
+            Table::record(fields).print(cmd.output);
+            Ok(())
+        }
+        KeysCmd::Derive(cmd) => {
+            Table::record(derive_keys(cmd.mnemonic.expose(), cmd.fx_path.as_str(), cmd.eth_path.as_str())?).print(cmd.output);
+            Ok(())
+        }
+        KeysCmd::Show(cmd) => {
+            let private_key = match (cmd.private_key, cmd.keystore, cmd.password_file) {
+                (Some(private_key), _, _) => EthPrivateKey::from_str(private_key.expose())?,
+                (None, Some(keystore), Some(password_file)) => Keystore::from_file(Path::new(keystore.as_str()))?.decrypt(read_password(password_file.as_str())?.as_str())?,
+                _ => return Err(eyre::Error::msg("--private-key or --keystore and --password-file are required")),
+            };
+            Table::record(key_addresses(&private_key)?).print(cmd.output);
+            Ok(())
+        }
+        KeysCmd::Import(cmd) => {
+            let keystore = Keystore::from_file(Path::new(cmd.keystore.as_str()))?;
+            let private_key = keystore.decrypt(read_password(cmd.password_file.as_str())?.as_str())?;
+            let path = keystore.save(Path::new(cmd.keystore_dir.as_str()))?;
+            println!("imported {} to {}", private_key.address().to_hex_string(), path.display());
+            Ok(())
+        }
+        KeysCmd::Export(cmd) => {
+            let private_key = match (cmd.private_key, cmd.mnemonic) {
+                (Some(private_key), _) => EthPrivateKey::from_str(private_key.expose())?,
+                (None, Some(mnemonic)) => EthPrivateKey::from_slice(&derive_secret(cmd.path.as_str(), mnemonic.expose(), "")?)?,
+                (None, None) => return Err(eyre::Error::msg("--mnemonic or --private-key is required")),
+            };
+            let keystore = Keystore::encrypt(&private_key, read_password(cmd.password_file.as_str())?.as_str())?;
+            let path = keystore.save(Path::new(cmd.keystore_dir.as_str()))?;
+            println!("exported {} to {}", private_key.address().to_hex_string(), path.display());
+            Ok(())
+        }
+    }
+}
+
+/// Addresses of the f(x)Core and Ethereum keys derived from a mnemonic
+fn derive_keys(mnemonic: &str, fx_path: &str, eth_path: &str) -> Result<Vec<(&'static str, String)>> {
+    let fx_address = FxPrivateKey::from_hd_wallet_path(fx_path, mnemonic, "")?.public_key().to_address();
+    let eth_private_key = EthPrivateKey::from_slice(&derive_secret(eth_path, mnemonic, "")?)?;
+    Ok(vec![
+        ("fx_path", fx_path.to_string()),
+        ("fx_address", fx_address.to_string()),
+        ("fx_valoper", fx_address.to_valoper()?),
+        ("eth_path", eth_path.to_string()),
+        ("eth_address", eth_private_key.address().to_hex_string()),
+    ])
+}
+
+/// Addresses of one secp256k1 key on both chains
+fn key_addresses(private_key: &EthPrivateKey) -> Result<Vec<(&'static str, String)>> {
+    let fx_address = FxPrivateKey::from_bytes(private_key.to_bytes())?.public_key().to_address();
+    Ok(vec![
+        ("fx_address", fx_address.to_string()),
+        ("fx_valoper", fx_address.to_valoper()?),
+        ("eth_address", private_key.address().to_hex_string()),
+    ])
+}
+
+fn read_password(password_file: &str) -> Result<String> {
+    let password = std::fs::read_to_string(password_file)?;
+    Ok(password.trim_end_matches(&['\r', '\n'][..]).to_string())
+}
+
+async fn register(cmd: Register) -> Result<()> {
+    let validator_key = FxPrivateKey::from_phrase(cmd.validator_key.expose(), "")?;
+    let validator = validator_key.public_key().to_address().to_valoper()?;
+    let orchestrator = match cmd.orchestrator {
+        Some(orchestrator) => FxAddress::from_str(orchestrator.as_str())?,
+        None => validator_key.public_key().to_address(),
+    };
+    let eth_address = match (cmd.ethereum_address, cmd.ethereum_key) {
+        (Some(eth_address), _) => EthAddress::from_str(eth_address.as_str())?,
+        (None, Some(ethereum_key)) => EthPrivateKey::from_str(ethereum_key.expose())?.address(),
+        (None, None) => return Err(eyre::Error::msg("--ethereum-address or --ethereum-key is required")),
+    };
+    info!("Register validator {}, orchestrator {}, eth address {}", validator, orchestrator, eth_address.to_hex_string());
+
+    let grpc_channel = new_grpc_channel(cmd.fx_chain_grpc.as_str()).await?;
+
+    let registered = DelegateKeys {
+        by_validator: get_delegate_key_by_validator(&grpc_channel, validator.clone())
+            .await?
+            .map(|keys| (keys.orchestrator_address, keys.eth_address)),
+        by_eth: get_delegate_key_by_eth(&grpc_channel, eth_address.to_hex_string()).await?.map(|keys| keys.validator_address),
+        by_orchestrator: get_delegate_key_by_orchestrator(&grpc_channel, orchestrator.to_string()).await?.map(|keys| keys.validator_address),
+    };
+    if !registered.check(validator.as_str(), orchestrator, eth_address, cmd.rotate)? {
+        info!("Delegate keys already registered, nothing to do");
+        return Ok(());
+    }
+
+    let fx_builder = Builder::from_net(&grpc_channel, validator_key, cmd.fees.as_str()).await?;
+    let msg = MsgSetOrchestratorAddress {
+        validator,
+        orchestrator: orchestrator.to_string(),
+        eth_address: eth_address.to_hex_string(),
+    };
+    let tx_resp = send_tx(&fx_builder, &grpc_channel, vec![msg.to_any("/fx.gravity.v1.MsgSetOrchestratorAddress")]).await?;
+    info!("Set orchestrator address tx response code {}, tx hash {}", tx_resp.code, tx_resp.txhash);
+    if tx_resp.code != 0 {
+        return Err(eyre::Error::msg(format!("set orchestrator address tx failed: {}", tx_resp.raw_log)));
+    }
+    Ok(())
+}
+
+/// Delegate keys already registered on f(x)Core
+#[derive(Debug, Default)]
+struct DelegateKeys {
+    /// (orchestrator, eth address) registered for the validator
+    by_validator: Option<(String, String)>,
+    /// validator owning the eth address
+    by_eth: Option<String>,
+    /// validator owning the orchestrator address
+    by_orchestrator: Option<String>,
+}
+
+impl DelegateKeys {
+    /// Returns whether a `MsgSetOrchestratorAddress` has to be sent
+    fn check(&self, validator: &str, orchestrator: FxAddress, eth_address: EthAddress, rotate: bool) -> Result<bool> {
+        if let Some(owner) = &self.by_eth {
+            if owner != validator {
+                return Err(eyre::Error::msg(format!("eth address {} is already registered by validator {}", eth_address.to_hex_string(), owner)));
+            }
+        }
+        if let Some(owner) = &self.by_orchestrator {
+            if owner != validator {
+                return Err(eyre::Error::msg(format!("orchestrator {} is already registered by validator {}", orchestrator, owner)));
+            }
+        }
+        if let Some((registered_orchestrator, registered_eth_address)) = &self.by_validator {
+            let same_orchestrator = FxAddress::from_str(registered_orchestrator.as_str()).map(|address| address == orchestrator).unwrap_or(false);
+            let same_eth_address = EthAddress::from_str(registered_eth_address.as_str()).map(|address| address == eth_address).unwrap_or(false);
+            if same_orchestrator && same_eth_address {
+                return Ok(false);
+            }
+            if !rotate {
+                return Err(eyre::Error::msg(format!(
+                    "validator {} already has orchestrator {} and eth address {}, use --rotate to replace them",
+                    validator, registered_orchestrator, registered_eth_address
+                )));
+            }
+            info!("Rotating delegate keys, previous orchestrator {}, eth address {}", registered_orchestrator, registered_eth_address);
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALIDATOR: &str = "fxvaloper1zgpzdf2uqla7hkx85wnn4p2r3duwqzd8wpk9j2";
+    const ORCHESTRATOR: &str = "fx1zgpzdf2uqla7hkx85wnn4p2r3duwqzd8xst6v2";
+    const ETH_ADDRESS: &str = "0xb4fA5979babd8Bb7e427157d0d353Cf205F43752";
+
+    fn check(keys: &DelegateKeys, rotate: bool) -> Result<bool> {
+        keys.check(VALIDATOR, FxAddress::from_str(ORCHESTRATOR).unwrap(), EthAddress::from_str(ETH_ADDRESS).unwrap(), rotate)
+    }
+
+    #[test]
+    fn test_derive_keys() {
+        let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let fields = derive_keys(mnemonic, FX_HD_PATH, ETH_HD_PATH).unwrap();
+        assert_eq!(fields[4], ("eth_address", "0x9858EfFD232B4033E47d90003D41EC34EcaEda94".to_string()));
+        assert!(fields[1].1.starts_with("fx1"));
+        assert!(fields[2].1.starts_with("fxvaloper1"));
+
+        let private_key = EthPrivateKey::from_slice(&derive_secret(FX_HD_PATH, mnemonic, "").unwrap()).unwrap();
+        assert_eq!(key_addresses(&private_key).unwrap()[0].1, fields[1].1);
+    }
+
+    #[test]
+    fn test_delegate_keys_check() {
+        assert!(check(&DelegateKeys::default(), false).unwrap());
+
+        let registered = DelegateKeys {
+            by_validator: Some((ORCHESTRATOR.to_string(), ETH_ADDRESS.to_lowercase())),
+            by_eth: Some(VALIDATOR.to_string()),
+            by_orchestrator: Some(VALIDATOR.to_string()),
+        };
+        assert!(!check(&registered, false).unwrap());
+
+        let rotation = DelegateKeys {
+            by_validator: Some((ORCHESTRATOR.to_string(), "0xeAD9C93b79Ae7C1591b1FB5323BD777E86e150d4".to_string())),
+            ..Default::default()
+        };
+        assert!(check(&rotation, false).is_err());
+        assert!(check(&rotation, true).unwrap());
+
+        let conflict = DelegateKeys {
+            by_eth: Some("fxvaloper1qllms2p25gec8fn4xvyak83g856xdltpal9w05".to_string()),
+            ..Default::default()
+        };
+        assert!(check(&conflict, true).is_err());
+    }
+}
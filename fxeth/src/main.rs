@@ -1,6 +1,7 @@
 #[macro_use]
 extern crate log;
 
+use std::path::Path;
 use std::str::FromStr;
 use std::time::Duration;
 
@@ -14,14 +15,33 @@ use web3::types::Address as EthAddress;
 use web3::types::U64;
 use web3::Web3;
 
-use bridge::oracle_loop::eth_oracle_bridge_loop;
+use bridge::audit::AuditLog;
+use bridge::oracle_loop::{eth_oracle_bridge_loop, eth_oracle_multi_bridge_loop};
+use bridge::remote_signer::{SignerServer, SigningPolicy};
 use bridge::singer_loop::eth_signer_main_loop;
 use ethereum::address::Checksum;
-use ethereum::private_key::{Key, PrivateKey as EthPrivateKey};
+use ethereum::keystore::KeystoreDir;
+use ethereum::private_key::PrivateKey as EthPrivateKey;
 use fxchain::builder::Builder;
 use fxchain::grpc_client::new_grpc_channel;
 use fxchain::private_key::PrivateKey as FxPrivateKey;
 
+use crate::config::OrchestratorKeys;
+use crate::secret::Secret;
+
+mod audit;
+mod config;
+mod export;
+mod keys;
+mod oracle;
+mod output;
+mod query;
+mod reconcile;
+mod secret;
+mod send_to_eth;
+mod send_to_fx;
+mod status;
+
 /// f(x)Core and Ethereum asset transfers
 #[derive(Parser, Debug)]
 #[clap(author, version)]
@@ -41,13 +61,49 @@ pub enum SubCmd {
     ///responsible for event on signature the f(x)Core and the Ethereum
     #[clap(name = "bridge")]
     BridgeCmd(Bridge),
+    ///run the bridge for every orchestrator listed in the config file
+    #[clap(name = "multi-bridge")]
+    MultiBridgeCmd(MultiBridge),
+    ///hold the orchestrator keys and sign for a remote bridge process
+    #[clap(name = "signer")]
+    SignerCmd(Signer),
+    ///manage the orchestrator keys
+    #[clap(subcommand, name = "keys")]
+    KeysCmd(keys::KeysCmd),
+    ///deposit ERC20 tokens from Ethereum to f(x)Core
+    #[clap(name = "send-to-fx")]
+    SendToFxCmd(send_to_fx::SendToFx),
+    ///send tokens from f(x)Core to Ethereum
+    #[clap(name = "send-to-eth")]
+    SendToEthCmd(send_to_eth::SendToEth),
+    ///cancel an unbatched transfer to Ethereum
+    #[clap(name = "cancel-send-to-eth")]
+    CancelSendToEthCmd(send_to_eth::CancelSendToEth),
+    ///inspect the signature and claim audit log
+    #[clap(subcommand, name = "audit")]
+    AuditCmd(audit::AuditCmd),
+    ///query the bridge state on f(x)Core and Ethereum
+    #[clap(name = "query")]
+    QueryCmd(query::Query),
+    ///check the health of an orchestrator and exit non-zero on failures
+    #[clap(name = "status")]
+    StatusCmd(status::Status),
+    ///replay the Ethereum oracle over a block range
+    #[clap(subcommand, name = "oracle")]
+    OracleCmd(oracle::OracleCmd),
+    ///export bridge events for accounting
+    #[clap(subcommand, name = "export")]
+    ExportCmd(export::ExportCmd),
+    ///compare the tokens locked on each side of the bridge and flag discrepancies
+    #[clap(name = "reconcile")]
+    ReconcileCmd(reconcile::Reconcile),
 }
 
 #[derive(Parser, Debug)]
 pub struct Bridge {
     /// f(x)Core validator private key
-    #[clap(long)]
-    fx_chain_key: String,
+    #[clap(long, required_unless_present_any = &["remote-signer", "mnemonic"])]
+    fx_chain_key: Option<Secret>,
     /// f(x)Core validator private key password
     #[clap(long, default_value = "/root/fx.password")]
     fx_chain_pwd: String,
@@ -55,8 +111,8 @@ pub struct Bridge {
     #[clap(long, default_value = "http://127.0.0.1:9090")]
     fx_chain_grpc: String,
     /// f(x)Core validator Ethereum private key
-    #[clap(long)]
-    ethereum_key: String,
+    #[clap(long, required_unless_present_any = &["remote-signer", "mnemonic"])]
+    ethereum_key: Option<Secret>,
     /// f(x)Core validator Ethereum private key password
     #[clap(long, default_value = "/root/eth.password")]
     ethereum_pwd: String,
@@ -72,6 +128,78 @@ pub struct Bridge {
     /// Ethereum start block number
     #[clap(long, default_value = "0")]
     eth_block_number: u64,
+    /// Mnemonic both orchestrator keys are derived from, instead of --fx-chain-key and --ethereum-key.
+    /// Prefer the FXETH_MNEMONIC env var, arguments show up in the process list
+    #[clap(long, env = "FXETH_MNEMONIC", hide_env_values = true, conflicts_with_all = &["fx-chain-key", "ethereum-key", "remote-signer"])]
+    mnemonic: Option<Secret>,
+    /// Account index of the f(x)Core key derived from --mnemonic, m/44'/118'/0'/0/n
+    #[clap(long, default_value = "0")]
+    fx_account_index: u32,
+    /// Account index of the Ethereum key derived from --mnemonic, m/44'/60'/0'/0/n
+    #[clap(long, default_value = "0")]
+    eth_account_index: u32,
+    /// Unix socket of a `fxeth signer` holding the orchestrator keys
+    #[clap(long)]
+    remote_signer: Option<String>,
+    /// Keystore directory to load a rotated Ethereum key from, decrypted with --ethereum-pwd
+    #[clap(long)]
+    ethereum_keystore_dir: Option<String>,
+    /// Append every confirm and claim to this hash-chained JSON Lines file
+    #[clap(long)]
+    audit_log: Option<String>,
+    /// Run the oracle and signer, sign every claim and confirm, but never broadcast them
+    #[clap(long)]
+    dry_run: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct MultiBridge {
+    /// f(x)Core gRPC address
+    #[clap(long, default_value = "http://127.0.0.1:9090")]
+    fx_chain_grpc: String,
+    /// Ethereum RPC address
+    #[clap(long, default_value = "http://127.0.0.1:8545")]
+    ethereum_rpc: String,
+    /// f(x)Core Bridge contract address
+    #[clap(long)]
+    bridge_addr: String,
+    /// f(x)Core send transactions fee
+    #[clap(long, default_value = "FX")]
+    fees: String,
+    /// Ethereum start block number
+    #[clap(long, default_value = "0")]
+    eth_block_number: u64,
+    /// Ethereum keystore password file of the orchestrators with an `ethereum_keystore_dir`
+    #[clap(long, default_value = "/root/eth.password")]
+    ethereum_pwd: String,
+    /// Append every confirm and claim to this hash-chained JSON Lines file
+    #[clap(long)]
+    audit_log: Option<String>,
+    /// Run the oracle and signer, sign every claim and confirm, but never broadcast them
+    #[clap(long)]
+    dry_run: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct Signer {
+    /// f(x)Core validator private key
+    #[clap(long)]
+    fx_chain_key: Secret,
+    /// f(x)Core validator Ethereum private key
+    #[clap(long)]
+    ethereum_key: Secret,
+    /// Unix socket the signer listens on
+    #[clap(long, default_value = "/root/fxeth-signer.sock")]
+    socket: String,
+    /// File of the highest valset and batch nonces signed, older or conflicting confirms are refused
+    #[clap(long, default_value = "/root/fxeth-signer-state.json")]
+    state_file: String,
+    /// Only sign f(x)Core transactions for this chain id
+    #[clap(long)]
+    chain_id: Option<String>,
+    /// Only sign confirms for this gravity id
+    #[clap(long)]
+    gravity_id: Option<String>,
 }
 
 #[tokio::main]
@@ -84,9 +212,23 @@ async fn main() {
         SubCmd::BridgeCmd(cmd) => {
             let transport = web3::transports::Http::new(cmd.ethereum_rpc.as_str()).unwrap();
             let web3 = web3::Web3::new(transport);
-            let ethereum_key_str = cmd.ethereum_key.as_str();
-            let eth_private_key = EthPrivateKey::from_str(ethereum_key_str).unwrap();
-            info!("Ethereum account address {}", eth_private_key.address().to_hex_string());
+            let orchestrator_keys = match (cmd.remote_signer, cmd.mnemonic) {
+                (Some(socket), _) => OrchestratorKeys::Remote(socket),
+                (None, Some(mnemonic)) => OrchestratorKeys::Mnemonic {
+                    mnemonic,
+                    fx_account_index: cmd.fx_account_index,
+                    eth_account_index: cmd.eth_account_index,
+                },
+                (None, None) => OrchestratorKeys::Keys {
+                    fx_chain_key: cmd.fx_chain_key.unwrap(),
+                    ethereum_key: cmd.ethereum_key.unwrap(),
+                },
+            };
+            let (fx_private_key, eth_signer) = orchestrator_keys.load().await.unwrap();
+            info!("Ethereum account address {}", eth_signer.address().to_hex_string());
+            let eth_keystore = cmd.ethereum_keystore_dir.map(|dir| KeystoreDir::new(dir.as_str(), cmd.ethereum_pwd.as_str()).unwrap());
+
+            let audit_log = cmd.audit_log.map(|path| AuditLog::open(path.as_str()).unwrap());
 
             let bridge_addr = EthAddress::from_str(cmd.bridge_addr.as_str()).unwrap();
             info!("Bridge address {}", bridge_addr.to_hex_string());
@@ -95,21 +237,138 @@ async fn main() {
 
             with_sync_block(&grpc_channel, &web3).await;
 
-            let fx_chain_key_str = cmd.fx_chain_key.as_str();
-            let fx_private_key = FxPrivateKey::from_phrase(fx_chain_key_str, "").unwrap();
-
-            let fx_builder = Builder::from_net(&grpc_channel, fx_private_key, cmd.fees.as_str()).await.unwrap();
+            let fx_builder = if cmd.dry_run {
+                Builder::from_net_dry_run(&grpc_channel, fx_private_key, cmd.fees.as_str()).await.unwrap()
+            } else {
+                Builder::from_net(&grpc_channel, fx_private_key, cmd.fees.as_str()).await.unwrap()
+            };
             info!("Fx bridge address {}", fx_builder.address().to_string());
 
             let eth_last_block = U64::from(cmd.eth_block_number);
             info!("ethereum start block number {}", eth_last_block);
 
-            let future1 = eth_oracle_bridge_loop(&fx_builder, &grpc_channel, &web3, bridge_addr, eth_last_block);
-            let future2 = eth_signer_main_loop(&fx_builder, &grpc_channel, &eth_private_key);
+            let future1 = eth_oracle_bridge_loop(&fx_builder, &grpc_channel, &web3, bridge_addr, eth_last_block, audit_log.as_ref());
+            let future2 = eth_signer_main_loop(&fx_builder, &grpc_channel, eth_signer.as_ref(), eth_keystore.as_ref(), audit_log.as_ref());
+            let future3 = prometheus::start(9899);
+
+            future::join3(future1, future2, future3).await;
+        }
+        SubCmd::MultiBridgeCmd(cmd) => {
+            let config = config::MultiBridgeConfig::from_file(opts.config_file.as_str()).unwrap();
+            let transport = web3::transports::Http::new(cmd.ethereum_rpc.as_str()).unwrap();
+            let web3 = web3::Web3::new(transport);
+            let audit_log = cmd.audit_log.map(|path| AuditLog::open(path.as_str()).unwrap());
+
+            let bridge_addr = EthAddress::from_str(cmd.bridge_addr.as_str()).unwrap();
+            info!("Bridge address {}", bridge_addr.to_hex_string());
+
+            let grpc_channel = new_grpc_channel(cmd.fx_chain_grpc.as_str()).await.unwrap();
+
+            with_sync_block(&grpc_channel, &web3).await;
+
+            let mut fx_builders = Vec::new();
+            let mut eth_signers = Vec::new();
+            let mut eth_keystores = Vec::new();
+            for orchestrator in config.orchestrators.iter() {
+                let (fx_private_key, eth_signer) = orchestrator.keys().unwrap().load().await.unwrap();
+                let ethereum_pwd = orchestrator.ethereum_pwd.as_ref().unwrap_or(&cmd.ethereum_pwd);
+                let eth_keystore = orchestrator.ethereum_keystore_dir.as_ref().map(|dir| KeystoreDir::new(dir.as_str(), ethereum_pwd.as_str()).unwrap());
+                let fees = orchestrator.fees.as_ref().unwrap_or(&cmd.fees);
+                let fx_builder = if cmd.dry_run {
+                    Builder::from_net_dry_run(&grpc_channel, fx_private_key, fees.as_str()).await.unwrap()
+                } else {
+                    Builder::from_net(&grpc_channel, fx_private_key, fees.as_str()).await.unwrap()
+                };
+                info!("Orchestrator {}, Ethereum account address {}", fx_builder.address(), eth_signer.address().to_hex_string());
+                fx_builders.push(fx_builder);
+                eth_signers.push(eth_signer);
+                eth_keystores.push(eth_keystore);
+            }
+
+            let eth_last_block = U64::from(cmd.eth_block_number);
+            info!("ethereum start block number {}, {} orchestrators", eth_last_block, fx_builders.len());
+
+            let future1 = eth_oracle_multi_bridge_loop(&fx_builders, &grpc_channel, &web3, bridge_addr, eth_last_block, audit_log.as_ref());
+            let future2 = future::join_all(
+                fx_builders
+                    .iter()
+                    .zip(eth_signers.iter().zip(eth_keystores.iter()))
+                    .map(|(fx_builder, (eth_signer, eth_keystore))| eth_signer_main_loop(fx_builder, &grpc_channel, eth_signer.as_ref(), eth_keystore.as_ref(), audit_log.as_ref())),
+            );
             let future3 = prometheus::start(9899);
 
             future::join3(future1, future2, future3).await;
         }
+        SubCmd::SignerCmd(cmd) => {
+            let fx_private_key = FxPrivateKey::from_phrase(cmd.fx_chain_key.expose(), "").unwrap();
+            let eth_private_key = EthPrivateKey::from_str(cmd.ethereum_key.expose()).unwrap();
+            let policy = SigningPolicy {
+                chain_id: cmd.chain_id,
+                gravity_id: cmd.gravity_id,
+            };
+            let server = SignerServer::new(fx_private_key, eth_private_key, policy, Path::new(cmd.state_file.as_str())).unwrap();
+            server.serve(cmd.socket.as_str()).await.unwrap();
+        }
+        SubCmd::KeysCmd(cmd) => {
+            if let Err(report) = keys::run(cmd).await {
+                error!("{:?}", report);
+                std::process::exit(1);
+            }
+        }
+        SubCmd::SendToFxCmd(cmd) => {
+            if let Err(report) = send_to_fx::run(cmd).await {
+                error!("{:?}", report);
+                std::process::exit(1);
+            }
+        }
+        SubCmd::SendToEthCmd(cmd) => {
+            if let Err(report) = send_to_eth::send_to_eth(cmd).await {
+                error!("{:?}", report);
+                std::process::exit(1);
+            }
+        }
+        SubCmd::CancelSendToEthCmd(cmd) => {
+            if let Err(report) = send_to_eth::cancel_send_to_eth(cmd).await {
+                error!("{:?}", report);
+                std::process::exit(1);
+            }
+        }
+        SubCmd::AuditCmd(cmd) => {
+            if let Err(report) = audit::run(cmd).await {
+                error!("{:?}", report);
+                std::process::exit(1);
+            }
+        }
+        SubCmd::QueryCmd(cmd) => {
+            if let Err(report) = query::run(cmd).await {
+                error!("{:?}", report);
+                std::process::exit(1);
+            }
+        }
+        SubCmd::StatusCmd(cmd) => {
+            if let Err(report) = status::run(cmd).await {
+                error!("{:?}", report);
+                std::process::exit(1);
+            }
+        }
+        SubCmd::OracleCmd(cmd) => {
+            if let Err(report) = oracle::run(cmd).await {
+                error!("{:?}", report);
+                std::process::exit(1);
+            }
+        }
+        SubCmd::ExportCmd(cmd) => {
+            if let Err(report) = export::run(cmd).await {
+                error!("{:?}", report);
+                std::process::exit(1);
+            }
+        }
+        SubCmd::ReconcileCmd(cmd) => {
+            if let Err(report) = reconcile::run(cmd).await {
+                error!("{:?}", report);
+                std::process::exit(1);
+            }
+        }
     }
 }
 
@@ -0,0 +1,104 @@
+use std::str::FromStr;
+
+use clap::Parser;
+use eyre::Result;
+use web3::types::{Address as EthAddress, U64};
+
+use bridge::audit::AuditLog;
+use bridge::oracle_loop::eth_oracle_replay;
+use fxchain::address::Address as FxAddress;
+use fxchain::builder::Builder;
+use fxchain::grpc_client::new_grpc_channel;
+use fxchain::private_key::PrivateKey as FxPrivateKey;
+
+use crate::secret::Secret;
+
+#[derive(Parser, Debug)]
+pub enum OracleCmd {
+    ///scan a block range once and submit the claims the orchestrator missed
+    #[clap(name = "replay")]
+    Replay(Replay),
+}
+
+#[derive(Parser, Debug)]
+pub struct Replay {
+    /// f(x)Core orchestrator private key
+    #[clap(long, required_unless_present_any = &["dry-run", "orchestrator"])]
+    fx_chain_key: Option<Secret>,
+    /// f(x)Core orchestrator address, enough for a dry run
+    #[clap(long, conflicts_with = "fx-chain-key", requires = "dry-run")]
+    orchestrator: Option<String>,
+    /// f(x)Core gRPC address
+    #[clap(long, default_value = "http://127.0.0.1:9090")]
+    fx_chain_grpc: String,
+    /// Ethereum RPC address
+    #[clap(long, default_value = "http://127.0.0.1:8545")]
+    ethereum_rpc: String,
+    /// f(x)Core Bridge contract address
+    #[clap(long)]
+    bridge_addr: String,
+    /// f(x)Core send transactions fee
+    #[clap(long, default_value = "FX")]
+    fees: String,
+    /// First Ethereum block of the range
+    #[clap(long)]
+    from_block: u64,
+    /// Last Ethereum block of the range
+    #[clap(long)]
+    to_block: u64,
+    /// Print the claims instead of sending them
+    #[clap(long)]
+    dry_run: bool,
+    /// Append the submitted claims to this hash-chained JSON Lines file
+    #[clap(long)]
+    audit_log: Option<String>,
+}
+
+pub async fn run(cmd: OracleCmd) -> Result<()> {
+    match cmd {
+        OracleCmd::Replay(cmd) => replay(cmd).await,
+    }
+}
+
+async fn replay(cmd: Replay) -> Result<()> {
+    if cmd.from_block > cmd.to_block {
+        return Err(eyre::Error::msg(format!("--from-block {} is after --to-block {}", cmd.from_block, cmd.to_block)));
+    }
+    let bridge_addr = EthAddress::from_str(cmd.bridge_addr.as_str())?;
+    let transport = web3::transports::Http::new(cmd.ethereum_rpc.as_str())?;
+    let web3 = web3::Web3::new(transport);
+    let grpc_channel = new_grpc_channel(cmd.fx_chain_grpc.as_str()).await?;
+    let audit_log = cmd.audit_log.map(|path| AuditLog::open(path.as_str())).transpose()?;
+
+    let fx_private_key = cmd.fx_chain_key.map(|key| FxPrivateKey::from_phrase(key.expose(), "")).transpose()?;
+    let orchestrator = match (&fx_private_key, cmd.orchestrator) {
+        (Some(fx_private_key), _) => fx_private_key.public_key().to_address(),
+        (None, Some(orchestrator)) => FxAddress::from_str(orchestrator.as_str())?,
+        (None, None) => return Err(eyre::Error::msg("--fx-chain-key or --orchestrator is required")),
+    };
+    let fx_builder = match fx_private_key {
+        Some(fx_private_key) if !cmd.dry_run => Some(Builder::from_net(&grpc_channel, fx_private_key, cmd.fees.as_str()).await?),
+        _ => None,
+    };
+
+    let claims = eth_oracle_replay(
+        fx_builder.as_ref(),
+        orchestrator,
+        &grpc_channel,
+        &web3,
+        bridge_addr,
+        U64::from(cmd.from_block),
+        U64::from(cmd.to_block),
+        audit_log.as_ref(),
+    )
+    .await?;
+    for claim in claims.iter() {
+        if cmd.dry_run {
+            println!("{} {:#?}", claim.type_url(), claim);
+        } else {
+            println!("submitted {} event nonce {}", claim.type_url(), claim.event_nonce());
+        }
+    }
+    println!("{} claims {}", claims.len(), if cmd.dry_run { "to submit" } else { "submitted" });
+    Ok(())
+}
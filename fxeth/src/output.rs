@@ -0,0 +1,104 @@
+use clap::ArgEnum;
+use serde_json::{Map, Value};
+
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Table,
+    Json,
+}
+
+/// Query result printed as an aligned table or as JSON
+#[derive(Debug, Default)]
+pub struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+    /// a single record, printed as `key value` lines or a JSON object
+    record: bool,
+}
+
+impl Table {
+    pub fn new(headers: &[&str]) -> Self {
+        Table {
+            headers: headers.iter().map(|header| header.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    pub fn record(fields: Vec<(&str, String)>) -> Self {
+        Table {
+            headers: fields.iter().map(|(key, _)| key.to_string()).collect(),
+            rows: vec![fields.into_iter().map(|(_, value)| value).collect()],
+            record: true,
+        }
+    }
+
+    pub fn push(&mut self, row: Vec<String>) {
+        self.rows.push(row);
+    }
+
+    pub fn print(&self, format: OutputFormat) {
+        println!("{}", self.render(format));
+    }
+
+    pub fn render(&self, format: OutputFormat) -> String {
+        match format {
+            OutputFormat::Json => {
+                let mut objects = self.rows.iter().map(|row| {
+                    let object: Map<String, Value> = self.headers.iter().cloned().zip(row.iter().map(|value| Value::String(value.clone()))).collect();
+                    Value::Object(object)
+                });
+                let value = if self.record {
+                    objects.next().unwrap_or_default()
+                } else {
+                    Value::Array(objects.collect())
+                };
+                serde_json::to_string_pretty(&value).unwrap_or_default()
+            }
+            OutputFormat::Table if self.record => {
+                let width = self.headers.iter().map(|header| header.len()).max().unwrap_or(0);
+                let row = self.rows.first().cloned().unwrap_or_default();
+                let lines: Vec<String> = self.headers.iter().zip(row.iter()).map(|(key, value)| format!("{:width$}  {}", key, value, width = width)).collect();
+                lines.join("\n")
+            }
+            OutputFormat::Table => {
+                let mut widths: Vec<usize> = self.headers.iter().map(|header| header.len()).collect();
+                for row in self.rows.iter() {
+                    for (width, value) in widths.iter_mut().zip(row.iter()) {
+                        *width = (*width).max(value.len());
+                    }
+                }
+                let format_row = |row: &Vec<String>| {
+                    let cells: Vec<String> = row.iter().zip(widths.iter()).map(|(value, width)| format!("{:width$}", value, width = width)).collect();
+                    cells.join("  ").trim_end().to_string()
+                };
+                let mut lines = vec![format_row(&self.headers)];
+                lines.extend(self.rows.iter().map(format_row));
+                lines.join("\n")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_table() {
+        let mut table = Table::new(&["nonce", "token"]);
+        table.push(vec!["1".to_string(), "0xabc".to_string()]);
+        table.push(vec!["12".to_string(), "0xd".to_string()]);
+        assert_eq!(table.render(OutputFormat::Table), "nonce  token\n1      0xabc\n12     0xd");
+        assert_eq!(
+            serde_json::from_str::<Value>(table.render(OutputFormat::Json).as_str()).unwrap(),
+            serde_json::json!([{"nonce": "1", "token": "0xabc"}, {"nonce": "12", "token": "0xd"}])
+        );
+
+        let record = Table::record(vec![("gravity_id", "fx-bridge".to_string()), ("bridge_chain_id", "1".to_string())]);
+        assert_eq!(record.render(OutputFormat::Table), "gravity_id       fx-bridge\nbridge_chain_id  1");
+        assert_eq!(
+            serde_json::from_str::<Value>(record.render(OutputFormat::Json).as_str()).unwrap(),
+            serde_json::json!({"gravity_id": "fx-bridge", "bridge_chain_id": "1"})
+        );
+    }
+}
@@ -0,0 +1,518 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use clap::Parser;
+use eyre::Result;
+use web3::types::Address as EthAddress;
+
+use ethereum::abi::fx_bridge::LastBatchNonceCall;
+use ethereum::address::Checksum;
+use ethereum::fx_bridge::{BridgeTokenStatus, FxBridge};
+use fxchain::address::Address as FxAddress;
+use fxchain::grpc_client::*;
+use fxchain::x::gravity::{MsgConfirmBatch, OutgoingTransferTx, OutgoingTxBatch, QueryErc20ToDenomResponse, Valset};
+
+use crate::output::{OutputFormat, Table};
+
+#[derive(Parser, Debug)]
+pub struct Query {
+    /// f(x)Core gRPC address
+    #[clap(long, global = true, default_value = "http://127.0.0.1:9090")]
+    fx_chain_grpc: String,
+    /// Ethereum RPC address
+    #[clap(long, global = true, default_value = "http://127.0.0.1:8545")]
+    ethereum_rpc: String,
+    /// f(x)Core Bridge contract address, defaults to the one in the gravity params
+    #[clap(long, global = true)]
+    bridge_addr: Option<String>,
+    /// Multicall3 contract address to batch the contract queries in one eth_call, they are a JSON-RPC batch request without it
+    #[clap(long, global = true)]
+    multicall_addr: Option<String>,
+    /// Output format
+    #[clap(long, global = true, arg_enum, default_value = "table")]
+    output: OutputFormat,
+    #[clap(subcommand)]
+    cmd: QueryCmd,
+}
+
+#[derive(Parser, Debug)]
+pub enum QueryCmd {
+    ///gravity module params
+    #[clap(name = "params")]
+    Params,
+    ///current validator set
+    #[clap(name = "current-valset")]
+    CurrentValset,
+    ///validator set request by nonce, or the latest requests
+    #[clap(name = "valset")]
+    Valset { nonce: Option<u64> },
+    ///confirms of a validator set
+    #[clap(name = "valset-confirms")]
+    ValsetConfirms { nonce: u64 },
+    ///outgoing transaction batches
+    #[clap(name = "batches")]
+    Batches,
+    ///transactions of a batch
+    #[clap(name = "batch")]
+    Batch { token: String, nonce: u64 },
+    ///confirms of a batch
+    #[clap(name = "batch-confirms")]
+    BatchConfirms { token: String, nonce: u64 },
+    ///fees of the unbatched transactions by token
+    #[clap(name = "batch-fees")]
+    BatchFees,
+    ///pending transfers to Ethereum of a sender
+    #[clap(name = "pending-send-to-eth")]
+    PendingSendToEth { sender: String },
+    ///delegate keys by validator, orchestrator or Ethereum address
+    #[clap(name = "delegate-keys")]
+    DelegateKeys { address: String },
+    ///last event nonce claimed by an orchestrator
+    #[clap(name = "last-event-nonce")]
+    LastEventNonce { orchestrator: String },
+    ///last Ethereum block height observed by f(x)Core
+    #[clap(name = "last-observed-eth-height")]
+    LastObservedEthHeight,
+    ///FxBridge contract state
+    #[clap(name = "contract")]
+    Contract,
+    ///last batch nonce of tokens in the FxBridge contract
+    #[clap(name = "last-batch-nonce")]
+    LastBatchNonce { tokens: Vec<String> },
+    ///tokens registered in the FxBridge contract
+    #[clap(name = "bridge-tokens")]
+    BridgeTokens,
+    ///every bridge token with its state in the FxBridge contract and its f(x)Core denom, flagging the ones on a single side
+    #[clap(name = "token-status")]
+    TokenStatus,
+}
+
+/// A bridge token, in the FxBridge contract, on f(x)Core or on both
+#[derive(Debug, Default, Clone, PartialEq)]
+struct TokenStatus {
+    token: EthAddress,
+    contract: Option<BridgeTokenStatus>,
+    denom: String,
+    fx_originated: bool,
+    /// the token is registered on f(x)Core or f(x)Core has a supply of its denom
+    on_fx_chain: bool,
+}
+
+impl TokenStatus {
+    fn side(&self) -> &'static str {
+        match (self.contract.is_some(), self.on_fx_chain) {
+            (true, true) => "both",
+            (true, false) => "ethereum only",
+            (false, _) => "fx only",
+        }
+    }
+}
+
+pub async fn run(query: Query) -> Result<()> {
+    let multicall_addr = query.multicall_addr.as_deref().map(EthAddress::from_str).transpose()?;
+    let grpc_channel = new_grpc_channel(query.fx_chain_grpc.as_str()).await?;
+    let table = match query.cmd {
+        QueryCmd::Params => {
+            let params = get_params(&grpc_channel).await?;
+            let dec = |bytes: &Vec<u8>| String::from_utf8_lossy(bytes).to_string();
+            Table::record(vec![
+                ("gravity_id", params.gravity_id.clone()),
+                ("contract_source_hash", params.contract_source_hash.clone()),
+                ("bridge_eth_address", params.bridge_eth_address.clone()),
+                ("bridge_chain_id", params.bridge_chain_id.to_string()),
+                ("signed_valsets_window", params.signed_valsets_window.to_string()),
+                ("signed_batches_window", params.signed_batches_window.to_string()),
+                ("signed_claims_window", params.signed_claims_window.to_string()),
+                ("target_batch_timeout", params.target_batch_timeout.to_string()),
+                ("average_block_time", params.average_block_time.to_string()),
+                ("average_eth_block_time", params.average_eth_block_time.to_string()),
+                ("slash_fraction_valset", dec(&params.slash_fraction_valset)),
+                ("slash_fraction_batch", dec(&params.slash_fraction_batch)),
+                ("slash_fraction_claim", dec(&params.slash_fraction_claim)),
+                ("slash_fraction_conflicting_claim", dec(&params.slash_fraction_conflicting_claim)),
+                ("unbond_slashing_valsets_window", params.unbond_slashing_valsets_window.to_string()),
+                ("ibc_transfer_timeout_height", params.ibc_transfer_timeout_height.to_string()),
+                ("valset_update_power_change_percent", dec(&params.valset_update_power_change_percent)),
+            ])
+        }
+        QueryCmd::CurrentValset => valsets_table(get_current_valset(&grpc_channel).await?.into_iter().collect()),
+        QueryCmd::Valset { nonce: Some(nonce) } => {
+            let valset = get_valset_request(&grpc_channel, nonce).await?.ok_or_else(|| eyre::Error::msg(format!("no valset {}", nonce)))?;
+            valsets_table(vec![valset])
+        }
+        QueryCmd::Valset { nonce: None } => valsets_table(get_last_valset_requests(&grpc_channel).await?),
+        QueryCmd::ValsetConfirms { nonce } => {
+            let mut table = Table::new(&["nonce", "orchestrator", "eth_address", "signature"]);
+            for confirm in get_valset_confirms(&grpc_channel, nonce).await? {
+                table.push(vec![confirm.nonce.to_string(), confirm.orchestrator, confirm.eth_address, confirm.signature]);
+            }
+            table
+        }
+        QueryCmd::Batches => {
+            let mut table = Table::new(&["batch_nonce", "token_contract", "txs", "batch_timeout", "block", "fee_receive"]);
+            for batch in get_outgoing_tx_batches(&grpc_channel).await? {
+                table.push(batch_row(&batch));
+            }
+            table
+        }
+        QueryCmd::Batch { token, nonce } => {
+            let token = EthAddress::from_str(token.as_str())?.to_hex_string();
+            let batch = get_batch_request_by_nonce(&grpc_channel, token.clone(), nonce)
+                .await?
+                .ok_or_else(|| eyre::Error::msg(format!("no batch {} of {}", nonce, token)))?;
+            transfers_table(batch.transactions.iter(), true)
+        }
+        QueryCmd::BatchConfirms { token, nonce } => {
+            let token = EthAddress::from_str(token.as_str())?.to_hex_string();
+            let mut table = Table::new(&["nonce", "token_contract", "orchestrator", "eth_signer", "signature"]);
+            for confirm in get_batch_confirms(&grpc_channel, token, nonce).await? {
+                let MsgConfirmBatch {
+                    nonce,
+                    token_contract,
+                    eth_signer,
+                    orchestrator,
+                    signature,
+                } = confirm;
+                table.push(vec![nonce.to_string(), token_contract, orchestrator, eth_signer, signature]);
+            }
+            table
+        }
+        QueryCmd::BatchFees => {
+            let mut table = Table::new(&["token_contract", "total_fees", "total_txs"]);
+            for fees in get_batch_fees(&grpc_channel).await? {
+                table.push(vec![fees.token_contract, fees.total_fees, fees.total_txs.to_string()]);
+            }
+            table
+        }
+        QueryCmd::PendingSendToEth { sender } => {
+            let pending = get_pending_send_to_eth(&grpc_channel, FxAddress::from_str(sender.as_str())?).await?;
+            let mut table = transfers_table(pending.unbatched_transfers.iter(), false);
+            for transfer in pending.transfers_in_batches.iter() {
+                table.push(transfer_row(transfer, true));
+            }
+            table
+        }
+        QueryCmd::DelegateKeys { address } => delegate_keys_table(&grpc_channel, address.as_str()).await?,
+        QueryCmd::LastEventNonce { orchestrator } => {
+            let orchestrator = FxAddress::from_str(orchestrator.as_str())?;
+            let nonce = get_last_event_nonce(&grpc_channel, orchestrator).await?;
+            let block_height = get_last_event_block_height_by_addr(&grpc_channel, orchestrator).await?;
+            Table::record(vec![("last_event_nonce", nonce.to_string()), ("last_event_block_height", block_height.to_string())])
+        }
+        QueryCmd::LastObservedEthHeight => Table::record(vec![("last_observed_eth_height", get_last_eth_block_height(&grpc_channel).await?.to_string())]),
+        QueryCmd::Contract => {
+            let fx_bridge = new_fx_bridge(&grpc_channel, query.ethereum_rpc.as_str(), query.bridge_addr).await?;
+            Table::record(vec![
+                ("fx_bridge_id", String::from_utf8_lossy(&fx_bridge.state_fx_bridge_id().await?).trim_end_matches('\0').to_string()),
+                ("last_valset_nonce", fx_bridge.state_last_valset_nonce().await?.to_string()),
+                ("last_valset_checkpoint", hex::encode(fx_bridge.state_last_valset_checkpoint().await?)),
+                ("last_event_nonce", fx_bridge.state_last_event_nonce().await?.to_string()),
+                ("power_threshold", fx_bridge.state_power_threshold().await?.to_string()),
+                ("fx_originated_token", fx_bridge.fx_originated_token().await?.to_hex_string()),
+                ("owner", fx_bridge.owner().await?.to_hex_string()),
+            ])
+        }
+        QueryCmd::LastBatchNonce { tokens } => {
+            let fx_bridge = new_fx_bridge(&grpc_channel, query.ethereum_rpc.as_str(), query.bridge_addr).await?;
+            let tokens = if tokens.is_empty() {
+                fx_bridge.get_bridge_token_list().await?.into_iter().map(|token| token.addr).collect()
+            } else {
+                tokens.iter().map(|token| EthAddress::from_str(token.as_str())).collect::<Result<Vec<_>, _>>()?
+            };
+            let mut multicall = fx_bridge.multicall(multicall_addr);
+            let nonces: Vec<_> = tokens.iter().map(|token| multicall.add(fx_bridge.address(), LastBatchNonceCall { erc20_address: *token })).collect();
+            let results = multicall.call().await?;
+            let mut table = Table::new(&["token_contract", "last_batch_nonce"]);
+            for (token, nonce) in tokens.iter().zip(nonces) {
+                table.push(vec![token.to_hex_string(), nonce.decode(results.as_slice())?.to_string()]);
+            }
+            table
+        }
+        QueryCmd::BridgeTokens => {
+            let fx_bridge = new_fx_bridge(&grpc_channel, query.ethereum_rpc.as_str(), query.bridge_addr).await?;
+            let mut table = Table::new(&["address", "name", "symbol", "decimals"]);
+            for token in fx_bridge.get_bridge_token_list().await? {
+                table.push(vec![token.addr.to_hex_string(), token.name, token.symbol, token.decimals.to_string()]);
+            }
+            table
+        }
+        QueryCmd::TokenStatus => {
+            let fx_bridge = new_fx_bridge(&grpc_channel, query.ethereum_rpc.as_str(), query.bridge_addr).await?;
+            token_status_table(token_status(&grpc_channel, &fx_bridge, multicall_addr).await?.as_slice())
+        }
+    };
+    table.print(query.output);
+    Ok(())
+}
+
+/// FxBridge of `--bridge-addr`, or of the gravity params
+pub(crate) async fn new_fx_bridge(grpc_channel: &tonic::transport::Channel, ethereum_rpc: &str, bridge_addr: Option<String>) -> Result<FxBridge> {
+    let bridge_addr = match bridge_addr {
+        Some(bridge_addr) => bridge_addr,
+        None => get_params(grpc_channel).await?.bridge_eth_address,
+    };
+    let transport = web3::transports::Http::new(ethereum_rpc)?;
+    let web3 = web3::Web3::new(transport);
+    Ok(FxBridge::new(None, None, web3.eth(), EthAddress::from_str(bridge_addr.as_str())?))
+}
+
+/// Every bridge token of the FxBridge contract and of f(x)Core
+async fn token_status(grpc_channel: &tonic::transport::Channel, fx_bridge: &FxBridge, multicall_addr: Option<EthAddress>) -> Result<Vec<TokenStatus>> {
+    let contract_tokens = fx_bridge.bridge_token_statuses(multicall_addr).await?;
+    let mut denoms = Vec::new();
+    for token in contract_tokens.iter() {
+        // a token only in the contract has no denom
+        denoms.push(find_erc20_to_denom(grpc_channel, token.addr.to_hex_string()).await?.unwrap_or_default());
+    }
+    let contract_denoms = contract_tokens
+        .iter()
+        .zip(denoms.iter())
+        .filter(|(_, denom)| !denom.denom.is_empty())
+        .map(|(token, denom)| (denom.denom.clone(), (token.addr, denom.fx_originated)))
+        .collect();
+    let fx_chain_tokens = fx_chain_bridge_tokens(grpc_channel, contract_denoms).await?;
+    Ok(token_statuses(contract_tokens, denoms, fx_chain_tokens))
+}
+
+/// ERC20 and fx_originated of every bridged denom with a supply, by ERC20. The denoms of the contract tokens
+/// are known, the other denoms are only looked up if they have the gravity prefix.
+async fn fx_chain_bridge_tokens(grpc_channel: &tonic::transport::Channel, mut contract_denoms: HashMap<String, (EthAddress, bool)>) -> Result<HashMap<EthAddress, (String, bool)>> {
+    let mut tokens = HashMap::new();
+    for coin in get_total_supply(grpc_channel).await? {
+        if let Some((token, fx_originated)) = contract_denoms.remove(&coin.denom) {
+            tokens.insert(token, (coin.denom, fx_originated));
+            continue;
+        }
+        if !coin.denom.starts_with(fxchain::GRAVITY_DENOM_PREFIX) {
+            continue;
+        }
+        if let Some(erc20) = find_denom_to_erc20(grpc_channel, coin.denom.clone()).await? {
+            if !erc20.erc20.is_empty() {
+                tokens.insert(EthAddress::from_str(erc20.erc20.as_str())?, (coin.denom, erc20.fx_originated));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Match the contract tokens, with their `erc20_to_denom`, to the bridged f(x)Core denoms
+fn token_statuses(contract_tokens: Vec<BridgeTokenStatus>, denoms: Vec<QueryErc20ToDenomResponse>, mut fx_chain_tokens: HashMap<EthAddress, (String, bool)>) -> Vec<TokenStatus> {
+    let mut tokens: Vec<TokenStatus> = contract_tokens
+        .into_iter()
+        .zip(denoms)
+        .map(|(token, denom)| TokenStatus {
+            token: token.addr,
+            // a registered denom without supply yet is still on f(x)Core
+            on_fx_chain: fx_chain_tokens.remove(&token.addr).is_some() || !denom.denom.is_empty(),
+            contract: Some(token),
+            denom: denom.denom,
+            fx_originated: denom.fx_originated,
+        })
+        .collect();
+    let mut fx_only: Vec<_> = fx_chain_tokens.into_iter().collect();
+    fx_only.sort_by(|(_, (a, _)), (_, (b, _))| a.cmp(b));
+    tokens.extend(fx_only.into_iter().map(|(token, (denom, fx_originated))| TokenStatus {
+        token,
+        denom,
+        fx_originated,
+        on_fx_chain: true,
+        ..Default::default()
+    }));
+    tokens
+}
+
+fn token_status_table(tokens: &[TokenStatus]) -> Table {
+    let mut table = Table::new(&["token", "name", "symbol", "decimals", "active", "last_batch_nonce", "denom", "fx_originated", "side"]);
+    for token in tokens.iter() {
+        // a token missing from the contract has no contract state
+        let contract = match &token.contract {
+            Some(contract) => vec![
+                contract.name.clone(),
+                contract.symbol.clone(),
+                contract.decimals.to_string(),
+                contract.active.to_string(),
+                contract.last_batch_nonce.to_string(),
+            ],
+            None => vec![String::new(); 5],
+        };
+        let mut row = vec![token.token.to_hex_string()];
+        row.extend(contract);
+        row.extend(vec![token.denom.clone(), token.fx_originated.to_string(), token.side().to_string()]);
+        table.push(row);
+    }
+    table
+}
+
+async fn delegate_keys_table(grpc_channel: &tonic::transport::Channel, address: &str) -> Result<Table> {
+    let mut table = Table::new(&["validator", "orchestrator", "eth_address"]);
+    if let Ok(eth_address) = EthAddress::from_str(address) {
+        if let Some(keys) = get_delegate_key_by_eth(grpc_channel, eth_address.to_hex_string()).await? {
+            table.push(vec![keys.validator_address, keys.orchestrator_address, eth_address.to_hex_string()]);
+        }
+    } else if address.starts_with("fxvaloper") {
+        if let Some(keys) = get_delegate_key_by_validator(grpc_channel, address.to_string()).await? {
+            table.push(vec![address.to_string(), keys.orchestrator_address, keys.eth_address]);
+        }
+    } else if let Some(keys) = get_delegate_key_by_orchestrator(grpc_channel, FxAddress::from_str(address)?.to_string()).await? {
+        table.push(vec![keys.validator_address, address.to_string(), keys.eth_address]);
+    }
+    Ok(table)
+}
+
+fn valsets_table(valsets: Vec<Valset>) -> Table {
+    let mut table = Table::new(&["nonce", "height", "eth_address", "power"]);
+    for valset in valsets {
+        for member in valset.members {
+            table.push(vec![valset.nonce.to_string(), valset.height.to_string(), member.eth_address, member.power.to_string()]);
+        }
+    }
+    table
+}
+
+fn batch_row(batch: &OutgoingTxBatch) -> Vec<String> {
+    vec![
+        batch.batch_nonce.to_string(),
+        batch.token_contract.clone(),
+        batch.transactions.len().to_string(),
+        batch.batch_timeout.to_string(),
+        batch.block.to_string(),
+        batch.fee_receive.clone(),
+    ]
+}
+
+fn transfers_table<'a>(transfers: impl Iterator<Item = &'a OutgoingTransferTx>, in_batch: bool) -> Table {
+    let mut table = Table::new(&["id", "sender", "dest_address", "token_contract", "amount", "fee", "in_batch"]);
+    for transfer in transfers {
+        table.push(transfer_row(transfer, in_batch));
+    }
+    table
+}
+
+fn transfer_row(transfer: &OutgoingTransferTx, in_batch: bool) -> Vec<String> {
+    let token = transfer.erc20_token.clone().unwrap_or_default();
+    let fee = transfer.erc20_fee.clone().unwrap_or_default();
+    vec![
+        transfer.id.to_string(),
+        transfer.sender.clone(),
+        transfer.dest_address.clone(),
+        token.contract,
+        token.amount,
+        fee.amount,
+        in_batch.to_string(),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use web3::ethabi::{encode, Token};
+    use web3::transports::Http;
+    use web3::Web3;
+
+    use ethereum::abi::fx_bridge::{BridgeToken, CheckAssetStatusCall, GetBridgeTokenListCall, StateFxOriginatedTokenCall};
+    use ethereum::abi::ContractCall;
+    use testkit::eth_node::{EthChainState, MockEthNode};
+    use testkit::fx_chain::{Erc20Denom, FxChainState, MockFxChain};
+
+    use super::*;
+
+    #[test]
+    fn test_token_statuses() {
+        let (usdt, fx, pundix, dai) = (EthAddress::repeat_byte(1), EthAddress::repeat_byte(2), EthAddress::repeat_byte(3), EthAddress::repeat_byte(4));
+        let contract_token = |addr, symbol: &str| BridgeTokenStatus {
+            addr,
+            symbol: symbol.to_string(),
+            active: true,
+            ..Default::default()
+        };
+        let denom = |denom: &str, fx_originated| QueryErc20ToDenomResponse {
+            denom: denom.to_string(),
+            fx_originated,
+        };
+        let mut fx_chain_tokens = HashMap::new();
+        fx_chain_tokens.insert(fx, ("FX".to_string(), true));
+        fx_chain_tokens.insert(pundix, ("eth0x0303".to_string(), false));
+
+        // USDT is registered without supply yet, DAI is not registered
+        let tokens = token_statuses(
+            vec![contract_token(usdt, "USDT"), contract_token(fx, "FX"), contract_token(dai, "DAI")],
+            vec![denom("eth0x0101", false), denom("FX", true), denom("", false)],
+            fx_chain_tokens,
+        );
+        let sides: Vec<_> = tokens.iter().map(|token| (token.token, token.denom.as_str(), token.side())).collect();
+        assert_eq!(
+            sides,
+            vec![(usdt, "eth0x0101", "both"), (fx, "FX", "both"), (dai, "", "ethereum only"), (pundix, "eth0x0303", "fx only")]
+        );
+        assert!(tokens[1].fx_originated && tokens[1].on_fx_chain);
+
+        let table: serde_json::Value = serde_json::from_str(token_status_table(tokens.as_slice()).render(OutputFormat::Json).as_str()).unwrap();
+        assert_eq!(table[0]["active"], "true");
+        assert_eq!(table[3]["active"], "");
+        assert_eq!(table[3]["side"], "fx only");
+    }
+
+    #[test]
+    fn test_transfers_table() {
+        let transfer = |id| OutgoingTransferTx { id, ..Default::default() };
+        let mut table = transfers_table([transfer(1)].iter(), false);
+        table.push(transfer_row(&transfer(2), true));
+        let table: serde_json::Value = serde_json::from_str(table.render(OutputFormat::Json).as_str()).unwrap();
+        assert_eq!(table[0]["in_batch"], "false");
+        assert_eq!(table[1]["id"], "2");
+        assert_eq!(table[1]["in_batch"], "true");
+    }
+
+    #[tokio::test]
+    async fn test_token_status() {
+        let bridge = EthAddress::repeat_byte(0xb0);
+        let (usdt, fx, eth_only, fx_only) = (EthAddress::repeat_byte(1), EthAddress::repeat_byte(2), EthAddress::repeat_byte(3), EthAddress::repeat_byte(4));
+        let bridge_token = |addr, symbol: &str| BridgeToken {
+            addr,
+            name: symbol.to_string(),
+            symbol: symbol.to_string(),
+            decimals: 18,
+        };
+        let bridge_tokens = vec![bridge_token(usdt, "USDT"), bridge_token(fx, "FX"), bridge_token(eth_only, "NEW")];
+        let mut eth_state = EthChainState::default();
+        eth_state.mock_call(
+            bridge,
+            &GetBridgeTokenListCall::SELECTOR,
+            Ok(encode(&[Token::Array(bridge_tokens.into_iter().map(|token| token.into_token()).collect())])),
+        );
+        eth_state.mock_call(bridge, &StateFxOriginatedTokenCall::SELECTOR, Ok(encode(&[Token::Address(fx)])));
+        for token in [usdt, fx, eth_only] {
+            eth_state.mock_call(bridge, CheckAssetStatusCall { token_addr: token }.encode().as_slice(), Ok(encode(&[Token::Bool(true)])));
+            eth_state.mock_call(bridge, LastBatchNonceCall { erc20_address: token }.encode().as_slice(), Ok(encode(&[Token::Uint(0.into())])));
+        }
+        let eth_node = MockEthNode::start(eth_state).await.unwrap();
+
+        let gravity_denom = |token: EthAddress| format!("{}{}", fxchain::GRAVITY_DENOM_PREFIX, hex::encode(token.as_bytes()));
+        let mut fx_state = FxChainState::default();
+        for (token, denom, fx_originated) in [(usdt, gravity_denom(usdt), false), (fx, "FX".to_string(), true), (fx_only, gravity_denom(fx_only), false)] {
+            fx_state.erc20_denoms.push(Erc20Denom {
+                erc20: token.to_hex_string(),
+                denom: denom.clone(),
+                fx_originated,
+            });
+            fx_state.mint("fx1holder", denom.as_str(), 100);
+        }
+        // not a gravity denom, never looked up
+        fx_state.mint("fx1holder", "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2", 100);
+        let fx_chain = MockFxChain::start(fx_state).await.unwrap();
+
+        let grpc_channel = new_grpc_channel(fx_chain.url().as_str()).await.unwrap();
+        let fx_bridge = FxBridge::new(None, None, Web3::new(Http::new(eth_node.url().as_str()).unwrap()).eth(), bridge);
+        let tokens = token_status(&grpc_channel, &fx_bridge, None).await.unwrap();
+        let sides: Vec<_> = tokens.iter().map(|token| (token.token, token.denom.clone(), token.side())).collect();
+        assert_eq!(
+            sides,
+            vec![
+                (usdt, gravity_denom(usdt), "both"),
+                (fx, "FX".to_string(), "both"),
+                (eth_only, String::new(), "ethereum only"),
+                (fx_only, gravity_denom(fx_only), "fx only"),
+            ]
+        );
+        assert!(tokens[1].fx_originated && tokens[1].on_fx_chain);
+    }
+}
@@ -0,0 +1,312 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
+
+use clap::Parser;
+use eyre::Result;
+use tokio::time::sleep;
+use tonic::transport::Channel;
+use web3::types::{Address as EthAddress, U256};
+
+use ethereum::address::Checksum;
+use ethereum::erc20::ERC20;
+use ethereum::fx_bridge::FxBridge;
+use ethereum::units::format_units;
+use fxchain::address::Address as FxAddress;
+use fxchain::grpc_client::*;
+use fxchain::x::gravity::OutgoingTransferTx;
+
+use crate::output::{OutputFormat, Table};
+use crate::query::new_fx_bridge;
+
+/// Module account holding the FX-originated tokens sent to Ethereum
+const GRAVITY_MODULE_NAME: &str = "gravity";
+
+#[derive(Parser, Debug)]
+pub struct Reconcile {
+    /// f(x)Core gRPC address
+    #[clap(long, default_value = "http://127.0.0.1:9090")]
+    fx_chain_grpc: String,
+    /// Ethereum RPC address
+    #[clap(long, default_value = "http://127.0.0.1:8545")]
+    ethereum_rpc: String,
+    /// f(x)Core Bridge contract address, defaults to the one in the gravity params
+    #[clap(long)]
+    bridge_addr: Option<String>,
+    /// Also count the unbatched transfers to Ethereum of this sender as pending
+    #[clap(long)]
+    sender: Vec<String>,
+    /// Also exit non-zero for OVERBACKED tokens, which unbatched transfers of other senders show up as
+    #[clap(long)]
+    strict: bool,
+    /// Reconcile every this many seconds and export the results as metrics instead of exiting
+    #[clap(long)]
+    interval: Option<u64>,
+    /// Prometheus port used with --interval
+    #[clap(long, default_value = "9899")]
+    metrics_port: u16,
+    /// Output format
+    #[clap(long, arg_enum, default_value = "table")]
+    output: OutputFormat,
+}
+
+/// Both sides of one bridged token, in its smallest unit
+#[derive(Debug, Default, Clone, PartialEq)]
+struct TokenReconcile {
+    token: EthAddress,
+    symbol: String,
+    decimals: u8,
+    denom: String,
+    fx_originated: bool,
+    /// locked in the FxBridge contract, or escrowed in the gravity module for FX-originated tokens
+    backing: U256,
+    /// f(x)Core denom supply, or the ERC20 total supply for FX-originated tokens
+    issued: U256,
+    /// sent to Ethereum but not executed there yet
+    pending: U256,
+}
+
+impl TokenReconcile {
+    fn expected(&self) -> U256 {
+        self.issued.saturating_add(self.pending)
+    }
+
+    fn status(&self) -> &'static str {
+        if self.denom.is_empty() {
+            return "UNREGISTERED";
+        }
+        match self.backing.cmp(&self.expected()) {
+            Ordering::Equal => "OK",
+            Ordering::Greater => "OVERBACKED",
+            Ordering::Less => "UNDERBACKED",
+        }
+    }
+
+    /// `backing - issued - pending`, in token units
+    fn discrepancy(&self) -> String {
+        let expected = self.expected();
+        if self.backing >= expected {
+            format_units(self.backing - expected, self.decimals)
+        } else {
+            format!("-{}", format_units(expected - self.backing, self.decimals))
+        }
+    }
+
+    fn set_metrics(&self) {
+        let token = self.token.to_hex_string();
+        let labels = [token.as_str(), self.symbol.as_str()];
+        let backing = format_units(self.backing, self.decimals).parse().unwrap_or(f64::NAN);
+        let discrepancy = self.discrepancy().parse().unwrap_or(f64::NAN);
+        prometheus::metrics::BRIDGE_RECONCILE_BACKING.with_label_values(&labels).set(backing);
+        prometheus::metrics::BRIDGE_RECONCILE_DISCREPANCY.with_label_values(&labels).set(discrepancy);
+    }
+}
+
+/// Number of tokens that fail the reconcile, an OVERBACKED token only fails when `strict`
+fn failures(tokens: &[TokenReconcile], strict: bool) -> usize {
+    tokens
+        .iter()
+        .filter(|token| matches!(token.status(), "UNDERBACKED" | "UNREGISTERED") || (strict && token.status() == "OVERBACKED"))
+        .count()
+}
+
+/// The `--sender` addresses without duplicates, their unbatched transfers are counted once
+fn parse_senders(senders: &[String]) -> Result<Vec<FxAddress>> {
+    let mut addresses = Vec::new();
+    for sender in senders.iter() {
+        let address = FxAddress::from_str(sender.as_str())?;
+        if !addresses.contains(&address) {
+            addresses.push(address);
+        }
+    }
+    Ok(addresses)
+}
+
+fn reconcile_table(tokens: &[TokenReconcile]) -> Table {
+    let mut table = Table::new(&["token", "symbol", "denom", "fx_originated", "backing", "issued", "pending", "discrepancy", "status"]);
+    for token in tokens.iter() {
+        table.push(vec![
+            token.token.to_hex_string(),
+            token.symbol.clone(),
+            token.denom.clone(),
+            token.fx_originated.to_string(),
+            format_units(token.backing, token.decimals),
+            format_units(token.issued, token.decimals),
+            format_units(token.pending, token.decimals),
+            token.discrepancy(),
+            token.status().to_string(),
+        ]);
+    }
+    table
+}
+
+/// Amount plus bridge fee of the transfers to Ethereum, by token contract
+fn pending_amounts(transfers: &[OutgoingTransferTx]) -> Result<HashMap<EthAddress, U256>> {
+    let mut pending: HashMap<EthAddress, U256> = HashMap::new();
+    for transfer in transfers.iter() {
+        for erc20 in transfer.erc20_token.iter().chain(transfer.erc20_fee.iter()) {
+            let token = EthAddress::from_str(erc20.contract.as_str())?;
+            let amount = U256::from_dec_str(erc20.amount.as_str()).map_err(|err| eyre::Error::msg(format!("invalid amount of transfer {}: {:?}", transfer.id, err)))?;
+            *pending.entry(token).or_default() += amount;
+        }
+    }
+    Ok(pending)
+}
+
+fn parse_coin_amount(amount: &str) -> Result<U256> {
+    U256::from_dec_str(amount).map_err(|err| eyre::Error::msg(format!("invalid amount {}: {:?}", amount, err)))
+}
+
+async fn reconcile(grpc_channel: &Channel, fx_bridge: &FxBridge, ethereum_rpc: &str, senders: &[FxAddress]) -> Result<Vec<TokenReconcile>> {
+    let transport = web3::transports::Http::new(ethereum_rpc)?;
+    let web3 = web3::Web3::new(transport);
+
+    let mut transfers: Vec<OutgoingTransferTx> = get_outgoing_tx_batches(grpc_channel).await?.into_iter().flat_map(|batch| batch.transactions).collect();
+    for sender in senders.iter() {
+        transfers.extend(get_pending_send_to_eth(grpc_channel, *sender).await?.unbatched_transfers);
+    }
+    let pending = pending_amounts(transfers.as_slice())?;
+    let gravity_module = FxAddress::from_module_name(GRAVITY_MODULE_NAME);
+
+    let mut tokens = Vec::new();
+    for bridge_token in fx_bridge.get_bridge_token_list().await? {
+        let erc20 = ERC20::new(None, None, web3.eth(), bridge_token.addr);
+        // a token without a denom is reported as UNREGISTERED, the tokens locked for it back nothing
+        let denom = find_erc20_to_denom(grpc_channel, bridge_token.addr.to_hex_string()).await?.unwrap_or_default();
+        let (backing, issued) = if denom.denom.is_empty() {
+            (erc20.balance_of(fx_bridge.address()).await?, U256::zero())
+        } else if denom.fx_originated {
+            let escrow = get_balance(grpc_channel, gravity_module, denom.denom.clone()).await?;
+            (parse_coin_amount(escrow.amount.as_str())?, erc20.total_supply().await?)
+        } else {
+            let supply = get_supply_of(grpc_channel, denom.denom.clone()).await?;
+            (erc20.balance_of(fx_bridge.address()).await?, parse_coin_amount(supply.amount.as_str())?)
+        };
+        tokens.push(TokenReconcile {
+            token: bridge_token.addr,
+            symbol: bridge_token.symbol,
+            decimals: bridge_token.decimals,
+            denom: denom.denom,
+            fx_originated: denom.fx_originated,
+            backing,
+            issued,
+            pending: pending.get(&bridge_token.addr).cloned().unwrap_or_default(),
+        });
+    }
+    Ok(tokens)
+}
+
+pub async fn run(cmd: Reconcile) -> Result<()> {
+    let senders = parse_senders(cmd.sender.as_slice())?;
+    let grpc_channel = new_grpc_channel(cmd.fx_chain_grpc.as_str()).await?;
+    let fx_bridge = new_fx_bridge(&grpc_channel, cmd.ethereum_rpc.as_str(), cmd.bridge_addr).await?;
+
+    let interval = match cmd.interval {
+        Some(interval) => Duration::from_secs(interval),
+        None => {
+            let tokens = reconcile(&grpc_channel, &fx_bridge, cmd.ethereum_rpc.as_str(), senders.as_slice()).await?;
+            reconcile_table(tokens.as_slice()).print(cmd.output);
+            return match failures(tokens.as_slice(), cmd.strict) {
+                0 => Ok(()),
+                failures => Err(eyre::Error::msg(format!("{} of {} tokens do not reconcile", failures, tokens.len()))),
+            };
+        }
+    };
+
+    let reconcile_loop = async {
+        loop {
+            match reconcile(&grpc_channel, &fx_bridge, cmd.ethereum_rpc.as_str(), senders.as_slice()).await {
+                Ok(tokens) => {
+                    for token in tokens.iter() {
+                        token.set_metrics();
+                        if token.status() != "OK" {
+                            warn!("{} {} is {}, discrepancy {}", token.symbol, token.token.to_hex_string(), token.status(), token.discrepancy());
+                        }
+                    }
+                    info!("Reconciled {} bridge tokens", tokens.len());
+                }
+                Err(report) => error!("Failed to reconcile the bridge tokens: {:?}", report),
+            }
+            sleep(interval).await;
+        }
+    };
+    futures::future::join(reconcile_loop, prometheus::start(cmd.metrics_port)).await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use fxchain::x::gravity::Erc20Token;
+
+    use super::*;
+
+    const USDT: &str = "0xdAC17F958D2ee523a2206206994597C13D831ec7";
+
+    fn transfer(id: u64, amount: &str, fee: &str) -> OutgoingTransferTx {
+        let erc20 = |amount: &str| Erc20Token {
+            contract: USDT.to_string(),
+            amount: amount.to_string(),
+        };
+        OutgoingTransferTx {
+            id,
+            erc20_token: Some(erc20(amount)),
+            erc20_fee: Some(erc20(fee)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_pending_amounts() {
+        let pending = pending_amounts(&[transfer(1, "1000", "10"), transfer(2, "500", "5")]).unwrap();
+        assert_eq!(pending.get(&EthAddress::from_str(USDT).unwrap()), Some(&U256::from(1515)));
+        assert!(pending_amounts(&[transfer(3, "1.5", "0")]).is_err());
+    }
+
+    #[test]
+    fn test_token_reconcile() {
+        let mut token = TokenReconcile {
+            token: EthAddress::from_str(USDT).unwrap(),
+            symbol: "USDT".to_string(),
+            denom: "eth0xdAC17F958D2ee523a2206206994597C13D831ec7".to_string(),
+            decimals: 6,
+            backing: U256::from(3_000_000),
+            issued: U256::from(2_500_000),
+            pending: U256::from(500_000),
+            ..Default::default()
+        };
+        assert_eq!((token.status(), token.discrepancy().as_str()), ("OK", "0"));
+        token.pending = U256::from(250_000);
+        assert_eq!((token.status(), token.discrepancy().as_str()), ("OVERBACKED", "0.25"));
+        token.issued = U256::from(3_000_000);
+        assert_eq!((token.status(), token.discrepancy().as_str()), ("UNDERBACKED", "-0.25"));
+    }
+
+    #[test]
+    fn test_failures() {
+        let token = |backing: u64| TokenReconcile {
+            denom: "eth0xdAC17F958D2ee523a2206206994597C13D831ec7".to_string(),
+            backing: U256::from(backing),
+            issued: U256::from(100),
+            ..Default::default()
+        };
+        let mut tokens = vec![token(100), token(150), token(50)];
+        assert_eq!(failures(&tokens[..2], false), 0);
+        assert_eq!(failures(&tokens, false), 1);
+        assert_eq!(failures(&tokens, true), 2);
+
+        // a token in the contract without a denom on f(x)Core
+        tokens.push(TokenReconcile { denom: String::new(), ..token(100) });
+        assert_eq!(tokens[3].status(), "UNREGISTERED");
+        assert_eq!(failures(&tokens, false), 2);
+    }
+
+    #[test]
+    fn test_parse_senders() {
+        let sender = "fx1zgpzdf2uqla7hkx85wnn4p2r3duwqzd8xst6v2".to_string();
+        let other = "fx16wvwsmpp4y4ttgzknyr6kqla877jud6u04lqey".to_string();
+        let senders = parse_senders(&[sender.clone(), other.clone(), sender.clone()]).unwrap();
+        assert_eq!(senders.iter().map(|sender| sender.to_string()).collect::<Vec<_>>(), vec![sender, other]);
+        assert!(parse_senders(&["fx1invalid".to_string()]).is_err());
+    }
+}
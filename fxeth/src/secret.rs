@@ -0,0 +1,42 @@
+use std::convert::Infallible;
+use std::fmt;
+use std::str::FromStr;
+
+use serde_derive::Deserialize;
+
+/// A mnemonic or private key given on the command line or in the config file, redacted from `Debug` so logging the args never prints it
+#[derive(Clone, PartialEq, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn expose(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl FromStr for Secret {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Secret(s.to_string()))
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_debug() {
+        let secret = Secret::from_str("test test test junk").unwrap();
+        assert_eq!(format!("{:?}", Some(secret.clone())), "Some(<redacted>)");
+        assert_eq!(secret.expose(), "test test test junk");
+    }
+}
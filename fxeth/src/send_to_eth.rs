@@ -0,0 +1,234 @@
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use clap::Parser;
+use cosmos_sdk_proto::cosmos::base::v1beta1::Coin;
+use eyre::Result;
+use tokio::time::sleep;
+use tonic::transport::Channel;
+use web3::types::{Address as EthAddress, U256};
+
+use ethereum::address::Checksum;
+use fxchain::address::Address as FxAddress;
+use fxchain::builder::Builder;
+use fxchain::grpc_client::{get_denom_to_erc20, get_outgoing_tx_batches, get_pending_send_to_eth, new_grpc_channel, send_tx};
+use fxchain::private_key::PrivateKey as FxPrivateKey;
+use fxchain::proto_ext::MessageExt;
+use fxchain::x::gravity::{MsgCancelSendToEth, MsgSendToEth, OutgoingTransferTx, QueryPendingSendToEthResponse};
+
+use crate::secret::Secret;
+
+/// Interval between two polls of the pending transfers
+const PENDING_POLL_INTERVAL: Duration = Duration::from_secs(6);
+
+#[derive(Parser, Debug)]
+pub struct SendToEth {
+    /// f(x)Core sender mnemonic
+    #[clap(long)]
+    fx_chain_key: Secret,
+    /// f(x)Core gRPC address
+    #[clap(long, default_value = "http://127.0.0.1:9090")]
+    fx_chain_grpc: String,
+    /// Ethereum receiver address
+    #[clap(long)]
+    receiver: String,
+    /// Denom to send, must be bridged to an ERC20
+    #[clap(long)]
+    denom: String,
+    /// Amount in the smallest unit of the denom
+    #[clap(long)]
+    amount: String,
+    /// Fee paid to the relayer of the batch, in the smallest unit of the denom
+    #[clap(long)]
+    bridge_fee: String,
+    /// f(x)Core send transactions fee
+    #[clap(long, default_value = "FX")]
+    fees: String,
+    /// Seconds to wait for the transfer to be batched and executed on Ethereum, 0 to skip
+    #[clap(long, default_value = "0")]
+    wait_timeout: u64,
+}
+
+#[derive(Parser, Debug)]
+pub struct CancelSendToEth {
+    /// f(x)Core sender mnemonic
+    #[clap(long)]
+    fx_chain_key: Secret,
+    /// f(x)Core gRPC address
+    #[clap(long, default_value = "http://127.0.0.1:9090")]
+    fx_chain_grpc: String,
+    /// Id of the unbatched transfer
+    #[clap(long)]
+    transaction_id: u64,
+    /// f(x)Core send transactions fee
+    #[clap(long, default_value = "FX")]
+    fees: String,
+}
+
+pub async fn send_to_eth(cmd: SendToEth) -> Result<()> {
+    let fx_private_key = FxPrivateKey::from_phrase(cmd.fx_chain_key.expose(), "")?;
+    let sender = fx_private_key.public_key().to_address();
+    let receiver = EthAddress::from_str(cmd.receiver.as_str())?;
+    let amount = U256::from_dec_str(cmd.amount.as_str()).map_err(|err| eyre::Error::msg(format!("invalid amount: {:?}", err)))?;
+    let bridge_fee = U256::from_dec_str(cmd.bridge_fee.as_str()).map_err(|err| eyre::Error::msg(format!("invalid bridge fee: {:?}", err)))?;
+
+    let grpc_channel = new_grpc_channel(cmd.fx_chain_grpc.as_str()).await?;
+    let erc20 = get_denom_to_erc20(&grpc_channel, cmd.denom.clone()).await?.erc20;
+    if erc20.is_empty() {
+        return Err(eyre::Error::msg(format!("denom {} is not bridged to Ethereum", cmd.denom)));
+    }
+    info!("Send {} {} (erc20 {}) from {} to {}, bridge fee {}", amount, cmd.denom, erc20, sender, receiver.to_hex_string(), bridge_fee);
+
+    let previous = pending_ids(&get_pending_send_to_eth(&grpc_channel, sender).await?);
+
+    let fx_builder = Builder::from_net(&grpc_channel, fx_private_key, cmd.fees.as_str()).await?;
+    let msg = MsgSendToEth {
+        sender: sender.to_string(),
+        eth_dest: receiver.to_hex_string(),
+        amount: Some(Coin {
+            denom: cmd.denom.clone(),
+            amount: amount.to_string(),
+        }),
+        bridge_fee: Some(Coin {
+            denom: cmd.denom.clone(),
+            amount: bridge_fee.to_string(),
+        }),
+    };
+    let tx_resp = send_tx(&fx_builder, &grpc_channel, vec![msg.to_any("/fx.gravity.v1.MsgSendToEth")]).await?;
+    println!("tx hash: {}", tx_resp.txhash);
+    if tx_resp.code != 0 {
+        return Err(eyre::Error::msg(format!("send to eth tx failed: {}", tx_resp.raw_log)));
+    }
+
+    let pending = get_pending_send_to_eth(&grpc_channel, sender).await?;
+    let transfer = pending
+        .unbatched_transfers
+        .iter()
+        .chain(pending.transfers_in_batches.iter())
+        .filter(|transfer| !previous.contains(&transfer.id))
+        .max_by_key(|transfer| transfer.id)
+        .ok_or_else(|| eyre::Error::msg("the transfer is not in the pending pool"))?;
+    print_transfer(transfer);
+
+    if cmd.wait_timeout == 0 {
+        return Ok(());
+    }
+    wait_for_execution(&grpc_channel, sender, transfer.id, Duration::from_secs(cmd.wait_timeout)).await
+}
+
+pub async fn cancel_send_to_eth(cmd: CancelSendToEth) -> Result<()> {
+    let fx_private_key = FxPrivateKey::from_phrase(cmd.fx_chain_key.expose(), "")?;
+    let sender = fx_private_key.public_key().to_address();
+    let grpc_channel = new_grpc_channel(cmd.fx_chain_grpc.as_str()).await?;
+
+    let pending = get_pending_send_to_eth(&grpc_channel, sender).await?;
+    match TransferStatus::of(&pending, cmd.transaction_id) {
+        TransferStatus::Unbatched => {}
+        TransferStatus::Batched => return Err(eyre::Error::msg(format!("transfer {} is already in a batch and can't be cancelled", cmd.transaction_id))),
+        TransferStatus::Gone => return Err(eyre::Error::msg(format!("no pending transfer {} from {}", cmd.transaction_id, sender))),
+    }
+
+    let fx_builder = Builder::from_net(&grpc_channel, fx_private_key, cmd.fees.as_str()).await?;
+    let msg = MsgCancelSendToEth {
+        transaction_id: cmd.transaction_id,
+        sender: sender.to_string(),
+    };
+    let tx_resp = send_tx(&fx_builder, &grpc_channel, vec![msg.to_any("/fx.gravity.v1.MsgCancelSendToEth")]).await?;
+    println!("tx hash: {}", tx_resp.txhash);
+    if tx_resp.code != 0 {
+        return Err(eyre::Error::msg(format!("cancel send to eth tx failed: {}", tx_resp.raw_log)));
+    }
+    println!("transfer {} cancelled", cmd.transaction_id);
+    Ok(())
+}
+
+/// Wait until the transfer is batched, then until the batch executed on Ethereum is observed
+/// and the transfer leaves the pending pool
+async fn wait_for_execution(grpc_channel: &Channel, sender: FxAddress, id: u64, timeout: Duration) -> Result<()> {
+    let start = Instant::now();
+    let mut last_status = TransferStatus::Unbatched;
+    loop {
+        let pending = get_pending_send_to_eth(grpc_channel, sender).await?;
+        let status = TransferStatus::of(&pending, id);
+        if status != last_status {
+            match status {
+                TransferStatus::Batched => {
+                    let batch = get_outgoing_tx_batches(grpc_channel)
+                        .await?
+                        .into_iter()
+                        .find(|batch| batch.transactions.iter().any(|transfer| transfer.id == id));
+                    match batch {
+                        Some(batch) => println!("transfer {} in batch {} of {}, timeout {}", id, batch.batch_nonce, batch.token_contract, batch.batch_timeout),
+                        None => println!("transfer {} in a batch", id),
+                    }
+                }
+                TransferStatus::Unbatched => println!("transfer {} is back in the pool, the batch timed out", id),
+                TransferStatus::Gone => {
+                    println!("transfer {} executed on Ethereum", id);
+                    return Ok(());
+                }
+            }
+            last_status = status;
+        }
+        if start.elapsed() > timeout {
+            return Err(eyre::Error::msg(format!("transfer {} not executed after {}s", id, timeout.as_secs())));
+        }
+        sleep(PENDING_POLL_INTERVAL).await;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TransferStatus {
+    Unbatched,
+    Batched,
+    /// executed or cancelled
+    Gone,
+}
+
+impl TransferStatus {
+    fn of(pending: &QueryPendingSendToEthResponse, id: u64) -> TransferStatus {
+        if pending.unbatched_transfers.iter().any(|transfer| transfer.id == id) {
+            TransferStatus::Unbatched
+        } else if pending.transfers_in_batches.iter().any(|transfer| transfer.id == id) {
+            TransferStatus::Batched
+        } else {
+            TransferStatus::Gone
+        }
+    }
+}
+
+fn pending_ids(pending: &QueryPendingSendToEthResponse) -> Vec<u64> {
+    pending.unbatched_transfers.iter().chain(pending.transfers_in_batches.iter()).map(|transfer| transfer.id).collect()
+}
+
+fn print_transfer(transfer: &OutgoingTransferTx) {
+    let token = |token: &Option<fxchain::x::gravity::Erc20Token>| token.as_ref().map(|token| format!("{} {}", token.amount, token.contract)).unwrap_or_default();
+    println!("id: {}", transfer.id);
+    println!("receiver: {}", transfer.dest_address);
+    println!("amount: {}", token(&transfer.erc20_token));
+    println!("bridge fee: {}", token(&transfer.erc20_fee));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer(id: u64) -> OutgoingTransferTx {
+        OutgoingTransferTx {
+            id,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_transfer_status() {
+        let pending = QueryPendingSendToEthResponse {
+            transfers_in_batches: vec![transfer(1)],
+            unbatched_transfers: vec![transfer(2), transfer(3)],
+        };
+        assert_eq!(TransferStatus::of(&pending, 1), TransferStatus::Batched);
+        assert_eq!(TransferStatus::of(&pending, 3), TransferStatus::Unbatched);
+        assert_eq!(TransferStatus::of(&pending, 4), TransferStatus::Gone);
+        assert_eq!(pending_ids(&pending), vec![2, 3, 1]);
+    }
+}
@@ -0,0 +1,219 @@
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use clap::Parser;
+use eyre::Result;
+use tokio::time::sleep;
+use tonic::transport::Channel;
+use web3::types::{Address as EthAddress, U256};
+
+use ethereum::address::Checksum;
+use ethereum::erc20::ERC20;
+use ethereum::fee::FeePolicy;
+use ethereum::fx_bridge::{FxBridge, SendToFxEvent};
+use ethereum::private_key::{Key, PrivateKey as EthPrivateKey};
+use ethereum::units::{format_units, parse_units};
+use fxchain::address::Address as FxAddress;
+use fxchain::grpc_client::{get_current_valset, get_delegate_key_by_eth, get_erc20_to_denom, get_last_event_nonce, new_grpc_channel};
+
+use crate::secret::Secret;
+
+/// Interval between two polls of the orchestrators' claimed event nonces
+const ATTESTATION_POLL_INTERVAL: Duration = Duration::from_secs(6);
+
+#[derive(Parser, Debug)]
+pub struct SendToFx {
+    /// Ethereum private key of the sender
+    #[clap(long)]
+    ethereum_key: Secret,
+    /// Ethereum RPC address
+    #[clap(long, default_value = "http://127.0.0.1:8545")]
+    ethereum_rpc: String,
+    /// f(x)Core Bridge contract address
+    #[clap(long)]
+    bridge_addr: String,
+    /// ERC20 token contract address
+    #[clap(long)]
+    token: String,
+    /// Amount in token units, e.g. 1.5
+    #[clap(long)]
+    amount: String,
+    /// f(x)Core bech32 receiver address
+    #[clap(long)]
+    receiver: String,
+    /// IBC target of the deposit, e.g. px/transfer/channel-0
+    #[clap(long, default_value = "")]
+    target_ibc: String,
+    /// Ethereum confirmations to wait for the sendToFx transaction
+    #[clap(long, default_value = "1")]
+    confirmations: usize,
+    /// f(x)Core gRPC address, used to watch the deposit attestation
+    #[clap(long, default_value = "http://127.0.0.1:9090")]
+    fx_chain_grpc: String,
+    /// Seconds to wait for the deposit to be observed on f(x)Core, 0 to skip
+    #[clap(long, default_value = "600")]
+    wait_timeout: u64,
+}
+
+pub async fn run(cmd: SendToFx) -> Result<()> {
+    let eth_private_key = EthPrivateKey::from_str(cmd.ethereum_key.expose())?;
+    let sender = eth_private_key.address();
+    let bridge_addr = EthAddress::from_str(cmd.bridge_addr.as_str())?;
+    let token = EthAddress::from_str(cmd.token.as_str())?;
+    let receiver = FxAddress::from_str(cmd.receiver.as_str())?;
+    let target_ibc = target_ibc_bytes(cmd.target_ibc.as_str())?;
+
+    let transport = web3::transports::Http::new(cmd.ethereum_rpc.as_str())?;
+    let web3 = web3::Web3::new(transport);
+
+    let erc20 = ERC20::new(Some(eth_private_key.clone()), None, web3.eth(), token);
+    let decimals = erc20.decimals().await?;
+    let symbol = erc20.symbol().await?;
+    let amount = parse_units(cmd.amount.as_str(), decimals)?;
+    if amount.is_zero() {
+        return Err(eyre::Error::msg("amount must be greater than zero"));
+    }
+
+    let balance = erc20.balance_of(sender).await?;
+    if balance < amount {
+        return Err(eyre::Error::msg(format!(
+            "insufficient {} balance {}, need {}",
+            symbol,
+            format_units(balance, decimals),
+            format_units(amount, decimals)
+        )));
+    }
+
+    let allowance = erc20.allowance(sender, bridge_addr).await?;
+    if allowance < amount {
+        info!("Allowance {} {} is below the amount, approving the bridge", format_units(allowance, decimals), symbol);
+        // tokens like USDT revert when changing a non-zero allowance, so reset it to zero first
+        if !allowance.is_zero() {
+            let receipt = erc20.approve(bridge_addr, U256::zero()).await?;
+            if receipt.status == Some(0.into()) {
+                return Err(eyre::Error::msg(format!("reset allowance tx {:?} failed", receipt.transaction_hash)));
+            }
+            info!("Reset allowance tx hash {:?}", receipt.transaction_hash);
+        }
+        let receipt = erc20.approve(bridge_addr, amount).await?;
+        if receipt.status == Some(0.into()) {
+            return Err(eyre::Error::msg(format!("approve tx {:?} failed", receipt.transaction_hash)));
+        }
+        info!("Approve tx hash {:?}", receipt.transaction_hash);
+    }
+
+    info!("Send {} {} from {} to {}", cmd.amount, symbol, sender.to_hex_string(), receiver);
+    let fx_bridge = FxBridge::new(Some(eth_private_key), None, web3.eth(), bridge_addr).with_fee_policy(FeePolicy::from_env()?);
+    let receipt = fx_bridge
+        .signed_call_with_confirmations("sendToFx", (token, receiver.to_bytes32(), target_ibc, amount), cmd.confirmations)
+        .await?;
+    let event = SendToFxEvent::from_receipt(&receipt, bridge_addr)?
+        .pop()
+        .ok_or_else(|| eyre::Error::msg(format!("no SendToFxEvent in tx {:?}", receipt.transaction_hash)))?;
+    println!("tx hash: {:?}", receipt.transaction_hash);
+    println!("event nonce: {}", event.event_nonce);
+    println!("block number: {}", event.block_number);
+
+    if cmd.wait_timeout == 0 {
+        return Ok(());
+    }
+    let grpc_channel = new_grpc_channel(cmd.fx_chain_grpc.as_str()).await?;
+    let denom = get_erc20_to_denom(&grpc_channel, token.to_hex_string()).await?.denom;
+    let event_nonce = event.event_nonce.as_u64();
+    let start = Instant::now();
+    loop {
+        let (claimed, total) = claimed_power(&grpc_channel, event_nonce).await?;
+        // an attestation is observed once more than 2/3 of the power claimed it
+        if claimed * 3 > total * 2 {
+            println!("deposit observed on f(x)Core, {} received {}", receiver, denom);
+            return Ok(());
+        }
+        if start.elapsed() > Duration::from_secs(cmd.wait_timeout) {
+            return Err(eyre::Error::msg(format!(
+                "deposit not observed after {}s, orchestrators with power {} of {} claimed event nonce {}",
+                cmd.wait_timeout, claimed, total, event_nonce
+            )));
+        }
+        debug!("Waiting for deposit attestation, power {} of {} claimed event nonce {}", claimed, total, event_nonce);
+        sleep(ATTESTATION_POLL_INTERVAL).await;
+    }
+}
+
+/// Power of the current valset whose orchestrators claimed `event_nonce`, and the total power
+async fn claimed_power(grpc_channel: &Channel, event_nonce: u64) -> Result<(u64, u64)> {
+    let valset = get_current_valset(grpc_channel).await?.ok_or_else(|| eyre::Error::msg("no current valset"))?;
+    let (mut claimed, mut total) = (0, 0);
+    for member in valset.members {
+        total += member.power;
+        let keys = match get_delegate_key_by_eth(grpc_channel, member.eth_address.clone()).await? {
+            Some(keys) => keys,
+            None => continue,
+        };
+        let orchestrator = FxAddress::from_str(keys.orchestrator_address.as_str())?;
+        if get_last_event_nonce(grpc_channel, orchestrator).await? >= event_nonce {
+            claimed += member.power;
+        }
+    }
+    Ok((claimed, total))
+}
+
+/// The target IBC string right-padded to bytes32, as read back by the oracle
+fn target_ibc_bytes(target_ibc: &str) -> Result<[u8; 32]> {
+    if target_ibc.len() > 32 {
+        return Err(eyre::Error::msg(format!("target ibc '{}' is longer than 32 bytes", target_ibc)));
+    }
+    let mut bytes = [0u8; 32];
+    bytes[..target_ibc.len()].copy_from_slice(target_ibc.as_bytes());
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use fxchain::private_key::PrivateKey as FxPrivateKey;
+    use fxchain::x::gravity::{BridgeValidator, Valset};
+    use testkit::fx_chain::{FxChainState, MockFxChain};
+
+    use super::*;
+
+    #[test]
+    fn test_target_ibc_bytes() {
+        let bytes = target_ibc_bytes("pay/transfer/channel-0").unwrap();
+        assert_eq!(hex::encode(bytes), "7061792f7472616e736665722f6368616e6e656c2d300000000000000000000000");
+        assert_eq!(target_ibc_bytes("").unwrap(), [0u8; 32]);
+        assert!(target_ibc_bytes(&"a".repeat(33)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_claimed_power() {
+        let eth_addresses = [
+            "0x0000000000000000000000000000000000000001",
+            "0x0000000000000000000000000000000000000002",
+            "0x0000000000000000000000000000000000000003",
+        ];
+        let mut state = FxChainState::default();
+        state.add_valset(Valset {
+            nonce: 1,
+            members: eth_addresses
+                .iter()
+                .zip([2000, 1000, 1000])
+                .map(|(eth_address, power)| BridgeValidator {
+                    power,
+                    eth_address: eth_address.to_string(),
+                })
+                .collect(),
+            height: 10,
+        });
+        // the third member has no orchestrator
+        for (i, (eth_address, event_nonce)) in eth_addresses[..2].iter().zip([3, 5]).enumerate() {
+            let orchestrator = FxPrivateKey::random().public_key().to_address();
+            state.add_orchestrator(format!("fxvaloper{}", i).as_str(), orchestrator, eth_address);
+            state.event_nonces.insert(orchestrator.to_string(), (event_nonce, 100));
+        }
+        let fx_chain = MockFxChain::start(state).await.unwrap();
+
+        let grpc_channel = new_grpc_channel(fx_chain.url().as_str()).await.unwrap();
+        assert_eq!(claimed_power(&grpc_channel, 3).await.unwrap(), (3000, 4000));
+        assert_eq!(claimed_power(&grpc_channel, 4).await.unwrap(), (1000, 4000));
+        assert_eq!(claimed_power(&grpc_channel, 6).await.unwrap(), (0, 4000));
+    }
+}
@@ -0,0 +1,198 @@
+use std::str::FromStr;
+
+use clap::Parser;
+use cosmos_sdk_proto::cosmos::staking::v1beta1::BondStatus;
+use eyre::Result;
+use web3::types::{Address as EthAddress, U256};
+
+use ethereum::address::Checksum;
+use ethereum::units::{format_units, parse_units};
+use fxchain::address::Address as FxAddress;
+use fxchain::grpc_client::*;
+
+use crate::output::{OutputFormat, Table};
+use crate::query::new_fx_bridge;
+
+/// Decimals of both FX and ETH
+const NATIVE_DECIMALS: u8 = 18;
+
+#[derive(Parser, Debug)]
+pub struct Status {
+    /// f(x)Core gRPC address
+    #[clap(long, default_value = "http://127.0.0.1:9090")]
+    fx_chain_grpc: String,
+    /// Ethereum RPC address
+    #[clap(long, default_value = "http://127.0.0.1:8545")]
+    ethereum_rpc: String,
+    /// f(x)Core Bridge contract address, defaults to the one in the gravity params
+    #[clap(long)]
+    bridge_addr: Option<String>,
+    /// f(x)Core orchestrator address
+    #[clap(long)]
+    orchestrator: String,
+    /// Ethereum address of the orchestrator eth key
+    #[clap(long)]
+    ethereum_address: String,
+    /// Denom the orchestrator pays f(x)Core fees in
+    #[clap(long, default_value = "FX")]
+    fee_denom: String,
+    /// Minimum orchestrator fee denom balance, in FX units
+    #[clap(long, default_value = "10")]
+    min_fx_balance: String,
+    /// Minimum eth key balance, in ETH units
+    #[clap(long, default_value = "0.1")]
+    min_eth_balance: String,
+    /// Output format
+    #[clap(long, arg_enum, default_value = "table")]
+    output: OutputFormat,
+}
+
+/// Pass/fail results of the status checks
+#[derive(Debug, Default)]
+struct Checklist {
+    checks: Vec<(String, bool, String)>,
+}
+
+impl Checklist {
+    fn check(&mut self, name: &str, result: Result<(bool, String)>) {
+        let (passed, detail) = result.unwrap_or_else(|report| (false, format!("{}", report.root_cause())));
+        self.checks.push((name.to_string(), passed, detail));
+    }
+
+    fn failures(&self) -> usize {
+        self.checks.iter().filter(|(_, passed, _)| !passed).count()
+    }
+
+    fn table(&self) -> Table {
+        let mut table = Table::new(&["check", "result", "detail"]);
+        for (name, passed, detail) in self.checks.iter() {
+            let result = if *passed { "PASS" } else { "FAIL" };
+            table.push(vec![name.clone(), result.to_string(), detail.clone()]);
+        }
+        table
+    }
+}
+
+pub async fn run(cmd: Status) -> Result<()> {
+    let orchestrator = FxAddress::from_str(cmd.orchestrator.as_str())?;
+    let eth_address = EthAddress::from_str(cmd.ethereum_address.as_str())?;
+    let min_fx_balance = parse_units(cmd.min_fx_balance.as_str(), NATIVE_DECIMALS)?;
+    let min_eth_balance = parse_units(cmd.min_eth_balance.as_str(), NATIVE_DECIMALS)?;
+
+    let transport = web3::transports::Http::new(cmd.ethereum_rpc.as_str())?;
+    let web3 = web3::Web3::new(transport);
+    let grpc_channel = new_grpc_channel(cmd.fx_chain_grpc.as_str()).await?;
+    let mut checklist = Checklist::default();
+
+    checklist.check(
+        "fx_chain_connectivity",
+        get_latest_block_height(&grpc_channel).await.map(|height| (true, format!("height {}", height))),
+    );
+    checklist.check(
+        "ethereum_connectivity",
+        web3.eth().block_number().await.map(|number| (true, format!("block {}", number))).map_err(eyre::Error::from),
+    );
+
+    let delegate = get_orchestrator_validator_status(&grpc_channel, orchestrator)
+        .await
+        .map_err(|report| format!("{}", report.root_cause()));
+    checklist.check(
+        "orchestrator_bonded",
+        delegate.clone().map_err(eyre::Error::msg).map(|(status, _)| {
+            let name = BondStatus::from_i32(status).unwrap_or(BondStatus::Unspecified);
+            (status == BondStatus::Bonded as i32, format!("{:?}", name))
+        }),
+    );
+    checklist.check(
+        "eth_address_match",
+        delegate.map_err(eyre::Error::msg).and_then(|(_, registered)| {
+            let matched = EthAddress::from_str(registered.as_str())? == eth_address;
+            Ok((matched, format!("registered {}, key {}", registered, eth_address.to_hex_string())))
+        }),
+    );
+
+    checklist.check(
+        "fx_balance",
+        get_balance(&grpc_channel, orchestrator, cmd.fee_denom.clone()).await.and_then(|coin| {
+            let balance = U256::from_dec_str(coin.amount.as_str()).map_err(|err| eyre::Error::msg(format!("invalid balance: {:?}", err)))?;
+            Ok(balance_check(balance, min_fx_balance, cmd.fee_denom.as_str()))
+        }),
+    );
+    checklist.check(
+        "eth_balance",
+        web3.eth()
+            .balance(eth_address, None)
+            .await
+            .map(|balance| balance_check(balance, min_eth_balance, "ETH"))
+            .map_err(eyre::Error::from),
+    );
+
+    let fx_bridge = new_fx_bridge(&grpc_channel, cmd.ethereum_rpc.as_str(), cmd.bridge_addr).await;
+    let fx_bridge = fx_bridge.as_ref().map_err(|report| format!("{}", report.root_cause()));
+
+    let event_nonce = async {
+        let fx_bridge = fx_bridge.clone().map_err(eyre::Error::msg)?;
+        let claimed = get_last_event_nonce(&grpc_channel, orchestrator).await?;
+        let contract = fx_bridge.state_last_event_nonce().await?;
+        Ok::<_, eyre::Error>((U256::from(claimed) == contract, format!("orchestrator {}, contract {}", claimed, contract)))
+    };
+    checklist.check("event_nonce", event_nonce.await);
+
+    checklist.check(
+        "unsigned_valsets",
+        get_last_pending_valset_requests(&grpc_channel, orchestrator).await.map(|valsets| {
+            let nonces: Vec<String> = valsets.iter().map(|valset| valset.nonce.to_string()).collect();
+            (nonces.is_empty(), format!("nonces [{}]", nonces.join(", ")))
+        }),
+    );
+    checklist.check(
+        "unsigned_batches",
+        get_last_pending_batch_request(&grpc_channel, orchestrator).await.map(|batch| match batch {
+            Some(batch) => (false, format!("batch {} of {}", batch.batch_nonce, batch.token_contract)),
+            None => (true, "none".to_string()),
+        }),
+    );
+
+    let gravity_id = async {
+        let fx_bridge = fx_bridge.clone().map_err(eyre::Error::msg)?;
+        let chain_id = get_gravity_id(&grpc_channel).await?;
+        let contract_id = fx_bridge.state_fx_bridge_id().await?;
+        let contract_id = String::from_utf8_lossy(&contract_id).trim_end_matches('\0').to_string();
+        Ok::<_, eyre::Error>((chain_id == contract_id, format!("f(x)Core {}, contract {}", chain_id, contract_id)))
+    };
+    checklist.check("gravity_id", gravity_id.await);
+
+    checklist.table().print(cmd.output);
+    match checklist.failures() {
+        0 => Ok(()),
+        failures => Err(eyre::Error::msg(format!("{} of {} checks failed", failures, checklist.checks.len()))),
+    }
+}
+
+fn balance_check(balance: U256, min: U256, symbol: &str) -> (bool, String) {
+    (
+        balance >= min,
+        format!("{} {}, minimum {}", format_units(balance, NATIVE_DECIMALS), symbol, format_units(min, NATIVE_DECIMALS)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checklist() {
+        let mut checklist = Checklist::default();
+        checklist.check("fx_balance", Ok(balance_check(U256::exp10(19), U256::exp10(18), "FX")));
+        checklist.check("eth_balance", Ok(balance_check(U256::exp10(16), U256::exp10(17), "ETH")));
+        checklist.check("event_nonce", Err(eyre::Error::msg("connection refused")));
+        assert_eq!(checklist.failures(), 2);
+        assert_eq!(
+            checklist.table().render(OutputFormat::Table),
+            "check        result  detail\n\
+             fx_balance   PASS    10 FX, minimum 1\n\
+             eth_balance  FAIL    0.01 ETH, minimum 0.1\n\
+             event_nonce  FAIL    connection refused"
+        );
+    }
+}
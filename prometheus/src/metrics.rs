@@ -1,20 +1,40 @@
 use lazy_static::lazy_static;
-use prometheus::{Counter, Gauge};
+use prometheus::{CounterVec, Gauge, GaugeVec};
 
 lazy_static! {
-    pub static ref ETH_BRIDGE_ORACLE_SYNC_BLOCK_HEIGHT: Gauge =
-        register_gauge!(opts!("eth_bridge_oracle_sync_block_height", "eth_bridge_oracle_sync_block_height")).unwrap();
+    pub static ref ETH_BRIDGE_ORACLE_SYNC_BLOCK_HEIGHT: GaugeVec =
+        register_gauge_vec!(opts!("eth_bridge_oracle_sync_block_height", "eth_bridge_oracle_sync_block_height"), &["orchestrator"]).unwrap();
     pub static ref ETH_BRIDGE_ORACLE_QUERY_LOG_BLOCK_HEIGHT_INTERVAL: Gauge =
         register_gauge!(opts!("eth_bridge_oracle_query_log_block_interval", "eth_bridge_oracle_query_log_block_interval")).unwrap();
     pub static ref ETH_BRIDGE_ORACLE_EVENT_PENDING_LEN: Gauge =
         register_gauge!(opts!("eth_bridge_oracle_event_pending_len", "eth_bridge_oracle_event_pending_len")).unwrap();
-    pub static ref ETH_BRIDGE_ORACLE_MSG_PENDING_LEN: Gauge =
-        register_gauge!(opts!("eth_bridge_oracle_msg_pending_len", "eth_bridge_oracle_msg_pending_len")).unwrap();
+    pub static ref ETH_BRIDGE_ORACLE_MSG_PENDING_LEN: GaugeVec =
+        register_gauge_vec!(opts!("eth_bridge_oracle_msg_pending_len", "eth_bridge_oracle_msg_pending_len"), &["orchestrator"]).unwrap();
 
-    pub static ref SUBMIT_BATCH_SIGN: Counter =
-        register_counter!(opts!("submit_batch_sign", "submit_batch_sign")).unwrap();
-    pub static ref UPDATE_VALSET_SIGN: Counter =
-        register_counter!(opts!("update_valset_sign", "update_valset_sign")).unwrap();
-    pub static ref FX_KEY_BALANCE: Gauge =
-        register_gauge!(opts!("fx_key_balance", "fx_key_balance")).unwrap();
+    pub static ref ETH_BRIDGE_ORACLE_CLAIMS_SUBMITTED: CounterVec =
+        register_counter_vec!(opts!("eth_bridge_oracle_claims_submitted", "eth_bridge_oracle_claims_submitted"), &["orchestrator"]).unwrap();
+
+    pub static ref SUBMIT_BATCH_SIGN: CounterVec =
+        register_counter_vec!(opts!("submit_batch_sign", "submit_batch_sign"), &["orchestrator"]).unwrap();
+    pub static ref UPDATE_VALSET_SIGN: CounterVec =
+        register_counter_vec!(opts!("update_valset_sign", "update_valset_sign"), &["orchestrator"]).unwrap();
+    pub static ref ETH_KEY_MISMATCH: GaugeVec =
+        register_gauge_vec!(opts!("eth_key_mismatch", "eth_key_mismatch"), &["orchestrator"]).unwrap();
+    pub static ref FX_KEY_BALANCE: GaugeVec =
+        register_gauge_vec!(opts!("fx_key_balance", "fx_key_balance"), &["orchestrator"]).unwrap();
+
+    pub static ref ETH_TX_FEE_PAID: CounterVec =
+        register_counter_vec!(opts!("eth_tx_fee_paid_gwei", "eth_tx_fee_paid_gwei"), &["operation"]).unwrap();
+    pub static ref ETH_TX_EFFECTIVE_GAS_PRICE: GaugeVec =
+        register_gauge_vec!(opts!("eth_tx_effective_gas_price_gwei", "eth_tx_effective_gas_price_gwei"), &["operation"]).unwrap();
+    pub static ref ETH_TX_FEE_ESCALATIONS: CounterVec =
+        register_counter_vec!(opts!("eth_tx_fee_escalations", "eth_tx_fee_escalations"), &["operation"]).unwrap();
+
+    pub static ref DRY_RUN_MSGS: CounterVec =
+        register_counter_vec!(opts!("dry_run_msgs", "dry_run_msgs"), &["orchestrator", "type_url"]).unwrap();
+
+    pub static ref BRIDGE_RECONCILE_BACKING: GaugeVec =
+        register_gauge_vec!(opts!("bridge_reconcile_backing", "bridge_reconcile_backing"), &["token", "symbol"]).unwrap();
+    pub static ref BRIDGE_RECONCILE_DISCREPANCY: GaugeVec =
+        register_gauge_vec!(opts!("bridge_reconcile_discrepancy", "bridge_reconcile_discrepancy"), &["token", "symbol"]).unwrap();
 }
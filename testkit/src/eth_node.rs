@@ -0,0 +1,1022 @@
+use std::collections::{HashMap, VecDeque};
+use std::convert::Infallible;
+use std::net::{SocketAddr, TcpListener};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use eyre::Result;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response};
+use rlp::{Rlp, RlpStream};
+use serde_json::{json, Value};
+use tokio::sync::oneshot;
+use web3::ethabi::{encode, Token};
+use web3::signing::{keccak256, recover};
+use web3::types::{Address, Block, BlockNumber, Bytes, FeeHistory, Index, Log, Transaction, TransactionReceipt, H256, U256, U64};
+
+use ethereum::fx_bridge::{FxOriginatedTokenEvent, SendToFxEvent, TransactionBatchExecutedEvent, ValsetUpdatedEvent};
+
+/// Error returned by Infura and geth for an `eth_getLogs` range with too many logs
+pub const TOO_MANY_RESULTS: &str = "query returned more than 10000 results";
+
+const GENESIS_TIMESTAMP: u64 = 1_600_000_000;
+
+const BLOCK_TIME: u64 = 12;
+
+/// Error returned by the next call of a method
+#[derive(Debug, Clone, PartialEq)]
+pub struct InjectedError {
+    pub method: String,
+    pub code: i64,
+    pub message: String,
+}
+
+/// Scripted `eth_call` result of the calls to `to` whose data starts with `data`, `Err` reverts with the reason
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockCall {
+    pub to: Address,
+    pub data: Vec<u8>,
+    pub result: Result<Vec<u8>, String>,
+}
+
+/// Transaction included in the mock chain
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockTx {
+    pub hash: H256,
+    pub from: Address,
+    pub to: Option<Address>,
+    pub nonce: U256,
+    pub data: Vec<u8>,
+    /// gas price of a legacy or EIP-2930 tx
+    pub max_fee_per_gas: U256,
+    /// EIP-1559 txs only
+    pub max_priority_fee_per_gas: Option<U256>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockBlock {
+    pub number: U64,
+    pub hash: H256,
+    pub timestamp: U256,
+    pub txs: Vec<MockTx>,
+}
+
+/// Ethereum chain served by `MockEthNode`, every field can be scripted by the test
+///
+/// Blocks are only mined by the test, by `eth_sendRawTransaction`, which includes the tx in a new
+/// block unless `hold_txs` is set, and by each poll of a block filter that finds no new block.
+#[derive(Debug, Clone)]
+pub struct EthChainState {
+    pub chain_id: u64,
+    pub gas_price: U256,
+    /// set to serve post-London blocks
+    pub base_fee_per_gas: Option<U256>,
+    /// reward of every block and percentile of `eth_feeHistory`
+    pub priority_fee_per_gas: U256,
+    pub gas_estimate: U256,
+    /// `eth_getLogs` fails with `TOO_MANY_RESULTS` above this many logs
+    pub max_logs: Option<usize>,
+    pub blocks: Vec<MockBlock>,
+    pub receipts: HashMap<H256, TransactionReceipt>,
+    pub calls: Vec<MockCall>,
+    pub nonces: HashMap<Address, U256>,
+    pub errors: VecDeque<InjectedError>,
+    /// transactions sent with `eth_sendRawTransaction`, in order
+    pub sent: Vec<MockTx>,
+    /// keep the sent transactions in `pending` until `mine_pending`, a pending one is replaced by a tx with
+    /// the same sender and nonce paying 10% more
+    pub hold_txs: bool,
+    pub pending: Vec<MockTx>,
+    /// replacements rejected for fees raised by less than 10%
+    pub rejected: Vec<MockTx>,
+    block_filters: HashMap<U256, usize>,
+}
+
+impl Default for EthChainState {
+    fn default() -> Self {
+        let mut state = EthChainState {
+            chain_id: 1,
+            gas_price: U256::exp10(9),
+            base_fee_per_gas: None,
+            priority_fee_per_gas: U256::exp10(9),
+            gas_estimate: U256::from(200_000),
+            max_logs: None,
+            blocks: Vec::new(),
+            receipts: HashMap::new(),
+            calls: Vec::new(),
+            nonces: HashMap::new(),
+            errors: VecDeque::new(),
+            sent: Vec::new(),
+            hold_txs: false,
+            pending: Vec::new(),
+            rejected: Vec::new(),
+            block_filters: HashMap::new(),
+        };
+        state.mine_block();
+        state
+    }
+}
+
+impl EthChainState {
+    pub fn block_number(&self) -> U64 {
+        U64::from(self.blocks.len() - 1)
+    }
+
+    /// Mine an empty block, returns its number
+    pub fn mine_block(&mut self) -> U64 {
+        self.push_block(Vec::new(), Vec::new())
+    }
+
+    pub fn mine_blocks(&mut self, count: u64) -> U64 {
+        for _ in 0..count {
+            self.mine_block();
+        }
+        self.block_number()
+    }
+
+    /// Mine a block with one successful transaction emitting `logs`, returns the tx hash
+    pub fn push_logs(&mut self, logs: Vec<Log>) -> H256 {
+        let number = self.blocks.len() as u64;
+        let tx = MockTx {
+            hash: H256::from(keccak256(format!("tx-{}", number).as_bytes())),
+            from: Address::zero(),
+            to: logs.first().map(|log| log.address),
+            nonce: U256::zero(),
+            data: Vec::new(),
+            max_fee_per_gas: self.gas_price,
+            max_priority_fee_per_gas: None,
+        };
+        let hash = tx.hash;
+        self.push_block(vec![tx], vec![(1, logs)]);
+        hash
+    }
+
+    /// Mine the pending transactions in a new block, returns its number
+    pub fn mine_pending(&mut self) -> U64 {
+        let txs = std::mem::take(&mut self.pending);
+        let results = txs.iter().map(|tx| (if self.reverts(tx) { 0 } else { 1 }, Vec::new())).collect();
+        for tx in txs.iter() {
+            self.nonces.insert(tx.from, tx.nonce + 1);
+        }
+        self.push_block(txs, results)
+    }
+
+    /// Script the `eth_call` result of `to` for calls starting with `data`, usually a function selector
+    pub fn mock_call(&mut self, to: Address, data: &[u8], result: Result<Vec<u8>, String>) {
+        self.calls.retain(|call| !(call.to == to && call.data == data));
+        self.calls.push(MockCall { to, data: data.to_vec(), result });
+    }
+
+    /// Fail the next call of `method` with a node error
+    pub fn inject_error(&mut self, method: &str, message: &str) {
+        self.errors.push_back(InjectedError {
+            method: method.to_string(),
+            code: -32000,
+            message: message.to_string(),
+        });
+    }
+
+    fn push_block(&mut self, txs: Vec<MockTx>, results: Vec<(u64, Vec<Log>)>) -> U64 {
+        let number = U64::from(self.blocks.len());
+        let hash = H256::from(keccak256(format!("block-{}", number).as_bytes()));
+        let mut log_index = 0u64;
+        for (i, (tx, (status, logs))) in txs.iter().zip(results).enumerate() {
+            let logs = logs
+                .into_iter()
+                .enumerate()
+                .map(|(j, log)| {
+                    log_index += 1;
+                    Log {
+                        block_hash: Some(hash),
+                        block_number: Some(number),
+                        transaction_hash: Some(tx.hash),
+                        transaction_index: Some(Index::from(i)),
+                        log_index: Some(U256::from(log_index - 1)),
+                        transaction_log_index: Some(U256::from(j)),
+                        removed: Some(false),
+                        ..log
+                    }
+                })
+                .collect();
+            let receipt = TransactionReceipt {
+                transaction_hash: tx.hash,
+                transaction_index: Index::from(i),
+                block_hash: Some(hash),
+                block_number: Some(number),
+                from: tx.from,
+                to: tx.to,
+                gas_used: Some(self.gas_estimate),
+                cumulative_gas_used: self.gas_estimate,
+                logs,
+                status: Some(U64::from(status)),
+                effective_gas_price: Some(self.gas_price),
+                ..Default::default()
+            };
+            self.receipts.insert(tx.hash, receipt);
+        }
+        self.blocks.push(MockBlock {
+            number,
+            hash,
+            timestamp: U256::from(GENESIS_TIMESTAMP + number.as_u64() * BLOCK_TIME),
+            txs,
+        });
+        number
+    }
+
+    fn block_by_tag(&self, tag: &Value) -> Result<Option<&MockBlock>, RpcError> {
+        let number = match tag.as_str() {
+            Some("latest") | Some("pending") | Some("safe") | Some("finalized") | None => self.block_number(),
+            Some("earliest") => U64::zero(),
+            Some(_) => parse::<U64>(tag)?,
+        };
+        Ok(self.blocks.get(number.as_usize()))
+    }
+
+    fn block_header<TX: Default>(&self, block: &MockBlock, transactions: Vec<TX>) -> Block<TX> {
+        Block {
+            hash: Some(block.hash),
+            parent_hash: block.number.as_usize().checked_sub(1).map(|parent| self.blocks[parent].hash).unwrap_or_default(),
+            number: Some(block.number),
+            gas_limit: U256::from(30_000_000),
+            base_fee_per_gas: self.base_fee_per_gas,
+            timestamp: block.timestamp,
+            transactions,
+            ..Default::default()
+        }
+    }
+
+    fn block_json(&self, block: &MockBlock, full: bool) -> Value {
+        if full {
+            let txs: Vec<Transaction> = block
+                .txs
+                .iter()
+                .enumerate()
+                .map(|(i, tx)| Transaction {
+                    hash: tx.hash,
+                    nonce: tx.nonce,
+                    block_hash: Some(block.hash),
+                    block_number: Some(block.number),
+                    transaction_index: Some(Index::from(i)),
+                    from: Some(tx.from),
+                    to: tx.to,
+                    gas_price: Some(self.gas_price),
+                    gas: self.gas_estimate,
+                    input: Bytes(tx.data.clone()),
+                    ..Default::default()
+                })
+                .collect();
+            serde_json::to_value(self.block_header(block, txs)).unwrap()
+        } else {
+            serde_json::to_value(self.block_header(block, block.txs.iter().map(|tx| tx.hash).collect())).unwrap()
+        }
+    }
+
+    fn fee_history(&self, block_count: &Value, newest_block: &Value, percentiles: &Value) -> Result<FeeHistory, RpcError> {
+        let newest = self.block_by_tag(newest_block)?.map(|block| block.number.as_usize()).unwrap_or(self.blocks.len() - 1);
+        let count = parse::<U256>(block_count)?.as_usize().min(newest + 1);
+        let percentiles: Vec<f64> = parse::<Option<Vec<f64>>>(percentiles)?.unwrap_or_default();
+        Ok(FeeHistory {
+            oldest_block: BlockNumber::Number(U64::from(newest + 1 - count)),
+            // with the base fee of the next block
+            base_fee_per_gas: vec![self.base_fee_per_gas.unwrap_or_default(); count + 1],
+            gas_used_ratio: vec![0.5; count],
+            reward: Some(vec![vec![self.priority_fee_per_gas; percentiles.len()]; count]),
+        })
+    }
+
+    fn logs(&self, filter: &Value) -> Result<Vec<Log>, RpcError> {
+        let from_block = self.block_by_tag(&filter["fromBlock"])?.map(|block| block.number.as_usize()).unwrap_or(self.blocks.len());
+        let to_block = self.block_by_tag(&filter["toBlock"])?.map(|block| block.number.as_usize()).unwrap_or_else(|| self.blocks.len() - 1);
+        let addresses: Vec<Address> = match &filter["address"] {
+            Value::Null => Vec::new(),
+            Value::Array(addresses) => addresses.iter().map(parse).collect::<Result<_, _>>()?,
+            address => vec![parse(address)?],
+        };
+        let topics: Vec<Vec<H256>> = match &filter["topics"] {
+            Value::Array(topics) => topics
+                .iter()
+                .map(|topic| match topic {
+                    Value::Null => Ok(Vec::new()),
+                    Value::Array(topic) => topic.iter().map(parse).collect(),
+                    topic => Ok(vec![parse(topic)?]),
+                })
+                .collect::<Result<_, _>>()?,
+            _ => Vec::new(),
+        };
+
+        let mut logs = Vec::new();
+        for block in self.blocks.iter().take(to_block + 1).skip(from_block) {
+            for tx in block.txs.iter() {
+                for log in self.receipts[&tx.hash].logs.iter() {
+                    let address_match = addresses.is_empty() || addresses.contains(&log.address);
+                    let topics_match = topics
+                        .iter()
+                        .enumerate()
+                        .all(|(i, topic)| topic.is_empty() || log.topics.get(i).map(|t| topic.contains(t)).unwrap_or(false));
+                    if address_match && topics_match {
+                        logs.push(log.clone());
+                    }
+                }
+            }
+        }
+        match self.max_logs {
+            Some(max_logs) if logs.len() > max_logs => Err(RpcError::new(-32005, TOO_MANY_RESULTS)),
+            _ => Ok(logs),
+        }
+    }
+
+    fn call(&self, request: &Value) -> Result<Vec<u8>, RpcError> {
+        let to: Address = parse(&request["to"])?;
+        let data: Bytes = match (&request["data"], &request["input"]) {
+            (Value::Null, Value::Null) => Bytes::default(),
+            (Value::Null, input) => parse(input)?,
+            (data, _) => parse(data)?,
+        };
+        let call = self
+            .calls
+            .iter()
+            .filter(|call| call.to == to && data.0.starts_with(call.data.as_slice()))
+            .max_by_key(|call| call.data.len());
+        match call.map(|call| &call.result) {
+            Some(Ok(result)) => Ok(result.clone()),
+            Some(Err(reason)) => Err(RpcError::revert(reason)),
+            None => Err(RpcError::new(-32000, "execution reverted")),
+        }
+    }
+
+    fn send_raw_transaction(&mut self, raw: &[u8]) -> Result<H256, RpcError> {
+        let tx = decode_raw_transaction(raw).map_err(|err| RpcError::new(-32602, format!("invalid raw transaction: {}", err).as_str()))?;
+        let nonce = self.nonces.get(&tx.from).cloned().unwrap_or_default();
+        if tx.nonce != nonce {
+            return Err(RpcError::new(-32000, format!("invalid nonce, expected {}, got {}", nonce, tx.nonce).as_str()));
+        }
+        let hash = tx.hash;
+        if self.hold_txs {
+            if let Some(i) = self.pending.iter().position(|pending| pending.from == tx.from && pending.nonce == tx.nonce) {
+                let raised = |fee: U256, pending: U256| fee >= pending * 110 / 100;
+                let pending = &self.pending[i];
+                let priority_raised = match (tx.max_priority_fee_per_gas, pending.max_priority_fee_per_gas) {
+                    (Some(fee), Some(pending)) => raised(fee, pending),
+                    _ => true,
+                };
+                if !raised(tx.max_fee_per_gas, pending.max_fee_per_gas) || !priority_raised {
+                    self.rejected.push(tx);
+                    return Err(RpcError::new(-32000, "replacement transaction underpriced"));
+                }
+                self.pending.remove(i);
+            }
+            self.sent.push(tx.clone());
+            self.pending.push(tx);
+            return Ok(hash);
+        }
+        self.nonces.insert(tx.from, nonce + 1);
+        let reverted = self.reverts(&tx);
+        self.sent.push(tx.clone());
+        self.push_block(vec![tx], vec![(if reverted { 0 } else { 1 }, Vec::new())]);
+        Ok(hash)
+    }
+
+    /// Whether `tx` reverts with the scripted calls
+    fn reverts(&self, tx: &MockTx) -> bool {
+        tx.to
+            .map(|to| matches!(self.call(&json!({ "to": to, "data": Bytes(tx.data.clone()) })), Err(ref err) if err.code == 3))
+            .unwrap_or(false)
+    }
+
+    fn handle(&mut self, method: &str, params: &Value) -> Result<Value, RpcError> {
+        if let Some(i) = self.errors.iter().position(|err| err.method == method) {
+            let err = self.errors.remove(i).unwrap();
+            return Err(RpcError::new(err.code, err.message.as_str()));
+        }
+        let param = |i: usize| params.get(i).cloned().unwrap_or(Value::Null);
+        let result = match method {
+            "eth_chainId" => json!(U64::from(self.chain_id)),
+            "net_version" => json!(self.chain_id.to_string()),
+            "eth_blockNumber" => json!(self.block_number()),
+            "eth_gasPrice" => json!(self.gas_price),
+            "eth_feeHistory" => json!(self.fee_history(&param(0), &param(1), &param(2))?),
+            "eth_getBlockByNumber" => match self.block_by_tag(&param(0))? {
+                Some(block) => self.block_json(block, param(1).as_bool().unwrap_or(false)),
+                None => Value::Null,
+            },
+            "eth_getBlockByHash" => {
+                let hash: H256 = parse(&param(0))?;
+                match self.blocks.iter().find(|block| block.hash == hash) {
+                    Some(block) => self.block_json(block, param(1).as_bool().unwrap_or(false)),
+                    None => Value::Null,
+                }
+            }
+            "eth_getLogs" => json!(self.logs(&param(0))?),
+            "eth_getTransactionReceipt" => json!(self.receipts.get(&parse::<H256>(&param(0))?)),
+            "eth_getTransactionCount" => json!(self.nonces.get(&parse::<Address>(&param(0))?).cloned().unwrap_or_default()),
+            "eth_call" => json!(Bytes(self.call(&param(0))?)),
+            "eth_estimateGas" => match self.call(&param(0)) {
+                Err(err) if err.code == 3 => return Err(err),
+                _ => json!(self.gas_estimate),
+            },
+            "eth_sendRawTransaction" => json!(self.send_raw_transaction(parse::<Bytes>(&param(0))?.0.as_slice())?),
+            "eth_newBlockFilter" => {
+                let id = U256::from(self.block_filters.len() + 1);
+                self.block_filters.insert(id, self.blocks.len());
+                json!(id)
+            }
+            "eth_getFilterChanges" => {
+                let id: U256 = parse(&param(0))?;
+                let next = *self.block_filters.get(&id).ok_or_else(|| RpcError::new(-32000, "filter not found"))?;
+                if next == self.blocks.len() {
+                    self.mine_block();
+                }
+                self.block_filters.insert(id, self.blocks.len());
+                json!(self.blocks[next..].iter().map(|block| block.hash).collect::<Vec<H256>>())
+            }
+            "eth_uninstallFilter" => json!(self.block_filters.remove(&parse::<U256>(&param(0))?).is_some()),
+            _ => return Err(RpcError::new(-32601, format!("the method {} does not exist/is not available", method).as_str())),
+        };
+        Ok(result)
+    }
+}
+
+/// JSON-RPC error object
+#[derive(Debug, Clone, PartialEq)]
+struct RpcError {
+    code: i64,
+    message: String,
+    data: Option<Bytes>,
+}
+
+impl RpcError {
+    fn new(code: i64, message: &str) -> Self {
+        RpcError {
+            code,
+            message: message.to_string(),
+            data: None,
+        }
+    }
+
+    /// geth revert error, with the reason encoded as `Error(string)`
+    fn revert(reason: &str) -> Self {
+        let mut data = keccak256(b"Error(string)")[..4].to_vec();
+        data.extend(encode(&[Token::String(reason.to_string())]));
+        RpcError {
+            code: 3,
+            message: format!("execution reverted: {}", reason),
+            data: Some(Bytes(data)),
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        match &self.data {
+            Some(data) => json!({ "code": self.code, "message": self.message, "data": data }),
+            None => json!({ "code": self.code, "message": self.message }),
+        }
+    }
+}
+
+fn parse<T: serde::de::DeserializeOwned>(value: &Value) -> Result<T, RpcError> {
+    serde_json::from_value(value.clone()).map_err(|err| RpcError::new(-32602, format!("invalid params: {}", err).as_str()))
+}
+
+/// Decode a signed legacy, EIP-2930 or EIP-1559 transaction and recover its sender
+pub fn decode_raw_transaction(raw: &[u8]) -> Result<MockTx> {
+    let invalid = |err: rlp::DecoderError| eyre::Error::msg(format!("{:?}", err));
+    let (tx_type, payload) = match raw.first() {
+        Some(tx_type) if *tx_type <= 0x7f => (Some(*tx_type), &raw[1..]),
+        _ => (None, raw),
+    };
+    let rlp = Rlp::new(payload);
+    // index of the nonce, the gas price or max fee, the recipient and the signature v or y parity
+    let (nonce_at, fee_at, to_at, v_at) = match tx_type {
+        None => (0, 1, 3, 6),
+        Some(1) => (1, 2, 4, 8),
+        Some(2) => (1, 3, 5, 9),
+        Some(tx_type) => return Err(eyre::Error::msg(format!("unsupported transaction type {}", tx_type))),
+    };
+    let v: u64 = rlp.val_at(v_at).map_err(invalid)?;
+    let (mut message, recovery_id) = match tx_type {
+        None => {
+            let mut stream = RlpStream::new();
+            if v >= 35 {
+                stream.begin_list(9);
+                (0..v_at).try_for_each(|i| rlp.at(i).map(|item| stream.append_raw(item.as_raw(), 1)).map(|_| ())).map_err(invalid)?;
+                stream.append(&((v - 35) / 2)).append(&0u8).append(&0u8);
+                (stream.out().to_vec(), (v - 35) % 2)
+            } else {
+                stream.begin_list(v_at);
+                (0..v_at).try_for_each(|i| rlp.at(i).map(|item| stream.append_raw(item.as_raw(), 1)).map(|_| ())).map_err(invalid)?;
+                (stream.out().to_vec(), v.saturating_sub(27))
+            }
+        }
+        Some(tx_type) => {
+            let mut stream = RlpStream::new_list(v_at);
+            (0..v_at).try_for_each(|i| rlp.at(i).map(|item| stream.append_raw(item.as_raw(), 1)).map(|_| ())).map_err(invalid)?;
+            let mut message = vec![tx_type];
+            message.extend_from_slice(stream.out().as_ref());
+            (message, v)
+        }
+    };
+    let mut signature = [0u8; 64];
+    for (i, at) in [v_at + 1, v_at + 2].iter().enumerate() {
+        let word: Vec<u8> = rlp.val_at(*at).map_err(invalid)?;
+        if word.len() > 32 {
+            return Err(eyre::Error::msg("invalid signature"));
+        }
+        signature[i * 32 + 32 - word.len()..(i + 1) * 32].copy_from_slice(word.as_slice());
+    }
+    message = keccak256(message.as_slice()).to_vec();
+    let from = recover(message.as_slice(), &signature, recovery_id as i32).map_err(|err| eyre::Error::msg(format!("{:?}", err)))?;
+    let to: Vec<u8> = rlp.val_at(to_at).map_err(invalid)?;
+    Ok(MockTx {
+        hash: H256::from(keccak256(raw)),
+        from,
+        to: if to.len() == 20 { Some(Address::from_slice(to.as_slice())) } else { None },
+        nonce: rlp.val_at(nonce_at).map_err(invalid)?,
+        data: rlp.val_at(to_at + 2).map_err(invalid)?,
+        max_fee_per_gas: rlp.val_at(fee_at).map_err(invalid)?,
+        max_priority_fee_per_gas: if tx_type == Some(2) { Some(rlp.val_at(2).map_err(invalid)?) } else { None },
+    })
+}
+
+fn log(address: Address, topics: Vec<H256>, data: Vec<u8>) -> Log {
+    Log {
+        address,
+        topics,
+        data: Bytes(data),
+        block_hash: None,
+        block_number: None,
+        transaction_hash: None,
+        transaction_index: None,
+        log_index: None,
+        transaction_log_index: None,
+        log_type: None,
+        removed: None,
+    }
+}
+
+fn address_topic(address: Address) -> H256 {
+    H256::from(address)
+}
+
+fn uint_topic(value: U256) -> H256 {
+    let mut topic = [0u8; 32];
+    value.to_big_endian(&mut topic);
+    H256::from(topic)
+}
+
+/// `SendToFxEvent` log, `target_ibc` is right padded to 32 bytes
+pub fn send_to_fx_log(bridge: Address, erc20: Address, sender: Address, destination: [u8; 20], target_ibc: &[u8], amount: U256, event_nonce: U256) -> Log {
+    let mut target = [0u8; 32];
+    target[..target_ibc.len()].copy_from_slice(target_ibc);
+    let mut destination_topic = [0u8; 32];
+    destination_topic[12..].copy_from_slice(&destination);
+    let data = encode(&[Token::FixedBytes(target.to_vec()), Token::Uint(amount), Token::Uint(event_nonce)]);
+    log(
+        bridge,
+        vec![SendToFxEvent::signature(), address_topic(erc20), address_topic(sender), H256::from(destination_topic)],
+        data,
+    )
+}
+
+pub fn batch_executed_log(bridge: Address, batch_nonce: U256, erc20: Address, event_nonce: U256) -> Log {
+    let data = encode(&[Token::Uint(event_nonce)]);
+    log(bridge, vec![TransactionBatchExecutedEvent::signature(), uint_topic(batch_nonce), address_topic(erc20)], data)
+}
+
+pub fn fx_originated_token_log(bridge: Address, erc20: Address, name: &str, symbol: &str, decimals: u8, event_nonce: U256) -> Log {
+    let data = encode(&[
+        Token::String(name.to_string()),
+        Token::String(symbol.to_string()),
+        Token::Uint(U256::from(decimals)),
+        Token::Uint(event_nonce),
+    ]);
+    log(bridge, vec![FxOriginatedTokenEvent::signature(), address_topic(erc20)], data)
+}
+
+pub fn valset_updated_log(bridge: Address, valset_nonce: U256, event_nonce: U256, validators: &[Address], powers: &[U256]) -> Log {
+    let data = encode(&[
+        Token::Uint(event_nonce),
+        Token::Array(validators.iter().map(|validator| Token::Address(*validator)).collect()),
+        Token::Array(powers.iter().map(|power| Token::Uint(*power)).collect()),
+    ]);
+    log(bridge, vec![ValsetUpdatedEvent::signature(), uint_topic(valset_nonce)], data)
+}
+
+/// In-process Ethereum JSON-RPC node serving a scriptable `EthChainState`
+pub struct MockEthNode {
+    addr: SocketAddr,
+    state: Arc<Mutex<EthChainState>>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl MockEthNode {
+    /// Serve `state` on a free local port, must be called from a tokio runtime
+    pub async fn start(state: EthChainState) -> Result<MockEthNode> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(state));
+        let service_state = state.clone();
+        let make_service = make_service_fn(move |_| {
+            let state = service_state.clone();
+            async move { Ok::<_, Infallible>(service_fn(move |request| serve(state.clone(), request))) }
+        });
+        let (shutdown, shutdown_rx) = oneshot::channel::<()>();
+        let server = hyper::Server::from_tcp(listener)?.serve(make_service).with_graceful_shutdown(async {
+            shutdown_rx.await.ok();
+        });
+        tokio::spawn(async move {
+            if let Err(err) = server.await {
+                error!("Mock eth node server error: {}", err);
+            }
+        });
+        debug!("Mock eth node listening on {}", addr);
+        Ok(MockEthNode {
+            addr,
+            state,
+            shutdown: Some(shutdown),
+        })
+    }
+
+    /// RPC url for `web3::transports::Http::new`
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Lock the state to script it or assert on it
+    pub fn state(&self) -> MutexGuard<'_, EthChainState> {
+        self.state.lock().unwrap()
+    }
+}
+
+impl Drop for MockEthNode {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            shutdown.send(()).ok();
+        }
+    }
+}
+
+fn handle_request(state: &Mutex<EthChainState>, request: &Value) -> Value {
+    let method = request["method"].as_str().unwrap_or_default();
+    debug!("Mock eth node call {}", method);
+    let result = state.lock().unwrap().handle(method, &request["params"]);
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }),
+        Err(err) => json!({ "jsonrpc": "2.0", "id": request["id"], "error": err.to_json() }),
+    }
+}
+
+async fn serve(state: Arc<Mutex<EthChainState>>, request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let body = match hyper::body::to_bytes(request.into_body()).await {
+        Ok(body) => body,
+        Err(err) => return Ok(Response::builder().status(400).body(Body::from(err.to_string())).unwrap()),
+    };
+    let response = match serde_json::from_slice::<Value>(&body) {
+        Ok(Value::Array(requests)) => Value::Array(requests.iter().map(|request| handle_request(&state, request)).collect()),
+        Ok(request) => handle_request(&state, &request),
+        Err(err) => json!({ "jsonrpc": "2.0", "id": Value::Null, "error": { "code": -32700, "message": err.to_string() } }),
+    };
+    Ok(Response::builder().header("content-type", "application/json").body(Body::from(response.to_string())).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    use web3::contract::Options;
+    use web3::transports::Http;
+    use web3::types::{BlockId, BlockNumber, TransactionParameters};
+    use web3::Web3;
+
+    use ethereum::abi::fx_bridge::{
+        BridgeToken, CheckAssetStatusCall, GetBridgeTokenListCall, LastBatchNonceCall, StateFxOriginatedTokenCall, StateLastEventNonceCall, SubmitBatchCall, UpdateValsetCall,
+    };
+    use ethereum::abi::multicall3::{Aggregate3Call, Multicall3Result};
+    use ethereum::abi::ContractCall;
+    use ethereum::fee::{FeePolicy, Operation};
+    use ethereum::fx_bridge::{query_all_event, query_all_event_san_block, BridgeTokenStatus, FxBridge};
+    use ethereum::multicall::MULTICALL3_ADDRESS;
+    use ethereum::private_key::{Key, PrivateKey};
+    use ethereum::revert::FxBridgeError;
+
+    use super::*;
+
+    const BRIDGE: &str = "0x0412C7c846bb6b7DC462CF6B453f76D8440b2609";
+    const USDT: &str = "0xdAC17F958D2ee523a2206206994597C13D831ec7";
+
+    fn bridge_state() -> EthChainState {
+        let bridge = Address::from_str(BRIDGE).unwrap();
+        let usdt = Address::from_str(USDT).unwrap();
+        let mut state = EthChainState::default();
+        state.mine_blocks(5);
+        state.push_logs(vec![fx_originated_token_log(bridge, usdt, "Tether USD", "USDT", 6, 1.into())]);
+        state.push_logs(vec![send_to_fx_log(bridge, usdt, Address::repeat_byte(1), [2; 20], b"px/transfer/channel-0", 1000.into(), 2.into())]);
+        state.push_logs(vec![batch_executed_log(bridge, 7.into(), usdt, 3.into())]);
+        state.push_logs(vec![valset_updated_log(bridge, 4.into(), 4.into(), &[Address::repeat_byte(3)], &[U256::from(u32::MAX)])]);
+        state
+    }
+
+    #[tokio::test]
+    async fn test_bridge_events() {
+        let eth_node = MockEthNode::start(bridge_state()).await.unwrap();
+        let web3 = Web3::new(Http::new(eth_node.url().as_str()).unwrap());
+        assert_eq!(web3.eth().block_number().await.unwrap(), U64::from(9));
+
+        let bridge = Address::from_str(BRIDGE).unwrap();
+        let (deposits, withdraws, tokens, valsets) = query_all_event(&web3, bridge, 0.into(), None).await.unwrap();
+        assert_eq!((deposits.len(), withdraws.len(), tokens.len(), valsets.len()), (1, 1, 1, 1));
+        assert_eq!((tokens[0].symbol.as_str(), tokens[0].decimals, tokens[0].block_number), ("USDT", 6.into(), 6.into()));
+        assert_eq!((deposits[0].amount, deposits[0].destination, deposits[0].event_nonce), (1000.into(), [2; 20], 2.into()));
+        assert_eq!(deposits[0].target_ibc, hex::encode(b"px/transfer/channel-0"));
+        assert_eq!((withdraws[0].batch_nonce, withdraws[0].erc20), (7.into(), Address::from_str(USDT).unwrap()));
+        assert_eq!((valsets[0].valset_nonce, valsets[0].validators.clone()), (4.into(), vec![Address::repeat_byte(3)]));
+
+        let (deposits, _, _, _) = query_all_event_san_block(&web3, bridge, 7.into()).await.unwrap();
+        assert_eq!(deposits.len(), 1);
+        let block = web3.eth().block(BlockId::Number(BlockNumber::Number(7.into()))).await.unwrap().unwrap();
+        assert_eq!(block.timestamp, U256::from(GENESIS_TIMESTAMP + 7 * BLOCK_TIME));
+    }
+
+    #[tokio::test]
+    async fn test_inject_errors() {
+        let mut state = bridge_state();
+        state.max_logs = Some(2);
+        state.inject_error("eth_blockNumber", "connection reset");
+        let eth_node = MockEthNode::start(state).await.unwrap();
+        let web3 = Web3::new(Http::new(eth_node.url().as_str()).unwrap());
+
+        assert!(web3.eth().block_number().await.is_err());
+        assert!(web3.eth().block_number().await.is_ok());
+        let bridge = Address::from_str(BRIDGE).unwrap();
+        let err = query_all_event(&web3, bridge, 0.into(), None).await.unwrap_err();
+        assert!(format!("{:?}", err).contains(TOO_MANY_RESULTS));
+        assert!(query_all_event(&web3, bridge, 0.into(), Some(7.into())).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_call_and_send_raw_transaction() {
+        let key = PrivateKey::from([9u8; 32]);
+        let bridge = Address::from_str(BRIDGE).unwrap();
+        let mut state = EthChainState::default();
+        let selector = keccak256(b"state_lastEventNonce()")[..4].to_vec();
+        state.mock_call(bridge, selector.as_slice(), Ok(encode(&[Token::Uint(42.into())])));
+        state.mock_call(
+            bridge,
+            &keccak256(b"sendToFx(address,bytes32,bytes32,uint256)")[..4],
+            Err("transfer amount exceeds balance".to_string()),
+        );
+        let eth_node = MockEthNode::start(state).await.unwrap();
+        let web3 = Web3::new(Http::new(eth_node.url().as_str()).unwrap());
+
+        let fx_bridge = FxBridge::new(None, None, web3.eth(), bridge);
+        assert_eq!(fx_bridge.state_last_event_nonce().await.unwrap(), U256::from(42));
+        let err = fx_bridge.send_to_fx(Address::zero(), [0; 32], [0; 32], 1.into()).await.unwrap_err();
+        assert!(format!("{:?}", err).contains("no private key"));
+
+        let tx = TransactionParameters {
+            to: Some(bridge),
+            data: Bytes(selector.clone()),
+            ..Default::default()
+        };
+        let signed = web3.accounts().sign_transaction(tx, &*key).await.unwrap();
+        let hash = web3.eth().send_raw_transaction(signed.raw_transaction).await.unwrap();
+        let receipt = web3.eth().transaction_receipt(hash).await.unwrap().unwrap();
+        assert_eq!((receipt.from, receipt.status, receipt.block_number), (Key::address(&key), Some(1.into()), Some(1.into())));
+        assert_eq!(eth_node.state().sent[0].data, selector);
+        assert_eq!(web3.eth().transaction_count(Key::address(&key), None).await.unwrap(), U256::one());
+    }
+
+    #[tokio::test]
+    async fn test_revert_reason() {
+        let key = PrivateKey::from([9u8; 32]);
+        let bridge = Address::from_str(BRIDGE).unwrap();
+        let mut state = EthChainState::default();
+        state.mock_call(
+            bridge,
+            &keccak256(b"updateValset(address[],uint256[],uint256,address[],uint256[],uint256,uint8[],bytes32[],bytes32[])")[..4],
+            Err("New valset nonce must be greater than the current nonce".to_string()),
+        );
+        let eth_node = MockEthNode::start(state).await.unwrap();
+        let web3 = Web3::new(Http::new(eth_node.url().as_str()).unwrap());
+        let params = (
+            Vec::<Token>::new(),
+            Vec::<Token>::new(),
+            U256::one(),
+            Vec::<Token>::new(),
+            Vec::<Token>::new(),
+            U256::one(),
+            Vec::<Token>::new(),
+            Vec::<Token>::new(),
+            Vec::<Token>::new(),
+        );
+
+        // the revert is caught by eth_estimateGas before sending
+        let fx_bridge = FxBridge::new(Some(key.clone()), None, web3.eth(), bridge);
+        let err = fx_bridge.signed_call_with_confirmations("updateValset", params.clone(), 0).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<FxBridgeError>(), Some(&FxBridgeError::InvalidValsetNonce));
+        assert!(eth_node.state().sent.is_empty());
+
+        // with a fixed gas limit the tx is mined with status 0, and replayed for the reason
+        let options = Options {
+            gas: Some(200_000.into()),
+            ..Default::default()
+        };
+        let fx_bridge = FxBridge::new(Some(key), Some(options), web3.eth(), bridge);
+        let err = fx_bridge.signed_call_with_confirmations("updateValset", params, 0).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<FxBridgeError>(), Some(&FxBridgeError::InvalidValsetNonce));
+        assert_eq!(eth_node.state().sent.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_multicall() {
+        let bridge = Address::from_str(BRIDGE).unwrap();
+        let usdt = Address::from_str(USDT).unwrap();
+        let multicall = Address::from_str(MULTICALL3_ADDRESS).unwrap();
+        let last_batch_nonce = |erc20_address| LastBatchNonceCall { erc20_address }.encode();
+        let mut state = EthChainState::default();
+        state.mock_call(bridge, &StateLastEventNonceCall::SELECTOR, Ok(encode(&[Token::Uint(42.into())])));
+        state.mock_call(bridge, last_batch_nonce(usdt).as_slice(), Ok(encode(&[Token::Uint(7.into())])));
+        state.mock_call(bridge, last_batch_nonce(Address::zero()).as_slice(), Err("token not supported".to_string()));
+        let results = vec![
+            Multicall3Result {
+                success: true,
+                return_data: encode(&[Token::Uint(43.into())]),
+            },
+            Multicall3Result {
+                success: false,
+                return_data: Vec::new(),
+            },
+        ];
+        let output = encode(&[Token::Array(results.into_iter().map(Multicall3Result::into_token).collect())]);
+        state.mock_call(multicall, &Aggregate3Call::SELECTOR, Ok(output));
+        let eth_node = MockEthNode::start(state).await.unwrap();
+        let web3 = Web3::new(Http::new(eth_node.url().as_str()).unwrap());
+        let fx_bridge = FxBridge::new(None, None, web3.eth(), bridge);
+
+        // without a multicall contract the calls are one JSON-RPC batch, a revert fails only its call
+        let mut batch = fx_bridge.multicall(None);
+        let event_nonce = batch.add(bridge, StateLastEventNonceCall {});
+        let usdt_nonce = batch.add(bridge, LastBatchNonceCall { erc20_address: usdt });
+        let unknown_nonce = batch.add(bridge, LastBatchNonceCall { erc20_address: Address::zero() });
+        let results = batch.call().await.unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(event_nonce.decode(results.as_slice()).unwrap(), U256::from(42));
+        assert_eq!(usdt_nonce.decode(results.as_slice()).unwrap(), U256::from(7));
+        let err = unknown_nonce.decode(results.as_slice()).unwrap_err();
+        assert_eq!(err.downcast_ref::<FxBridgeError>(), Some(&FxBridgeError::Revert("token not supported".to_string())));
+
+        // with Multicall3 they are one aggregate3 call
+        let mut batch = fx_bridge.multicall(Some(multicall));
+        let event_nonce = batch.add(bridge, StateLastEventNonceCall {});
+        let usdt_nonce = batch.add(bridge, LastBatchNonceCall { erc20_address: usdt });
+        let results = batch.call().await.unwrap();
+        assert_eq!(event_nonce.decode(results.as_slice()).unwrap(), U256::from(43));
+        assert_eq!(
+            usdt_nonce.decode(results.as_slice()).unwrap_err().downcast_ref::<FxBridgeError>(),
+            Some(&FxBridgeError::Unknown(Vec::new()))
+        );
+
+        // Multicall3 returns a result per call
+        batch.add(bridge, StateLastEventNonceCall {});
+        assert!(batch.call().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_bridge_token_statuses() {
+        let bridge = Address::from_str(BRIDGE).unwrap();
+        let usdt = Address::from_str(USDT).unwrap();
+        let fx = Address::repeat_byte(0xf8);
+        let uint = |value: u64| Ok(encode(&[Token::Uint(value.into())]));
+        let bridge_token = |addr, name: &str, symbol: &str, decimals| BridgeToken {
+            addr,
+            name: name.to_string(),
+            symbol: symbol.to_string(),
+            decimals,
+        };
+        let bridge_tokens = vec![bridge_token(usdt, "Tether USD", "USDT", 6), bridge_token(fx, "Function X", "FX", 18)];
+        let mut state = EthChainState::default();
+        state.mock_call(
+            bridge,
+            &GetBridgeTokenListCall::SELECTOR,
+            Ok(encode(&[Token::Array(bridge_tokens.into_iter().map(|token| token.into_token()).collect())])),
+        );
+        state.mock_call(bridge, &StateFxOriginatedTokenCall::SELECTOR, Ok(encode(&[Token::Address(fx)])));
+        state.mock_call(bridge, CheckAssetStatusCall { token_addr: usdt }.encode().as_slice(), Ok(encode(&[Token::Bool(true)])));
+        state.mock_call(bridge, CheckAssetStatusCall { token_addr: fx }.encode().as_slice(), Ok(encode(&[Token::Bool(false)])));
+        state.mock_call(bridge, LastBatchNonceCall { erc20_address: usdt }.encode().as_slice(), uint(7));
+        state.mock_call(bridge, LastBatchNonceCall { erc20_address: fx }.encode().as_slice(), uint(0));
+        let eth_node = MockEthNode::start(state).await.unwrap();
+        let web3 = Web3::new(Http::new(eth_node.url().as_str()).unwrap());
+        let fx_bridge = FxBridge::new(None, None, web3.eth(), bridge);
+
+        let tokens = fx_bridge.bridge_token_statuses(None).await.unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                BridgeTokenStatus {
+                    addr: usdt,
+                    name: "Tether USD".to_string(),
+                    symbol: "USDT".to_string(),
+                    decimals: 6,
+                    active: true,
+                    last_batch_nonce: 7.into(),
+                    fx_originated: false,
+                },
+                BridgeTokenStatus {
+                    addr: fx,
+                    name: "Function X".to_string(),
+                    symbol: "FX".to_string(),
+                    decimals: 18,
+                    active: false,
+                    last_batch_nonce: 0.into(),
+                    fx_originated: true,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fee_policy() {
+        let gwei = |gwei: u64| U256::exp10(9) * gwei;
+        let bridge = Address::from_str(BRIDGE).unwrap();
+        let mut state = EthChainState {
+            base_fee_per_gas: Some(gwei(30)),
+            priority_fee_per_gas: gwei(2),
+            ..Default::default()
+        };
+        state.mock_call(bridge, &UpdateValsetCall::SELECTOR, Ok(Vec::new()));
+        state.mock_call(bridge, &SubmitBatchCall::SELECTOR, Ok(Vec::new()));
+        let eth_node = MockEthNode::start(state).await.unwrap();
+        let web3 = Web3::new(Http::new(eth_node.url().as_str()).unwrap());
+        let mut fee_policy = FeePolicy {
+            max_fee_per_gas: gwei(100),
+            ..Default::default()
+        };
+        fee_policy.caps.insert(Operation::BatchRelay, gwei(20));
+        let fx_bridge = FxBridge::new(Some(PrivateKey::from([9u8; 32])), None, web3.eth(), bridge).with_fee_policy(fee_policy);
+
+        // twice the next base fee on top of the priority fee of the fee history
+        fx_bridge.signed_call_with_confirmations(UpdateValsetCall::NAME, UpdateValsetCall::default(), 0).await.unwrap();
+        let tx = eth_node.state().sent[0].clone();
+        assert_eq!((tx.max_fee_per_gas, tx.max_priority_fee_per_gas), (gwei(62), Some(gwei(2))));
+
+        // the base fee is above the cap of batch relays
+        let err = fx_bridge.sign_call(SubmitBatchCall::NAME, SubmitBatchCall::default()).await.unwrap_err();
+        assert_eq!(err.to_string(), format!("batch_relay gas price {} > max gas price {}", gwei(30), gwei(20)));
+
+        // before London the gas price of the node
+        eth_node.state().base_fee_per_gas = None;
+        fx_bridge.signed_call_with_confirmations(SubmitBatchCall::NAME, SubmitBatchCall::default(), 0).await.unwrap();
+        let tx = eth_node.state().sent[1].clone();
+        assert_eq!((tx.max_fee_per_gas, tx.max_priority_fee_per_gas), (gwei(1), None));
+    }
+
+    #[tokio::test]
+    async fn test_fee_escalation() {
+        let gwei = |gwei: u64| U256::exp10(9) * gwei;
+        let bridge = Address::from_str(BRIDGE).unwrap();
+        let mut state = EthChainState {
+            base_fee_per_gas: Some(gwei(30)),
+            priority_fee_per_gas: gwei(2),
+            hold_txs: true,
+            ..Default::default()
+        };
+        state.mock_call(bridge, &UpdateValsetCall::SELECTOR, Ok(Vec::new()));
+        let eth_node = MockEthNode::start(state).await.unwrap();
+        let web3 = Web3::new(Http::new(eth_node.url().as_str()).unwrap());
+        let fee_policy = FeePolicy {
+            max_fee_per_gas: gwei(72),
+            confirmation_timeout: Duration::from_secs(1),
+            poll_interval: Duration::from_millis(50),
+            ..Default::default()
+        };
+        let fx_bridge = FxBridge::new(Some(PrivateKey::from([9u8; 32])), None, web3.eth(), bridge).with_fee_policy(fee_policy);
+
+        let miner = async {
+            // the fees of the node drop once the first tx is sent, replacements still raise the fees it paid
+            while eth_node.state().sent.is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            eth_node.state().base_fee_per_gas = Some(gwei(10));
+            // the second replacement is capped at the fees of the first one, the node rejects it
+            while eth_node.state().rejected.is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            // the first tx is mined at last, instead of the replacement
+            let mut state = eth_node.state();
+            state.pending = vec![state.sent[0].clone()];
+            state.mine_pending();
+        };
+        let (receipt, _) = tokio::join!(fx_bridge.signed_call_with_confirmations(UpdateValsetCall::NAME, UpdateValsetCall::default(), 1), miner);
+        let receipt = receipt.unwrap();
+
+        let state = eth_node.state();
+        assert_eq!(state.sent.len(), 2);
+        assert_eq!(receipt.transaction_hash, state.sent[0].hash);
+        let fees: Vec<(U256, Option<U256>)> = state.sent.iter().map(|tx| (tx.max_fee_per_gas, tx.max_priority_fee_per_gas)).collect();
+        assert_eq!(fees, vec![(gwei(62), Some(gwei(2))), (U256::from(71_300_000_000u64), Some(U256::from(2_300_000_000u64)))]);
+        assert_eq!(state.sent[1].nonce, state.sent[0].nonce);
+        assert_eq!(state.rejected.len(), 1);
+        assert_eq!((state.rejected[0].max_fee_per_gas, state.rejected[0].nonce), (gwei(72), state.sent[0].nonce));
+        assert_eq!(state.nonces.get(&state.sent[0].from), Some(&U256::one()));
+    }
+}
@@ -0,0 +1,1099 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::convert::{Infallible, TryFrom};
+use std::net::{SocketAddr, TcpListener};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use cosmos_sdk_proto::cosmos::auth::v1beta1::{BaseAccount, QueryAccountRequest, QueryAccountResponse};
+use cosmos_sdk_proto::cosmos::bank::v1beta1::{QueryAllBalancesRequest, QueryAllBalancesResponse, QueryBalanceRequest, QueryBalanceResponse};
+use cosmos_sdk_proto::cosmos::bank::v1beta1::{QuerySupplyOfRequest, QuerySupplyOfResponse, QueryTotalSupplyRequest, QueryTotalSupplyResponse};
+use cosmos_sdk_proto::cosmos::base::abci::v1beta1::{GasInfo, TxResponse};
+use cosmos_sdk_proto::cosmos::base::tendermint::v1beta1::{GetLatestBlockRequest, GetLatestBlockResponse};
+use cosmos_sdk_proto::cosmos::base::v1beta1::Coin;
+use cosmos_sdk_proto::cosmos::staking::v1beta1::{BondStatus, QueryValidatorRequest, QueryValidatorResponse, Validator};
+use cosmos_sdk_proto::cosmos::tx::v1beta1::{AuthInfo, BroadcastTxRequest, BroadcastTxResponse, SimulateRequest, SimulateResponse, TxBody, TxRaw};
+use eyre::Result;
+use futures::future::{ready, Ready};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::Body;
+use prost::Message;
+use prost_types::Any;
+use sha2::{Digest, Sha256};
+use tendermint_proto::types::{Block, Header};
+use tokio::sync::oneshot;
+use tonic::body::BoxBody;
+use tonic::codec::ProstCodec;
+use tonic::server::{Grpc, UnaryService};
+use tonic::Status;
+
+use fxchain::address::Address as FxAddress;
+use fxchain::proto_ext::MessageExt;
+use fxchain::public_key::PublicKey;
+use fxchain::x::gravity::*;
+use fxchain::x::other::{GasPriceRequest, GasPriceResponse};
+
+/// ABCI code of a tx whose signer sequence is not the account sequence
+const CODE_WRONG_SEQUENCE: u32 = 32;
+
+/// ABCI code of a tx rejected by a message handler
+const CODE_MSG_FAILED: u32 = 1;
+
+/// Registered orchestrator of a validator
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DelegateKey {
+    pub validator: String,
+    pub orchestrator: String,
+    pub eth_address: String,
+}
+
+/// ERC20 token mapped to a f(x)Core denom
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Erc20Denom {
+    pub erc20: String,
+    pub denom: String,
+    pub fx_originated: bool,
+}
+
+/// Scripted result of the next broadcast tx, a non-zero code rejects its messages
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TxResult {
+    pub code: u32,
+    pub raw_log: String,
+}
+
+/// Chain state served by `MockFxChain`, every field can be scripted by the test
+///
+/// Claims are observed as soon as one orchestrator submits them, there is no voting power.
+#[derive(Debug, Clone)]
+pub struct FxChainState {
+    pub chain_id: String,
+    pub block_height: u64,
+    pub params: Params,
+    pub gas_prices: Vec<Coin>,
+    pub simulate_gas_used: u64,
+    pub accounts: HashMap<String, BaseAccount>,
+    pub balances: HashMap<String, Vec<Coin>>,
+    pub supply: Vec<Coin>,
+    pub validators: HashMap<String, Validator>,
+    pub delegate_keys: Vec<DelegateKey>,
+    pub erc20_denoms: Vec<Erc20Denom>,
+    pub current_valset: Option<Valset>,
+    pub valsets: BTreeMap<u64, Valset>,
+    pub valset_confirms: BTreeMap<u64, Vec<MsgValsetConfirm>>,
+    pub batches: Vec<OutgoingTxBatch>,
+    pub batch_confirms: HashMap<(String, u64), Vec<MsgConfirmBatch>>,
+    pub unbatched: Vec<OutgoingTransferTx>,
+    /// last observed event nonce and Ethereum block height of each orchestrator
+    pub event_nonces: HashMap<String, (u64, u64)>,
+    pub last_observed_eth_height: u64,
+    pub tx_results: VecDeque<TxResult>,
+    /// responses of every broadcast tx, in order
+    pub txs: Vec<TxResponse>,
+    /// messages of the successful txs and Msg service calls, in order
+    pub delivered: Vec<Any>,
+    next_tx_id: u64,
+    next_batch_nonce: u64,
+}
+
+impl Default for FxChainState {
+    fn default() -> Self {
+        FxChainState {
+            chain_id: "fxcore".to_string(),
+            block_height: 1,
+            params: Params {
+                gravity_id: "fx-bridge-eth".to_string(),
+                bridge_chain_id: 1,
+                ..Default::default()
+            },
+            gas_prices: vec![Coin {
+                denom: "FX".to_string(),
+                amount: "1".to_string(),
+            }],
+            simulate_gas_used: 100_000,
+            accounts: HashMap::new(),
+            balances: HashMap::new(),
+            supply: Vec::new(),
+            validators: HashMap::new(),
+            delegate_keys: Vec::new(),
+            erc20_denoms: Vec::new(),
+            current_valset: None,
+            valsets: BTreeMap::new(),
+            valset_confirms: BTreeMap::new(),
+            batches: Vec::new(),
+            batch_confirms: HashMap::new(),
+            unbatched: Vec::new(),
+            event_nonces: HashMap::new(),
+            last_observed_eth_height: 0,
+            tx_results: VecDeque::new(),
+            txs: Vec::new(),
+            delivered: Vec::new(),
+            next_tx_id: 1,
+            next_batch_nonce: 1,
+        }
+    }
+}
+
+impl FxChainState {
+    /// Create the account if it does not exist, returns its account number
+    pub fn add_account(&mut self, address: FxAddress) -> u64 {
+        let account_number = self.accounts.len() as u64 + 1;
+        self.accounts
+            .entry(address.to_string())
+            .or_insert_with(|| BaseAccount {
+                address: address.to_string(),
+                account_number,
+                ..Default::default()
+            })
+            .account_number
+    }
+
+    pub fn set_balance(&mut self, address: FxAddress, denom: &str, amount: &str) {
+        let balances = self.balances.entry(address.to_string()).or_default();
+        balances.retain(|coin| coin.denom != denom);
+        balances.push(Coin {
+            denom: denom.to_string(),
+            amount: amount.to_string(),
+        });
+    }
+
+    /// Register a bonded validator with its orchestrator and a funded orchestrator account
+    pub fn add_orchestrator(&mut self, validator: &str, orchestrator: FxAddress, eth_address: &str) {
+        self.validators.insert(
+            validator.to_string(),
+            Validator {
+                operator_address: validator.to_string(),
+                status: BondStatus::Bonded as i32,
+                ..Default::default()
+            },
+        );
+        self.delegate_keys.retain(|key| key.validator != validator);
+        self.delegate_keys.push(DelegateKey {
+            validator: validator.to_string(),
+            orchestrator: orchestrator.to_string(),
+            eth_address: eth_address.to_string(),
+        });
+        self.add_account(orchestrator);
+        let fee_denom = self.gas_prices.first().map(|coin| coin.denom.clone()).unwrap_or_default();
+        self.set_balance(orchestrator, fee_denom.as_str(), "100000000000000000000");
+    }
+
+    /// Add a valset request, which also becomes the current valset
+    pub fn add_valset(&mut self, valset: Valset) {
+        self.current_valset = Some(valset.clone());
+        self.valsets.insert(valset.nonce, valset);
+    }
+
+    /// Add an outgoing batch, its nonce must be above the batches added so far
+    pub fn add_batch(&mut self, batch: OutgoingTxBatch) {
+        self.next_batch_nonce = self.next_batch_nonce.max(batch.batch_nonce + 1);
+        self.batches.push(batch);
+    }
+
+    pub fn push_tx_result(&mut self, code: u32, raw_log: &str) {
+        self.tx_results.push_back(TxResult { code, raw_log: raw_log.to_string() });
+    }
+
+    /// Delivered messages of one type, decoded
+    pub fn delivered_msgs<M: Message + Default>(&self, type_url: &str) -> Vec<M> {
+        self.delivered
+            .iter()
+            .filter(|msg| msg.type_url == type_url)
+            .filter_map(|msg| M::decode(msg.value.as_slice()).ok())
+            .collect()
+    }
+
+    /// Add `amount` to the balance of `address` and to the supply of `denom`
+    pub fn mint(&mut self, address: &str, denom: &str, amount: u128) {
+        fn add(coins: &mut Vec<Coin>, denom: &str, amount: u128) {
+            match coins.iter_mut().find(|coin| coin.denom == denom) {
+                Some(coin) => coin.amount = (coin.amount.parse::<u128>().unwrap_or_default() + amount).to_string(),
+                None => coins.push(Coin {
+                    denom: denom.to_string(),
+                    amount: amount.to_string(),
+                }),
+            }
+        }
+        add(self.balances.entry(address.to_string()).or_default(), denom, amount);
+        add(&mut self.supply, denom, amount);
+    }
+
+    /// Remove `amount` from the balance of `address` and from the supply of `denom`
+    pub fn burn(&mut self, address: &str, denom: &str, amount: u128) -> Result<(), String> {
+        let balance = self.balances.get_mut(address).and_then(|coins| coins.iter_mut().find(|coin| coin.denom == denom));
+        let balance = balance.ok_or_else(|| format!("{} has no {}", address, denom))?;
+        let available = parse_amount(balance.amount.as_str())?;
+        if available < amount {
+            return Err(format!("insufficient funds, {}{} < {}{}", available, denom, amount, denom));
+        }
+        balance.amount = (available - amount).to_string();
+        if let Some(supply) = self.supply.iter_mut().find(|coin| coin.denom == denom) {
+            supply.amount = parse_amount(supply.amount.as_str())?.saturating_sub(amount).to_string();
+        }
+        Ok(())
+    }
+
+    fn denom_of_erc20(&self, erc20: &str) -> Option<String> {
+        self.erc20_denoms.iter().find(|token| token.erc20.eq_ignore_ascii_case(erc20)).map(|token| token.denom.clone())
+    }
+
+    fn erc20_of_denom(&self, denom: &str) -> Result<String, String> {
+        self.erc20_denoms
+            .iter()
+            .find(|token| token.denom == denom)
+            .map(|token| token.erc20.clone())
+            .ok_or_else(|| format!("denom {} is not bridged", denom))
+    }
+
+    /// Record the claim of `orchestrator`, returns true if no orchestrator claimed `event_nonce` before
+    fn observe_claim(&mut self, orchestrator: &str, event_nonce: u64, block_height: u64) -> Result<bool, String> {
+        let (last_event_nonce, _) = self.event_nonces.get(orchestrator).cloned().unwrap_or_default();
+        if event_nonce != last_event_nonce + 1 {
+            return Err(format!("non contiguous event nonce, expected {}, got {}", last_event_nonce + 1, event_nonce));
+        }
+        let first = self.event_nonces.values().all(|(nonce, _)| *nonce < event_nonce);
+        self.event_nonces.insert(orchestrator.to_string(), (event_nonce, block_height));
+        self.last_observed_eth_height = self.last_observed_eth_height.max(block_height);
+        Ok(first)
+    }
+
+    fn deliver(&mut self, msg: &Any) -> Result<(), String> {
+        fn decode<M: Message + Default>(msg: &Any) -> Result<M, String> {
+            M::decode(msg.value.as_slice()).map_err(|err| format!("invalid {}: {}", msg.type_url, err))
+        }
+        match msg.type_url.as_str() {
+            "/fx.gravity.v1.MsgValsetConfirm" => {
+                let confirm: MsgValsetConfirm = decode(msg)?;
+                if !self.valsets.contains_key(&confirm.nonce) {
+                    return Err(format!("no valset request {}", confirm.nonce));
+                }
+                self.valset_confirms.entry(confirm.nonce).or_default().push(confirm);
+            }
+            "/fx.gravity.v1.MsgConfirmBatch" => {
+                let confirm: MsgConfirmBatch = decode(msg)?;
+                let key = (confirm.token_contract.clone(), confirm.nonce);
+                if !self.batches.iter().any(|batch| (batch.token_contract.clone(), batch.batch_nonce) == key) {
+                    return Err(format!("no batch {} of {}", confirm.nonce, confirm.token_contract));
+                }
+                self.batch_confirms.entry(key).or_default().push(confirm);
+            }
+            "/fx.gravity.v1.MsgDepositClaim" => {
+                let claim: MsgDepositClaim = decode(msg)?;
+                let first = self.observe_claim(claim.orchestrator.as_str(), claim.event_nonce, claim.block_height)?;
+                if let (true, Some(denom)) = (first, self.denom_of_erc20(claim.token_contract.as_str())) {
+                    self.mint(claim.fx_receiver.as_str(), denom.as_str(), parse_amount(claim.amount.as_str())?);
+                }
+            }
+            "/fx.gravity.v1.MsgWithdrawClaim" => {
+                let claim: MsgWithdrawClaim = decode(msg)?;
+                self.observe_claim(claim.orchestrator.as_str(), claim.event_nonce, claim.block_height)?;
+                self.batches
+                    .retain(|batch| !(batch.token_contract.eq_ignore_ascii_case(claim.token_contract.as_str()) && batch.batch_nonce <= claim.batch_nonce));
+            }
+            "/fx.gravity.v1.MsgFxOriginatedTokenClaim" => {
+                let claim: MsgFxOriginatedTokenClaim = decode(msg)?;
+                self.observe_claim(claim.orchestrator.as_str(), claim.event_nonce, claim.block_height)?;
+            }
+            "/fx.gravity.v1.MsgValsetUpdatedClaim" => {
+                let claim: MsgValsetUpdatedClaim = decode(msg)?;
+                self.observe_claim(claim.orchestrator.as_str(), claim.event_nonce, claim.block_height)?;
+            }
+            "/fx.gravity.v1.MsgSendToEth" => {
+                let send: MsgSendToEth = decode(msg)?;
+                let (amount, fee) = match (send.amount, send.bridge_fee) {
+                    (Some(amount), Some(fee)) => (amount, fee),
+                    _ => return Err("amount and bridge fee are required".to_string()),
+                };
+                let contract = self.erc20_of_denom(amount.denom.as_str())?;
+                let total = parse_amount(amount.amount.as_str())? + parse_amount(fee.amount.as_str())?;
+                self.burn(send.sender.as_str(), amount.denom.as_str(), total)?;
+                self.unbatched.push(OutgoingTransferTx {
+                    id: self.next_tx_id,
+                    sender: send.sender,
+                    dest_address: send.eth_dest,
+                    erc20_token: Some(Erc20Token {
+                        contract: contract.clone(),
+                        amount: amount.amount,
+                    }),
+                    erc20_fee: Some(Erc20Token { contract, amount: fee.amount }),
+                });
+                self.next_tx_id += 1;
+            }
+            "/fx.gravity.v1.MsgRequestBatch" => {
+                let request: MsgRequestBatch = decode(msg)?;
+                let contract = self.erc20_of_denom(request.denom.as_str())?;
+                let (transactions, unbatched) = self
+                    .unbatched
+                    .drain(..)
+                    .partition(|tx| tx.erc20_token.as_ref().map(|token| token.contract == contract).unwrap_or(false));
+                self.unbatched = unbatched;
+                let transactions: Vec<OutgoingTransferTx> = transactions;
+                if transactions.is_empty() {
+                    return Err(format!("no unbatched transfers of {}", request.denom));
+                }
+                let batch_nonce = self.next_batch_nonce;
+                self.add_batch(OutgoingTxBatch {
+                    batch_nonce,
+                    batch_timeout: 0,
+                    transactions,
+                    token_contract: contract,
+                    block: self.block_height,
+                    fee_receive: request.fee_receive,
+                });
+            }
+            "/fx.gravity.v1.MsgCancelSendToEth" => {
+                let cancel: MsgCancelSendToEth = decode(msg)?;
+                let position = self.unbatched.iter().position(|tx| tx.id == cancel.transaction_id && tx.sender == cancel.sender);
+                let position = position.ok_or_else(|| format!("no unbatched transfer {} of {}", cancel.transaction_id, cancel.sender))?;
+                let transfer = self.unbatched.remove(position);
+                for erc20 in transfer.erc20_token.iter().chain(transfer.erc20_fee.iter()) {
+                    if let Some(denom) = self.denom_of_erc20(erc20.contract.as_str()) {
+                        self.mint(transfer.sender.as_str(), denom.as_str(), parse_amount(erc20.amount.as_str())?);
+                    }
+                }
+            }
+            "/fx.gravity.v1.MsgSetOrchestratorAddress" => {
+                let set: MsgSetOrchestratorAddress = decode(msg)?;
+                self.delegate_keys.retain(|key| key.validator != set.validator);
+                self.delegate_keys.push(DelegateKey {
+                    validator: set.validator,
+                    orchestrator: set.orchestrator,
+                    eth_address: set.eth_address,
+                });
+            }
+            type_url => return Err(format!("unknown message type {}", type_url)),
+        }
+        self.delivered.push(msg.clone());
+        Ok(())
+    }
+
+    /// Deliver all messages or none of them
+    fn deliver_all(&mut self, msgs: &[Any]) -> Result<(), String> {
+        let mut next = self.clone();
+        for msg in msgs.iter() {
+            next.deliver(msg)?;
+        }
+        *self = next;
+        Ok(())
+    }
+
+    fn deliver_msg<M: Message>(&mut self, msg: M, type_url: &str) -> Result<(), Status> {
+        self.deliver_all(&[msg.to_any(type_url)]).map_err(Status::invalid_argument)
+    }
+
+    fn delegate_key(&self, matches: impl Fn(&DelegateKey) -> bool) -> Result<DelegateKey, Status> {
+        self.delegate_keys.iter().find(|key| matches(key)).cloned().ok_or_else(|| Status::not_found("no delegate key"))
+    }
+
+    /* ===== cosmos ===== */
+
+    fn account(&self, request: QueryAccountRequest) -> Result<QueryAccountResponse, Status> {
+        let account = self.accounts.get(&request.address).ok_or_else(|| Status::not_found(format!("account {} not found", request.address)))?;
+        Ok(QueryAccountResponse {
+            account: Some(account.to_any("/cosmos.auth.v1beta1.BaseAccount")),
+        })
+    }
+
+    fn balance(&self, request: QueryBalanceRequest) -> Result<QueryBalanceResponse, Status> {
+        let amount = self
+            .balances
+            .get(&request.address)
+            .and_then(|coins| coins.iter().find(|coin| coin.denom == request.denom))
+            .map(|coin| coin.amount.clone());
+        Ok(QueryBalanceResponse {
+            balance: Some(Coin {
+                denom: request.denom,
+                amount: amount.unwrap_or_else(|| "0".to_string()),
+            }),
+        })
+    }
+
+    fn all_balances(&self, request: QueryAllBalancesRequest) -> Result<QueryAllBalancesResponse, Status> {
+        Ok(QueryAllBalancesResponse {
+            balances: self.balances.get(&request.address).cloned().unwrap_or_default(),
+            pagination: None,
+        })
+    }
+
+    fn supply_of(&self, request: QuerySupplyOfRequest) -> Result<QuerySupplyOfResponse, Status> {
+        let amount = self.supply.iter().find(|coin| coin.denom == request.denom).map(|coin| coin.amount.clone());
+        Ok(QuerySupplyOfResponse {
+            amount: Some(Coin {
+                denom: request.denom,
+                amount: amount.unwrap_or_else(|| "0".to_string()),
+            }),
+        })
+    }
+
+    fn total_supply(&self, _: QueryTotalSupplyRequest) -> Result<QueryTotalSupplyResponse, Status> {
+        Ok(QueryTotalSupplyResponse {
+            supply: self.supply.clone(),
+            pagination: None,
+        })
+    }
+
+    fn validator(&self, request: QueryValidatorRequest) -> Result<QueryValidatorResponse, Status> {
+        let validator = self
+            .validators
+            .get(&request.validator_addr)
+            .ok_or_else(|| Status::not_found(format!("validator {} not found", request.validator_addr)))?;
+        Ok(QueryValidatorResponse { validator: Some(validator.clone()) })
+    }
+
+    fn latest_block(&self, _: GetLatestBlockRequest) -> Result<GetLatestBlockResponse, Status> {
+        Ok(GetLatestBlockResponse {
+            block_id: None,
+            block: Some(Block {
+                header: Some(Header {
+                    chain_id: self.chain_id.clone(),
+                    height: self.block_height as i64,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+        })
+    }
+
+    fn gas_price(&self, _: GasPriceRequest) -> Result<GasPriceResponse, Status> {
+        Ok(GasPriceResponse { gas_prices: self.gas_prices.clone() })
+    }
+
+    #[allow(deprecated)]
+    fn simulate(&self, request: SimulateRequest) -> Result<SimulateResponse, Status> {
+        if request.tx.is_none() && request.tx_bytes.is_empty() {
+            return Err(Status::invalid_argument("empty tx"));
+        }
+        Ok(SimulateResponse {
+            gas_info: Some(GasInfo {
+                gas_wanted: self.simulate_gas_used,
+                gas_used: self.simulate_gas_used,
+            }),
+            result: None,
+        })
+    }
+
+    fn broadcast_tx(&mut self, request: BroadcastTxRequest) -> Result<BroadcastTxResponse, Status> {
+        let invalid = |err: prost::DecodeError| Status::invalid_argument(format!("invalid tx: {}", err));
+        let tx_raw = TxRaw::decode(request.tx_bytes.as_slice()).map_err(invalid)?;
+        let body = TxBody::decode(tx_raw.body_bytes.as_slice()).map_err(invalid)?;
+        let auth_info = AuthInfo::decode(tx_raw.auth_info_bytes.as_slice()).map_err(invalid)?;
+        let signer_info = auth_info.signer_infos.first().ok_or_else(|| Status::invalid_argument("tx has no signer"))?;
+        let public_key = signer_info.public_key.as_ref().ok_or_else(|| Status::invalid_argument("tx has no signer public key"))?;
+        let signer = PublicKey::try_from(public_key)
+            .map_err(|err| Status::invalid_argument(format!("invalid public key: {}", err)))?
+            .to_address()
+            .to_string();
+
+        self.block_height += 1;
+        let account = self.accounts.get_mut(&signer).ok_or_else(|| Status::not_found(format!("account {} not found", signer)))?;
+        let (code, raw_log) = if signer_info.sequence != account.sequence {
+            (CODE_WRONG_SEQUENCE, format!("account sequence mismatch, expected {}, got {}", account.sequence, signer_info.sequence))
+        } else {
+            account.sequence += 1;
+            match self.tx_results.pop_front() {
+                Some(result) if result.code != 0 => (result.code, result.raw_log),
+                _ => match self.deliver_all(body.messages.as_slice()) {
+                    Ok(()) => (0, "[]".to_string()),
+                    Err(raw_log) => (CODE_MSG_FAILED, raw_log),
+                },
+            }
+        };
+        let tx_response = TxResponse {
+            height: self.block_height as i64,
+            txhash: hex::encode_upper(Sha256::digest(&request.tx_bytes)),
+            code,
+            raw_log,
+            gas_wanted: self.simulate_gas_used as i64,
+            gas_used: self.simulate_gas_used as i64,
+            ..Default::default()
+        };
+        self.txs.push(tx_response.clone());
+        Ok(BroadcastTxResponse { tx_response: Some(tx_response) })
+    }
+
+    /* ===== gravity ===== */
+
+    fn params(&self, _: QueryParamsRequest) -> Result<QueryParamsResponse, Status> {
+        Ok(QueryParamsResponse { params: Some(self.params.clone()) })
+    }
+
+    fn current_valset(&self, _: QueryCurrentValsetRequest) -> Result<QueryCurrentValsetResponse, Status> {
+        Ok(QueryCurrentValsetResponse { valset: self.current_valset.clone() })
+    }
+
+    fn valset_request(&self, request: QueryValsetRequestRequest) -> Result<QueryValsetRequestResponse, Status> {
+        Ok(QueryValsetRequestResponse {
+            valset: self.valsets.get(&request.nonce).cloned(),
+        })
+    }
+
+    fn valset_confirm(&self, request: QueryValsetConfirmRequest) -> Result<QueryValsetConfirmResponse, Status> {
+        let confirms = self.valset_confirms.get(&request.nonce).cloned().unwrap_or_default();
+        Ok(QueryValsetConfirmResponse {
+            confirm: confirms.into_iter().find(|confirm| confirm.orchestrator == request.address),
+        })
+    }
+
+    fn valset_confirms_by_nonce(&self, request: QueryValsetConfirmsByNonceRequest) -> Result<QueryValsetConfirmsByNonceResponse, Status> {
+        Ok(QueryValsetConfirmsByNonceResponse {
+            confirms: self.valset_confirms.get(&request.nonce).cloned().unwrap_or_default(),
+        })
+    }
+
+    fn last_valset_requests(&self, _: QueryLastValsetRequestsRequest) -> Result<QueryLastValsetRequestsResponse, Status> {
+        Ok(QueryLastValsetRequestsResponse {
+            valsets: self.valsets.values().rev().take(5).cloned().collect(),
+        })
+    }
+
+    fn last_pending_valset_request_by_addr(&self, request: QueryLastPendingValsetRequestByAddrRequest) -> Result<QueryLastPendingValsetRequestByAddrResponse, Status> {
+        let confirmed = |nonce: &u64| {
+            self.valset_confirms
+                .get(nonce)
+                .map(|confirms| confirms.iter().any(|confirm| confirm.orchestrator == request.address))
+                .unwrap_or(false)
+        };
+        Ok(QueryLastPendingValsetRequestByAddrResponse {
+            valsets: self.valsets.iter().filter(|(nonce, _)| !confirmed(nonce)).map(|(_, valset)| valset.clone()).collect(),
+        })
+    }
+
+    fn last_pending_batch_request_by_addr(&self, request: QueryLastPendingBatchRequestByAddrRequest) -> Result<QueryLastPendingBatchRequestByAddrResponse, Status> {
+        let confirmed = |batch: &OutgoingTxBatch| {
+            self.batch_confirms
+                .get(&(batch.token_contract.clone(), batch.batch_nonce))
+                .map(|confirms| confirms.iter().any(|confirm| confirm.orchestrator == request.address))
+                .unwrap_or(false)
+        };
+        let mut pending: Vec<&OutgoingTxBatch> = self.batches.iter().filter(|batch| !confirmed(batch)).collect();
+        pending.sort_by_key(|batch| batch.batch_nonce);
+        Ok(QueryLastPendingBatchRequestByAddrResponse {
+            batch: pending.first().cloned().cloned(),
+        })
+    }
+
+    fn last_event_nonce_by_addr(&self, request: QueryLastEventNonceByAddrRequest) -> Result<QueryLastEventNonceByAddrResponse, Status> {
+        Ok(QueryLastEventNonceByAddrResponse {
+            event_nonce: self.event_nonces.get(&request.address).map(|(nonce, _)| *nonce).unwrap_or_default(),
+        })
+    }
+
+    fn last_event_block_height_by_addr(&self, request: QueryLastEventBlockHeightByAddrRequest) -> Result<QueryLastEventBlockHeightByAddrResponse, Status> {
+        Ok(QueryLastEventBlockHeightByAddrResponse {
+            block_height: self.event_nonces.get(&request.address).map(|(_, height)| *height).unwrap_or_default(),
+        })
+    }
+
+    fn batch_fees(&self, _: QueryBatchFeeRequest) -> Result<QueryBatchFeeResponse, Status> {
+        let mut batch_fees: Vec<BatchFees> = Vec::new();
+        for fee in self.unbatched.iter().filter_map(|tx| tx.erc20_fee.as_ref()) {
+            let amount: u128 = fee.amount.parse().map_err(|_| Status::internal(format!("invalid fee amount {}", fee.amount)))?;
+            match batch_fees.iter_mut().find(|fees| fees.token_contract == fee.contract) {
+                Some(fees) => {
+                    let total: u128 = fees.total_fees.parse().unwrap_or_default();
+                    fees.total_fees = (total + amount).to_string();
+                    fees.total_txs += 1;
+                }
+                None => batch_fees.push(BatchFees {
+                    token_contract: fee.contract.clone(),
+                    total_fees: amount.to_string(),
+                    total_txs: 1,
+                }),
+            }
+        }
+        Ok(QueryBatchFeeResponse { batch_fees })
+    }
+
+    fn last_observed_eth_block_height(&self, _: QueryLastObservedEthBlockHeightRequest) -> Result<QueryLastObservedEthBlockHeightResponse, Status> {
+        Ok(QueryLastObservedEthBlockHeightResponse {
+            block_height: self.last_observed_eth_height,
+        })
+    }
+
+    fn outgoing_tx_batches(&self, _: QueryOutgoingTxBatchesRequest) -> Result<QueryOutgoingTxBatchesResponse, Status> {
+        Ok(QueryOutgoingTxBatchesResponse { batches: self.batches.clone() })
+    }
+
+    fn batch_request_by_nonce(&self, request: QueryBatchRequestByNonceRequest) -> Result<QueryBatchRequestByNonceResponse, Status> {
+        Ok(QueryBatchRequestByNonceResponse {
+            batch: self
+                .batches
+                .iter()
+                .find(|batch| batch.batch_nonce == request.nonce && batch.token_contract.eq_ignore_ascii_case(request.contract_address.as_str()))
+                .cloned(),
+        })
+    }
+
+    fn batch_confirm(&self, request: QueryBatchConfirmRequest) -> Result<QueryBatchConfirmResponse, Status> {
+        let confirms = self.batch_confirms.get(&(request.contract_address, request.nonce)).cloned().unwrap_or_default();
+        Ok(QueryBatchConfirmResponse {
+            confirm: confirms.into_iter().find(|confirm| confirm.orchestrator == request.address),
+        })
+    }
+
+    fn batch_confirms(&self, request: QueryBatchConfirmsRequest) -> Result<QueryBatchConfirmsResponse, Status> {
+        Ok(QueryBatchConfirmsResponse {
+            confirms: self.batch_confirms.get(&(request.contract_address, request.nonce)).cloned().unwrap_or_default(),
+        })
+    }
+
+    fn erc20_to_denom(&self, request: QueryErc20ToDenomRequest) -> Result<QueryErc20ToDenomResponse, Status> {
+        let token = self.erc20_denoms.iter().find(|token| token.erc20.eq_ignore_ascii_case(request.erc20.as_str()));
+        let token = token.ok_or_else(|| Status::not_found(format!("erc20 {} is not bridged", request.erc20)))?;
+        Ok(QueryErc20ToDenomResponse {
+            denom: token.denom.clone(),
+            fx_originated: token.fx_originated,
+        })
+    }
+
+    fn denom_to_erc20(&self, request: QueryDenomToErc20Request) -> Result<QueryDenomToErc20Response, Status> {
+        let token = self.erc20_denoms.iter().find(|token| token.denom == request.denom);
+        let token = token.ok_or_else(|| Status::not_found(format!("denom {} is not bridged", request.denom)))?;
+        Ok(QueryDenomToErc20Response {
+            erc20: token.erc20.clone(),
+            fx_originated: token.fx_originated,
+        })
+    }
+
+    fn delegate_key_by_validator(&self, request: QueryDelegateKeyByValidatorRequest) -> Result<QueryDelegateKeyByValidatorResponse, Status> {
+        let key = self.delegate_key(|key| key.validator == request.validator_address)?;
+        Ok(QueryDelegateKeyByValidatorResponse {
+            eth_address: key.eth_address,
+            orchestrator_address: key.orchestrator,
+        })
+    }
+
+    fn delegate_key_by_eth(&self, request: QueryDelegateKeyByEthRequest) -> Result<QueryDelegateKeyByEthResponse, Status> {
+        let key = self.delegate_key(|key| key.eth_address.eq_ignore_ascii_case(request.eth_address.as_str()))?;
+        Ok(QueryDelegateKeyByEthResponse {
+            validator_address: key.validator,
+            orchestrator_address: key.orchestrator,
+        })
+    }
+
+    fn delegate_key_by_orchestrator(&self, request: QueryDelegateKeyByOrchestratorRequest) -> Result<QueryDelegateKeyByOrchestratorResponse, Status> {
+        let key = self.delegate_key(|key| key.orchestrator == request.orchestrator_address)?;
+        Ok(QueryDelegateKeyByOrchestratorResponse {
+            validator_address: key.validator,
+            eth_address: key.eth_address,
+        })
+    }
+
+    fn pending_send_to_eth(&self, request: QueryPendingSendToEthRequest) -> Result<QueryPendingSendToEthResponse, Status> {
+        let by_sender = |tx: &&OutgoingTransferTx| tx.sender == request.sender_address;
+        Ok(QueryPendingSendToEthResponse {
+            transfers_in_batches: self.batches.iter().flat_map(|batch| batch.transactions.iter()).filter(by_sender).cloned().collect(),
+            unbatched_transfers: self.unbatched.iter().filter(by_sender).cloned().collect(),
+        })
+    }
+}
+
+fn parse_amount(amount: &str) -> Result<u128, String> {
+    amount.parse().map_err(|_| format!("invalid amount {}", amount))
+}
+
+/// In-process f(x)Core gRPC node serving a scriptable `FxChainState`
+pub struct MockFxChain {
+    addr: SocketAddr,
+    state: Arc<Mutex<FxChainState>>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl MockFxChain {
+    /// Serve `state` on a free local port, must be called from a tokio runtime
+    pub async fn start(state: FxChainState) -> Result<MockFxChain> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(state));
+        let service_state = state.clone();
+        let make_service = make_service_fn(move |_| {
+            let state = service_state.clone();
+            async move { Ok::<_, Infallible>(service_fn(move |request| route(state.clone(), request))) }
+        });
+        let (shutdown, shutdown_rx) = oneshot::channel::<()>();
+        let server = hyper::Server::from_tcp(listener)?.http2_only(true).serve(make_service).with_graceful_shutdown(async {
+            shutdown_rx.await.ok();
+        });
+        tokio::spawn(async move {
+            if let Err(err) = server.await {
+                error!("Mock fx chain server error: {}", err);
+            }
+        });
+        debug!("Mock fx chain listening on {}", addr);
+        Ok(MockFxChain {
+            addr,
+            state,
+            shutdown: Some(shutdown),
+        })
+    }
+
+    /// gRPC url for `new_grpc_channel`
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Lock the state to script it or assert on it
+    pub fn state(&self) -> MutexGuard<'_, FxChainState> {
+        self.state.lock().unwrap()
+    }
+}
+
+impl Drop for MockFxChain {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            shutdown.send(()).ok();
+        }
+    }
+}
+
+/// Adapts a state handler to a tonic unary service
+struct Handler<F>(F);
+
+impl<F, Req, Resp> UnaryService<Req> for Handler<F>
+where
+    F: FnMut(Req) -> Result<Resp, Status>,
+{
+    type Response = Resp;
+    type Future = Ready<Result<tonic::Response<Resp>, Status>>;
+
+    fn call(&mut self, request: tonic::Request<Req>) -> Self::Future {
+        ready((self.0)(request.into_inner()).map(tonic::Response::new))
+    }
+}
+
+async fn unary<Req, Resp, F>(request: http::Request<Body>, handler: F) -> http::Response<BoxBody>
+where
+    Req: Message + Default + Send + 'static,
+    Resp: Message + Send + 'static,
+    F: FnMut(Req) -> Result<Resp, Status> + Send,
+{
+    let mut grpc = Grpc::new(ProstCodec::<Resp, Req>::default());
+    grpc.unary(Handler(handler), request).await
+}
+
+fn unimplemented() -> http::Response<BoxBody> {
+    http::Response::builder()
+        .status(200)
+        .header("grpc-status", "12")
+        .header("content-type", "application/grpc")
+        .body(tonic::body::empty_body())
+        .unwrap()
+}
+
+async fn route(state: Arc<Mutex<FxChainState>>, request: http::Request<Body>) -> Result<http::Response<BoxBody>, Infallible> {
+    let lock = || state.lock().unwrap();
+    let path = request.uri().path().to_string();
+    debug!("Mock fx chain call {}", path);
+    let response = match path.as_str() {
+        "/cosmos.auth.v1beta1.Query/Account" => unary(request, |r| lock().account(r)).await,
+        "/cosmos.bank.v1beta1.Query/Balance" => unary(request, |r| lock().balance(r)).await,
+        "/cosmos.bank.v1beta1.Query/AllBalances" => unary(request, |r| lock().all_balances(r)).await,
+        "/cosmos.bank.v1beta1.Query/SupplyOf" => unary(request, |r| lock().supply_of(r)).await,
+        "/cosmos.bank.v1beta1.Query/TotalSupply" => unary(request, |r| lock().total_supply(r)).await,
+        "/cosmos.staking.v1beta1.Query/Validator" => unary(request, |r| lock().validator(r)).await,
+        "/cosmos.base.tendermint.v1beta1.Service/GetLatestBlock" => unary(request, |r| lock().latest_block(r)).await,
+        "/cosmos.tx.v1beta1.Service/Simulate" => unary(request, |r| lock().simulate(r)).await,
+        "/cosmos.tx.v1beta1.Service/BroadcastTx" => unary(request, |r| lock().broadcast_tx(r)).await,
+        "/fx.other.Query/GasPrice" => unary(request, |r| lock().gas_price(r)).await,
+
+        "/fx.gravity.v1.Query/Params" => unary(request, |r| lock().params(r)).await,
+        "/fx.gravity.v1.Query/CurrentValset" => unary(request, |r| lock().current_valset(r)).await,
+        "/fx.gravity.v1.Query/ValsetRequest" => unary(request, |r| lock().valset_request(r)).await,
+        "/fx.gravity.v1.Query/ValsetConfirm" => unary(request, |r| lock().valset_confirm(r)).await,
+        "/fx.gravity.v1.Query/ValsetConfirmsByNonce" => unary(request, |r| lock().valset_confirms_by_nonce(r)).await,
+        "/fx.gravity.v1.Query/LastValsetRequests" => unary(request, |r| lock().last_valset_requests(r)).await,
+        "/fx.gravity.v1.Query/LastPendingValsetRequestByAddr" => unary(request, |r| lock().last_pending_valset_request_by_addr(r)).await,
+        "/fx.gravity.v1.Query/LastPendingBatchRequestByAddr" => unary(request, |r| lock().last_pending_batch_request_by_addr(r)).await,
+        "/fx.gravity.v1.Query/LastEventNonceByAddr" => unary(request, |r| lock().last_event_nonce_by_addr(r)).await,
+        "/fx.gravity.v1.Query/LastEventBlockHeightByAddr" => unary(request, |r| lock().last_event_block_height_by_addr(r)).await,
+        "/fx.gravity.v1.Query/BatchFees" => unary(request, |r| lock().batch_fees(r)).await,
+        "/fx.gravity.v1.Query/LastObservedEthBlockHeight" => unary(request, |r| lock().last_observed_eth_block_height(r)).await,
+        "/fx.gravity.v1.Query/OutgoingTxBatches" => unary(request, |r| lock().outgoing_tx_batches(r)).await,
+        "/fx.gravity.v1.Query/BatchRequestByNonce" => unary(request, |r| lock().batch_request_by_nonce(r)).await,
+        "/fx.gravity.v1.Query/BatchConfirm" => unary(request, |r| lock().batch_confirm(r)).await,
+        "/fx.gravity.v1.Query/BatchConfirms" => unary(request, |r| lock().batch_confirms(r)).await,
+        "/fx.gravity.v1.Query/ERC20ToDenom" => unary(request, |r| lock().erc20_to_denom(r)).await,
+        "/fx.gravity.v1.Query/DenomToERC20" => unary(request, |r| lock().denom_to_erc20(r)).await,
+        "/fx.gravity.v1.Query/GetDelegateKeyByValidator" => unary(request, |r| lock().delegate_key_by_validator(r)).await,
+        "/fx.gravity.v1.Query/GetDelegateKeyByEth" => unary(request, |r| lock().delegate_key_by_eth(r)).await,
+        "/fx.gravity.v1.Query/GetDelegateKeyByOrchestrator" => unary(request, |r| lock().delegate_key_by_orchestrator(r)).await,
+        "/fx.gravity.v1.Query/GetPendingSendToEth" => unary(request, |r| lock().pending_send_to_eth(r)).await,
+
+        "/fx.gravity.v1.Msg/ValsetConfirm" => {
+            unary(request, |msg: MsgValsetConfirm| {
+                lock().deliver_msg(msg, "/fx.gravity.v1.MsgValsetConfirm").map(|_| MsgValsetConfirmResponse {})
+            })
+            .await
+        }
+        "/fx.gravity.v1.Msg/SendToEth" => unary(request, |msg: MsgSendToEth| lock().deliver_msg(msg, "/fx.gravity.v1.MsgSendToEth").map(|_| MsgSendToEthResponse {})).await,
+        "/fx.gravity.v1.Msg/RequestBatch" => {
+            unary(request, |msg: MsgRequestBatch| {
+                lock().deliver_msg(msg, "/fx.gravity.v1.MsgRequestBatch").map(|_| MsgRequestBatchResponse {})
+            })
+            .await
+        }
+        "/fx.gravity.v1.Msg/ConfirmBatch" => {
+            unary(request, |msg: MsgConfirmBatch| {
+                lock().deliver_msg(msg, "/fx.gravity.v1.MsgConfirmBatch").map(|_| MsgConfirmBatchResponse {})
+            })
+            .await
+        }
+        "/fx.gravity.v1.Msg/DepositClaim" => {
+            unary(request, |msg: MsgDepositClaim| {
+                lock().deliver_msg(msg, "/fx.gravity.v1.MsgDepositClaim").map(|_| MsgDepositClaimResponse {})
+            })
+            .await
+        }
+        "/fx.gravity.v1.Msg/WithdrawClaim" => {
+            unary(request, |msg: MsgWithdrawClaim| {
+                lock().deliver_msg(msg, "/fx.gravity.v1.MsgWithdrawClaim").map(|_| MsgWithdrawClaimResponse {})
+            })
+            .await
+        }
+        "/fx.gravity.v1.Msg/ValsetUpdateClaim" => {
+            unary(request, |msg: MsgValsetUpdatedClaim| {
+                lock().deliver_msg(msg, "/fx.gravity.v1.MsgValsetUpdatedClaim").map(|_| MsgValsetUpdatedClaimResponse {})
+            })
+            .await
+        }
+        "/fx.gravity.v1.Msg/SetOrchestratorAddress" => {
+            unary(request, |msg: MsgSetOrchestratorAddress| {
+                lock().deliver_msg(msg, "/fx.gravity.v1.MsgSetOrchestratorAddress").map(|_| MsgSetOrchestratorAddressResponse {})
+            })
+            .await
+        }
+        "/fx.gravity.v1.Msg/CancelSendToEth" => {
+            unary(request, |msg: MsgCancelSendToEth| {
+                lock().deliver_msg(msg, "/fx.gravity.v1.MsgCancelSendToEth").map(|_| MsgCancelSendToEthResponse {})
+            })
+            .await
+        }
+        "/fx.gravity.v1.Msg/FxOriginatedTokenClaim" => {
+            unary(request, |msg: MsgFxOriginatedTokenClaim| {
+                lock().deliver_msg(msg, "/fx.gravity.v1.MsgFxOriginatedTokenClaim").map(|_| MsgFxOriginatedTokenClaimResponse {})
+            })
+            .await
+        }
+        _ => {
+            warn!("Mock fx chain does not implement {}", path);
+            unimplemented()
+        }
+    };
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    use bridge::singer_loop::{encode_tx_batch_confirm_hash, encode_valset_confirm_hash, eth_signer_main_loop, set_fx_key_balance_metrics};
+    use ethereum::address::Checksum;
+    use ethereum::private_key::{Key, PrivateKey as EthPrivateKey, Signature};
+    use fxchain::builder::Builder;
+    use fxchain::grpc_client::{check_for_fee_denom, get_account_info, get_chain_id, get_delegate_key_by_validator, get_gas_price_by_denom, new_grpc_channel, send_tx};
+    use fxchain::grpc_client::{get_last_event_block_height_by_addr, get_last_event_nonce};
+    use fxchain::private_key::PrivateKey as FxPrivateKey;
+
+    use super::*;
+
+    const VALIDATOR: &str = "fxvaloper1a73plz6w7fc8ydlwxddanc7a239kk45jnl9xwj";
+
+    fn valset(nonce: u64, eth_address: &str) -> Valset {
+        Valset {
+            nonce,
+            members: vec![BridgeValidator {
+                power: 4294967295,
+                eth_address: eth_address.to_string(),
+            }],
+            height: nonce * 10,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_tx() {
+        let fx_key = FxPrivateKey::random();
+        let orchestrator = fx_key.public_key().to_address();
+        let mut state = FxChainState::default();
+        state.add_orchestrator(VALIDATOR, orchestrator, "0x0000000000000000000000000000000000000001");
+        state.add_valset(valset(1, "0x0000000000000000000000000000000000000001"));
+        state.push_tx_result(5, "insufficient funds");
+        let fx_chain = MockFxChain::start(state).await.unwrap();
+
+        let grpc_channel = new_grpc_channel(fx_chain.url().as_str()).await.unwrap();
+        assert_eq!(get_chain_id(&grpc_channel).await.unwrap().as_str(), "fxcore");
+        let builder = Builder::from_net(&grpc_channel, fx_key, "FX").await.unwrap();
+
+        let confirm = MsgValsetConfirm {
+            nonce: 1,
+            orchestrator: orchestrator.to_string(),
+            ..Default::default()
+        };
+        let msgs = vec![confirm.to_any("/fx.gravity.v1.MsgValsetConfirm")];
+        let tx_response = send_tx(&builder, &grpc_channel, msgs.clone()).await.unwrap();
+        assert_eq!((tx_response.code, tx_response.raw_log.as_str()), (5, "insufficient funds"));
+        assert!(fx_chain.state().valset_confirms.is_empty());
+
+        let tx_response = send_tx(&builder, &grpc_channel, msgs).await.unwrap();
+        assert_eq!(tx_response.code, 0);
+        assert_eq!(fx_chain.state().delivered_msgs::<MsgValsetConfirm>("/fx.gravity.v1.MsgValsetConfirm"), vec![confirm]);
+        assert_eq!(fx_chain.state().accounts[&orchestrator.to_string()].sequence, 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_eth_signer_main_loop() {
+        let fx_key = FxPrivateKey::random();
+        let eth_key = EthPrivateKey::from([7u8; 32]);
+        let eth_address = Key::address(&eth_key).to_hex_string();
+        let orchestrator = fx_key.public_key().to_address();
+        let mut state = FxChainState::default();
+        state.add_orchestrator(VALIDATOR, orchestrator, eth_address.as_str());
+        state.add_valset(valset(1, eth_address.as_str()));
+        state.add_valset(valset(2, eth_address.as_str()));
+        state.add_batch(OutgoingTxBatch {
+            batch_nonce: 1,
+            token_contract: "0xdAC17F958D2ee523a2206206994597C13D831ec7".to_string(),
+            fee_receive: "0x0000000000000000000000000000000000000001".to_string(),
+            ..Default::default()
+        });
+        let fx_chain = MockFxChain::start(state).await.unwrap();
+
+        let grpc_channel = new_grpc_channel(fx_chain.url().as_str()).await.unwrap();
+        let builder = Builder::from_net(&grpc_channel, fx_key, "FX").await.unwrap();
+        let confirmed = async {
+            loop {
+                let state = fx_chain.state();
+                if state.valset_confirms.len() == 2 && state.batch_confirms.len() == 1 {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+        };
+        let signer_loop = eth_signer_main_loop(&builder, &grpc_channel, &eth_key, None, None);
+        tokio::time::timeout(Duration::from_secs(3600), async {
+            tokio::select! {
+                _ = signer_loop => {}
+                _ = confirmed => {}
+            }
+        })
+        .await
+        .unwrap();
+
+        let state = fx_chain.state();
+        assert_eq!(state.valset_confirms.keys().cloned().collect::<Vec<u64>>(), vec![1, 2]);
+        for confirm in state.valset_confirms.values().flatten() {
+            assert_eq!(confirm.eth_address, eth_address);
+            let message = encode_valset_confirm_hash(state.params.gravity_id.clone(), &state.valsets[&confirm.nonce]);
+            let signature = Signature::from_str(confirm.signature.as_str()).unwrap();
+            assert_eq!(signature.recover_ethereum_msg(message.as_slice()).unwrap(), Key::address(&eth_key));
+        }
+        assert_eq!(state.batch_confirms.len(), 1);
+        let confirm = state.batch_confirms.values().flatten().next().unwrap();
+        assert_eq!(confirm.eth_signer, eth_address);
+        let message = encode_tx_batch_confirm_hash(state.params.gravity_id.clone(), state.batches[0].clone());
+        let signature = Signature::from_str(confirm.signature.as_str()).unwrap();
+        assert_eq!(signature.recover_ethereum_msg(message.as_slice()).unwrap(), Key::address(&eth_key));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_eth_signer_main_loop_dry_run() {
+        // neither registered nor funded, a dry run observes anyway
+        let fx_key = FxPrivateKey::random();
+        let eth_key = EthPrivateKey::from([7u8; 32]);
+        let eth_address = Key::address(&eth_key).to_hex_string();
+        let orchestrator = fx_key.public_key().to_address().to_string();
+        let mut state = FxChainState::default();
+        state.add_valset(valset(1, eth_address.as_str()));
+        state.add_valset(valset(2, eth_address.as_str()));
+        state.add_batch(OutgoingTxBatch {
+            batch_nonce: 1,
+            token_contract: "0xdAC17F958D2ee523a2206206994597C13D831ec7".to_string(),
+            fee_receive: "0x0000000000000000000000000000000000000001".to_string(),
+            ..Default::default()
+        });
+        let fx_chain = MockFxChain::start(state).await.unwrap();
+
+        let grpc_channel = new_grpc_channel(fx_chain.url().as_str()).await.unwrap();
+        let builder = Builder::from_net_dry_run(&grpc_channel, fx_key, "FX").await.unwrap();
+        let msgs = vec![MsgValsetConfirm::default().to_any("/fx.gravity.v1.MsgValsetConfirm")];
+        assert!(send_tx(&builder, &grpc_channel, msgs).await.is_err());
+
+        let dry_run_msgs = |type_url: &str| prometheus::metrics::DRY_RUN_MSGS.with_label_values(&[orchestrator.as_str(), type_url]).get();
+        let observed = async {
+            while dry_run_msgs("/fx.gravity.v1.MsgConfirmBatch") < 1.0 {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+            // later loops skip the nonces already signed
+            tokio::time::sleep(Duration::from_secs(60)).await;
+        };
+        let signer_loop = eth_signer_main_loop(&builder, &grpc_channel, &eth_key, None, None);
+        tokio::time::timeout(Duration::from_secs(3600), async {
+            tokio::select! {
+                _ = signer_loop => {}
+                _ = observed => {}
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(dry_run_msgs("/fx.gravity.v1.MsgValsetConfirm"), 2.0);
+        assert_eq!(dry_run_msgs("/fx.gravity.v1.MsgConfirmBatch"), 1.0);
+        let state = fx_chain.state();
+        assert!(state.txs.is_empty());
+        assert!(state.delivered.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_grpc_client_queries() {
+        let orchestrator = FxPrivateKey::random().public_key().to_address();
+        let mut state = FxChainState::default();
+        let account_number = state.add_account(orchestrator);
+        state.set_balance(orchestrator, "FX", "3000000000000000000");
+        state.event_nonces.insert(orchestrator.to_string(), (5, 120));
+        let fx_chain = MockFxChain::start(state).await.unwrap();
+
+        let grpc_channel = new_grpc_channel(fx_chain.url().as_str()).await.unwrap();
+        assert_eq!(get_account_info(&grpc_channel, orchestrator.to_string()).await.unwrap().account_number, account_number);
+        assert_eq!(get_last_event_nonce(&grpc_channel, orchestrator).await.unwrap(), 5);
+        assert_eq!(get_last_event_block_height_by_addr(&grpc_channel, orchestrator).await.unwrap(), 120);
+        assert_eq!(get_gas_price_by_denom(&grpc_channel, "FX".to_string()).await.unwrap().amount, "1");
+        assert_eq!(get_gas_price_by_denom(&grpc_channel, "USDT".to_string()).await.unwrap().amount, "0");
+        check_for_fee_denom(&grpc_channel, orchestrator, "FX").await;
+    }
+
+    #[tokio::test]
+    async fn test_set_orchestrator_address() {
+        let fx_key = FxPrivateKey::random();
+        let mut state = FxChainState::default();
+        state.add_account(fx_key.public_key().to_address());
+        let fx_chain = MockFxChain::start(state).await.unwrap();
+
+        let grpc_channel = new_grpc_channel(fx_chain.url().as_str()).await.unwrap();
+        let builder = Builder::from_net(&grpc_channel, fx_key, "FX").await.unwrap();
+        let msg = MsgSetOrchestratorAddress {
+            validator: builder.address().to_valoper().unwrap(),
+            orchestrator: "fx1zgpzdf2uqla7hkx85wnn4p2r3duwqzd8xst6v2".to_string(),
+            eth_address: "0xeAD9C93b79Ae7C1591b1FB5323BD777E86e150d4".to_string(),
+        };
+        let tx_response = send_tx(&builder, &grpc_channel, vec![msg.to_any("/fx.gravity.v1.MsgSetOrchestratorAddress")]).await.unwrap();
+        assert_eq!(tx_response.code, 0);
+
+        let delegate_key = get_delegate_key_by_validator(&grpc_channel, msg.validator).await.unwrap().unwrap();
+        assert_eq!(delegate_key.orchestrator_address, msg.orchestrator);
+        assert_eq!(delegate_key.eth_address, msg.eth_address);
+    }
+
+    #[tokio::test]
+    async fn test_set_fx_key_balance_metrics() {
+        let fx_key = FxPrivateKey::random();
+        let orchestrator = fx_key.public_key().to_address();
+        let mut state = FxChainState::default();
+        state.add_account(orchestrator);
+        state.set_balance(orchestrator, "FX", "3000000000000000000");
+        let fx_chain = MockFxChain::start(state).await.unwrap();
+
+        let grpc_channel = new_grpc_channel(fx_chain.url().as_str()).await.unwrap();
+        let builder = Builder::from_net(&grpc_channel, fx_key, "FX").await.unwrap();
+        set_fx_key_balance_metrics(&builder, &grpc_channel).await;
+        assert_eq!(prometheus::metrics::FX_KEY_BALANCE.with_label_values(&[orchestrator.to_string().as_str()]).get(), 3.0);
+    }
+}
@@ -0,0 +1,672 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use eyre::Result;
+use futures::future::{join, join_all};
+use tokio::time::{sleep, Instant};
+use tonic::transport::Channel;
+use web3::ethabi::{decode, ParamType, Token};
+use web3::transports::Http;
+use web3::types::{Address as EthAddress, H256, U256, U64};
+use web3::Web3;
+
+use bridge::oracle_loop::eth_oracle_bridge_loop;
+use bridge::singer_loop::{encode_tx_batch_confirm_hash, encode_valset_confirm_hash, eth_signer_main_loop};
+use ethereum::address::Checksum;
+use ethereum::fx_bridge::FxBridge;
+use ethereum::private_key::{Key, PrivateKey as EthPrivateKey, Signature};
+use fxchain::address::Address as FxAddress;
+use fxchain::builder::Builder;
+use fxchain::grpc_client::{get_batch_confirms, get_batch_request_by_nonce, get_valset_confirms, get_valset_request, new_grpc_channel};
+use fxchain::private_key::PrivateKey as FxPrivateKey;
+use fxchain::x::gravity::{BridgeValidator, Erc20Token, OutgoingTransferTx, OutgoingTxBatch, Valset};
+
+use crate::eth_node::{batch_executed_log, decode_raw_transaction, send_to_fx_log, valset_updated_log, EthChainState, MockEthNode, TOO_MANY_RESULTS};
+use crate::fx_chain::{Erc20Denom, FxChainState, MockFxChain};
+
+/// Address of the FxBridge contract on the mock Ethereum node
+pub const BRIDGE_ADDRESS: &str = "0x0412C7c846bb6b7DC462CF6B453f76D8440b2609";
+
+/// ERC20 bridged to the `usdt` denom
+pub const TOKEN: &str = "0xdAC17F958D2ee523a2206206994597C13D831ec7";
+
+pub const TOKEN_DENOM: &str = "usdt";
+
+/// Confirmations the oracle waits for, mirrors the bridge crate
+const ETH_BLOCK_DELAY: u64 = 12;
+
+/// Total power of a valset, as normalized by f(x)Core
+const TOTAL_POWER: u64 = u32::MAX as u64;
+
+/// Power the signatures of a call must exceed, set by the FxBridge constructor
+const POWER_THRESHOLD: u64 = 2_863_311_530;
+
+/// Upper bound of `wait_for` in paused tokio time, which jumps ahead at every idle await
+const WAIT_TIMEOUT: Duration = Duration::from_secs(3600);
+
+/// A validator running the oracle and signer loops
+pub struct Orchestrator {
+    pub validator: String,
+    pub fx_builder: Builder,
+    pub eth_key: EthPrivateKey,
+}
+
+impl Orchestrator {
+    pub fn address(&self) -> FxAddress {
+        self.fx_builder.address()
+    }
+
+    pub fn eth_address(&self) -> EthAddress {
+        Key::address(&self.eth_key)
+    }
+}
+
+/// State of the FxBridge contract the test relayer calls
+#[derive(Debug, Clone, Default)]
+pub struct BridgeContract {
+    pub valset_nonce: u64,
+    pub validators: Vec<EthAddress>,
+    pub powers: Vec<u64>,
+    pub last_event_nonce: u64,
+    pub last_batch_nonces: HashMap<EthAddress, u64>,
+    /// `(token, destination, amount)` of every executed batch transfer, fees included
+    pub transfers: Vec<(EthAddress, EthAddress, U256)>,
+}
+
+/// Both chains of the bridge with the real oracle and signer loops of every orchestrator in between.
+///
+/// The repo has no Ethereum relayer, so `relay_valset` and `relay_batch` play its part: they sign
+/// `updateValset` and `submitBatch` calls with `FxBridge::sign_call`, check them against `BridgeContract`
+/// like the contract would, and emit the resulting events on the Ethereum node.
+pub struct BridgeHarness {
+    pub fx_chain: MockFxChain,
+    pub eth_node: MockEthNode,
+    pub grpc_channel: Channel,
+    pub web3: Web3<Http>,
+    pub bridge_addr: EthAddress,
+    pub gravity_id: String,
+    pub orchestrators: Vec<Orchestrator>,
+    pub relayer: FxBridge,
+    contract: Mutex<BridgeContract>,
+}
+
+impl BridgeHarness {
+    /// Start both chains with `count` orchestrators of equal power, deployed as the genesis valset of the
+    /// FxBridge contract, and `TOKEN` bridged to `TOKEN_DENOM`
+    pub async fn start(count: usize) -> Result<BridgeHarness> {
+        let bridge_addr = EthAddress::from_str(BRIDGE_ADDRESS)?;
+        let eth_keys: Vec<EthPrivateKey> = (0..count).map(|i| EthPrivateKey::from([i as u8 + 1; 32])).collect();
+        let fx_keys: Vec<FxPrivateKey> = (0..count).map(|_| FxPrivateKey::random()).collect();
+
+        let mut fx_state = FxChainState::default();
+        let mut validators = Vec::new();
+        for (fx_key, eth_key) in fx_keys.iter().zip(eth_keys.iter()) {
+            let orchestrator = fx_key.public_key().to_address();
+            let validator = orchestrator.to_valoper()?;
+            fx_state.add_orchestrator(validator.as_str(), orchestrator, Key::address(eth_key).to_hex_string().as_str());
+            validators.push(validator);
+        }
+        fx_state.erc20_denoms.push(Erc20Denom {
+            erc20: TOKEN.to_string(),
+            denom: TOKEN_DENOM.to_string(),
+            fx_originated: false,
+        });
+        let gravity_id = fx_state.params.gravity_id.clone();
+
+        let contract = BridgeContract {
+            validators: eth_keys.iter().map(|key| Key::address(key)).collect(),
+            powers: vec![TOTAL_POWER / count as u64; count],
+            ..Default::default()
+        };
+        let mut eth_state = EthChainState::default();
+        let powers: Vec<U256> = contract.powers.iter().map(|power| U256::from(*power)).collect();
+        eth_state.push_logs(vec![valset_updated_log(bridge_addr, U256::zero(), U256::zero(), &contract.validators, &powers)]);
+        eth_state.mine_blocks(ETH_BLOCK_DELAY);
+
+        let fx_chain = MockFxChain::start(fx_state).await?;
+        let eth_node = MockEthNode::start(eth_state).await?;
+        let grpc_channel = new_grpc_channel(fx_chain.url().as_str()).await?;
+        let web3 = Web3::new(Http::new(eth_node.url().as_str())?);
+
+        let mut orchestrators = Vec::new();
+        for ((validator, fx_key), eth_key) in validators.into_iter().zip(fx_keys).zip(eth_keys) {
+            let fx_builder = Builder::from_net(&grpc_channel, fx_key, "FX").await?;
+            orchestrators.push(Orchestrator { validator, fx_builder, eth_key });
+        }
+        let relayer = FxBridge::new(Some(EthPrivateKey::from([0x42; 32])), None, web3.eth(), bridge_addr);
+
+        Ok(BridgeHarness {
+            fx_chain,
+            eth_node,
+            grpc_channel,
+            web3,
+            bridge_addr,
+            gravity_id,
+            orchestrators,
+            relayer,
+            contract: Mutex::new(contract),
+        })
+    }
+
+    /// Run `scenario` while the oracle and signer loops of every orchestrator run against both chains
+    pub async fn run<F: Future<Output = Result<()>>>(&self, scenario: F) -> Result<()> {
+        let loops = join_all(self.orchestrators.iter().map(|orchestrator| {
+            join(
+                eth_oracle_bridge_loop(&orchestrator.fx_builder, &self.grpc_channel, &self.web3, self.bridge_addr, U64::zero(), None),
+                eth_signer_main_loop(&orchestrator.fx_builder, &self.grpc_channel, &orchestrator.eth_key, None, None),
+            )
+        }));
+        tokio::select! {
+            _ = loops => Err(eyre::Error::msg("orchestrator loops exited")),
+            result = scenario => result,
+        }
+    }
+
+    /// Poll the f(x)Core state until `done`
+    pub async fn wait_for(&self, what: &str, done: impl Fn(&FxChainState) -> bool) -> Result<()> {
+        let deadline = Instant::now() + WAIT_TIMEOUT;
+        while !done(&self.fx_chain.state()) {
+            if Instant::now() > deadline {
+                return Err(eyre::Error::msg(format!("timed out waiting for {}", what)));
+            }
+            sleep(Duration::from_secs(1)).await;
+        }
+        debug!("Harness done waiting for {}", what);
+        Ok(())
+    }
+
+    pub fn contract(&self) -> BridgeContract {
+        self.contract.lock().unwrap().clone()
+    }
+
+    /// `sendToFx` of `amount` `TOKEN` to `receiver`, confirmed by `ETH_BLOCK_DELAY` blocks
+    pub fn deposit(&self, sender: EthAddress, receiver: FxAddress, amount: U256) -> Result<()> {
+        let mut destination = [0u8; 20];
+        destination.copy_from_slice(receiver.as_bytes());
+        let event_nonce = self.next_event_nonce();
+        let log = send_to_fx_log(self.bridge_addr, EthAddress::from_str(TOKEN)?, sender, destination, &[], amount, event_nonce.into());
+        self.emit(log);
+        Ok(())
+    }
+
+    /// Relay valset `nonce` with the confirms on f(x)Core, like a relayer calling `updateValset`
+    pub async fn relay_valset(&self, nonce: u64) -> Result<()> {
+        let valset = get_valset_request(&self.grpc_channel, nonce).await?.ok_or_else(|| eyre::Error::msg(format!("no valset {}", nonce)))?;
+        let confirms = get_valset_confirms(&self.grpc_channel, nonce).await?;
+        let confirms: Vec<(String, String)> = confirms.into_iter().map(|confirm| (confirm.eth_address, confirm.signature)).collect();
+
+        let current = self.contract();
+        let (v, r, s) = signature_tokens(&current.validators, &confirms)?;
+        let mut new_validators = Vec::new();
+        let mut new_powers = Vec::new();
+        for member in valset.members.iter() {
+            new_validators.push(Token::Address(EthAddress::from_str(member.eth_address.as_str())?));
+            new_powers.push(Token::Uint(member.power.into()));
+        }
+        let params = (
+            new_validators,
+            new_powers,
+            U256::from(valset.nonce),
+            address_tokens(&current.validators),
+            uint_tokens(&current.powers),
+            U256::from(current.valset_nonce),
+            v,
+            r,
+            s,
+        );
+        let calldata = self.send_relay_tx("updateValset", params).await?;
+        self.execute_update_valset(calldata.as_slice())
+    }
+
+    /// Relay batch `nonce` of `token_contract` with the confirms on f(x)Core, like a relayer calling `submitBatch`
+    pub async fn relay_batch(&self, token_contract: &str, nonce: u64) -> Result<()> {
+        let batch = get_batch_request_by_nonce(&self.grpc_channel, token_contract.to_string(), nonce).await?;
+        let batch = batch.ok_or_else(|| eyre::Error::msg(format!("no batch {} of {}", nonce, token_contract)))?;
+        let confirms = get_batch_confirms(&self.grpc_channel, token_contract.to_string(), nonce).await?;
+        let confirms: Vec<(String, String)> = confirms.into_iter().map(|confirm| (confirm.eth_signer, confirm.signature)).collect();
+
+        let current = self.contract();
+        let (v, r, s) = signature_tokens(&current.validators, &confirms)?;
+        let mut amounts = Vec::new();
+        let mut destinations = Vec::new();
+        let mut fees = Vec::new();
+        for tx in batch.transactions.iter() {
+            let amount = tx.erc20_token.as_ref().map(|token| token.amount.clone()).unwrap_or_default();
+            let fee = tx.erc20_fee.as_ref().map(|fee| fee.amount.clone()).unwrap_or_default();
+            amounts.push(Token::Uint(U256::from_dec_str(amount.as_str())?));
+            destinations.push(Token::Address(EthAddress::from_str(tx.dest_address.as_str())?));
+            fees.push(Token::Uint(U256::from_dec_str(fee.as_str())?));
+        }
+        let params = (
+            address_tokens(&current.validators),
+            uint_tokens(&current.powers),
+            v,
+            r,
+            s,
+            amounts,
+            destinations,
+            fees,
+            [U256::from(current.valset_nonce), U256::from(batch.batch_nonce)],
+            EthAddress::from_str(batch.token_contract.as_str())?,
+            U256::from(batch.batch_timeout),
+            EthAddress::from_str(batch.fee_receive.as_str())?,
+        );
+        let calldata = self.send_relay_tx("submitBatch", params).await?;
+        self.execute_submit_batch(calldata.as_slice())
+    }
+
+    /// Sign and send `func` from the relayer, returns the calldata after the selector
+    async fn send_relay_tx(&self, func: &str, params: impl web3::contract::tokens::Tokenize) -> Result<Vec<u8>> {
+        let signed = self.relayer.sign_call(func, params).await?;
+        let tx = decode_raw_transaction(signed.raw_transaction.0.as_slice())?;
+        let tx_hash = self.web3.eth().send_raw_transaction(signed.raw_transaction).await?;
+        let receipt = self.web3.eth().transaction_receipt(tx_hash).await?;
+        if receipt.and_then(|receipt| receipt.status) != Some(1.into()) {
+            return Err(eyre::Error::msg(format!("{} tx {:?} failed", func, tx_hash)));
+        }
+        info!("Harness relayed {} in tx {:?}", func, tx_hash);
+        Ok(tx.data[4..].to_vec())
+    }
+
+    fn execute_update_valset(&self, calldata: &[u8]) -> Result<()> {
+        let uints = ParamType::Array(Box::new(ParamType::Uint(256)));
+        let addresses = ParamType::Array(Box::new(ParamType::Address));
+        let tokens = decode(
+            &[
+                addresses.clone(),
+                uints.clone(),
+                ParamType::Uint(256),
+                addresses,
+                uints,
+                ParamType::Uint(256),
+                ParamType::Array(Box::new(ParamType::Uint(8))),
+                ParamType::Array(Box::new(ParamType::FixedBytes(32))),
+                ParamType::Array(Box::new(ParamType::FixedBytes(32))),
+            ],
+            calldata,
+        )?;
+        let new_validators = to_addresses(&tokens[0]);
+        let new_powers: Vec<u64> = to_uints(&tokens[1]).iter().map(U256::as_u64).collect();
+        let new_valset_nonce = to_uints(&tokens[2])[0].as_u64();
+
+        let mut contract = self.contract.lock().unwrap();
+        check_current_valset(&contract, &tokens[3], &tokens[4], to_uints(&tokens[5])[0].as_u64())?;
+        if new_valset_nonce <= contract.valset_nonce {
+            return Err(eyre::Error::msg("New valset nonce must be greater than the current nonce"));
+        }
+        let valset = Valset {
+            nonce: new_valset_nonce,
+            members: new_validators
+                .iter()
+                .zip(new_powers.iter())
+                .map(|(validator, power)| BridgeValidator {
+                    power: *power,
+                    eth_address: validator.to_hex_string(),
+                })
+                .collect(),
+            height: 0,
+        };
+        check_signatures(&contract, &tokens[6..9], encode_valset_confirm_hash(self.gravity_id.clone(), &valset).as_slice())?;
+
+        contract.valset_nonce = new_valset_nonce;
+        contract.validators = new_validators;
+        contract.powers = new_powers;
+        contract.last_event_nonce += 1;
+        let powers: Vec<U256> = contract.powers.iter().map(|power| U256::from(*power)).collect();
+        let log = valset_updated_log(self.bridge_addr, new_valset_nonce.into(), contract.last_event_nonce.into(), &contract.validators, &powers);
+        drop(contract);
+        self.emit(log);
+        Ok(())
+    }
+
+    fn execute_submit_batch(&self, calldata: &[u8]) -> Result<()> {
+        let uints = ParamType::Array(Box::new(ParamType::Uint(256)));
+        let addresses = ParamType::Array(Box::new(ParamType::Address));
+        let tokens = decode(
+            &[
+                addresses.clone(),
+                uints.clone(),
+                ParamType::Array(Box::new(ParamType::Uint(8))),
+                ParamType::Array(Box::new(ParamType::FixedBytes(32))),
+                ParamType::Array(Box::new(ParamType::FixedBytes(32))),
+                uints.clone(),
+                addresses,
+                uints,
+                ParamType::FixedArray(Box::new(ParamType::Uint(256)), 2),
+                ParamType::Address,
+                ParamType::Uint(256),
+                ParamType::Address,
+            ],
+            calldata,
+        )?;
+        let amounts = to_uints(&tokens[5]);
+        let destinations = to_addresses(&tokens[6]);
+        let fees = to_uints(&tokens[7]);
+        let nonces = to_uints(&tokens[8]);
+        let token_contract = to_addresses(&tokens[9])[0];
+        let batch_timeout = to_uints(&tokens[10])[0];
+        let fee_receive = to_addresses(&tokens[11])[0];
+
+        let mut contract = self.contract.lock().unwrap();
+        check_current_valset(&contract, &tokens[0], &tokens[1], nonces[0].as_u64())?;
+        let batch_nonce = nonces[1].as_u64();
+        if batch_nonce <= contract.last_batch_nonces.get(&token_contract).cloned().unwrap_or_default() {
+            return Err(eyre::Error::msg("New batch nonce must be greater than the current nonce"));
+        }
+        let batch = OutgoingTxBatch {
+            batch_nonce,
+            batch_timeout: batch_timeout.as_u64(),
+            transactions: (0..amounts.len())
+                .map(|i| OutgoingTransferTx {
+                    dest_address: destinations[i].to_hex_string(),
+                    erc20_token: Some(Erc20Token {
+                        contract: token_contract.to_hex_string(),
+                        amount: amounts[i].to_string(),
+                    }),
+                    erc20_fee: Some(Erc20Token {
+                        contract: token_contract.to_hex_string(),
+                        amount: fees[i].to_string(),
+                    }),
+                    ..Default::default()
+                })
+                .collect(),
+            token_contract: token_contract.to_hex_string(),
+            fee_receive: fee_receive.to_hex_string(),
+            ..Default::default()
+        };
+        check_signatures(&contract, &tokens[2..5], encode_tx_batch_confirm_hash(self.gravity_id.clone(), batch).as_slice())?;
+
+        contract.last_batch_nonces.insert(token_contract, batch_nonce);
+        for i in 0..amounts.len() {
+            contract.transfers.push((token_contract, destinations[i], amounts[i]));
+        }
+        let total_fee = fees.iter().fold(U256::zero(), |total, fee| total + fee);
+        contract.transfers.push((token_contract, fee_receive, total_fee));
+        contract.last_event_nonce += 1;
+        let log = batch_executed_log(self.bridge_addr, batch_nonce.into(), token_contract, contract.last_event_nonce.into());
+        drop(contract);
+        self.emit(log);
+        Ok(())
+    }
+
+    fn next_event_nonce(&self) -> u64 {
+        let mut contract = self.contract.lock().unwrap();
+        contract.last_event_nonce += 1;
+        contract.last_event_nonce
+    }
+
+    /// Mine `log` and enough blocks on top for the oracle to pick it up
+    fn emit(&self, log: web3::types::Log) {
+        let mut eth_state = self.eth_node.state();
+        eth_state.push_logs(vec![log]);
+        eth_state.mine_blocks(ETH_BLOCK_DELAY);
+    }
+}
+
+fn address_tokens(addresses: &[EthAddress]) -> Vec<Token> {
+    addresses.iter().map(|address| Token::Address(*address)).collect()
+}
+
+fn uint_tokens(uints: &[u64]) -> Vec<Token> {
+    uints.iter().map(|uint| Token::Uint((*uint).into())).collect()
+}
+
+/// Addresses of an address or address array token
+fn to_addresses(token: &Token) -> Vec<EthAddress> {
+    match token {
+        Token::Array(tokens) => tokens.iter().filter_map(|token| token.clone().into_address()).collect(),
+        token => token.clone().into_address().into_iter().collect(),
+    }
+}
+
+/// Uints of a uint or uint array token
+fn to_uints(token: &Token) -> Vec<U256> {
+    match token {
+        Token::Array(tokens) | Token::FixedArray(tokens) => tokens.iter().filter_map(|token| token.clone().into_uint()).collect(),
+        token => token.clone().into_uint().into_iter().collect(),
+    }
+}
+
+/// v, r and s of the confirm of each validator, zero for the validators without one
+fn signature_tokens(validators: &[EthAddress], confirms: &[(String, String)]) -> Result<(Vec<Token>, Vec<Token>, Vec<Token>)> {
+    let (mut v, mut r, mut s) = (Vec::new(), Vec::new(), Vec::new());
+    for validator in validators.iter() {
+        let confirm = confirms.iter().find(|(eth_address, _)| EthAddress::from_str(eth_address.as_str()).ok() == Some(*validator));
+        let signature = match confirm {
+            Some((_, signature)) => {
+                Signature::from_bytes(hex::decode(signature.trim_start_matches("0x"))?.as_slice()).map_err(|err| eyre::Error::msg(format!("invalid signature {}: {}", signature, err)))?
+            }
+            None => Signature::default(),
+        };
+        v.push(Token::Uint(signature.v.into()));
+        r.push(Token::FixedBytes(signature.r.as_bytes().to_vec()));
+        s.push(Token::FixedBytes(signature.s.as_bytes().to_vec()));
+    }
+    Ok((v, r, s))
+}
+
+fn check_current_valset(contract: &BridgeContract, validators: &Token, powers: &Token, valset_nonce: u64) -> Result<()> {
+    let powers: Vec<u64> = to_uints(powers).iter().map(U256::as_u64).collect();
+    if to_addresses(validators) != contract.validators || powers != contract.powers || valset_nonce != contract.valset_nonce {
+        return Err(eyre::Error::msg("Supplied current validators and powers do not match checkpoint"));
+    }
+    Ok(())
+}
+
+/// Require the signatures `[v, r, s]` of the current validators over `message` to exceed `POWER_THRESHOLD`
+fn check_signatures(contract: &BridgeContract, signatures: &[Token], message: &[u8]) -> Result<()> {
+    let v = to_uints(&signatures[0]);
+    let bytes32 = |tokens: &Token| -> Vec<H256> {
+        match tokens {
+            Token::Array(tokens) => tokens.iter().filter_map(|token| token.clone().into_fixed_bytes()).map(|bytes| H256::from_slice(&bytes)).collect(),
+            _ => Vec::new(),
+        }
+    };
+    let (r, s) = (bytes32(&signatures[1]), bytes32(&signatures[2]));
+    if v.len() != contract.validators.len() || r.len() != v.len() || s.len() != v.len() {
+        return Err(eyre::Error::msg("Malformed current validator set"));
+    }
+    let mut power = 0;
+    for (i, validator) in contract.validators.iter().enumerate() {
+        if v[i].is_zero() {
+            continue;
+        }
+        let signer = Signature::new(v[i].as_u64(), r[i], s[i]).recover_ethereum_msg(message)?;
+        if signer != *validator {
+            return Err(eyre::Error::msg(format!(
+                "Validator signature does not match, {} != {}",
+                signer.to_hex_string(),
+                validator.to_hex_string()
+            )));
+        }
+        power += contract.powers[i];
+        if power > POWER_THRESHOLD {
+            return Ok(());
+        }
+    }
+    Err(eyre::Error::msg("Submitted validator set signatures do not have enough power"))
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmos_sdk_proto::cosmos::base::v1beta1::Coin;
+    use fxchain::grpc_client::send_tx;
+    use fxchain::proto_ext::MessageExt;
+    use fxchain::x::gravity::{MsgDepositClaim, MsgRequestBatch, MsgSendToEth, MsgValsetUpdatedClaim, MsgWithdrawClaim};
+
+    use super::*;
+
+    fn claimed_by_all(harness: &BridgeHarness, event_nonce: u64) -> impl Fn(&FxChainState) -> bool + '_ {
+        move |state| {
+            harness
+                .orchestrators
+                .iter()
+                .all(|orchestrator| state.event_nonces.get(&orchestrator.address().to_string()).map(|(nonce, _)| *nonce) == Some(event_nonce))
+        }
+    }
+
+    fn balance(harness: &BridgeHarness, address: FxAddress, denom: &str) -> String {
+        let state = harness.fx_chain.state();
+        let coins = state.balances.get(&address.to_string()).cloned().unwrap_or_default();
+        coins.into_iter().find(|coin| coin.denom == denom).map(|coin| coin.amount).unwrap_or_else(|| "0".to_string())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_deposit() {
+        let harness = BridgeHarness::start(3).await.unwrap();
+        let receiver = FxPrivateKey::random().public_key().to_address();
+        let sender = EthAddress::from_low_u64_be(0xbeef);
+
+        harness
+            .run(async {
+                harness.deposit(sender, receiver, U256::from(1_000_000u64))?;
+                harness.wait_for("deposit claims", claimed_by_all(&harness, 1)).await
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(balance(&harness, receiver, TOKEN_DENOM), "1000000");
+        let claims = harness.fx_chain.state().delivered_msgs::<MsgDepositClaim>("/fx.gravity.v1.MsgDepositClaim");
+        assert_eq!(claims.len(), 3);
+        assert!(claims.iter().all(|claim| claim.fx_receiver == receiver.to_string() && claim.eth_sender == sender.to_hex_string()));
+        for orchestrator in harness.orchestrators.iter() {
+            let claims_submitted = prometheus::metrics::ETH_BRIDGE_ORACLE_CLAIMS_SUBMITTED.with_label_values(&[orchestrator.address().to_string().as_str()]);
+            assert_eq!(claims_submitted.get(), 1.0);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_valset_update() {
+        let harness = BridgeHarness::start(3).await.unwrap();
+        let members: Vec<BridgeValidator> = harness
+            .orchestrators
+            .iter()
+            .zip([3u64, 2, 1].iter())
+            .map(|(orchestrator, weight)| BridgeValidator {
+                power: TOTAL_POWER * weight / 6,
+                eth_address: orchestrator.eth_address().to_hex_string(),
+            })
+            .collect();
+        harness.fx_chain.state().add_valset(Valset {
+            nonce: 1,
+            members: members.clone(),
+            height: 100,
+        });
+
+        harness
+            .run(async {
+                let confirmed = |state: &FxChainState| state.valset_confirms.get(&1).map(Vec::len) == Some(3);
+                harness.wait_for("valset confirms", confirmed).await?;
+                harness.relay_valset(1).await?;
+                harness.wait_for("valset updated claims", claimed_by_all(&harness, 1)).await
+            })
+            .await
+            .unwrap();
+
+        let contract = harness.contract();
+        assert_eq!(contract.valset_nonce, 1);
+        assert_eq!(contract.powers, members.iter().map(|member| member.power).collect::<Vec<u64>>());
+        let claims = harness.fx_chain.state().delivered_msgs::<MsgValsetUpdatedClaim>("/fx.gravity.v1.MsgValsetUpdatedClaim");
+        assert_eq!(claims.len(), 3);
+        assert!(claims.iter().all(|claim| claim.valset_nonce == 1 && claim.members == members));
+        for orchestrator in harness.orchestrators.iter() {
+            let valset_signed = prometheus::metrics::UPDATE_VALSET_SIGN.with_label_values(&[orchestrator.address().to_string().as_str()]);
+            assert_eq!(valset_signed.get(), 1.0);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_deposits_over_log_limit() {
+        let harness = BridgeHarness::start(2).await.unwrap();
+        let receiver = FxPrivateKey::random().public_key().to_address();
+        let sender = EthAddress::from_low_u64_be(0xbeef);
+        // the oracle range holds the genesis valset and 6 deposits, it is halved until a range fits
+        harness.eth_node.state().max_logs = Some(2);
+
+        harness
+            .run(async {
+                for _ in 0..6 {
+                    harness.deposit(sender, receiver, U256::from(100u64))?;
+                }
+                let latest_block = harness.eth_node.state().block_number();
+                let err = ethereum::fx_bridge::query_all_event(&harness.web3, harness.bridge_addr, U64::zero(), Some(latest_block))
+                    .await
+                    .unwrap_err();
+                assert!(format!("{:?}", err).contains(TOO_MANY_RESULTS));
+                harness.wait_for("deposit claims", claimed_by_all(&harness, 6)).await
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(balance(&harness, receiver, TOKEN_DENOM), "600");
+        let claims = harness.fx_chain.state().delivered_msgs::<MsgDepositClaim>("/fx.gravity.v1.MsgDepositClaim");
+        for orchestrator in harness.orchestrators.iter() {
+            let nonces: Vec<u64> = claims
+                .iter()
+                .filter(|claim| claim.orchestrator == orchestrator.address().to_string())
+                .map(|claim| claim.event_nonce)
+                .collect();
+            assert_eq!(nonces, (1..=6).collect::<Vec<u64>>());
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_send_to_eth() {
+        let harness = BridgeHarness::start(3).await.unwrap();
+        let user_key = FxPrivateKey::random();
+        let user = user_key.public_key().to_address();
+        harness.fx_chain.state().add_account(user);
+        harness.fx_chain.state().mint(user.to_string().as_str(), TOKEN_DENOM, 1000);
+        let user_builder = Builder::from_net(&harness.grpc_channel, user_key, "FX").await.unwrap();
+        let destination = EthAddress::from_low_u64_be(0xd00d);
+        let fee_receive = Key::address(&EthPrivateKey::from([0x42; 32]));
+
+        let coin = |amount: &str| Coin {
+            denom: TOKEN_DENOM.to_string(),
+            amount: amount.to_string(),
+        };
+        let msgs = vec![
+            MsgSendToEth {
+                sender: user.to_string(),
+                eth_dest: destination.to_hex_string(),
+                amount: Some(coin("100")),
+                bridge_fee: Some(coin("1")),
+            }
+            .to_any("/fx.gravity.v1.MsgSendToEth"),
+            MsgRequestBatch {
+                sender: user.to_string(),
+                denom: TOKEN_DENOM.to_string(),
+                minimum_fee: "1".to_string(),
+                fee_receive: fee_receive.to_hex_string(),
+            }
+            .to_any("/fx.gravity.v1.MsgRequestBatch"),
+        ];
+        let tx_response = send_tx(&user_builder, &harness.grpc_channel, msgs).await.unwrap();
+        assert_eq!(tx_response.code, 0, "{}", tx_response.raw_log);
+        assert_eq!(balance(&harness, user, TOKEN_DENOM), "899");
+
+        harness
+            .run(async {
+                let confirmed = |state: &FxChainState| state.batch_confirms.get(&(TOKEN.to_string(), 1)).map(Vec::len) == Some(3);
+                harness.wait_for("batch confirms", confirmed).await?;
+                harness.relay_batch(TOKEN, 1).await?;
+                harness.wait_for("withdraw claims", claimed_by_all(&harness, 1)).await
+            })
+            .await
+            .unwrap();
+
+        let token = EthAddress::from_str(TOKEN).unwrap();
+        assert_eq!(harness.contract().transfers, vec![(token, destination, U256::from(100)), (token, fee_receive, U256::from(1))]);
+        let state = harness.fx_chain.state();
+        assert!(state.batches.is_empty());
+        let claims = state.delivered_msgs::<MsgWithdrawClaim>("/fx.gravity.v1.MsgWithdrawClaim");
+        assert!(claims.iter().all(|claim| claim.batch_nonce == 1 && claim.token_contract == TOKEN));
+        for orchestrator in harness.orchestrators.iter() {
+            let batch_signed = prometheus::metrics::SUBMIT_BATCH_SIGN.with_label_values(&[orchestrator.address().to_string().as_str()]);
+            assert_eq!(batch_signed.get(), 1.0);
+        }
+    }
+}
@@ -0,0 +1,6 @@
+#[macro_use]
+extern crate log;
+
+pub mod eth_node;
+pub mod fx_chain;
+pub mod harness;